@@ -1,18 +1,24 @@
 //! A simple cache system for heimdall-rs
-//! Stores objects in ~/.bifrost/cache as bincode serialized files
-//! Objects are stored with an expiry time, and are deleted if they are expired
+//! Stores objects in a single-file store at ~/.bifrost/cache/heimdall.db as bincode serialized
+//! values. Objects are stored with an expiry time, and are deleted if they are expired
 
 use clap::Parser;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Serialize};
 #[allow(deprecated)]
 use std::env::home_dir;
+use std::{path::PathBuf, sync::Mutex};
 
 use error::Error;
+use store::{Namespace, Store, DEFAULT_MAX_SIZE};
 use util::*;
 
 pub mod error;
+pub mod store;
 pub(crate) mod util;
 
+/// The store backing the cache, opened on first use
+static STORE: Mutex<Option<Store>> = Mutex::new(None);
+
 /// Clap argument parser for the cache subcommand
 #[derive(Debug, Clone, Parser)]
 #[clap(
@@ -30,6 +36,49 @@ pub struct CacheArgs {
 #[derive(Debug, Clone, Parser)]
 pub struct NoArguments {}
 
+/// Arguments for cache subcommands which may be restricted to a single namespace
+#[derive(Debug, Clone, Parser)]
+pub struct NamespaceArguments {
+    /// Only include objects in this namespace.
+    #[clap(long, short)]
+    pub namespace: Option<Namespace>,
+}
+
+/// Arguments for the `export` cache subcommand
+#[derive(Debug, Clone, Parser)]
+pub struct ExportArguments {
+    /// The path to write the exported store to.
+    pub path: PathBuf,
+
+    /// Only export objects in this namespace.
+    #[clap(long, short)]
+    pub namespace: Option<Namespace>,
+}
+
+/// Arguments for the `import` cache subcommand
+#[derive(Debug, Clone, Parser)]
+pub struct ImportArguments {
+    /// The path of a store written by `heimdall cache export`.
+    pub path: PathBuf,
+
+    /// Only import objects in this namespace.
+    #[clap(long, short)]
+    pub namespace: Option<Namespace>,
+}
+
+/// Arguments for the `prune` cache subcommand
+#[derive(Debug, Clone, Parser)]
+pub struct PruneArguments {
+    /// Only prune objects in this namespace.
+    #[clap(long, short)]
+    pub namespace: Option<Namespace>,
+
+    /// Evict the least recently used objects until the cache, or namespace, is no larger than
+    /// this many bytes.
+    #[clap(long)]
+    pub max_size: Option<u64>,
+}
+
 /// Clap subcommand parser for cache subcommands
 #[derive(Debug, Clone, Parser)]
 #[clap(
@@ -39,26 +88,95 @@ pub struct NoArguments {}
 #[allow(clippy::large_enum_variant)]
 pub enum Subcommands {
     /// Clear the cache, removing all objects
-    #[clap(name = "clean", about = "Removes all cached objects in ~/.bifrost/cache")]
-    Clean(NoArguments),
+    #[clap(name = "clean", about = "Removes all cached objects, or those in a namespace")]
+    Clean(NamespaceArguments),
 
     /// List all cached objects
-    #[clap(name = "ls", about = "Lists all cached objects in ~/.bifrost/cache")]
-    Ls(NoArguments),
+    #[clap(name = "ls", about = "Lists all cached objects, or those in a namespace")]
+    Ls(NamespaceArguments),
 
     /// Print the size of the cache in ~/.bifrost/cache
     #[clap(name = "size", about = "Prints the size of the cache in ~/.bifrost/cache")]
     Size(NoArguments),
+
+    /// Print the entries, size, hits, misses and evictions of each namespace
+    #[clap(name = "stats", about = "Prints the size and hit rate of each cache namespace")]
+    Stats(NoArguments),
+
+    /// Export cached objects to a standalone store
+    #[clap(name = "export", about = "Exports cached objects, or those in a namespace, to a file")]
+    Export(ExportArguments),
+
+    /// Import cached objects from a standalone store
+    #[clap(name = "import", about = "Imports cached objects from a file written by `export`")]
+    Import(ImportArguments),
+
+    /// Remove expired objects, and evict the least recently used objects
+    #[clap(
+        name = "prune",
+        about = "Removes expired objects, and evicts the least recently used objects"
+    )]
+    Prune(PruneArguments),
+}
+
+/// The directory containing the cache, which is the `CACHE_DIR` environment variable if it's set,
+/// or `~/.bifrost/cache`
+#[allow(deprecated)]
+fn cache_dir() -> Result<PathBuf, Error> {
+    if let Some(dir) = std::env::var_os("CACHE_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+
+    let home = home_dir().ok_or_else(|| {
+        Error::Generic(
+            "failed to get home directory. does your os support `std::env::home_dir()`?"
+                .to_string(),
+        )
+    })?;
+
+    Ok(home.join(".bifrost").join("cache"))
 }
 
-/// A simple cache object that stores a value and an expiry time \
-/// The expiry time is a unix timestamp
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Cache<T> {
-    /// The value stored in the cache
-    pub value: T,
-    /// The expiry time of the cache object
-    pub expiry: u64,
+/// Runs `f` with the store backing the cache, opening it on first use. The store is bounded to
+/// the number of bytes in the `CACHE_MAX_SIZE` environment variable, or [`DEFAULT_MAX_SIZE`].
+///
+/// Objects cached before the store existed are moved into it when it's opened.
+pub fn with_store<T>(f: impl FnOnce(&mut Store) -> Result<T, Error>) -> Result<T, Error> {
+    let mut guard =
+        STORE.lock().map_err(|_| Error::Generic("cache store lock was poisoned".to_string()))?;
+
+    let store = match &mut *guard {
+        Some(store) => store,
+        slot @ None => {
+            let max_size = std::env::var("CACHE_MAX_SIZE")
+                .ok()
+                .and_then(|size| size.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_MAX_SIZE);
+            let dir = cache_dir()?;
+            let mut store = Store::open(dir.join("heimdall.db"), max_size)?;
+
+            let migrated = store.migrate(&dir)?;
+            if migrated > 0 {
+                tracing::debug!("migrated {} cached objects into {}", migrated, dir.display());
+            }
+            slot.insert(store)
+        }
+    };
+
+    f(store)
+}
+
+/// Persists the hit and miss counters and the recency of reads made through the cache, which
+/// are otherwise only persisted when the store is compacted. The store lives in a static and is
+/// never dropped, so this should be called whenever a command finishes.
+pub fn flush_cache() -> Result<(), Error> {
+    let mut guard =
+        STORE.lock().map_err(|_| Error::Generic("cache store lock was poisoned".to_string()))?;
+
+    match &mut *guard {
+        Some(store) => store.flush(),
+        None => Ok(()),
+    }
 }
 
 /// Clear the cache, removing all objects
@@ -78,16 +196,14 @@ pub struct Cache<T> {
 /// /// assert that the cache no longer contains the key
 /// assert!(!keys("*").expect("!").contains(&"clear_cache_key".to_string()));
 /// ```
-#[allow(deprecated)]
 pub fn clear_cache() -> Result<(), Error> {
-    let home = home_dir().ok_or_else(|| {
-        Error::Generic(
-            "failed to get home directory. does your os support `std::env::home_dir()`?"
-                .to_string(),
-        )
-    })?;
-    let cache_dir = home.join(".bifrost").join("cache");
+    with_store(|store| store.clear(None))?;
 
+    // remove anything else in the cache directory, such as objects which failed to migrate
+    let cache_dir = cache_dir()?;
+    if !cache_dir.exists() {
+        return Ok(());
+    }
     for entry in cache_dir
         .read_dir()
         .map_err(|e| Error::Generic(format!("failed to read cache directory: {e:?}")))?
@@ -119,18 +235,8 @@ pub fn clear_cache() -> Result<(), Error> {
 /// /// assert that the cache does not contain a non-existent key
 /// assert!(!exists("non_existent_key").expect("!"));
 /// ```
-#[allow(deprecated)]
 pub fn exists(key: &str) -> Result<bool, Error> {
-    let home = home_dir().ok_or_else(|| {
-        Error::Generic(
-            "failed to get home directory. does your os support `std::env::home_dir()`?"
-                .to_string(),
-        )
-    })?;
-    let cache_dir = home.join(".bifrost").join("cache");
-    let cache_file = cache_dir.join(format!("{key}.bin"));
-
-    Ok(cache_file.exists())
+    with_store(|store| store.contains(key))
 }
 
 /// List all cached objects
@@ -150,42 +256,15 @@ pub fn exists(key: &str) -> Result<bool, Error> {
 /// /// assert that the cache contains the key
 /// assert!(keys("keys_*").expect("!").contains(&"keys_key".to_string()));
 /// ```
-#[allow(deprecated)]
 pub fn keys(pattern: &str) -> Result<Vec<String>, Error> {
-    let home = home_dir().ok_or_else(|| {
-        Error::Generic(
-            "failed to get home directory. does your os support `std::env::home_dir()`?"
-                .to_string(),
-        )
-    })?;
-    let cache_dir = home.join(".bifrost").join("cache");
-    let mut keys = Vec::new();
-
     // remove wildcard
     let pattern = pattern.replace('*', "");
 
-    for entry in cache_dir
-        .read_dir()
-        .map_err(|e| Error::Generic(format!("failed to read cache directory: {e:?}")))?
-    {
-        let entry =
-            entry.map_err(|e| Error::Generic(format!("failed to read cache entry: {e:?}")))?;
-        let key = entry
-            .path()
-            .file_name()
-            .ok_or_else(|| Error::Generic("failed to get file name".to_string()))?
-            .to_str()
-            .ok_or_else(|| Error::Generic("failed to convert path to string".to_string()))?
-            .to_string();
-        if pattern.is_empty() || key.contains(&pattern) {
-            keys.push(key.replace(".bin", ""));
-        }
-    }
-
-    // sort keys alphabetically
-    keys.sort();
-
-    Ok(keys)
+    // keys are sorted alphabetically by the store
+    Ok(with_store(|store| store.keys(None))?
+        .into_iter()
+        .filter(|key| pattern.is_empty() || key.contains(&pattern))
+        .collect())
 }
 
 /// Delete a cached object
@@ -204,23 +283,8 @@ pub fn keys(pattern: &str) -> Result<Vec<String>, Error> {
 /// /// assert that the cache does not contain the key
 /// assert!(!keys("*").expect("!").contains(&"delete_cache_key".to_string()));
 /// ```
-#[allow(deprecated)]
 pub fn delete_cache(key: &str) -> Result<(), Error> {
-    let home = home_dir().ok_or_else(|| {
-        Error::Generic(
-            "failed to get home directory. does your os support `std::env::home_dir()`?"
-                .to_string(),
-        )
-    })?;
-    let cache_dir = home.join(".bifrost").join("cache");
-    let cache_file = cache_dir.join(format!("{key}.bin"));
-
-    if cache_file.exists() {
-        std::fs::remove_file(cache_file)
-            .map_err(|e| Error::Generic(format!("failed to delete cache file: {e:?}")))?;
-    }
-
-    Ok(())
+    with_store(|store| store.delete(key))
 }
 
 /// Read a cached object
@@ -234,46 +298,19 @@ pub fn delete_cache(key: &str) -> Result<(), Error> {
 /// /// read the cached object
 /// assert_eq!(read_cache::<String>("read_cache_key").expect("!").expect("!"), "value");
 /// ```
-#[allow(deprecated)]
 pub fn read_cache<T>(key: &str) -> Result<Option<T>, Error>
 where
     T: 'static + DeserializeOwned, {
-    let home = home_dir().ok_or_else(|| {
-        Error::Generic(
-            "failed to get home directory. does your os support `std::env::home_dir()`?"
-                .to_string(),
-        )
-    })?;
-    let cache_dir = home.join(".bifrost").join("cache");
-    let cache_file = cache_dir.join(format!("{key}.bin"));
-
-    let binary_string = match read_file(
-        cache_file
-            .to_str()
-            .ok_or_else(|| Error::Generic("failed to convert path to string".to_string()))?,
-    ) {
-        Ok(s) => s,
-        Err(_) => return Ok(None),
+    // expired objects are deleted by the store, and read as `None`
+    let binary_vec = match with_store(|store| store.get(key))? {
+        Some(binary_vec) => binary_vec,
+        None => return Ok(None),
     };
 
-    let binary_vec = decode_hex(&binary_string)
-        .map_err(|e| Error::Generic(format!("failed to decode hex: {e:?}")))?;
-
-    let cache: Cache<T> = bincode::deserialize::<Cache<T>>(&binary_vec)
+    let value: T = bincode::deserialize::<T>(&binary_vec)
         .map_err(|e| Error::Generic(format!("failed to deserialize cache object: {e:?}")))?;
 
-    // check if the cache has expired, if so, delete it and return None
-    if cache.expiry <
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| Error::Generic(format!("failed to get current time: {e:?}")))?
-            .as_secs()
-    {
-        delete_cache(key)?;
-        return Ok(None);
-    }
-
-    Ok(Some(*Box::new(cache.value)))
+    Ok(Some(value))
 }
 
 /// Store a value in the cache, with an optional expiry time \
@@ -288,40 +325,18 @@ where
 /// /// add a value to the cache with an expiry time of 1 day
 /// store_cache("store_cache_key2", "value", Some(60 * 60 * 24));
 /// ```
-#[allow(deprecated)]
 pub fn store_cache<T>(key: &str, value: T, expiry: Option<u64>) -> Result<(), Error>
 where
     T: Serialize, {
-    let home = home_dir().ok_or_else(|| {
-        Error::Generic(
-            "failed to get home directory. does your os support `std::env::home_dir()`?"
-                .to_string(),
-        )
-    })?;
-    let cache_dir = home.join(".bifrost").join("cache");
-    let cache_file = cache_dir.join(format!("{key}.bin"));
-
     // expire in 90 days
-    let expiry = expiry.unwrap_or(
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| Error::Generic(format!("failed to get current time: {e:?}")))?
-            .as_secs() +
-            60 * 60 * 24 * 90,
-    );
-
-    let cache = Cache { value, expiry };
-    let encoded: Vec<u8> = bincode::serialize(&cache)
-        .map_err(|e| Error::Generic(format!("failed to serialize cache object: {e:?}")))?;
-    let binary_string = encode_hex(encoded);
-    write_file(
-        cache_file
-            .to_str()
-            .ok_or_else(|| Error::Generic("failed to convert path to string".to_string()))?,
-        &binary_string,
-    )?;
+    let expiry = match expiry {
+        Some(expiry) => expiry,
+        None => unix_timestamp()? + 60 * 60 * 24 * 90,
+    };
 
-    Ok(())
+    let encoded: Vec<u8> = bincode::serialize(&value)
+        .map_err(|e| Error::Generic(format!("failed to serialize cache object: {e:?}")))?;
+    with_store(|store| store.put(key, encoded, expiry))
 }
 
 /// Takes in an &str and an async function that returns a Result<T, E> where T is ser/de
@@ -354,15 +369,20 @@ where
 }
 
 /// Cache subcommand handler
-#[allow(deprecated)]
 pub fn cache(args: CacheArgs) -> Result<(), Error> {
     match args.sub {
-        Subcommands::Clean(_) => {
-            clear_cache()?;
-            println!("Cache cleared.")
-        }
-        Subcommands::Ls(_) => {
-            let keys = keys("*")?;
+        Subcommands::Clean(args) => match args.namespace {
+            Some(namespace) => {
+                let removed = with_store(|store| store.clear(Some(namespace)))?;
+                println!("Removed {removed} cached objects from the {namespace} namespace.")
+            }
+            None => {
+                clear_cache()?;
+                println!("Cache cleared.")
+            }
+        },
+        Subcommands::Ls(args) => {
+            let keys = with_store(|store| store.keys(args.namespace))?;
             println!("Displaying {} cached objects:", keys.len());
 
             for (i, key) in keys.iter().enumerate() {
@@ -370,56 +390,84 @@ pub fn cache(args: CacheArgs) -> Result<(), Error> {
             }
         }
         Subcommands::Size(_) => {
-            let home = home_dir().ok_or_else(|| {
-                Error::Generic(
-                    "failed to get home directory. does your os support `std::env::home_dir()`?"
-                        .to_string(),
-                )
-            })?;
-            let cache_dir = home.join(".bifrost").join("cache");
-            let mut size = 0;
-
-            for entry in cache_dir
-                .read_dir()
-                .map_err(|e| Error::Generic(format!("failed to read cache directory: {e:?}")))?
-            {
-                let entry = entry
-                    .map_err(|e| Error::Generic(format!("failed to read cache entry: {e:?}")))?;
-                let path = entry.path();
-                let metadata = std::fs::metadata(path)
-                    .map_err(|e| Error::Generic(format!("failed to get metadata: {e:?}")))?;
-                size += metadata.len();
-            }
+            let (objects, size) =
+                with_store(|store| Ok((store.keys(None)?.len(), store.size_on_disk())))?;
 
-            println!("Cached objects: {}", keys("*")?.len());
+            println!("Cached objects: {objects}");
             println!("Cache size: {}", prettify_bytes(size));
         }
+        Subcommands::Stats(_) => {
+            let stats = with_store(|store| store.stats())?;
+
+            println!(
+                "{:<10} {:>8} {:>8} {:>10} {:>10} {:>10} {:>9}",
+                "namespace", "objects", "size", "hits", "misses", "evictions", "hit rate"
+            );
+            for stats in stats {
+                println!(
+                    "{:<10} {:>8} {:>8} {:>10} {:>10} {:>10} {:>9}",
+                    stats.namespace.as_str(),
+                    stats.entries,
+                    prettify_bytes(stats.size),
+                    stats.counters.hits,
+                    stats.counters.misses,
+                    stats.counters.evictions,
+                    stats
+                        .hit_rate()
+                        .map(|rate| format!("{:.1}%", rate * 100.0))
+                        .unwrap_or_else(|| "-".to_string()),
+                );
+            }
+        }
+        Subcommands::Export(args) => {
+            let exported = with_store(|store| store.export(&args.path, args.namespace))?;
+            println!("Exported {exported} cached objects to {}.", args.path.display());
+        }
+        Subcommands::Import(args) => {
+            let imported = with_store(|store| store.import(&args.path, args.namespace))?;
+            println!("Imported {imported} cached objects from {}.", args.path.display());
+        }
+        Subcommands::Prune(args) => {
+            let removed = with_store(|store| store.prune(args.namespace, args.max_size))?;
+            println!("Pruned {removed} cached objects.");
+        }
     }
 
     Ok(())
 }
 
-#[allow(deprecated)]
 #[allow(unused_must_use)]
 #[cfg(test)]
 mod tests {
-    use crate::{delete_cache, exists, keys, read_cache, store_cache};
+    use crate::{cache_dir, delete_cache, exists, keys, read_cache, store_cache};
     use serde::{Deserialize, Serialize};
-    use std::env::home_dir;
+    use std::sync::Once;
+
+    /// Points the cache at a temporary directory, rather than the user's. Every test calls this
+    /// before touching the cache, since the store is opened once and shared between tests.
+    fn temporary_cache() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let dir =
+                std::env::temp_dir().join(format!("heimdall-cache-lib-{}", std::process::id()));
+            std::env::set_var("CACHE_DIR", dir);
+        });
+    }
 
     #[test]
     fn test_store_cache() {
+        temporary_cache();
         store_cache("key", "value".to_string(), None);
 
-        // assert cached file exists
-        let home = home_dir().expect("failed to get home_dir");
-        let cache_dir = home.join(".bifrost").join("cache");
-        let cache_file = cache_dir.join("key.bin");
-        assert!(cache_file.exists());
+        // assert the object was written to the store
+        let cache_dir = cache_dir().expect("failed to get cache_dir");
+        assert!(cache_dir.join("heimdall.db").exists());
+        assert!(exists("key").expect("failed to check if key exists"));
     }
 
     #[test]
     fn test_get_cache() {
+        temporary_cache();
         store_cache("key3", "value".to_string(), None);
         let value = read_cache("key3");
         let value: String = value.expect("failed to get cache").expect("failed to get cache");
@@ -430,6 +478,7 @@ mod tests {
 
     #[test]
     fn test_store_struct() {
+        temporary_cache();
         #[derive(Serialize, Deserialize, Debug)]
         struct TestStruct {
             name: String,
@@ -440,15 +489,15 @@ mod tests {
 
         store_cache("struct", test_struct, None);
 
-        // assert cached file exists
-        let home = home_dir().expect("failed to get home_dir");
-        let cache_dir = home.join(".bifrost").join("cache");
-        let cache_file = cache_dir.join("struct.bin");
-        assert!(cache_file.exists());
+        // assert the object was written to the store
+        let cache_dir = cache_dir().expect("failed to get cache_dir");
+        assert!(cache_dir.join("heimdall.db").exists());
+        assert!(exists("struct").expect("failed to check if key exists"));
     }
 
     #[test]
     fn test_get_struct() {
+        temporary_cache();
         #[derive(Serialize, Deserialize, Debug)]
         struct TestStruct {
             name: String,
@@ -468,6 +517,7 @@ mod tests {
 
     #[test]
     fn test_keys() {
+        temporary_cache();
         store_cache("some_key", "some_value", None);
         store_cache("some_other_key", "some_value", None);
         store_cache("not_a_key", "some_value", None);
@@ -477,6 +527,7 @@ mod tests {
 
     #[test]
     fn test_keys_wildcard() {
+        temporary_cache();
         store_cache("a", "some_value", None);
         store_cache("b", "some_value", None);
         store_cache("c", "some_value", None);
@@ -491,6 +542,7 @@ mod tests {
 
     #[test]
     fn test_exists() {
+        temporary_cache();
         assert!(!exists("does_not_exist").expect("failed to check if key exists"));
         store_cache("does_not_exist", "some_value", None);
         assert!(exists("does_not_exist").expect("failed to check if key exists"));
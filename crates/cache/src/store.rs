@@ -0,0 +1,1007 @@
+//! A single-file, embedded key-value store backing the cache.
+//!
+//! The store is an append-only log of checksummed [`Record`]s. Opening it replays the log into an
+//! in-memory index, and every write and eviction is appended to the log. Reads aren't logged:
+//! hit/miss counters and the access order used for LRU eviction are kept in memory, and persisted
+//! when the log is compacted. Once the log grows to more than twice the size of its live entries,
+//! it is compacted by rewriting the live entries to a new file, which replaces the old one.
+//!
+//! Processes sharing a store serialize their accesses with an exclusive lock on a file beside it,
+//! so a log is never read while it's being written. A record which is incomplete or fails its
+//! checksum, i.e. one torn by a crash, is truncated along with everything after it.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    util::{decode_hex, unix_timestamp},
+};
+
+/// Identifies a file as a heimdall cache store
+const MAGIC: &[u8; 8] = b"HMDLSTOR";
+
+/// The size of the log header: [`MAGIC`], followed by the log's generation
+const HEADER_SIZE: u64 = 16;
+
+/// The size of a record's prefix: the length of its encoding, followed by its checksum
+const RECORD_PREFIX_SIZE: usize = 8;
+
+/// Logs smaller than this are never compacted
+const MIN_COMPACTION_SIZE: u64 = 1024 * 1024;
+
+/// The default bound on the size of the live entries in the store, 512 MB
+pub const DEFAULT_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+/// The namespaces of the cache. A key's namespace is determined by the prefix before its first
+/// `.`, e.g. `function.095ea7b3` belongs to [`Namespace::Selectors`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+pub enum Namespace {
+    /// Resolved function, event and error selectors
+    Selectors,
+    /// Runtime and creation bytecode of contracts
+    Bytecode,
+    /// Labels of addresses
+    Labels,
+    /// Traces of mined transactions
+    Traces,
+    /// Results of analyses such as decompilation, keyed by the hash of the analyzed bytecode
+    Results,
+    /// Everything else, such as chain ids and contract creation blocks
+    General,
+}
+
+impl Namespace {
+    /// All namespaces, in display order
+    pub const ALL: [Namespace; 6] = [
+        Namespace::Selectors,
+        Namespace::Bytecode,
+        Namespace::Labels,
+        Namespace::Traces,
+        Namespace::Results,
+        Namespace::General,
+    ];
+
+    /// Returns the namespace `key` belongs to
+    pub fn of(key: &str) -> Self {
+        match key.split('.').next().unwrap_or_default() {
+            "function" | "event" | "error" | "selector" => Namespace::Selectors,
+            "contract" | "creation_bytecode" | "bytecode" => Namespace::Bytecode,
+            "label" => Namespace::Labels,
+            "trace" => Namespace::Traces,
+            "decompile" | "cfg" => Namespace::Results,
+            _ => Namespace::General,
+        }
+    }
+
+    /// The name of the namespace, as accepted by `--namespace`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Namespace::Selectors => "selectors",
+            Namespace::Bytecode => "bytecode",
+            Namespace::Labels => "labels",
+            Namespace::Traces => "traces",
+            Namespace::Results => "results",
+            Namespace::General => "general",
+        }
+    }
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Hit, miss and eviction counters of a namespace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    /// Reads which found an unexpired value
+    pub hits: u64,
+    /// Reads which found no value, or an expired one
+    pub misses: u64,
+    /// Entries removed to keep the store within its size bound
+    pub evictions: u64,
+}
+
+/// Statistics of a namespace, as reported by [`Store::stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamespaceStats {
+    /// The namespace these statistics describe
+    pub namespace: Namespace,
+    /// The number of live entries
+    pub entries: usize,
+    /// The size of the live entries' keys and values, in bytes
+    pub size: u64,
+    /// The namespace's hit, miss and eviction counters
+    pub counters: Counters,
+}
+
+impl NamespaceStats {
+    /// The fraction of reads which were hits, or `None` if there were no reads
+    pub fn hit_rate(&self) -> Option<f64> {
+        let reads = self.counters.hits + self.counters.misses;
+        (reads > 0).then(|| self.counters.hits as f64 / reads as f64)
+    }
+}
+
+/// A single entry of the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Record {
+    /// Stores a value under a key, replacing any previous value
+    Put { key: String, value: Vec<u8>, expiry: u64 },
+    /// Removes a key
+    Delete { key: String },
+    /// Removes a key to keep the store within its size bound
+    Evict { key: String },
+    /// Counters persisted by compaction, or by a store being flushed
+    Counters { namespace: Namespace, counters: Counters },
+    /// Keys read by a store since it was last flushed, least recently read first
+    Touch { keys: Vec<String> },
+}
+
+/// A live value in the store
+#[derive(Debug, Clone)]
+struct Entry {
+    value: Vec<u8>,
+    expiry: u64,
+    /// When the entry was last written or read, on the store's logical clock
+    last_access: u64,
+}
+
+/// A single-file key-value store with namespaces, statistics and LRU eviction
+#[derive(Debug)]
+pub struct Store {
+    path: PathBuf,
+    max_size: u64,
+    entries: HashMap<String, Entry>,
+    /// Counters replayed from the log
+    counters: HashMap<Namespace, Counters>,
+    /// Hits and misses counted by this store which haven't been persisted yet
+    pending: HashMap<Namespace, Counters>,
+    /// Keys read by this store which haven't been persisted yet, with the clock of their last read
+    touched: HashMap<String, u64>,
+    /// Logical clock, advanced by every record applied and every read
+    clock: u64,
+    /// Total size of the live entries' keys and values
+    size: u64,
+    /// The generation of the log which has been replayed, or 0 if there is no log
+    generation: u64,
+    /// How far into the log has been replayed
+    offset: u64,
+}
+
+impl Store {
+    /// Opens the store at `path`, creating it on the first write. Whenever the live entries grow
+    /// beyond `max_size` bytes, the least recently used entries are evicted.
+    pub fn open(path: impl Into<PathBuf>, max_size: u64) -> Result<Self, Error> {
+        let mut store = Store {
+            path: path.into(),
+            max_size,
+            entries: HashMap::new(),
+            counters: HashMap::new(),
+            pending: HashMap::new(),
+            touched: HashMap::new(),
+            clock: 0,
+            size: 0,
+            generation: 0,
+            offset: 0,
+        };
+        let _lock = store.lock()?;
+        store.refresh()?;
+        drop(_lock);
+
+        Ok(store)
+    }
+
+    /// The path of the store's file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The size of the store's file on disk, in bytes
+    pub fn size_on_disk(&self) -> u64 {
+        std::fs::metadata(&self.path).map(|metadata| metadata.len()).unwrap_or(0)
+    }
+
+    /// Reads the value stored under `key`, counting the read as a hit or miss. Expired values are
+    /// removed and read as misses.
+    pub fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        let now = unix_timestamp()?;
+        self.clock += 1;
+        let counters = self.pending.entry(Namespace::of(key)).or_default();
+        match self.entries.get_mut(key) {
+            Some(entry) if entry.expiry >= now => {
+                counters.hits += 1;
+                entry.last_access = self.clock;
+                self.touched.insert(key.to_string(), self.clock);
+                Ok(Some(entry.value.clone()))
+            }
+            Some(_) => {
+                counters.misses += 1;
+                self.append(vec![Record::Delete { key: key.to_string() }])?;
+                Ok(None)
+            }
+            None => {
+                counters.misses += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Stores `value` under `key` until the unix timestamp `expiry`, evicting the least recently
+    /// used entries if the store outgrows its size bound.
+    pub fn put(&mut self, key: &str, value: Vec<u8>, expiry: u64) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        self.append(vec![Record::Put { key: key.to_string(), value, expiry }])?;
+        self.evict(None, self.max_size)?;
+        self.maybe_compact()
+    }
+
+    /// Removes the value stored under `key`, if any
+    pub fn delete(&mut self, key: &str) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        if self.entries.contains_key(key) {
+            self.append(vec![Record::Delete { key: key.to_string() }])?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether an unexpired value is stored under `key`. Unlike [`Store::get`], this isn't
+    /// counted as a read.
+    pub fn contains(&mut self, key: &str) -> Result<bool, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        let now = unix_timestamp()?;
+
+        Ok(self.entries.get(key).is_some_and(|entry| entry.expiry >= now))
+    }
+
+    /// Lists the keys in the store, or in `namespace`, sorted alphabetically
+    pub fn keys(&mut self, namespace: Option<Namespace>) -> Result<Vec<String>, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        Ok(self.live_keys(namespace))
+    }
+
+    /// Reports the statistics of every namespace
+    pub fn stats(&mut self) -> Result<Vec<NamespaceStats>, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        let counters = self.total_counters();
+        let mut stats = Namespace::ALL
+            .iter()
+            .map(|namespace| NamespaceStats {
+                namespace: *namespace,
+                entries: 0,
+                size: 0,
+                counters: counters.get(namespace).copied().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        for (key, entry) in &self.entries {
+            let namespace = Namespace::of(key);
+            if let Some(stats) = stats.iter_mut().find(|stats| stats.namespace == namespace) {
+                stats.entries += 1;
+                stats.size += entry_size(key, entry);
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Removes every entry in the store, or in `namespace`, returning the number removed.
+    /// Clearing the whole store also resets its statistics.
+    pub fn clear(&mut self, namespace: Option<Namespace>) -> Result<usize, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        let keys = self.live_keys(namespace);
+
+        match namespace {
+            Some(_) => {
+                self.append(
+                    keys.iter().map(|key| Record::Delete { key: key.to_string() }).collect(),
+                )?;
+                self.compact()?;
+            }
+            None => {
+                if self.path.exists() {
+                    std::fs::remove_file(&self.path)?;
+                }
+                self.reset();
+                self.pending.clear();
+                self.touched.clear();
+            }
+        }
+
+        Ok(keys.len())
+    }
+
+    /// Removes expired entries from the store, or from `namespace`, then evicts its least
+    /// recently used entries until it's no larger than `max_size` bytes. Returns the number of
+    /// entries removed.
+    pub fn prune(
+        &mut self,
+        namespace: Option<Namespace>,
+        max_size: Option<u64>,
+    ) -> Result<usize, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        let now = unix_timestamp()?;
+        let expired = self
+            .entries
+            .iter()
+            .filter(|(key, entry)| {
+                entry.expiry < now &&
+                    namespace.is_none_or(|namespace| Namespace::of(key) == namespace)
+            })
+            .map(|(key, _)| Record::Delete { key: key.to_string() })
+            .collect::<Vec<_>>();
+        let mut removed = expired.len();
+        self.append(expired)?;
+
+        removed += self.evict(namespace, max_size.unwrap_or(self.max_size))?;
+        self.compact()?;
+
+        Ok(removed)
+    }
+
+    /// Writes the entries of the store, or of `namespace`, to a new store at `path`, returning
+    /// the number of entries written.
+    pub fn export(&mut self, path: &Path, namespace: Option<Namespace>) -> Result<usize, Error> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        let now = unix_timestamp()?;
+        let records = self
+            .entries_by_access()
+            .into_iter()
+            .filter(|(key, entry)| {
+                entry.expiry >= now &&
+                    namespace.is_none_or(|namespace| Namespace::of(key) == namespace)
+            })
+            .map(|(key, entry)| Record::Put {
+                key: key.to_string(),
+                value: entry.value.clone(),
+                expiry: entry.expiry,
+            })
+            .collect::<Vec<_>>();
+        let exported = records.len();
+        write_log(path, generation()?, &records)?;
+
+        Ok(exported)
+    }
+
+    /// Copies the entries of the store at `path`, or of `namespace`, into this store, returning
+    /// the number of entries imported.
+    pub fn import(&mut self, path: &Path, namespace: Option<Namespace>) -> Result<usize, Error> {
+        if !path.exists() {
+            return Err(Error::Generic(format!("no cache store found at {}", path.display())));
+        }
+        let other = Store::open(path, u64::MAX)?;
+
+        let now = unix_timestamp()?;
+        let records = other
+            .entries_by_access()
+            .into_iter()
+            .filter(|(key, entry)| {
+                entry.expiry >= now &&
+                    namespace.is_none_or(|namespace| Namespace::of(key) == namespace)
+            })
+            .map(|(key, entry)| Record::Put {
+                key: key.to_string(),
+                value: entry.value.clone(),
+                expiry: entry.expiry,
+            })
+            .collect::<Vec<_>>();
+        let imported = records.len();
+
+        let _lock = self.lock()?;
+        self.append(records)?;
+        self.evict(None, self.max_size)?;
+        self.maybe_compact()?;
+
+        Ok(imported)
+    }
+
+    /// Moves the objects of the per-key cache, which stored each object in its own
+    /// `{key}.bin` file of hex-encoded bincode, into the store. Files which can't be read are
+    /// left in place.
+    pub(crate) fn migrate(&mut self, dir: &Path) -> Result<usize, Error> {
+        let Ok(entries) = dir.read_dir() else {
+            return Ok(0);
+        };
+
+        let mut records = Vec::new();
+        let mut migrated = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "bin") {
+                continue;
+            }
+            let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            // the legacy objects are bincode-encoded `(value, expiry)` pairs, and bincode
+            // encodes the expiry as a trailing little-endian u64
+            let Some(mut value) = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| decode_hex(contents.trim()).ok())
+                .filter(|bytes| bytes.len() >= 8)
+            else {
+                continue;
+            };
+            let expiry = value.split_off(value.len() - 8);
+            let expiry = u64::from_le_bytes(expiry.try_into().unwrap_or_default());
+
+            records.push(Record::Put { key: key.to_string(), value, expiry });
+            migrated.push(path);
+        }
+
+        if records.is_empty() {
+            return Ok(0);
+        }
+        let _lock = self.lock()?;
+        self.append(records)?;
+        for path in &migrated {
+            std::fs::remove_file(path).ok();
+        }
+        self.evict(None, self.max_size)?;
+        self.maybe_compact()?;
+
+        Ok(migrated.len())
+    }
+
+    /// Persists the hit and miss counters and the recency of the reads which haven't been
+    /// persisted yet, so other processes see them. Reads aren't logged as they happen, so this
+    /// should be called whenever a unit of work, such as a command, finishes.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.pending.is_empty() && self.touched.is_empty() {
+            return Ok(());
+        }
+
+        let mut records = self
+            .pending
+            .drain()
+            .map(|(namespace, counters)| Record::Counters { namespace, counters })
+            .collect::<Vec<_>>();
+        let mut touched = self.touched.drain().collect::<Vec<_>>();
+        if !touched.is_empty() {
+            touched.sort_by_key(|(_, clock)| *clock);
+            records.push(Record::Touch { keys: touched.into_iter().map(|(key, _)| key).collect() });
+        }
+
+        let _lock = self.lock()?;
+        self.append(records)?;
+        self.maybe_compact()
+    }
+
+    /// Evicts the least recently used entries of the store, or of `namespace`, until it's no
+    /// larger than `max_size` bytes. Returns the number of entries evicted.
+    fn evict(&mut self, namespace: Option<Namespace>, max_size: u64) -> Result<usize, Error> {
+        let candidates = self
+            .entries_by_access()
+            .into_iter()
+            .filter(|(key, _)| namespace.is_none_or(|namespace| Namespace::of(key) == namespace))
+            .map(|(key, entry)| (key.to_string(), entry_size(key, entry)))
+            .collect::<Vec<_>>();
+
+        let mut size = candidates.iter().map(|(_, size)| size).sum::<u64>();
+        let mut evicted = Vec::new();
+        for (key, entry_size) in candidates {
+            if size <= max_size {
+                break;
+            }
+            size -= entry_size;
+            evicted.push(Record::Evict { key });
+        }
+
+        let count = evicted.len();
+        if count > 0 {
+            tracing::debug!("evicting {} cached objects", count);
+            self.append(evicted)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Lists the live keys of the store, or of `namespace`, sorted alphabetically
+    fn live_keys(&self, namespace: Option<Namespace>) -> Vec<String> {
+        let mut keys = self
+            .entries
+            .keys()
+            .filter(|key| namespace.is_none_or(|namespace| Namespace::of(key) == namespace))
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    /// The counters replayed from the log, plus those which haven't been persisted yet
+    fn total_counters(&self) -> HashMap<Namespace, Counters> {
+        let mut total = self.counters.clone();
+        for (namespace, counters) in &self.pending {
+            let counts = total.entry(*namespace).or_default();
+            counts.hits += counters.hits;
+            counts.misses += counters.misses;
+            counts.evictions += counters.evictions;
+        }
+        total
+    }
+
+    /// Takes an exclusive lock on the file beside the store, which is held until the returned
+    /// file is dropped. The log is replaced when compacted, so it can't be locked itself.
+    fn lock(&self) -> Result<File, Error> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// The live entries, least recently used first
+    fn entries_by_access(&self) -> Vec<(&String, &Entry)> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| entry.last_access);
+        entries
+    }
+
+    /// Compacts the log if it's grown to more than twice the size of the live entries
+    fn maybe_compact(&mut self) -> Result<(), Error> {
+        if self.offset > MIN_COMPACTION_SIZE && self.offset > 2 * (self.size + HEADER_SIZE) {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrites the log with only the live entries, in access order, and the counters, including
+    /// those which haven't been persisted yet
+    fn compact(&mut self) -> Result<(), Error> {
+        self.refresh()?;
+
+        let now = unix_timestamp()?;
+        let mut records = self
+            .total_counters()
+            .into_iter()
+            .map(|(namespace, counters)| Record::Counters { namespace, counters })
+            .collect::<Vec<_>>();
+        records.extend(
+            self.entries_by_access().into_iter().filter(|(_, entry)| entry.expiry >= now).map(
+                |(key, entry)| Record::Put {
+                    key: key.to_string(),
+                    value: entry.value.clone(),
+                    expiry: entry.expiry,
+                },
+            ),
+        );
+
+        // write the new log beside the old one, then replace it, so readers never see a partial
+        // log
+        let temporary = self.path.with_extension("tmp");
+        write_log(&temporary, generation()?, &records)?;
+        std::fs::rename(&temporary, &self.path)?;
+
+        self.reset();
+        self.pending.clear();
+        self.touched.clear();
+        self.refresh()
+    }
+
+    /// Appends `records` to the log, then replays them along with anything other processes
+    /// have appended. The store must be locked.
+    fn append(&mut self, records: Vec<Record>) -> Result<(), Error> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        // start a new log if there isn't one
+        self.refresh()?;
+        if self.generation == 0 {
+            write_log(&self.path, generation()?, &[])?;
+        }
+
+        let mut buffer = Vec::new();
+        for record in &records {
+            encode_record(record, &mut buffer)?;
+        }
+        OpenOptions::new().append(true).open(&self.path)?.write_all(&buffer)?;
+
+        self.refresh()
+    }
+
+    /// Replays whatever has been appended to the log since it was last replayed. If the log was
+    /// replaced, e.g. by another process compacting it, it's replayed from the start. The store
+    /// must be locked.
+    fn refresh(&mut self) -> Result<(), Error> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                if self.generation != 0 {
+                    self.reset();
+                }
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut header = [0u8; HEADER_SIZE as usize];
+        if file.read_exact(&mut header).is_err() || &header[..8] != MAGIC {
+            self.reset();
+            return Ok(());
+        }
+        let generation = u64::from_le_bytes(header[8..].try_into().unwrap_or_default());
+        let length = file.metadata()?.len();
+        if generation != self.generation || length < self.offset {
+            self.reset();
+            self.generation = generation;
+            self.offset = HEADER_SIZE;
+        }
+
+        if length == self.offset {
+            return Ok(());
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let mut position = 0;
+        while position < buffer.len() {
+            let Some((record, size)) = decode_record(&buffer[position..]) else {
+                break;
+            };
+            self.apply(record);
+            position += size;
+        }
+        self.offset += position as u64;
+
+        // the log is locked while it's written, so a record which can't be decoded was torn by a
+        // crash. it's truncated, so later appends aren't hidden behind it
+        if position < buffer.len() {
+            tracing::warn!(
+                "truncating corrupt cache store {} at offset {}",
+                self.path.display(),
+                self.offset
+            );
+            OpenOptions::new().write(true).open(&self.path)?.set_len(self.offset)?;
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a record of the log to the in-memory index
+    fn apply(&mut self, record: Record) {
+        self.clock += 1;
+
+        match record {
+            Record::Put { key, value, expiry } => {
+                self.remove(&key);
+                let entry = Entry { value, expiry, last_access: self.clock };
+                self.size += entry_size(&key, &entry);
+                self.entries.insert(key, entry);
+            }
+            Record::Delete { key } => {
+                self.remove(&key);
+            }
+            Record::Evict { key } => {
+                self.counters.entry(Namespace::of(&key)).or_default().evictions += 1;
+                self.remove(&key);
+            }
+            Record::Counters { namespace, counters } => {
+                let total = self.counters.entry(namespace).or_default();
+                total.hits += counters.hits;
+                total.misses += counters.misses;
+                total.evictions += counters.evictions;
+            }
+            Record::Touch { keys } => {
+                for key in keys {
+                    self.clock += 1;
+                    if let Some(entry) = self.entries.get_mut(&key) {
+                        entry.last_access = self.clock;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes `key` from the in-memory index
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry_size(key, &entry);
+        }
+    }
+
+    /// Forgets everything replayed from the log
+    fn reset(&mut self) {
+        self.entries.clear();
+        self.counters.clear();
+        self.size = 0;
+        self.generation = 0;
+        self.offset = 0;
+    }
+}
+
+impl Drop for Store {
+    /// Persists the counters and recency which haven't been persisted yet
+    fn drop(&mut self) {
+        self.flush().ok();
+    }
+}
+
+/// The number of bytes an entry counts towards the store's size bound
+fn entry_size(key: &str, entry: &Entry) -> u64 {
+    (key.len() + entry.value.len()) as u64
+}
+
+/// A new, non-zero log generation
+fn generation() -> Result<u64, Error> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| Error::Generic(format!("failed to get current time: {e:?}")))?
+        .as_nanos();
+
+    Ok((nanos as u64).max(1))
+}
+
+/// Appends the encoding of `record` to `buffer`, prefixed by its length and checksum
+fn encode_record(record: &Record, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    let encoded = bincode::serialize(record)
+        .map_err(|e| Error::Generic(format!("failed to serialize cache record: {e:?}")))?;
+    let size = u32::try_from(encoded.len())
+        .map_err(|_| Error::Generic("cache record is too large".to_string()))?;
+
+    buffer.extend_from_slice(&size.to_le_bytes());
+    buffer.extend_from_slice(&crc32(&encoded).to_le_bytes());
+    buffer.extend_from_slice(&encoded);
+    Ok(())
+}
+
+/// Computes the CRC-32 (IEEE) checksum of `bytes`
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Decodes the record at the start of `bytes`, returning it along with the number of bytes it
+/// spans. Returns `None` if the record is incomplete, fails its checksum or can't be decoded.
+fn decode_record(bytes: &[u8]) -> Option<(Record, usize)> {
+    let prefix = bytes.get(..RECORD_PREFIX_SIZE)?;
+    let size = u32::from_le_bytes(prefix[..4].try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(prefix[4..].try_into().ok()?);
+
+    let encoded = bytes.get(RECORD_PREFIX_SIZE..RECORD_PREFIX_SIZE + size)?;
+    if crc32(encoded) != checksum {
+        return None;
+    }
+    let record = bincode::deserialize::<Record>(encoded).ok()?;
+
+    Some((record, RECORD_PREFIX_SIZE + size))
+}
+
+/// Writes a new log containing `records` to `path`, creating its parent directory if needed
+fn write_log(path: &Path, generation: u64, records: &[Record]) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut buffer = Vec::with_capacity(HEADER_SIZE as usize);
+    buffer.extend_from_slice(MAGIC);
+    buffer.extend_from_slice(&generation.to_le_bytes());
+    for record in records {
+        encode_record(record, &mut buffer)?;
+    }
+    std::fs::write(path, buffer)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opens an empty store in a fresh temporary directory
+    fn temporary_store(name: &str, max_size: u64) -> Store {
+        let dir = std::env::temp_dir()
+            .join(format!("heimdall-cache-{name}-{}", generation().expect("failed to get time")));
+        Store::open(dir.join("store.db"), max_size).expect("failed to open store")
+    }
+
+    fn far_future() -> u64 {
+        unix_timestamp().expect("failed to get time") + 60 * 60
+    }
+
+    #[test]
+    fn test_namespace_of_key() {
+        assert_eq!(Namespace::of("function.095ea7b3"), Namespace::Selectors);
+        assert_eq!(Namespace::of("event.ddf252ad"), Namespace::Selectors);
+        assert_eq!(Namespace::of("contract.1.0x00"), Namespace::Bytecode);
+        assert_eq!(Namespace::of("label.0x00"), Namespace::Labels);
+        assert_eq!(Namespace::of("trace.1.0x00"), Namespace::Traces);
+        assert_eq!(Namespace::of("cfg.00.latest.00"), Namespace::Results);
+        assert_eq!(Namespace::of("chain_id.localhost"), Namespace::General);
+        assert_eq!(Namespace::of("no_namespace"), Namespace::General);
+    }
+
+    #[test]
+    fn test_put_get_persists_across_opens() {
+        let mut store = temporary_store("persist", DEFAULT_MAX_SIZE);
+        store.put("function.aabbccdd", vec![1, 2, 3], far_future()).expect("failed to put");
+        store.put("label.0x00", vec![4], far_future()).expect("failed to put");
+        store.delete("label.0x00").expect("failed to delete");
+
+        let mut reopened =
+            Store::open(store.path().to_path_buf(), DEFAULT_MAX_SIZE).expect("failed to open");
+        assert_eq!(reopened.get("function.aabbccdd").expect("!"), Some(vec![1, 2, 3]));
+        assert_eq!(reopened.get("label.0x00").expect("!"), None);
+
+        // reads aren't logged, but their counters are persisted once the second store is dropped
+        let size = reopened.size_on_disk();
+        reopened.get("function.aabbccdd").expect("failed to get");
+        assert_eq!(reopened.size_on_disk(), size);
+        drop(reopened);
+
+        let stats = store.stats().expect("failed to get stats");
+        let selectors = stats.iter().find(|s| s.namespace == Namespace::Selectors).expect("!");
+        let labels = stats.iter().find(|s| s.namespace == Namespace::Labels).expect("!");
+        assert_eq!((selectors.entries, selectors.counters.hits), (1, 2));
+        assert_eq!((labels.entries, labels.counters.misses), (0, 1));
+    }
+
+    #[test]
+    fn test_expired_values_are_misses() {
+        let mut store = temporary_store("expiry", DEFAULT_MAX_SIZE);
+        store.put("contract.1.0x00", vec![1], 1).expect("failed to put");
+
+        assert!(!store.contains("contract.1.0x00").expect("!"));
+        assert_eq!(store.get("contract.1.0x00").expect("!"), None);
+        assert!(store.keys(None).expect("!").is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut store = temporary_store("evict", 25);
+        store.put("a", vec![0; 9], far_future()).expect("failed to put");
+        store.put("b", vec![0; 9], far_future()).expect("failed to put");
+
+        // reading `a` makes `b` the least recently used entry
+        store.get("a").expect("failed to get");
+        store.put("c", vec![0; 9], far_future()).expect("failed to put");
+
+        assert_eq!(store.keys(None).expect("!"), vec!["a", "c"]);
+        let stats = store.stats().expect("failed to get stats");
+        let general = stats.iter().find(|s| s.namespace == Namespace::General).expect("!");
+        assert_eq!(general.counters.evictions, 1);
+    }
+
+    #[test]
+    fn test_flush_persists_counters_and_recency() {
+        let mut store = temporary_store("flush", 25);
+        store.put("a", vec![0; 9], far_future()).expect("failed to put");
+        store.put("b", vec![0; 9], far_future()).expect("failed to put");
+
+        // another process reads `a` and flushes without being dropped, e.g. from a static
+        let mut other = Store::open(store.path().to_path_buf(), 25).expect("failed to open");
+        other.get("a").expect("failed to get");
+        other.flush().expect("failed to flush");
+
+        // the read made `b` the least recently used entry, and was counted as a hit
+        store.put("c", vec![0; 9], far_future()).expect("failed to put");
+        assert_eq!(store.keys(None).expect("!"), vec!["a", "c"]);
+        let stats = store.stats().expect("failed to get stats");
+        let general = stats.iter().find(|s| s.namespace == Namespace::General).expect("!");
+        assert_eq!(general.counters.hits, 1);
+    }
+
+    #[test]
+    fn test_prune_namespace() {
+        let mut store = temporary_store("prune", DEFAULT_MAX_SIZE);
+        store.put("function.00000001", vec![0; 16], far_future()).expect("failed to put");
+        store.put("function.00000002", vec![0; 16], far_future()).expect("failed to put");
+        store.put("contract.1.0x00", vec![0; 16], far_future()).expect("failed to put");
+
+        assert_eq!(store.prune(Some(Namespace::Selectors), Some(40)).expect("!"), 1);
+        assert_eq!(store.keys(None).expect("!"), vec!["contract.1.0x00", "function.00000002"]);
+    }
+
+    #[test]
+    fn test_compaction_keeps_entries_and_counters() {
+        let mut store = temporary_store("compact", DEFAULT_MAX_SIZE);
+        store.put("function.00000001", vec![1], far_future()).expect("failed to put");
+        store.put("function.00000001", vec![2], far_future()).expect("failed to put");
+        store.get("function.00000001").expect("failed to get");
+        let size = store.size_on_disk();
+
+        store.compact().expect("failed to compact");
+        assert!(store.size_on_disk() < size);
+
+        let mut reopened =
+            Store::open(store.path().to_path_buf(), DEFAULT_MAX_SIZE).expect("failed to open");
+        assert_eq!(reopened.get("function.00000001").expect("!"), Some(vec![2]));
+        let stats = reopened.stats().expect("failed to get stats");
+        assert_eq!(stats[0].counters.hits, 2);
+    }
+
+    #[test]
+    fn test_truncates_torn_records() {
+        let mut store = temporary_store("torn", DEFAULT_MAX_SIZE);
+        store.put("function.00000001", vec![1], far_future()).expect("failed to put");
+
+        // a record torn by a crash, whose checksum doesn't match its contents
+        let mut torn = Vec::new();
+        encode_record(
+            &Record::Put { key: "function.00000002".to_string(), value: vec![2], expiry: 0 },
+            &mut torn,
+        )
+        .expect("failed to encode");
+        torn[RECORD_PREFIX_SIZE] ^= 0xff;
+        torn.truncate(torn.len() - 1);
+        let mut file = OpenOptions::new().append(true).open(store.path()).expect("!");
+        file.write_all(&torn).expect("failed to write");
+
+        let mut reopened =
+            Store::open(store.path().to_path_buf(), DEFAULT_MAX_SIZE).expect("failed to open");
+        reopened.put("function.00000003", vec![3], far_future()).expect("failed to put");
+
+        // later appends aren't hidden behind the torn record
+        assert_eq!(store.keys(None).expect("!"), vec!["function.00000001", "function.00000003"]);
+    }
+
+    #[test]
+    fn test_export_import_namespace() {
+        let mut store = temporary_store("export", DEFAULT_MAX_SIZE);
+        store.put("function.00000001", vec![1], far_future()).expect("failed to put");
+        store.put("label.0x00", vec![2], far_future()).expect("failed to put");
+
+        let exported = store.path().with_file_name("exported.db");
+        assert_eq!(store.export(&exported, Some(Namespace::Selectors)).expect("!"), 1);
+
+        let mut other = temporary_store("import", DEFAULT_MAX_SIZE);
+        assert_eq!(other.import(&exported, None).expect("!"), 1);
+        assert_eq!(other.keys(None).expect("!"), vec!["function.00000001"]);
+    }
+
+    #[test]
+    fn test_migrates_legacy_cache_files() {
+        let mut store = temporary_store("migrate", DEFAULT_MAX_SIZE);
+        let dir = store.path().parent().expect("!").to_path_buf();
+        std::fs::create_dir_all(&dir).expect("failed to create dir");
+
+        // a legacy `Cache { value: 7u8, expiry }` object
+        let mut legacy = vec![7u8];
+        legacy.extend_from_slice(&far_future().to_le_bytes());
+        let hex = legacy.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        std::fs::write(dir.join("chain_id.localhost.bin"), hex).expect("failed to write");
+
+        assert_eq!(store.migrate(&dir).expect("failed to migrate"), 1);
+        assert_eq!(store.get("chain_id.localhost").expect("!"), Some(vec![7]));
+        assert!(!dir.join("chain_id.localhost.bin").exists());
+    }
+}
@@ -1,11 +1,4 @@
-use std::{
-    fmt::Write as FmtWrite,
-    fs::File,
-    io::{Read, Write},
-    num::ParseIntError,
-    path::Path,
-    process::Command,
-};
+use std::{num::ParseIntError, process::Command};
 
 use crate::error::Error;
 
@@ -14,14 +7,6 @@ pub(crate) fn decode_hex(s: &str) -> Result<Vec<u8>, ParseIntError> {
     (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16)).collect()
 }
 
-/// Encode a bytearray into a hex string
-pub(crate) fn encode_hex(s: Vec<u8>) -> String {
-    s.iter().fold(String::new(), |mut acc: String, b| {
-        write!(acc, "{b:02x}").expect("unable to write");
-        acc
-    })
-}
-
 /// Prettify bytes into a human-readable format
 pub(crate) fn prettify_bytes(bytes: u64) -> String {
     if bytes < 1024 {
@@ -38,31 +23,12 @@ pub(crate) fn prettify_bytes(bytes: u64) -> String {
     }
 }
 
-/// Write contents to a file on the disc
-/// If the parent directory does not exist, it will be created
-pub(crate) fn write_file(path_str: &str, contents: &str) -> Result<(), Error> {
-    let path = Path::new(path_str);
-
-    if let Some(prefix) = path.parent() {
-        std::fs::create_dir_all(prefix)?;
-    } else {
-        return Err(Error::IOError(std::io::Error::other("Unable to create directory")));
-    }
-
-    let mut file = File::create(path)?;
-    file.write_all(contents.as_bytes())?;
-
-    Ok(())
-}
-
-/// Read contents from a file on the disc
-/// Returns the contents as a string
-pub(crate) fn read_file(path: &str) -> Result<String, Error> {
-    let path = Path::new(path);
-    let mut file = File::open(path).map_err(|e| Error::IOError(std::io::Error::other(e)))?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    Ok(contents)
+/// The current unix timestamp, in seconds
+pub(crate) fn unix_timestamp() -> Result<u64, Error> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| Error::Generic(format!("failed to get current time: {e:?}")))?
+        .as_secs())
 }
 
 /// Delete a file or directory on the disc
@@ -94,13 +60,6 @@ mod tests {
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_encode_hex() {
-        let bytes = vec![72, 101, 108, 108, 111, 32, 87, 111, 114, 108, 100];
-        let result = encode_hex(bytes);
-        assert_eq!(result, "48656c6c6f20576f726c64");
-    }
-
     #[test]
     fn test_prettify_bytes_less_than_1_kb() {
         let bytes = 500;
@@ -129,40 +88,6 @@ mod tests {
         assert_eq!(result, "4 GB");
     }
 
-    #[test]
-    fn test_write_file_successful() {
-        let path = "/tmp/test.txt";
-        let contents = "Hello, World!";
-        let result = write_file(path, contents);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_write_file_failure() {
-        // Assuming the path is read-only or permission denied
-        let path = "/root/test.txt";
-        let contents = "Hello, World!";
-        let result = write_file(path, contents);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_read_file_successful() {
-        let path = "/tmp/test.txt";
-        let contents = "Hello, World!";
-        write_file(path, contents).expect("unable to write file");
-
-        let result = read_file(path);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_read_file_failure() {
-        let path = "/nonexistent/test.txt";
-        let result = read_file(path);
-        assert!(result.is_err());
-    }
-
     #[test]
     fn test_delete_path_successful() {
         let path = "/tmp/test_dir";
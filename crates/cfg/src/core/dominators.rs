@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use petgraph::{
+    algo::dominators::simple_fast, graph::NodeIndex, visit::Reversed, Direction, Graph,
+};
+
+/// A dominator (or post-dominator) tree over the nodes of a control flow graph.
+///
+/// A node `a` dominates `b` if every path from the entry to `b` passes through `a`, and
+/// post-dominates `b` if every path from `b` to an exit passes through `a`. Nodes which can't
+/// reach (or be reached from) the root aren't part of the tree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DominatorTree {
+    /// The root of the tree, which is `None` for post-dominator trees since they're rooted at a
+    /// virtual exit
+    root: Option<NodeIndex>,
+
+    /// The immediate dominator of each node in the tree, other than the root
+    immediate: HashMap<NodeIndex, NodeIndex>,
+}
+
+impl DominatorTree {
+    /// Builds the dominator tree of `graph`, rooted at `entry`.
+    pub(crate) fn dominators_of<N, E>(graph: &Graph<N, E>, entry: NodeIndex) -> Self {
+        let dominators = simple_fast(graph, entry);
+        let immediate = graph
+            .node_indices()
+            .filter_map(|node| Some((node, dominators.immediate_dominator(node)?)))
+            .collect();
+
+        Self { root: Some(entry), immediate }
+    }
+
+    /// Builds the post-dominator tree of `graph`. Every node without successors for which
+    /// `is_exit` holds is an exit, and the tree is rooted at a virtual node which all of them lead
+    /// to. Nodes without successors which aren't exits, i.e. unresolved jumps, lead nowhere.
+    pub(crate) fn post_dominators_of<N, E>(
+        graph: &Graph<N, E>,
+        is_exit: impl Fn(&N) -> bool,
+    ) -> Self {
+        let mut augmented = graph.map(|_, _| (), |_, _| ());
+        let exit = augmented.add_node(());
+        for node in graph.node_indices() {
+            if graph.neighbors_directed(node, Direction::Outgoing).next().is_none() &&
+                is_exit(&graph[node])
+            {
+                augmented.add_edge(node, exit, ());
+            }
+        }
+
+        let dominators = simple_fast(Reversed(&augmented), exit);
+        let immediate = graph
+            .node_indices()
+            .filter_map(|node| {
+                let dominator = dominators.immediate_dominator(node)?;
+                (dominator != exit).then_some((node, dominator))
+            })
+            .collect();
+
+        Self { root: None, immediate }
+    }
+
+    /// Returns the root of the tree, if it's a node of the graph.
+    pub fn root(&self) -> Option<NodeIndex> {
+        self.root
+    }
+
+    /// Returns the immediate dominator of `node`, which is `None` for the root and for nodes
+    /// outside of the tree.
+    pub fn immediate_dominator(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.immediate.get(&node).copied()
+    }
+
+    /// Returns every dominator of `node`, beginning with `node` itself and ending at the root.
+    pub fn dominators(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        std::iter::successors(Some(node), |node| self.immediate_dominator(*node)).collect()
+    }
+
+    /// Returns true if `a` dominates `b`. Every node dominates itself.
+    pub fn dominates(&self, a: NodeIndex, b: NodeIndex) -> bool {
+        self.dominators(b).contains(&a)
+    }
+
+    /// Returns the nodes immediately dominated by `node`, i.e. its children in the tree.
+    pub fn children(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        let mut children = self
+            .immediate
+            .iter()
+            .filter(|(_, dominator)| **dominator == node)
+            .map(|(child, _)| *child)
+            .collect::<Vec<_>>();
+        children.sort();
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a diamond, `0 -> {1, 2} -> 3`, followed by a branch to one of two exits, `3 -> {4, 5}`
+    fn diamond() -> (Graph<(), ()>, Vec<NodeIndex>) {
+        let mut graph = Graph::new();
+        let nodes = (0..6).map(|_| graph.add_node(())).collect::<Vec<_>>();
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (3, 5)] {
+            graph.add_edge(nodes[from], nodes[to], ());
+        }
+
+        (graph, nodes)
+    }
+
+    #[test]
+    fn test_dominators() {
+        let (graph, nodes) = diamond();
+        let tree = DominatorTree::dominators_of(&graph, nodes[0]);
+
+        assert_eq!(tree.root(), Some(nodes[0]));
+        assert_eq!(tree.immediate_dominator(nodes[3]), Some(nodes[0]));
+        assert_eq!(tree.immediate_dominator(nodes[4]), Some(nodes[3]));
+        assert_eq!(tree.dominators(nodes[5]), vec![nodes[5], nodes[3], nodes[0]]);
+        assert!(!tree.dominates(nodes[1], nodes[3]));
+        assert_eq!(tree.children(nodes[0]), vec![nodes[1], nodes[2], nodes[3]]);
+    }
+
+    #[test]
+    fn test_post_dominators() {
+        let (graph, nodes) = diamond();
+        let tree = DominatorTree::post_dominators_of(&graph, |_| true);
+
+        assert_eq!(tree.root(), None);
+        assert_eq!(tree.immediate_dominator(nodes[1]), Some(nodes[3]));
+        assert_eq!(tree.immediate_dominator(nodes[0]), Some(nodes[3]));
+
+        // neither exit post-dominates the branch which leads to both
+        assert_eq!(tree.immediate_dominator(nodes[3]), None);
+        assert_eq!(tree.immediate_dominator(nodes[4]), None);
+    }
+
+    #[test]
+    fn test_post_dominators_skip_unresolved_jumps() {
+        // `0 -> {1, 2}`, where 1 halts and 2 ends in a jump which wasn't resolved
+        let mut graph = Graph::new();
+        let nodes = [true, true, false].map(|exit| graph.add_node(exit));
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[0], nodes[2], ());
+
+        let tree = DominatorTree::post_dominators_of(&graph, |exit| *exit);
+        assert_eq!(tree.immediate_dominator(nodes[0]), Some(nodes[1]));
+        assert_eq!(tree.immediate_dominator(nodes[2]), None);
+    }
+}
@@ -0,0 +1,458 @@
+//! Renderers for [`CfgResult`] in each [`CfgFormat`] other than DOT.
+
+use std::collections::BTreeMap;
+
+use heimdall_common::utils::strings::encode_hex;
+use heimdall_vm::{core::opcodes::opcode_name, ext::blocks::BlockExit};
+use petgraph::{
+    graph::NodeIndex,
+    visit::{Bfs, EdgeRef},
+};
+use serde_json::{json, Value};
+
+use crate::{
+    core::{graph::CfgEdge, CfgResult},
+    interfaces::CfgFormat,
+};
+
+/// The width of a character in the SVG renderer's monospace font, in pixels.
+const SVG_CHAR_WIDTH: usize = 7;
+
+/// The height of a line of text in the SVG renderer, in pixels.
+const SVG_LINE_HEIGHT: usize = 14;
+
+/// The padding inside of each block, and the gap between blocks, in pixels.
+const SVG_PADDING: usize = 8;
+const SVG_GAP: usize = 40;
+
+impl CfgResult {
+    /// Returns the control flow graph in the given format.
+    pub fn render(&self, format: CfgFormat, color_edges: bool) -> String {
+        match format {
+            CfgFormat::Dot => self.as_dot(color_edges),
+            CfgFormat::Json => self.as_json(),
+            CfgFormat::Graphml => self.as_graphml(),
+            CfgFormat::Mermaid => self.as_mermaid(color_edges),
+            CfgFormat::Svg => self.as_svg(color_edges),
+        }
+    }
+
+    /// Returns the control flow graph as a JSON document, with a node per basic block and an
+    /// edge per jump or fallthrough. Edges of conditional jumps carry the condition under which
+    /// they're followed, if symbolic execution reached the jump.
+    pub fn as_json(&self) -> String {
+        let nodes = self
+            .graph
+            .node_indices()
+            .map(|index| {
+                let node = &self.graph[index];
+                json!({
+                    "id": index.index(),
+                    "start": node.block.start,
+                    "end": node.block.end,
+                    "exit": exit_name(node.block.exit),
+                    "reached": node.reached,
+                    "instructions": node.block.instructions.iter().map(|instruction| json!({
+                        "offset": instruction.offset,
+                        "opcode": instruction.opcode,
+                        "name": opcode_name(instruction.opcode),
+                        "immediate": (!instruction.immediate.is_empty())
+                            .then(|| encode_hex(&instruction.immediate)),
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|edge| {
+                json!({
+                    "source": edge.source().index(),
+                    "target": edge.target().index(),
+                    "kind": edge_name(*edge.weight()),
+                    "condition": self.edge_condition(edge.source(), *edge.weight()),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let functions = self
+            .functions
+            .iter()
+            .map(|(selector, function)| {
+                (
+                    selector.clone(),
+                    json!({
+                        "entry_point": function.entry_point,
+                        "entry": function.entry.index(),
+                        "nodes": function.nodes.iter().map(|node| node.index()).collect::<Vec<_>>(),
+                    }),
+                )
+            })
+            .collect::<serde_json::Map<String, Value>>();
+
+        let document = json!({
+            "entry": self.entry.map(|entry| entry.index()),
+            "nodes": nodes,
+            "edges": edges,
+            "functions": functions,
+        });
+
+        serde_json::to_string_pretty(&document).unwrap_or_default()
+    }
+
+    /// Returns the control flow graph as a GraphML document, which can be imported into tools
+    /// such as Gephi and yEd.
+    pub fn as_graphml(&self) -> String {
+        let mut output = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"start\" for=\"node\" attr.name=\"start\" attr.type=\"int\"/>\n",
+            "  <key id=\"end\" for=\"node\" attr.name=\"end\" attr.type=\"int\"/>\n",
+            "  <key id=\"reached\" for=\"node\" attr.name=\"reached\" attr.type=\"boolean\"/>\n",
+            "  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"condition\" for=\"edge\" attr.name=\"condition\" attr.type=\"string\"/>\n",
+            "  <graph id=\"cfg\" edgedefault=\"directed\">\n",
+        ));
+
+        for index in self.graph.node_indices() {
+            let node = &self.graph[index];
+            output.push_str(&format!(
+                concat!(
+                    "    <node id=\"n{}\">\n",
+                    "      <data key=\"label\">{}</data>\n",
+                    "      <data key=\"start\">{}</data>\n",
+                    "      <data key=\"end\">{}</data>\n",
+                    "      <data key=\"reached\">{}</data>\n",
+                    "    </node>\n",
+                ),
+                index.index(),
+                escape_xml(&node.to_string()),
+                node.block.start,
+                node.block.end,
+                node.reached
+            ));
+        }
+
+        for edge in self.graph.edge_references() {
+            output.push_str(&format!(
+                "    <edge source=\"n{}\" target=\"n{}\">\n      <data key=\"kind\">{}</data>\n",
+                edge.source().index(),
+                edge.target().index(),
+                edge_name(*edge.weight())
+            ));
+            if let Some(condition) = self.edge_condition(edge.source(), *edge.weight()) {
+                output.push_str(&format!(
+                    "      <data key=\"condition\">{}</data>\n",
+                    escape_xml(&condition)
+                ));
+            }
+            output.push_str("    </edge>\n");
+        }
+
+        output.push_str("  </graph>\n</graphml>\n");
+        output
+    }
+
+    /// Returns the control flow graph as a Mermaid flowchart, which can be embedded in markdown.
+    pub fn as_mermaid(&self, color_edges: bool) -> String {
+        let mut output = String::from("flowchart TD\n");
+
+        for index in self.graph.node_indices() {
+            let label = self.graph[index]
+                .to_string()
+                .lines()
+                .map(|line| line.trim_end().replace('"', "#quot;"))
+                .collect::<Vec<_>>()
+                .join("<br/>");
+            output.push_str(&format!("    n{}[\"{}\"]\n", index.index(), label));
+        }
+
+        let mut link_styles = Vec::new();
+        for (link, edge) in self.graph.edge_references().enumerate() {
+            let label = match edge.weight() {
+                CfgEdge::Unconditional => String::new(),
+                kind => format!("|{kind}|"),
+            };
+            output.push_str(&format!(
+                "    n{} -->{} n{}\n",
+                edge.source().index(),
+                label,
+                edge.target().index()
+            ));
+
+            if let Some(color) = edge_color(*edge.weight()).filter(|_| color_edges) {
+                link_styles.push(format!("    linkStyle {link} stroke:{color}\n"));
+            }
+        }
+
+        output.extend(link_styles);
+        output
+    }
+
+    /// Returns the control flow graph as a self-contained SVG image, which doesn't require
+    /// graphviz to render.
+    ///
+    /// Blocks are laid out in layers by their distance from the entry, with blocks which can't
+    /// be reached from it placed in the final layer.
+    pub fn as_svg(&self, color_edges: bool) -> String {
+        // assign each node to a layer, and find the size of each node's box
+        let mut layers: BTreeMap<usize, Vec<NodeIndex>> = BTreeMap::new();
+        let mut depths = vec![None; self.graph.node_count()];
+        if let Some(entry) = self.entry {
+            depths[entry.index()] = Some(0);
+            let mut bfs = Bfs::new(&self.graph, entry);
+            while let Some(node) = bfs.next(&self.graph) {
+                let depth = depths[node.index()].unwrap_or_default();
+                for neighbor in self.graph.neighbors(node) {
+                    depths[neighbor.index()].get_or_insert(depth + 1);
+                }
+            }
+        }
+        let unreached_layer = depths.iter().flatten().max().map_or(0, |depth| depth + 1);
+        for node in self.graph.node_indices() {
+            layers.entry(depths[node.index()].unwrap_or(unreached_layer)).or_default().push(node);
+        }
+
+        let labels = self
+            .graph
+            .node_indices()
+            .map(|node| {
+                self.graph[node]
+                    .to_string()
+                    .lines()
+                    .map(|line| line.trim_end().to_string())
+                    .collect()
+            })
+            .collect::<Vec<Vec<String>>>();
+        let sizes = labels
+            .iter()
+            .map(|lines| {
+                let width = lines.iter().map(|line| line.len()).max().unwrap_or_default();
+                (
+                    width * SVG_CHAR_WIDTH + 2 * SVG_PADDING,
+                    lines.len() * SVG_LINE_HEIGHT + 2 * SVG_PADDING,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // position each node, with layers stacked vertically and nodes side by side
+        let mut positions = vec![(0, 0); self.graph.node_count()];
+        let (mut y, mut total_width) = (SVG_GAP, 0);
+        for nodes in layers.values() {
+            let mut x = SVG_GAP;
+            let mut layer_height = 0;
+            for node in nodes {
+                let (width, height) = sizes[node.index()];
+                positions[node.index()] = (x, y);
+                x += width + SVG_GAP;
+                layer_height = layer_height.max(height);
+            }
+
+            total_width = total_width.max(x);
+            y += layer_height + SVG_GAP;
+        }
+
+        let mut output = format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" ",
+                "viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"12\">\n",
+                "  <defs>\n",
+                "    <marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" ",
+                "markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\">\n",
+                "      <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"context-stroke\"/>\n",
+                "    </marker>\n",
+                "  </defs>\n",
+                "  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            ),
+            width = total_width,
+            height = y
+        );
+
+        // edges leave the bottom of their source and enter the top of their target
+        for edge in self.graph.edge_references() {
+            let ((x1, y1), (w1, h1)) =
+                (positions[edge.source().index()], sizes[edge.source().index()]);
+            let ((x2, y2), (w2, _)) =
+                (positions[edge.target().index()], sizes[edge.target().index()]);
+            let color = edge_color(*edge.weight()).filter(|_| color_edges).unwrap_or("black");
+
+            output.push_str(&format!(
+                concat!(
+                    "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" ",
+                    "marker-end=\"url(#arrow)\"/>\n"
+                ),
+                x1 + w1 / 2,
+                y1 + h1,
+                x2 + w2 / 2,
+                y2,
+                color
+            ));
+        }
+
+        for node in self.graph.node_indices() {
+            let ((x, y), (width, height)) = (positions[node.index()], sizes[node.index()]);
+            output.push_str(&format!(
+                concat!(
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" ",
+                    "fill=\"{}\" stroke=\"black\"/>\n"
+                ),
+                x,
+                y,
+                width,
+                height,
+                if self.graph[node].reached { "white" } else { "#eeeeee" }
+            ));
+
+            output.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\">\n",
+                x + SVG_PADDING,
+                y + SVG_PADDING
+            ));
+            for line in &labels[node.index()] {
+                output.push_str(&format!(
+                    "    <tspan x=\"{}\" dy=\"{}\">{}</tspan>\n",
+                    x + SVG_PADDING,
+                    SVG_LINE_HEIGHT,
+                    escape_xml(line)
+                ));
+            }
+            output.push_str("  </text>\n");
+        }
+
+        output.push_str("</svg>\n");
+        output
+    }
+
+    /// Returns the condition under which `edge` is followed out of `source`, if it's an edge of a
+    /// conditional jump which symbolic execution reached.
+    fn edge_condition(&self, source: NodeIndex, edge: CfgEdge) -> Option<String> {
+        let condition = self.graph[source].condition.as_ref()?;
+        match edge {
+            CfgEdge::Taken => Some(condition.clone()),
+            CfgEdge::NotTaken => Some(format!("!({condition})")),
+            CfgEdge::Unconditional => None,
+        }
+    }
+}
+
+fn exit_name(exit: BlockExit) -> &'static str {
+    match exit {
+        BlockExit::Fallthrough => "fallthrough",
+        BlockExit::Jump => "jump",
+        BlockExit::Branch => "branch",
+        BlockExit::Halt => "halt",
+    }
+}
+
+fn edge_name(edge: CfgEdge) -> &'static str {
+    match edge {
+        CfgEdge::Taken => "taken",
+        CfgEdge::NotTaken => "not_taken",
+        CfgEdge::Unconditional => "unconditional",
+    }
+}
+
+fn edge_color(edge: CfgEdge) -> Option<&'static str> {
+    match edge {
+        CfgEdge::Taken => Some("green"),
+        CfgEdge::NotTaken => Some("red"),
+        CfgEdge::Unconditional => None,
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use heimdall_common::utils::strings::decode_hex;
+
+    use super::*;
+    use crate::core::{
+        dominators::DominatorTree,
+        graph::{build_cfg, CfgNode},
+        FunctionCfg,
+    };
+
+    /// CALLDATASIZE, PUSH1 0x07, JUMPI, PUSH0, PUSH0, REVERT, JUMPDEST, STOP
+    fn result() -> CfgResult {
+        let bytecode = decode_hex("366007575f5ffd5b00").expect("failed to decode bytecode");
+        let mut contract_cfg = build_cfg(&bytecode).expect("failed to build cfg");
+        let entry = contract_cfg.entry().expect("no entry");
+        contract_cfg.graph[entry].condition = Some(String::from("msg.data.length > 0"));
+
+        CfgResult {
+            dominators: DominatorTree::dominators_of(&contract_cfg.graph, entry),
+            post_dominators: DominatorTree::post_dominators_of(
+                &contract_cfg.graph,
+                CfgNode::is_exit,
+            ),
+            functions: BTreeMap::from([(
+                String::from("00000000"),
+                FunctionCfg { entry_point: 0, entry, nodes: BTreeSet::from([entry]) },
+            )]),
+            graph: contract_cfg.graph,
+            entry: Some(entry),
+            deployment: None,
+        }
+    }
+
+    #[test]
+    fn test_as_json() {
+        let json: Value = serde_json::from_str(&result().as_json()).expect("invalid json");
+
+        assert_eq!(json["nodes"].as_array().map(Vec::len), Some(3));
+        assert_eq!(json["nodes"][0]["exit"], "branch");
+        assert_eq!(json["nodes"][0]["instructions"][1]["name"], "PUSH1");
+        assert_eq!(json["nodes"][0]["instructions"][1]["immediate"], "07");
+
+        let edges = json["edges"].as_array().expect("no edges");
+        assert!(edges
+            .iter()
+            .any(|edge| edge["kind"] == "taken" && edge["condition"] == "msg.data.length > 0"));
+        assert!(edges.iter().any(
+            |edge| edge["kind"] == "not_taken" && edge["condition"] == "!(msg.data.length > 0)"
+        ));
+        assert_eq!(json["functions"]["00000000"]["entry"], 0);
+    }
+
+    #[test]
+    fn test_as_graphml() {
+        let graphml = result().as_graphml();
+
+        assert!(graphml.starts_with("<?xml"));
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+        assert!(graphml.contains("<data key=\"condition\">!(msg.data.length &gt; 0)</data>"));
+    }
+
+    #[test]
+    fn test_as_mermaid() {
+        let mermaid = result().as_mermaid(true);
+
+        assert!(mermaid.starts_with("flowchart TD\n"));
+        assert!(mermaid.contains("n0 -->|true| n2"));
+        assert!(mermaid.contains("n0 -->|false| n1"));
+        assert!(mermaid.contains("linkStyle"));
+    }
+
+    #[test]
+    fn test_as_svg() {
+        let svg = result().as_svg(true);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<line ").count(), 2);
+        assert!(svg.contains("stroke=\"green\""));
+        assert!(svg.contains("JUMPDEST"));
+    }
+}
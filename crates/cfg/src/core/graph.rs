@@ -1,88 +1,289 @@
+use std::{collections::BTreeMap, fmt};
+
 use alloy::primitives::U256;
-use eyre::{OptionExt, Result};
+use eyre::{eyre, Result};
 use heimdall_common::utils::strings::encode_hex_reduced;
 use heimdall_vm::{
-    core::opcodes::{opcode_name, JUMPDEST},
-    ext::exec::VMTrace,
+    core::{
+        eof::EofContainer,
+        opcodes::{opcode_name, WrappedOpcode, JUMP, JUMPDEST, JUMPI, RJUMPI},
+    },
+    ext::{
+        blocks::{find_basic_blocks, BasicBlock, BlockExit},
+        exec::VMTrace,
+        layout::BytecodeLayout,
+    },
 };
-use petgraph::{matrix_graph::NodeIndex, Graph};
-use std::collections::HashSet;
-
-/// convert a symbolic execution [`VMTrace`] into a [`Graph`] of blocks, illustrating the
-/// control-flow graph found by the symbolic execution engine.
-pub(crate) fn build_cfg(
-    vm_trace: &VMTrace,
-    contract_cfg: &mut Graph<String, String>,
-    parent_node: Option<NodeIndex<u32>>,
-    jump_taken: bool,
-    seen_nodes: &mut HashSet<String>,
-) -> Result<()> {
-    let mut cfg_node: String = String::new();
-    let mut parent_node = parent_node;
-
-    // add the current operations to the cfg
-    for operation in &vm_trace.operations {
-        let opcode_name = opcode_name(operation.last_instruction.opcode);
-
-        let opcode_offset = operation.last_instruction.instruction - 1; // start from 0x00
-
-        let assembly = format!(
-            "{} {} {}",
-            encode_hex_reduced(U256::from(opcode_offset)),
-            opcode_name,
-            if opcode_name.contains("PUSH") {
-                encode_hex_reduced(
-                    *operation
-                        .last_instruction
-                        .outputs
-                        .first()
-                        .ok_or_eyre("failed to get output for PUSH instruction")?,
+use petgraph::{graph::NodeIndex, visit::EdgeRef, Graph};
+use serde::{Deserialize, Serialize};
+
+/// A node of the control flow graph, which is a single basic block of the contract's bytecode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CfgNode {
+    /// The basic block this node represents
+    pub block: BasicBlock,
+
+    /// Whether symbolic execution reached this block. Blocks which weren't reached are still
+    /// part of the graph, since symbolic execution doesn't explore every path
+    pub reached: bool,
+
+    /// The condition of the block's conditional jump, if symbolic execution reached it
+    pub condition: Option<String>,
+}
+
+impl CfgNode {
+    /// Returns true if execution can end at this block. Blocks ending in a jump are never exits,
+    /// even if symbolic execution didn't resolve where they jump to.
+    pub(crate) fn is_exit(&self) -> bool {
+        self.block.exit != BlockExit::Jump
+    }
+}
+
+impl fmt::Display for CfgNode {
+    /// Formats the block as assembly, with one instruction per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instruction in &self.block.instructions {
+            writeln!(
+                f,
+                "{} {} {}",
+                encode_hex_reduced(U256::from(instruction.offset)),
+                opcode_name(instruction.opcode),
+                instruction.push_value().map(encode_hex_reduced).unwrap_or_default()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An edge of the control flow graph, describing how control passes between two blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CfgEdge {
+    /// The condition of a conditional jump holds, so the jump is taken
+    Taken,
+
+    /// The condition of a conditional jump doesn't hold, so execution falls through
+    NotTaken,
+
+    /// An unconditional jump, or execution falling through into a jump target
+    Unconditional,
+}
+
+impl fmt::Display for CfgEdge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfgEdge::Taken => write!(f, "true"),
+            CfgEdge::NotTaken => write!(f, "false"),
+            CfgEdge::Unconditional => write!(f, ""),
+        }
+    }
+}
+
+/// Everything symbolic execution adds to a contract's control flow graph: the blocks it reached,
+/// the conditions of their conditional jumps, and every edge of the resolved graph. Applying it
+/// to the graph built from the same bytecode reproduces the resolved graph, so it's what's cached
+/// in place of the symbolic execution trace.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ResolvedFlow {
+    /// The offsets of the blocks which were reached
+    pub reached: Vec<usize>,
+
+    /// The condition of each reached block's conditional jump, by the block's offset
+    pub conditions: Vec<(usize, String)>,
+
+    /// The edges of the graph as offsets of the blocks they connect, in the order they were added
+    pub edges: Vec<(usize, usize, CfgEdge)>,
+}
+
+/// The control flow graph of a contract, along with the node of each block by its offset.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ContractGraph {
+    pub graph: Graph<CfgNode, CfgEdge>,
+    nodes: BTreeMap<usize, NodeIndex>,
+}
+
+impl ContractGraph {
+    /// Returns the node of the block containing the instruction at `offset`.
+    pub(crate) fn node_containing(&self, offset: usize) -> Option<NodeIndex> {
+        self.nodes
+            .range(..=offset)
+            .next_back()
+            .map(|(_, node)| *node)
+            .filter(|node| self.graph[*node].block.contains(offset))
+    }
+
+    /// Returns the node of the first block, which is where execution begins.
+    pub(crate) fn entry(&self) -> Option<NodeIndex> {
+        self.nodes.values().next().copied()
+    }
+
+    /// Returns the node of the block beginning at `offset`.
+    pub(crate) fn node_at(&self, offset: usize) -> Option<NodeIndex> {
+        self.nodes.get(&offset).copied()
+    }
+
+    /// Adds an edge from the block containing the instruction at `from` to the block beginning at
+    /// `to`, if both exist.
+    fn add_edge(&mut self, from: usize, to: usize, edge: CfgEdge) {
+        if let (Some(from), Some(to)) = (self.node_containing(from), self.node_at(to)) {
+            self.graph.update_edge(from, to, edge);
+        }
+    }
+
+    /// Records the condition of the conditional jump at `offset`, as it was first reached.
+    fn record_condition(&mut self, offset: usize, condition: &WrappedOpcode) {
+        if let Some(node) = self.node_containing(offset) {
+            self.graph[node].condition.get_or_insert_with(|| condition.solidify());
+        }
+    }
+
+    /// Adds the jumps taken during symbolic execution, which includes jumps whose targets are
+    /// computed rather than pushed, and marks every block executed by `vm_trace` as reached.
+    pub(crate) fn resolve_jumps(&mut self, vm_trace: &VMTrace) {
+        for operation in &vm_trace.operations {
+            let instruction = &operation.last_instruction;
+            let offset = (instruction.instruction - 1) as usize;
+            if let Some(node) = self.node_containing(offset) {
+                self.graph[node].reached = true;
+            }
+
+            match instruction.opcode {
+                JUMPI => self.record_condition(offset, &instruction.input_operations[1]),
+                RJUMPI => self.record_condition(offset, &instruction.input_operations[0]),
+                _ => {}
+            }
+
+            let edge = match instruction.opcode {
+                JUMP => CfgEdge::Unconditional,
+                JUMPI => CfgEdge::Taken,
+                _ => continue,
+            };
+
+            // only jumps which land on a JUMPDEST are valid
+            if let Some(target) =
+                instruction.inputs.first().and_then(|target| usize::try_from(*target).ok()).filter(
+                    |target| {
+                        self.node_at(*target).is_some_and(|node| {
+                            self.graph[node].block.instructions[0].opcode == JUMPDEST
+                        })
+                    },
                 )
-            } else {
-                String::from("")
+            {
+                self.add_edge(offset, target, edge);
             }
-        );
+        }
 
-        cfg_node.push_str(&format!("{}\n", &assembly));
+        for child in &vm_trace.children {
+            self.resolve_jumps(child);
+        }
     }
 
-    // check if this node has been seen before
-    if seen_nodes.contains(&cfg_node) {
-        return Ok(());
+    /// Returns what symbolic execution added to the graph, see [`ResolvedFlow`].
+    pub(crate) fn resolved_flow(&self) -> ResolvedFlow {
+        let graph = &self.graph;
+
+        ResolvedFlow {
+            reached: graph
+                .node_weights()
+                .filter(|node| node.reached)
+                .map(|node| node.block.start)
+                .collect(),
+            conditions: graph
+                .node_weights()
+                .filter_map(|node| Some((node.block.start, node.condition.clone()?)))
+                .collect(),
+            edges: graph
+                .edge_references()
+                .map(|edge| {
+                    (
+                        graph[edge.source()].block.start,
+                        graph[edge.target()].block.start,
+                        *edge.weight(),
+                    )
+                })
+                .collect(),
+        }
     }
-    seen_nodes.insert(cfg_node.clone());
 
-    // add the node to the graph
-    let node_index = contract_cfg.add_node(cfg_node);
-    if let Some(parent_node) = parent_node {
-        contract_cfg.update_edge(parent_node, node_index, jump_taken.to_string());
+    /// Applies a [`ResolvedFlow`] taken from the graph of the same bytecode, in place of
+    /// resolving jumps from a symbolic execution trace.
+    pub(crate) fn apply_flow(&mut self, flow: &ResolvedFlow) {
+        for start in &flow.reached {
+            if let Some(node) = self.node_at(*start) {
+                self.graph[node].reached = true;
+            }
+        }
+        for (start, condition) in &flow.conditions {
+            if let Some(node) = self.node_at(*start) {
+                self.graph[node].condition = Some(condition.clone());
+            }
+        }
+
+        // edges which already exist are only updated, so the edges keep their order
+        for (from, to, edge) in &flow.edges {
+            self.add_edge(*from, *to, *edge);
+        }
+    }
+}
+
+/// Builds the basic-block control flow graph of `bytecode`. Every block is included whether or
+/// not it's reachable, connected by fallthroughs and the jumps whose targets are known statically.
+///
+/// The code sections of EOF containers are split into blocks individually, while legacy bytecode
+/// is split up to the end of its code (see [`BytecodeLayout`]).
+pub(crate) fn build_cfg(bytecode: &[u8]) -> Result<ContractGraph> {
+    let blocks = if EofContainer::is_eof(bytecode) {
+        let container = EofContainer::parse(bytecode)
+            .map_err(|e| eyre!("parsing EOF container failed: {}", e))?;
+        container
+            .code_sections
+            .iter()
+            .flat_map(|code| find_basic_blocks(&container.raw, code.clone(), true))
+            .collect::<Vec<_>>()
+    } else {
+        // metadata and data appended to the code aren't executable, so they aren't blocks
+        find_basic_blocks(bytecode, BytecodeLayout::analyze(bytecode).code, false)
+    };
+
+    let mut contract_cfg = ContractGraph::default();
+    for block in blocks {
+        let start = block.start;
+        let node = contract_cfg.graph.add_node(CfgNode { block, reached: false, condition: None });
+        contract_cfg.nodes.insert(start, node);
     }
-    parent_node = Some(node_index);
 
-    // recurse into the children of the VMTrace map
-    for child in vm_trace.children.iter() {
-        build_cfg(
-            child,
-            contract_cfg,
-            parent_node,
-            child
-                .operations
-                .first()
-                .ok_or_eyre("failed to get first operation")?
-                .last_instruction
-                .opcode ==
-                JUMPDEST,
-            seen_nodes,
-        )?;
+    let edges = contract_cfg
+        .graph
+        .node_weights()
+        .flat_map(|node| {
+            let block = &node.block;
+            let (jump, fallthrough) = match block.exit {
+                BlockExit::Branch => (CfgEdge::Taken, CfgEdge::NotTaken),
+                _ => (CfgEdge::Unconditional, CfgEdge::Unconditional),
+            };
+
+            block
+                .static_targets
+                .iter()
+                .map(move |target| (block.start, *target, jump))
+                .chain(block.fallthrough().map(|end| (block.start, end, fallthrough)))
+        })
+        .collect::<Vec<_>>();
+    for (from, to, edge) in edges {
+        contract_cfg.add_edge(from, to, edge);
     }
 
-    Ok(())
+    Ok(contract_cfg)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{cfg, CfgArgsBuilder};
+    use alloy::primitives::Address;
+    use heimdall_common::utils::strings::decode_hex;
+    use heimdall_vm::core::vm::VM;
+    use std::time::{Duration, Instant};
     use tokio::test;
 
     #[test]
@@ -95,6 +296,121 @@ mod tests {
 
         println!("Contract Cfg: {:#?}", result);
 
+        // both functions are found, and begin at the dispatcher's jump targets
+        assert_eq!(result.functions.len(), 2);
+        for function in result.functions.values() {
+            assert_eq!(result.graph[function.entry].block.start as u128, function.entry_point);
+            assert!(function.nodes.contains(&function.entry));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_build_cfg_eof() -> Result<(), Box<dyn std::error::Error>> {
+        // CALLDATASIZE, RJUMPI +1, STOP, PUSH0, PUSH0, REVERT
+        let args = CfgArgsBuilder::new()
+            .target("0xef00010100040200010008ff0000000080000236e10001005f5ffd".to_string())
+            .build()?;
+
+        let result = cfg(args).await?;
+
+        // the entry block branches to the STOP and REVERT blocks
+        assert_eq!(result.graph.node_count(), 3);
+        assert_eq!(result.graph.edge_count(), 2);
+        assert!(result.as_dot(true).contains("RJUMPI"));
+        assert!(result.graph.edge_weights().any(|edge| *edge == CfgEdge::Taken));
+        assert!(result.graph.edge_weights().any(|edge| *edge == CfgEdge::NotTaken));
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_build_cfg_includes_unreached_blocks() -> Result<(), Box<dyn std::error::Error>> {
+        // PUSH1 0x05, JUMP, PUSH0, STOP, JUMPDEST, STOP: the PUSH0, STOP block is never reached
+        let bytecode = decode_hex("6005565f005b00")?;
+        let contract_cfg = build_cfg(&bytecode)?;
+
+        assert_eq!(contract_cfg.graph.node_count(), 3);
+        let unreached = contract_cfg.node_at(3).expect("missing unreached block");
+        assert_eq!(contract_cfg.graph.edges_directed(unreached, petgraph::Incoming).count(), 0);
+
+        let entry = contract_cfg.node_at(0).expect("missing entry block");
+        let targets = contract_cfg
+            .graph
+            .edges(entry)
+            .map(|edge| (contract_cfg.graph[edge.target()].block.start, *edge.weight()))
+            .collect::<Vec<_>>();
+        assert_eq!(targets, vec![(5, CfgEdge::Unconditional)]);
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_build_cfg_branch_edges() -> Result<(), Box<dyn std::error::Error>> {
+        // CALLDATASIZE, PUSH1 0x07, JUMPI, PUSH0, PUSH0, REVERT, JUMPDEST, PUSH0, PUSH0, REVERT
+        let bytecode = decode_hex("366007575f5ffd5b5f5ffd")?;
+        let contract_cfg = build_cfg(&bytecode)?;
+
+        assert_eq!(contract_cfg.graph.node_count(), 3);
+        assert_eq!(contract_cfg.graph.edge_count(), 2);
+
+        let entry = contract_cfg.node_at(0).expect("missing entry block");
+        let mut targets = contract_cfg
+            .graph
+            .edges(entry)
+            .map(|edge| (contract_cfg.graph[edge.target()].block.start, *edge.weight()))
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|(start, _)| *start);
+        assert_eq!(targets, vec![(4, CfgEdge::NotTaken), (7, CfgEdge::Taken)]);
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_build_cfg_excludes_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        // JUMPDEST, STOP, INVALID, followed by solc 0.4 metadata
+        let bytecode = decode_hex(concat!(
+            "5b00fe",
+            "a165627a7a72305820",
+            "1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8",
+            "0029"
+        ))?;
+        let contract_cfg = build_cfg(&bytecode)?;
+
+        assert_eq!(contract_cfg.graph.node_count(), 2);
+        assert!(contract_cfg.graph.node_weights().all(|node| node.block.end <= 3));
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_apply_resolved_flow() -> Result<(), Box<dyn std::error::Error>> {
+        // CALLDATASIZE, PUSH1 0x08, PUSH1 0x01, ADD, JUMP, ... JUMPDEST, STOP: the jump's target
+        // is computed, so it's only found by symbolic execution
+        let bytecode = decode_hex("3660086001015600005b00")?;
+        let mut vm = VM::new(
+            &bytecode,
+            &[],
+            Address::default(),
+            Address::default(),
+            Address::default(),
+            0,
+            u128::MAX,
+        );
+        let (trace, _) = vm.symbolic_exec(Instant::now() + Duration::from_secs(10))?;
+
+        let mut resolved = build_cfg(&bytecode)?;
+        resolved.resolve_jumps(&trace);
+        let flow = resolved.resolved_flow();
+        assert!(flow.edges.contains(&(0, 9, CfgEdge::Unconditional)));
+
+        // applying the flow to a fresh graph reproduces the resolved graph
+        let mut applied = build_cfg(&bytecode)?;
+        applied.apply_flow(&flow);
+        assert_eq!(applied.resolved_flow(), flow);
+        assert_eq!(applied.graph.edge_count(), resolved.graph.edge_count());
+
         Ok(())
     }
 }
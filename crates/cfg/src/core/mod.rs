@@ -1,27 +1,86 @@
+pub(crate) mod dominators;
+pub(crate) mod export;
 pub(crate) mod graph;
 
 use alloy::primitives::Address;
 use eyre::eyre;
-use heimdall_common::{ether::compiler::detect_compiler, utils::strings::StringExt};
-use heimdall_vm::core::vm::VM;
-use std::collections::HashSet;
+use heimdall_cache::{read_cache, store_cache};
+use heimdall_common::{
+    ether::compiler::detect_compiler,
+    utils::strings::{encode_hex, StringExt},
+};
+use heimdall_disassembler::{disassemble_instructions, DisassemblerArgsBuilder};
+use heimdall_vm::{
+    core::vm::VM,
+    ext::{
+        creation::{deploy, Deployment},
+        selectors::find_function_selectors,
+        solver::default_solver,
+    },
+};
+use std::collections::{BTreeMap, BTreeSet};
 
-use petgraph::{dot::Dot, Graph};
+use petgraph::{dot::Dot, graph::NodeIndex, visit::Bfs, Graph};
 use std::time::{Duration, Instant};
 
 use super::CfgArgs;
 
-use crate::{core::graph::build_cfg, error::Error};
+use crate::{
+    core::{
+        dominators::DominatorTree,
+        graph::{build_cfg, CfgEdge, CfgNode, ResolvedFlow},
+    },
+    error::Error,
+};
 use tracing::{debug, info};
 
 /// The result of the cfg command. Contains the generated control flow graph.
 #[derive(Debug, Clone)]
 pub struct CfgResult {
-    /// The generated control flow graph of the contract.
-    pub graph: Graph<String, String>,
+    /// The generated control flow graph of the contract, with one node per basic block.
+    pub graph: Graph<CfgNode, CfgEdge>,
+
+    /// The node of the block execution begins at.
+    pub entry: Option<NodeIndex>,
+
+    /// The subgraph of each function, keyed by selector.
+    pub functions: BTreeMap<String, FunctionCfg>,
+
+    /// The dominator tree of the graph, rooted at [`CfgResult::entry`].
+    pub dominators: DominatorTree,
+
+    /// The post-dominator tree of the graph.
+    pub post_dominators: DominatorTree,
+
+    /// The result of executing the constructor, if the target is creation bytecode. The graph is
+    /// built from the runtime code it deploys.
+    pub deployment: Option<Deployment>,
+}
+
+/// The subgraph of a contract's control flow graph belonging to a single function.
+#[derive(Debug, Clone)]
+pub struct FunctionCfg {
+    /// The offset the dispatcher transfers control to for this function.
+    pub entry_point: u128,
+
+    /// The node of the block containing the entry point.
+    pub entry: NodeIndex,
+
+    /// Every node reachable from the entry, including internal functions and reverts which may
+    /// be shared with other functions.
+    pub nodes: BTreeSet<NodeIndex>,
 }
 
 impl CfgResult {
+    /// Returns the subgraph of the function with the given selector.
+    pub fn function_graph(&self, selector: &str) -> Option<Graph<CfgNode, CfgEdge>> {
+        let function = self.functions.get(selector)?;
+        Some(self.graph.filter_map(
+            |node, weight| function.nodes.contains(&node).then(|| weight.clone()),
+            |_, edge| Some(*edge),
+        ))
+    }
+
     /// Returns the control flow graph as a graphviz formatted string.
     pub fn as_dot(&self, color_edges: bool) -> String {
         let output = format!("{}", Dot::with_config(&self.graph, &[]));
@@ -60,7 +119,7 @@ pub async fn cfg(args: CfgArgs) -> Result<CfgResult, Error> {
 
     // get the bytecode from the target
     let start_fetch_time = Instant::now();
-    let contract_bytecode = args
+    let mut contract_bytecode = args
         .get_bytecode()
         .await
         .map_err(|e| Error::FetchError(format!("fetching target bytecode failed: {e}")))?;
@@ -70,6 +129,9 @@ pub async fn cfg(args: CfgArgs) -> Result<CfgResult, Error> {
         return Err(Error::Eyre(eyre!("contract bytecode is empty")));
     }
 
+    // results are cached by the hash of the bytecode, so they're shared between identical contracts
+    let cache_key = args.cache_key(&contract_bytecode, hardfork);
+
     // perform versioning and compiler heuristics
     let (_compiler, _version) = detect_compiler(&contract_bytecode);
 
@@ -86,30 +148,114 @@ pub async fn cfg(args: CfgArgs) -> Result<CfgResult, Error> {
     )
     .with_hardfork(hardfork);
 
-    info!("performing symbolic execution on '{}'", args.target.truncate(64));
-    let start_sym_exec_time = Instant::now();
-    let (map, jumpdest_count) = evm
-        .symbolic_exec(
-            Instant::now()
-                .checked_add(Duration::from_millis(args.timeout))
-                .expect("invalid timeout"),
-        )
-        .map_err(|e| Error::Eyre(eyre!("symbolic execution failed: {}", e)))?;
+    if args.solver {
+        evm = evm.with_solver(default_solver());
+    }
 
-    debug!("'{}' has {} unique branches", args.target.truncate(64), jumpdest_count);
-    debug!("symbolic execution took {:?}", start_sym_exec_time.elapsed());
-    info!("symbolically executed '{}'", args.target.truncate(64));
+    // creation bytecode returns the contract's runtime code from its constructor, which is what
+    // the graph is built from
+    let deployment = deploy(&evm);
+    if let Some(deployment) = &deployment {
+        info!("target is creation bytecode, building cfg for its deployed runtime code");
+        contract_bytecode = deployment.runtime_code.clone();
+        evm = deployment.runtime_vm(&evm);
+    }
+
+    // find the entry point of each function
+    let instructions = disassemble_instructions(
+        DisassemblerArgsBuilder::new()
+            .target(encode_hex(&contract_bytecode))
+            .hardfork(hardfork)
+            .build()
+            .expect("impossible case: failed to build disassembly arguments"),
+    )
+    .await?;
+    let selectors = find_function_selectors(&evm, &instructions);
 
-    // run cfg generation
     let start_cfg_time = Instant::now();
-    info!("building cfg for '{}' from symbolic execution trace", args.target.truncate(64));
-    let mut contract_cfg = Graph::new();
-    let mut seen_nodes: HashSet<String> = HashSet::new();
-    build_cfg(&map, &mut contract_cfg, None, false, &mut seen_nodes)?;
+    info!("building cfg for '{}' from bytecode", args.target.truncate(64));
+    let mut contract_cfg = build_cfg(&contract_bytecode)?;
     debug!("building cfg took {:?}", start_cfg_time.elapsed());
 
+    // the jumps found by symbolic execution only depend on the bytecode and arguments, so they're
+    // reused from the cache when the same bytecode has been analyzed before
+    match cache_key.as_deref().and_then(read_cached_flow) {
+        Some(flow) => {
+            info!("using cached symbolic execution of '{}'", args.target.truncate(64));
+            contract_cfg.apply_flow(&flow);
+        }
+        None => {
+            info!("performing symbolic execution on '{}'", args.target.truncate(64));
+            let start_sym_exec_time = Instant::now();
+            let timeout_at = Instant::now()
+                .checked_add(Duration::from_millis(args.timeout))
+                .expect("invalid timeout");
+            let (map, jumpdest_count) = evm
+                .symbolic_exec(timeout_at)
+                .map_err(|e| Error::Eyre(eyre!("symbolic execution failed: {}", e)))?;
+
+            debug!("'{}' has {} unique branches", args.target.truncate(64), jumpdest_count);
+            debug!("symbolic execution took {:?}", start_sym_exec_time.elapsed());
+            info!("symbolically executed '{}'", args.target.truncate(64));
+
+            contract_cfg.resolve_jumps(&map);
+
+            // a flow explored only until the timeout isn't cached, since a later run may explore
+            // further
+            if Instant::now() >= timeout_at {
+                debug!(
+                    "not caching cfg of '{}', symbolic execution timed out",
+                    args.target.truncate(64)
+                );
+            } else if let Some(key) = &cache_key {
+                if let Err(e) = store_cache(key, contract_cfg.resolved_flow(), None) {
+                    debug!("failed to cache cfg of '{}': {}", args.target.truncate(64), e);
+                }
+            }
+        }
+    }
+
+    // each function's subgraph is rooted at its entry point
+    let functions = selectors
+        .into_iter()
+        .filter_map(|(selector, entry_point)| {
+            let entry = contract_cfg.node_containing(entry_point.try_into().ok()?)?;
+            let mut nodes = BTreeSet::new();
+            let mut bfs = Bfs::new(&contract_cfg.graph, entry);
+            while let Some(node) = bfs.next(&contract_cfg.graph) {
+                nodes.insert(node);
+            }
+
+            Some((selector, FunctionCfg { entry_point, entry, nodes }))
+        })
+        .collect::<BTreeMap<_, _>>();
+    debug!("found {} function subgraphs", functions.len());
+
+    let start_dominators_time = Instant::now();
+    let entry = contract_cfg.entry();
+    let dominators = entry
+        .map(|entry| DominatorTree::dominators_of(&contract_cfg.graph, entry))
+        .unwrap_or_default();
+    let post_dominators = DominatorTree::post_dominators_of(&contract_cfg.graph, CfgNode::is_exit);
+    debug!("building dominator trees took {:?}", start_dominators_time.elapsed());
+
     debug!("cfg generated in {:?}", start_time.elapsed());
     info!("generated cfg successfully");
 
-    Ok(CfgResult { graph: contract_cfg })
+    Ok(CfgResult {
+        graph: contract_cfg.graph,
+        entry,
+        functions,
+        dominators,
+        post_dominators,
+        deployment,
+    })
+}
+
+/// Reads the [`ResolvedFlow`] cached under `key`, if any.
+fn read_cached_flow(key: &str) -> Option<ResolvedFlow> {
+    read_cache::<ResolvedFlow>(key)
+        .inspect_err(|e| debug!("failed to read cached cfg: {}", e))
+        .ok()
+        .flatten()
 }
@@ -1,10 +1,43 @@
-use alloy::primitives::Address;
-use clap::Parser;
+use alloy::primitives::{keccak256, Address};
+use clap::{Parser, ValueEnum};
 use derive_builder::Builder;
 use eyre::Result;
-use heimdall_common::ether::bytecode::get_bytecode_from_target;
+use heimdall_common::{ether::bytecode::get_bytecode_from_target, utils::strings::encode_hex};
 use heimdall_config::parse_url_arg;
-use heimdall_vm::core::hardfork::HardFork;
+use heimdall_vm::{core::hardfork::HardFork, ext::solver::default_solver};
+
+/// The formats a control flow graph can be written in.
+#[derive(Debug, Copy, Clone, Default, ValueEnum, Eq, PartialEq)]
+pub enum CfgFormat {
+    /// Graphviz DOT, which can be rendered with `dot -Tpng`
+    #[default]
+    Dot,
+
+    /// A JSON document of nodes and edges, for consumption by other tools
+    Json,
+
+    /// GraphML, which can be imported into tools such as Gephi and yEd
+    Graphml,
+
+    /// A Mermaid flowchart, which can be embedded in markdown
+    Mermaid,
+
+    /// A self-contained SVG image, which doesn't require graphviz to render
+    Svg,
+}
+
+impl CfgFormat {
+    /// Returns the file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CfgFormat::Dot => "dot",
+            CfgFormat::Json => "json",
+            CfgFormat::Graphml => "graphml",
+            CfgFormat::Mermaid => "mmd",
+            CfgFormat::Svg => "svg",
+        }
+    }
+}
 
 /// Arguments for the CFG subcommand
 #[derive(Debug, Clone, Parser, Builder)]
@@ -32,6 +65,10 @@ pub struct CfgArgs {
     #[clap(long = "color-edges", short)]
     pub color_edges: bool,
 
+    /// The format to write the control flow graph in.
+    #[clap(long, value_enum, default_value_t = CfgFormat::Dot)]
+    pub format: CfgFormat,
+
     /// The output directory to write the output to or 'print' to print to the console
     #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
     pub output: String,
@@ -52,6 +89,17 @@ pub struct CfgArgs {
     /// Etherscan API key for fetching contract creation block when using auto hardfork detection.
     #[clap(long, short = 'e', default_value = "", hide_default_value = true)]
     pub etherscan_api_key: String,
+
+    /// Whether to prune branches which can't be reached during symbolic execution, by checking
+    /// the conditions along each path with a constraint solver. The solver is Z3 if heimdall is
+    /// built with the `z3` feature.
+    #[clap(long)]
+    pub solver: bool,
+
+    /// Whether to bypass the cache, always symbolically executing the target rather than reusing
+    /// the result of a previous run on the same bytecode.
+    #[clap(long = "no-cache")]
+    pub no_cache: bool,
 }
 
 impl CfgArgs {
@@ -60,6 +108,31 @@ impl CfgArgs {
         get_bytecode_from_target(&self.target, &self.rpc_url, "").await
     }
 
+    /// Builds the key the control flow of `bytecode` is cached under, from the hash of the
+    /// bytecode, the hardfork, and everything else symbolic execution depends on: the version of
+    /// heimdall, the timeout, and the solver used to prune branches.
+    ///
+    /// # Returns
+    /// The key, or `None` if caching is disabled with `--no-cache`
+    pub fn cache_key(&self, bytecode: &[u8], hardfork: HardFork) -> Option<String> {
+        if self.no_cache {
+            return None;
+        }
+
+        let arguments = format!(
+            "{}|{}|{:?}",
+            env!("CARGO_PKG_VERSION"),
+            self.timeout,
+            self.solver.then(default_solver)
+        );
+        Some(format!(
+            "cfg.{}.{}.{}",
+            encode_hex(keccak256(bytecode).as_slice()),
+            hardfork,
+            &encode_hex(keccak256(arguments).as_slice())[..16]
+        ))
+    }
+
     /// Gets the hardfork to use for CFG generation.
     ///
     /// If `hardfork` is set to `Auto`, attempts to detect the hardfork based on the
@@ -117,11 +190,37 @@ impl CfgArgsBuilder {
             rpc_url: Some(String::new()),
             default: Some(true),
             color_edges: Some(false),
+            format: Some(CfgFormat::Dot),
             output: Some(String::new()),
             name: Some(String::new()),
             timeout: Some(10000),
             hardfork: Some(HardFork::Latest),
             etherscan_api_key: Some(String::new()),
+            solver: Some(false),
+            no_cache: Some(false),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_changes_with_inputs() {
+        let args = CfgArgsBuilder::new().build().expect("failed to build args");
+        let key = args.cache_key(&[0x60, 0x00], HardFork::Latest).expect("caching is enabled");
+        assert_eq!(args.cache_key(&[0x60, 0x00], HardFork::Latest), Some(key.clone()));
+
+        // the bytecode, hardfork, timeout and solver all change the result of symbolic execution
+        assert_ne!(args.cache_key(&[0x60, 0x01], HardFork::Latest), Some(key.clone()));
+        assert_ne!(args.cache_key(&[0x60, 0x00], HardFork::Shanghai), Some(key.clone()));
+        let solver_args = CfgArgs { solver: true, ..args.clone() };
+        assert_ne!(solver_args.cache_key(&[0x60, 0x00], HardFork::Latest), Some(key.clone()));
+        let args = CfgArgs { timeout: 20000, ..args };
+        assert_ne!(args.cache_key(&[0x60, 0x00], HardFork::Latest), Some(key));
+
+        let args = CfgArgs { no_cache: true, ..args };
+        assert_eq!(args.cache_key(&[0x60, 0x00], HardFork::Latest), None);
+    }
+}
@@ -7,7 +7,12 @@ mod core;
 mod interfaces;
 
 // re-export the public interface
-pub use core::{cfg, CfgResult};
+pub use core::{
+    cfg,
+    dominators::DominatorTree,
+    graph::{CfgEdge, CfgNode},
+    CfgResult, FunctionCfg,
+};
 pub use error::Error;
 pub use heimdall_vm::core::hardfork::HardFork;
-pub use interfaces::{CfgArgs, CfgArgsBuilder};
+pub use interfaces::{CfgArgs, CfgArgsBuilder, CfgFormat};
@@ -1,7 +1,10 @@
 use clap::{Parser, Subcommand};
 
+use crate::{batch::BatchArgs, serve::ServeArgs};
+
 use clap::{ArgAction, Args, ValueEnum};
 use heimdall_cache::CacheArgs;
+use heimdall_common::ether::signatures::SignatureDatabaseArgs;
 use heimdall_config::ConfigArgs;
 use heimdall_core::{
     heimdall_cfg::CfgArgs, heimdall_decoder::DecodeArgs, heimdall_decompiler::DecompilerArgs,
@@ -53,6 +56,9 @@ pub(crate) enum Subcommands {
     #[clap(name = "cache", about = "Manage heimdall-rs' cached files")]
     Cache(CacheArgs),
 
+    #[clap(name = "signatures", about = "Manage the local signature database")]
+    Signatures(SignatureDatabaseArgs),
+
     #[clap(name = "dump", about = "Dump the value of all storage slots accessed by a contract")]
     Dump(DumpArgs),
 
@@ -61,6 +67,15 @@ pub(crate) enum Subcommands {
         about = "Detailed inspection of Ethereum transactions, including calldata & trace decoding, log visualization, and more"
     )]
     Inspect(InspectArgs),
+
+    #[clap(
+        name = "batch",
+        about = "Run a module over many targets from a file, stdin, or a block range"
+    )]
+    Batch(BatchArgs),
+
+    #[clap(name = "serve", about = "Serve the heimdall modules over JSON-RPC and HTTP")]
+    Serve(ServeArgs),
 }
 
 #[derive(Debug, Args)]
@@ -0,0 +1,478 @@
+//! Batch mode, which runs a module over many targets in a single invocation. Targets are read
+//! from a file or stdin, or collected from a range of blocks, and are run concurrently, sharing
+//! the configuration and the process' caches.
+
+use std::{
+    collections::HashSet,
+    env,
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+
+use alloy::{
+    primitives::{Address, TxHash},
+    rpc::types::trace::parity::Delta,
+};
+use clap::{Arg, Args, Subcommand};
+use eyre::{bail, eyre, Result};
+use futures::{stream, StreamExt, TryStreamExt};
+use heimdall_common::{
+    ether::rpc::StateDiffSource,
+    utils::{hex::ToLowerHex, io::file::read_file},
+};
+use heimdall_config::Configuration;
+use heimdall_core::{
+    heimdall_cfg::CfgArgs, heimdall_decoder::DecodeArgs, heimdall_decompiler::DecompilerArgs,
+    heimdall_disassembler::DisassemblerArgs, heimdall_dump::DumpArgs,
+    heimdall_inspect::InspectArgs,
+};
+use serde::Serialize;
+use tokio::runtime::Handle;
+use tracing::{info, warn};
+
+use crate::commands::{run_cfg, run_decode, run_decompile, run_disassemble, run_dump, run_inspect};
+
+/// The number of blocks fetched at once when collecting targets from a block range.
+const BLOCK_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Args)]
+#[clap(override_usage = "heimdall batch [OPTIONS] <COMMAND> [MODULE OPTIONS]")]
+pub(crate) struct BatchArgs {
+    /// The module to run over each target, along with its arguments.
+    #[clap(subcommand)]
+    pub sub: BatchSubcommands,
+
+    /// A file of targets, one per line, or '-' to read them from stdin. Blank lines and lines
+    /// starting with '#' are skipped. Targets are read from stdin if neither a file nor a block
+    /// range is given.
+    #[clap(long, short = 'f', value_name = "FILE")]
+    pub targets: Option<String>,
+
+    /// The first block to collect targets from. `decode` and `inspect` are run over the
+    /// transactions in each block, while the other modules are run over the contracts created
+    /// in each block.
+    #[clap(long, requires = "to_block")]
+    pub from_block: Option<u64>,
+
+    /// The last block to collect targets from, inclusive.
+    #[clap(long, requires = "from_block")]
+    pub to_block: Option<u64>,
+
+    /// The number of targets to run at once.
+    #[clap(long, short = 'j', default_value = "4")]
+    pub concurrency: usize,
+
+    /// The path to write the summary to, as one JSON line per target. Defaults to
+    /// `summary.jsonl` within the output directory.
+    #[clap(long, value_name = "PATH")]
+    pub summary: Option<String>,
+}
+
+/// The modules which can be run in batch mode. Each takes the module's usual arguments, except
+/// for the target, which is taken from the batch.
+#[derive(Debug, Clone, Subcommand)]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum BatchSubcommands {
+    #[clap(
+        name = "disassemble",
+        about = "Disassemble each target",
+        mut_arg("target", batch_target)
+    )]
+    Disassemble(DisassemblerArgs),
+
+    #[clap(name = "decompile", about = "Decompile each target", mut_arg("target", batch_target))]
+    Decompile(DecompilerArgs),
+
+    #[clap(
+        name = "cfg",
+        about = "Generate a cfg for each target",
+        mut_arg("target", batch_target)
+    )]
+    Cfg(CfgArgs),
+
+    #[clap(name = "decode", about = "Decode each target", mut_arg("target", batch_target))]
+    Decode(DecodeArgs),
+
+    #[clap(
+        name = "dump",
+        about = "Dump the storage of each target",
+        mut_arg("target", batch_target)
+    )]
+    Dump(DumpArgs),
+
+    #[clap(name = "inspect", about = "Inspect each target", mut_arg("target", batch_target))]
+    Inspect(InspectArgs),
+}
+
+/// The positional target isn't used in batch mode, so it's made optional and hidden.
+fn batch_target(arg: Arg) -> Arg {
+    arg.required(false).default_value("").hide(true)
+}
+
+/// The outcome of running the module over a single target, as written to the summary.
+#[derive(Debug, Serialize)]
+struct TargetSummary {
+    index: usize,
+    target: String,
+    status: TargetStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    outputs: Vec<String>,
+    elapsed_ms: u128,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum TargetStatus {
+    Ok,
+    Error,
+}
+
+impl BatchSubcommands {
+    /// The output directory given to the module. Batch outputs are always written, so `print`
+    /// is treated as the default output directory.
+    fn output(&self) -> &str {
+        let output = match self {
+            Self::Disassemble(cmd) => &cmd.output,
+            Self::Decompile(cmd) => &cmd.output,
+            Self::Cfg(cmd) => &cmd.output,
+            Self::Decode(cmd) => &cmd.output,
+            Self::Dump(cmd) => &cmd.output,
+            Self::Inspect(cmd) => &cmd.output,
+        };
+
+        match output.as_str() {
+            "print" => "output",
+            output => output,
+        }
+    }
+
+    /// The rpc url given to the module.
+    fn rpc_url(&self) -> &str {
+        match self {
+            Self::Disassemble(cmd) => &cmd.rpc_url,
+            Self::Decompile(cmd) => &cmd.rpc_url,
+            Self::Cfg(cmd) => &cmd.rpc_url,
+            Self::Decode(cmd) => &cmd.rpc_url,
+            Self::Dump(cmd) => &cmd.rpc_url,
+            Self::Inspect(cmd) => &cmd.rpc_url,
+        }
+    }
+
+    /// Whether the module is run over transactions, rather than contracts.
+    fn takes_transactions(&self) -> bool {
+        matches!(self, Self::Decode(_) | Self::Inspect(_))
+    }
+
+    /// Returns a copy of the module's arguments with the given target and output directory.
+    fn with_target(&self, target: &str, output: &str) -> Self {
+        let mut sub = self.clone();
+        let (sub_target, sub_output) = match &mut sub {
+            Self::Disassemble(cmd) => (&mut cmd.target, &mut cmd.output),
+            Self::Decompile(cmd) => (&mut cmd.target, &mut cmd.output),
+            Self::Cfg(cmd) => (&mut cmd.target, &mut cmd.output),
+            Self::Decode(cmd) => (&mut cmd.target, &mut cmd.output),
+            Self::Dump(cmd) => (&mut cmd.target, &mut cmd.output),
+            Self::Inspect(cmd) => (&mut cmd.target, &mut cmd.output),
+        };
+        *sub_target = target.to_string();
+        *sub_output = output.to_string();
+        sub
+    }
+
+    /// Runs the module, returning the paths of the files written.
+    async fn run(self, configuration: &Configuration) -> Result<Vec<String>> {
+        match self {
+            Self::Disassemble(cmd) => run_disassemble(cmd, configuration).await,
+            Self::Decompile(cmd) => run_decompile(cmd, configuration).await,
+            Self::Cfg(cmd) => run_cfg(cmd, configuration).await,
+            Self::Decode(cmd) => run_decode(cmd, configuration).await,
+            Self::Dump(cmd) => run_dump(cmd, configuration).await,
+            Self::Inspect(cmd) => run_inspect(cmd, configuration, false).await,
+        }
+    }
+}
+
+/// Runs the module over each of the batch's targets, writing each target's outputs to its own
+/// directory and a line per target to the summary. Failed targets are recorded in the summary
+/// rather than stopping the batch.
+pub(crate) async fn batch(args: BatchArgs, configuration: Configuration) -> Result<()> {
+    let start_time = Instant::now();
+    let rpc_url = match args.sub.rpc_url() {
+        "" => configuration.rpc_url.clone(),
+        rpc_url => rpc_url.to_string(),
+    };
+
+    // collect the targets, skipping duplicates while preserving their order
+    let mut targets = Vec::new();
+    if let Some(path) = &args.targets {
+        targets.extend(read_targets(path)?);
+    }
+    if let (Some(from_block), Some(to_block)) = (args.from_block, args.to_block) {
+        targets.extend(
+            block_targets(from_block, to_block, args.sub.takes_transactions(), &rpc_url).await?,
+        );
+    }
+    if args.targets.is_none() && args.from_block.is_none() {
+        targets.extend(read_targets("-")?);
+    }
+    let mut seen = HashSet::new();
+    targets.retain(|target| seen.insert(target.clone()));
+
+    if targets.is_empty() {
+        bail!("no targets to run");
+    }
+
+    let output = args.sub.output().to_string();
+    let summary_path = match &args.summary {
+        Some(path) => path.clone(),
+        None => format!("{}/summary.jsonl", output_base(&output, false)?),
+    };
+    let mut summary = create_file(&summary_path)?;
+    info!("running {} targets with a concurrency of {}", targets.len(), args.concurrency.max(1));
+
+    // each target is run on a blocking thread, so that cpu-bound modules run in parallel
+    let configuration = Arc::new(configuration);
+    let total = targets.len();
+    let mut results = stream::iter(targets.into_iter().enumerate())
+        .map(|(index, target)| {
+            let sub = args.sub.clone();
+            let configuration = configuration.clone();
+            let output = output.clone();
+            async move {
+                let start_time = Instant::now();
+                let result = match target_output(&output, &target, index) {
+                    Ok(target_output) => {
+                        let sub = sub.with_target(&target, &target_output);
+                        let handle = Handle::current();
+                        tokio::task::spawn_blocking(move || {
+                            handle.block_on(sub.run(&configuration))
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(eyre!("module panicked: {}", e)))
+                    }
+                    Err(e) => Err(e),
+                };
+
+                let (status, error, outputs) = match result {
+                    Ok(outputs) => (TargetStatus::Ok, None, outputs),
+                    Err(e) => (TargetStatus::Error, Some(e.to_string()), Vec::new()),
+                };
+                TargetSummary {
+                    index,
+                    target,
+                    status,
+                    error,
+                    outputs,
+                    elapsed_ms: start_time.elapsed().as_millis(),
+                }
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1));
+
+    // summaries are written as targets finish, so an interrupted batch keeps its progress
+    let mut failed = 0;
+    let mut completed = 0;
+    while let Some(result) = results.next().await {
+        completed += 1;
+        match &result.error {
+            Some(error) => {
+                failed += 1;
+                warn!("[{}/{}] {} failed: {}", completed, total, result.target, error);
+            }
+            None => info!("[{}/{}] {} succeeded", completed, total, result.target),
+        }
+
+        writeln!(summary, "{}", serde_json::to_string(&result)?)
+            .and_then(|_| summary.flush())
+            .map_err(|e| eyre!("failed to write summary: {}", e))?;
+    }
+
+    info!(
+        "ran {} targets in {:?}, {} failed. summary written to {}",
+        total,
+        start_time.elapsed(),
+        failed,
+        summary_path
+    );
+    Ok(())
+}
+
+/// Reads the targets from the file at `path`, or from stdin if `path` is `-`.
+fn read_targets(path: &str) -> Result<Vec<String>> {
+    let contents = match path {
+        "-" => {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(|e| eyre!("failed to read targets from stdin: {}", e))?;
+            contents
+        }
+        path => read_file(path).map_err(|e| eyre!("failed to read targets file: {}", e))?,
+    };
+
+    Ok(parse_targets(&contents))
+}
+
+/// Parses a list of targets, one per line, skipping blank lines and `#` comments.
+fn parse_targets(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Collects targets from the blocks in `from_block..=to_block`: the hashes of their transactions
+/// if `transactions` is set, otherwise the addresses of the contracts created within them.
+async fn block_targets(
+    from_block: u64,
+    to_block: u64,
+    transactions: bool,
+    rpc_url: &str,
+) -> Result<Vec<String>> {
+    if from_block > to_block {
+        bail!("invalid block range: {}..={}", from_block, to_block);
+    }
+
+    let source = StateDiffSource::detect(from_block, rpc_url)
+        .await
+        .map_err(|e| eyre!("failed to trace block {}: {}", from_block, e))?;
+
+    // blocks are fetched concurrently, but kept in order
+    let blocks: Vec<_> = stream::iter(from_block..=to_block)
+        .map(|block_number| async move {
+            source
+                .get_block_state_diff(block_number, false, rpc_url)
+                .await
+                .map_err(|e| eyre!("failed to trace block {}: {}", block_number, e))
+        })
+        .buffered(BLOCK_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut targets = Vec::new();
+    for trace in blocks.iter().flatten() {
+        if transactions {
+            targets.push(trace.transaction_hash.to_lower_hex());
+        } else if let Some(diff) = &trace.full_trace.state_diff {
+            targets.extend(
+                diff.0
+                    .iter()
+                    .filter(|(_, account)| {
+                        matches!(&account.code, Delta::Added(code) if !is_delegation(code))
+                    })
+                    .map(|(address, _)| address.to_lower_hex()),
+            );
+        }
+    }
+
+    info!("collected {} targets from blocks {}..={}", targets.len(), from_block, to_block);
+    Ok(targets)
+}
+
+/// Whether `code` is an EIP-7702 delegation designator, i.e. `0xef0100` followed by an address,
+/// which an EOA is given by an authorization rather than a contract being created.
+fn is_delegation(code: &[u8]) -> bool {
+    code.len() == 23 && code.starts_with(&[0xef, 0x01, 0x00])
+}
+
+/// Whether the target is an address or transaction hash, rather than bytecode, calldata or a
+/// file.
+fn is_onchain(target: &str) -> bool {
+    target.parse::<Address>().is_ok() || target.parse::<TxHash>().is_ok()
+}
+
+/// The directory that batch outputs are written within. The default `output` directory is
+/// resolved against the current directory, with offchain targets written to `output/local`.
+fn output_base(output: &str, local: bool) -> Result<String> {
+    if output != "output" {
+        return Ok(output.trim_end_matches('/').to_string());
+    }
+
+    let cwd = env::current_dir()?
+        .into_os_string()
+        .into_string()
+        .map_err(|_| eyre!("Unable to get current working directory"))?;
+    Ok(match local {
+        true => format!("{cwd}/output/local"),
+        false => format!("{cwd}/output"),
+    })
+}
+
+/// The output directory for the target at `index`. Onchain targets are written to a directory
+/// named after them, which for the default output is `output/{chain_id}/{target}`, while other
+/// targets are written to `target-{index}`.
+fn target_output(output: &str, target: &str, index: usize) -> Result<String> {
+    match is_onchain(target) {
+        true if output == "output" => Ok(output.to_string()),
+        true => Ok(format!("{}/{}", output_base(output, false)?, target)),
+        false => Ok(format!("{}/target-{}", output_base(output, true)?, index)),
+    }
+}
+
+/// Creates the file at `path`, along with its parent directories.
+fn create_file(path: &str) -> Result<File> {
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    File::create(path).map_err(|e| eyre!("failed to create {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        let contents = "
+            # contracts
+            0x1bf797219482a29013d804ad96d1c6f84fba4c45
+
+            0x6080604052
+            # trailing comment
+        ";
+
+        assert_eq!(
+            parse_targets(contents),
+            vec!["0x1bf797219482a29013d804ad96d1c6f84fba4c45".to_string(), "0x6080604052".into()]
+        );
+    }
+
+    #[test]
+    fn test_is_delegation() {
+        let mut designator = vec![0xef, 0x01, 0x00];
+        designator.extend_from_slice(&[0x11; 20]);
+        assert!(is_delegation(&designator));
+
+        // contracts, including ones starting with the EOF magic, aren't delegations
+        assert!(!is_delegation(&[0x60, 0x80, 0x60, 0x40, 0x52]));
+        assert!(!is_delegation(&designator[..22]));
+        assert!(!is_delegation(&[&[0xef, 0x00, 0x01][..], &[0x00; 20]].concat()));
+    }
+
+    #[test]
+    fn test_target_output() {
+        let address = "0x1bf797219482a29013d804ad96d1c6f84fba4c45";
+
+        // onchain targets with the default output are left to `build_output_path`
+        assert_eq!(target_output("output", address, 0).expect("failed"), "output");
+        assert_eq!(
+            target_output("/tmp/out/", address, 0).expect("failed"),
+            format!("/tmp/out/{address}")
+        );
+
+        // offchain targets are labelled by their index
+        assert_eq!(
+            target_output("/tmp/out", "0x6080604052", 3).expect("failed"),
+            "/tmp/out/target-3"
+        );
+        assert!(target_output("output", "0x6080604052", 3)
+            .expect("failed")
+            .ends_with("/output/local/target-3"));
+    }
+}
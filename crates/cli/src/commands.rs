@@ -0,0 +1,371 @@
+//! The module subcommands, which fill in any arguments the user hasn't specified from the
+//! configuration, run the module, and print or write its output.
+
+use std::collections::HashSet;
+
+use eyre::{eyre, Result};
+use heimdall_common::utils::{hex::ToLowerHex, io::file::write_file};
+use heimdall_config::Configuration;
+use heimdall_core::{
+    heimdall_cfg::{cfg, CfgArgs},
+    heimdall_decoder::{decode, DecodeArgs},
+    heimdall_decompiler::{decompile, DecompilerArgs},
+    heimdall_disassembler::{disassemble, DisassemblerArgs},
+    heimdall_dump::{dump, DumpArgs},
+    heimdall_inspect::{inspect, InspectArgs},
+};
+
+use crate::output::{build_output_path, csv_escape, print_with_less};
+
+/// Disassembles the target, printing or writing the assembly.
+///
+/// Returns the paths of the files written, which is empty if the output was printed.
+pub(crate) async fn run_disassemble(
+    mut cmd: DisassemblerArgs,
+    configuration: &Configuration,
+) -> Result<Vec<String>> {
+    // if the user has not specified a rpc url, use the default
+    if cmd.rpc_url.as_str() == "" {
+        cmd.rpc_url = configuration.rpc_url.clone();
+    }
+
+    // if the user has passed an output filename, override the default filename
+    let mut filename: String =
+        if cmd.json { "disassembled.json" } else { "disassembled.asm" }.to_string();
+    let given_name = cmd.name.as_str();
+
+    if !given_name.is_empty() {
+        filename = format!("{given_name}-{filename}");
+    }
+
+    let assembly = disassemble(cmd.clone())
+        .await
+        .map_err(|e| eyre!("failed to disassemble bytecode: {}", e))?;
+
+    let mut written = Vec::new();
+    if cmd.output == "print" {
+        print_with_less(&assembly).await.map_err(|e| eyre!("failed to print assembly: {}", e))?;
+    } else {
+        let output_path = build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+            .await
+            .map_err(|e| eyre!("failed to build output path: {}", e))?;
+
+        write_file(&output_path, &assembly)
+            .map_err(|e| eyre!("failed to write assembly: {}", e))?;
+        written.push(output_path);
+    }
+
+    Ok(written)
+}
+
+/// Decompiles the target, printing or writing its ABI, storage layout and source.
+///
+/// Returns the paths of the files written, which is empty if the output was printed.
+pub(crate) async fn run_decompile(
+    mut cmd: DecompilerArgs,
+    configuration: &Configuration,
+) -> Result<Vec<String>> {
+    // if the user has not specified a rpc url, use the default
+    if cmd.rpc_url.as_str() == "" {
+        cmd.rpc_url = configuration.rpc_url.clone();
+    }
+
+    // if the user has not specified an openrouter api key, use the default
+    if cmd.openrouter_api_key.as_str() == "" {
+        cmd.openrouter_api_key = configuration.openrouter_api_key.clone();
+    }
+
+    // if the user has not specified a model, use the default
+    if cmd.model.as_str() == "" {
+        cmd.model = configuration.openrouter_model.clone();
+    }
+
+    // if the user has not specified an etherscan api key, use the default
+    if cmd.etherscan_api_key.as_str() == "" {
+        cmd.etherscan_api_key = configuration.etherscan_api_key.clone();
+    }
+
+    // if the user has passed an output filename, override the default filename
+    let mut abi_filename: String = "abi.json".to_string();
+    let mut storage_layout_filename: String = "storage-layout.json".to_string();
+    let mut decompiled_output_filename: String = "decompiled".to_string();
+
+    let given_name = cmd.name.as_str();
+
+    if !given_name.is_empty() {
+        abi_filename = format!("{given_name}-{abi_filename}");
+        storage_layout_filename = format!("{given_name}-{storage_layout_filename}");
+        decompiled_output_filename = format!("{given_name}-{decompiled_output_filename}");
+    }
+
+    let result =
+        decompile(cmd.clone()).await.map_err(|e| eyre!("failed to decompile bytecode: {}", e))?;
+
+    let mut written = Vec::new();
+    if cmd.output == "print" {
+        let mut output_str = String::new();
+        output_str.push_str(&format!("ABI:\n\n{}\n", serde_json::to_string_pretty(&result.abi)?));
+
+        if let Some(source) = &result.source {
+            output_str.push_str(&format!("Source:\n\n{source}\n"));
+        }
+
+        print_with_less(&output_str)
+            .await
+            .map_err(|e| eyre!("failed to print decompiled bytecode: {}", e))?;
+    } else {
+        // write the contract ABI
+        let output_path = build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &abi_filename)
+            .await
+            .map_err(|e| eyre!("failed to build output path: {}", e))?;
+
+        write_file(&output_path, &serde_json::to_string_pretty(&result.abi)?)
+            .map_err(|e| eyre!("failed to write ABI: {}", e))?;
+        written.push(output_path);
+
+        // write the reconstructed storage layout
+        let output_path =
+            build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &storage_layout_filename)
+                .await
+                .map_err(|e| eyre!("failed to build output path: {}", e))?;
+
+        write_file(&output_path, &serde_json::to_string_pretty(&result.storage_layout)?)
+            .map_err(|e| eyre!("failed to write storage layout: {}", e))?;
+        written.push(output_path);
+
+        // write the contract source
+        if let Some(source) = &result.source {
+            let output_path = if cmd.include_solidity {
+                build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    &format!("{}.sol", &decompiled_output_filename),
+                )
+                .await
+                .map_err(|e| eyre!("failed to build output path: {}", e))?
+            } else {
+                build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    &format!("{}.yul", &decompiled_output_filename,),
+                )
+                .await
+                .map_err(|e| eyre!("failed to build output path: {}", e))?
+            };
+            write_file(&output_path, source).map_err(|e| eyre!("failed to write source: {}", e))?;
+            written.push(output_path);
+        }
+    }
+
+    Ok(written)
+}
+
+/// Decodes the target calldata, printing or writing the decoded call.
+///
+/// Returns the paths of the files written, which is empty if the output was printed.
+pub(crate) async fn run_decode(
+    mut cmd: DecodeArgs,
+    configuration: &Configuration,
+) -> Result<Vec<String>> {
+    // if the user has not specified a rpc url, use the default
+    if cmd.rpc_url.as_str() == "" {
+        cmd.rpc_url = configuration.rpc_url.clone();
+    }
+
+    // if the user has not specified an openrouter api key, use the default
+    if cmd.openrouter_api_key.as_str() == "" {
+        cmd.openrouter_api_key = configuration.openrouter_api_key.clone();
+    }
+
+    // if the user has not specified a model, use the default
+    if cmd.model.as_str() == "" {
+        cmd.model = configuration.openrouter_model.clone();
+    }
+
+    let result =
+        decode(cmd.clone()).await.map_err(|e| eyre!("failed to decode calldata: {}", e))?;
+
+    let mut written = Vec::new();
+    if cmd.output == "print" {
+        result.display()
+    } else {
+        let output_path = build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "decoded.json")
+            .await
+            .map_err(|e| eyre!("failed to build output path: {}", e))?;
+        write_file(&output_path, &result.to_json()?)
+            .map_err(|e| eyre!("failed to write decoded output: {}", e))?;
+        written.push(output_path);
+    }
+
+    Ok(written)
+}
+
+/// Generates the control flow graph of the target, printing or writing the rendered graph.
+///
+/// Returns the paths of the files written, which is empty if the output was printed.
+pub(crate) async fn run_cfg(
+    mut cmd: CfgArgs,
+    configuration: &Configuration,
+) -> Result<Vec<String>> {
+    // if the user has not specified a rpc url, use the default
+    if cmd.rpc_url.as_str() == "" {
+        cmd.rpc_url = configuration.rpc_url.clone();
+    }
+
+    // if the user has passed an output filename, override the default filename
+    let mut filename = format!("cfg.{}", cmd.format.extension());
+    let given_name = cmd.name.as_str();
+
+    if !given_name.is_empty() {
+        filename = format!("{given_name}-{filename}");
+    }
+    let cfg = cfg(cmd.clone()).await.map_err(|e| eyre!("failed to generate cfg: {}", e))?;
+    let stringified_cfg = cfg.render(cmd.format, cmd.color_edges);
+
+    let mut written = Vec::new();
+    if cmd.output == "print" {
+        print_with_less(&stringified_cfg).await.map_err(|e| eyre!("failed to print cfg: {}", e))?;
+    } else {
+        let output_path = build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+            .await
+            .map_err(|e| eyre!("failed to build output path: {}", e))?;
+        write_file(&output_path, &stringified_cfg)
+            .map_err(|e| eyre!("failed to write cfg: {}", e))?;
+        written.push(output_path);
+    }
+
+    Ok(written)
+}
+
+/// Dumps the storage of the target, printing or writing it as csv.
+///
+/// Returns the paths of the files written, which is empty if the output was printed.
+pub(crate) async fn run_dump(
+    mut cmd: DumpArgs,
+    configuration: &Configuration,
+) -> Result<Vec<String>> {
+    // if the user has not specified a rpc url, use the default
+    if cmd.rpc_url.as_str() == "" {
+        cmd.rpc_url = configuration.rpc_url.clone();
+    }
+
+    // if the user has passed an output filename, override the default filename
+    let mut filename = "dump.csv".to_string();
+    let given_name = cmd.name.as_str();
+
+    if !given_name.is_empty() {
+        filename = format!("{given_name}-{filename}");
+    }
+
+    let result = dump(cmd.clone()).await.map_err(|e| eyre!("failed to dump storage: {}", e))?;
+    let mut lines = Vec::new();
+
+    if result.layout.is_some() {
+        // add header
+        lines.push(String::from("slot,offset,label,type,value"));
+
+        // add decoded rows, followed by any slots the layout doesn't describe
+        for slot in &result.decoded {
+            lines.push(format!(
+                "{},{},{},{},{}",
+                slot.slot.to_lower_hex(),
+                slot.offset,
+                slot.label,
+                slot.type_label,
+                csv_escape(&slot.value)
+            ));
+        }
+        let decoded_slots: HashSet<_> = result.decoded.iter().map(|slot| slot.slot).collect();
+        for (slot, value) in
+            result.storage.iter().filter(|(slot, _)| !decoded_slots.contains(*slot))
+        {
+            lines.push(format!("{},0,,,{}", slot.to_lower_hex(), value.to_lower_hex()));
+        }
+    } else {
+        // add header
+        lines.push(String::from("slot,value"));
+
+        // add rows
+        for (slot, value) in result.storage {
+            lines.push(format!("{},{}", slot.to_lower_hex(), value.to_lower_hex()));
+        }
+    }
+
+    let mut written = Vec::new();
+    if cmd.output == "print" {
+        print_with_less(&lines.join("\n"))
+            .await
+            .map_err(|e| eyre!("failed to print dump: {}", e))?;
+    } else {
+        let output_path = build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+            .await
+            .map_err(|e| eyre!("failed to build output path: {}", e))?;
+
+        write_file(&output_path, &lines.join("\n"))
+            .map_err(|e| eyre!("failed to write dump: {}", e))?;
+        written.push(output_path);
+    }
+
+    Ok(written)
+}
+
+/// Inspects the target transaction, printing or writing its decoded trace. If `display` is set,
+/// the trace is also displayed as a tree, which batches skip since their targets run concurrently.
+///
+/// Returns the paths of the files written, which is empty if the output was printed.
+pub(crate) async fn run_inspect(
+    mut cmd: InspectArgs,
+    configuration: &Configuration,
+    display: bool,
+) -> Result<Vec<String>> {
+    // if the user has not specified a rpc url, use the default
+    if cmd.rpc_url.as_str() == "" {
+        cmd.rpc_url = configuration.rpc_url.clone();
+    }
+
+    // if the user has not specified a transpose api key, use the default
+    if cmd.transpose_api_key.is_empty() {
+        cmd.transpose_api_key = configuration.transpose_api_key.clone();
+    }
+
+    // if the user has passed an output filename, override the default filename
+    let mut filename = "decoded_trace.json".to_string();
+    let given_name = cmd.name.as_str();
+
+    if !given_name.is_empty() {
+        filename = format!("{given_name}-{filename}");
+    }
+
+    let inspect_result =
+        inspect(cmd.clone()).await.map_err(|e| eyre!("failed to inspect transaction: {}", e))?;
+    if display {
+        inspect_result.display();
+    }
+
+    let mut written = Vec::new();
+    if cmd.output == "print" {
+        let mut output_str = String::new();
+
+        output_str.push_str(&format!(
+            "Decoded Trace:\n\n{}\n",
+            serde_json::to_string_pretty(&inspect_result.decoded_trace)?
+        ));
+
+        print_with_less(&output_str)
+            .await
+            .map_err(|e| eyre!("failed to print decoded trace: {}", e))?;
+    } else {
+        // write decoded trace with serde
+        let output_path = build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+            .await
+            .map_err(|e| eyre!("failed to build output path: {}", e))?;
+
+        write_file(&output_path, &serde_json::to_string_pretty(&inspect_result.decoded_trace)?)
+            .map_err(|e| eyre!("failed to write decoded trace: {}", e))?;
+        written.push(output_path);
+    }
+
+    Ok(written)
+}
@@ -1,25 +1,28 @@
 //! The Heimdall CLI is a command line interface for interacting with Heimdall modules.
 
 pub(crate) mod args;
+pub(crate) mod batch;
+pub(crate) mod commands;
 pub(crate) mod output;
+pub(crate) mod serve;
 
 use args::{Arguments, Subcommands};
+use batch::batch;
 use clap::Parser;
+use commands::{run_cfg, run_decode, run_decompile, run_disassemble, run_dump, run_inspect};
 use eyre::{eyre, Result};
-use heimdall_cache::cache;
-use output::{build_output_path, print_with_less};
-use tracing::info;
-
-use heimdall_common::utils::{
-    hex::ToLowerHex,
-    io::file::write_file,
-    version::{current_version, remote_nightly_version, remote_version},
+use heimdall_cache::{cache, flush_cache};
+use serve::serve;
+use tracing::{debug, info};
+
+use heimdall_common::{
+    ether::signatures::signature_database,
+    utils::{
+        env::set_env,
+        version::{current_version, remote_nightly_version, remote_version},
+    },
 };
 use heimdall_config::{config, Configuration};
-use heimdall_core::{
-    heimdall_cfg::cfg, heimdall_decoder::decode, heimdall_decompiler::decompile,
-    heimdall_disassembler::disassemble, heimdall_dump::dump, heimdall_inspect::inspect,
-};
 
 #[allow(clippy::large_stack_frames)]
 #[tokio::main]
@@ -39,275 +42,49 @@ async fn main() -> Result<()> {
 
     let configuration =
         Configuration::load().map_err(|e| eyre!("failed to load configuration: {}", e))?;
-    match args.sub {
-        Subcommands::Disassemble(mut cmd) => {
-            // if the user has not specified a rpc url, use the default
-            if cmd.rpc_url.as_str() == "" {
-                cmd.rpc_url = configuration.rpc_url;
-            }
 
-            // if the user has passed an output filename, override the default filename
-            let mut filename: String = "disassembled.asm".to_string();
-            let given_name = cmd.name.as_str();
-
-            if !given_name.is_empty() {
-                filename = format!("{given_name}-{filename}");
-            }
-
-            let assembly = disassemble(cmd.clone())
-                .await
-                .map_err(|e| eyre!("failed to disassemble bytecode: {}", e))?;
+    // selector resolution reads its sources and local database from the environment
+    set_env("SIGNATURE_SOURCES", &configuration.signature_sources.join(","));
+    if !configuration.signature_database.is_empty() {
+        set_env("SIGNATURE_DATABASE", &configuration.signature_database);
+    }
 
-            if cmd.output == "print" {
-                print_with_less(&assembly)
-                    .await
-                    .map_err(|e| eyre!("failed to print assembly: {}", e))?;
-            } else {
-                let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?;
+    // the cache reads its size bound from the environment when it's first opened
+    if configuration.cache_max_size > 0 {
+        set_env("CACHE_MAX_SIZE", &configuration.cache_max_size.to_string());
+    }
 
-                write_file(&output_path, &assembly)
-                    .map_err(|e| eyre!("failed to write assembly: {}", e))?;
-            }
+    match args.sub {
+        Subcommands::Disassemble(cmd) => {
+            run_disassemble(cmd, &configuration).await?;
         }
 
-        Subcommands::Decompile(mut cmd) => {
-            // if the user has not specified a rpc url, use the default
-            if cmd.rpc_url.as_str() == "" {
-                cmd.rpc_url = configuration.rpc_url;
-            }
-
-            // if the user has not specified an openrouter api key, use the default
-            if cmd.openrouter_api_key.as_str() == "" {
-                cmd.openrouter_api_key = configuration.openrouter_api_key;
-            }
-
-            // if the user has not specified a model, use the default
-            if cmd.model.as_str() == "" {
-                cmd.model = configuration.openrouter_model;
-            }
-
-            // if the user has not specified an etherscan api key, use the default
-            if cmd.etherscan_api_key.as_str() == "" {
-                cmd.etherscan_api_key = configuration.etherscan_api_key;
-            }
-
-            // if the user has passed an output filename, override the default filename
-            let mut abi_filename: String = "abi.json".to_string();
-            let mut decompiled_output_filename: String = "decompiled".to_string();
-
-            let given_name = cmd.name.as_str();
-
-            if !given_name.is_empty() {
-                abi_filename = format!("{given_name}-{abi_filename}");
-                decompiled_output_filename = format!("{given_name}-{decompiled_output_filename}");
-            }
-
-            let result = decompile(cmd.clone())
-                .await
-                .map_err(|e| eyre!("failed to decompile bytecode: {}", e))?;
-
-            if cmd.output == "print" {
-                let mut output_str = String::new();
-                output_str
-                    .push_str(&format!("ABI:\n\n{}\n", serde_json::to_string_pretty(&result.abi)?));
-
-                if let Some(source) = &result.source {
-                    output_str.push_str(&format!("Source:\n\n{source}\n"));
-                }
-
-                print_with_less(&output_str)
-                    .await
-                    .map_err(|e| eyre!("failed to print decompiled bytecode: {}", e))?;
-            } else {
-                // write the contract ABI
-                let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &abi_filename)
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?;
-
-                write_file(&output_path, &serde_json::to_string_pretty(&result.abi)?)
-                    .map_err(|e| eyre!("failed to write ABI: {}", e))?;
-
-                // write the contract source
-                if let Some(source) = &result.source {
-                    let output_path = if cmd.include_solidity {
-                        build_output_path(
-                            &cmd.output,
-                            &cmd.target,
-                            &cmd.rpc_url,
-                            &format!("{}.sol", &decompiled_output_filename),
-                        )
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?
-                    } else {
-                        build_output_path(
-                            &cmd.output,
-                            &cmd.target,
-                            &cmd.rpc_url,
-                            &format!("{}.yul", &decompiled_output_filename,),
-                        )
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?
-                    };
-                    write_file(&output_path, source)
-                        .map_err(|e| eyre!("failed to write source: {}", e))?;
-                }
-            }
+        Subcommands::Decompile(cmd) => {
+            run_decompile(cmd, &configuration).await?;
         }
 
-        Subcommands::Decode(mut cmd) => {
-            // if the user has not specified a rpc url, use the default
-            if cmd.rpc_url.as_str() == "" {
-                cmd.rpc_url = configuration.rpc_url;
-            }
-
-            // if the user has not specified an openrouter api key, use the default
-            if cmd.openrouter_api_key.as_str() == "" {
-                cmd.openrouter_api_key = configuration.openrouter_api_key;
-            }
-
-            // if the user has not specified a model, use the default
-            if cmd.model.as_str() == "" {
-                cmd.model = configuration.openrouter_model.clone();
-            }
-
-            let result =
-                decode(cmd.clone()).await.map_err(|e| eyre!("failed to decode calldata: {}", e))?;
-
-            if cmd.output == "print" {
-                result.display()
-            } else {
-                let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "decoded.json")
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?;
-                write_file(&output_path, &result.to_json()?)
-                    .map_err(|e| eyre!("failed to write decoded output: {}", e))?;
-            }
+        Subcommands::Decode(cmd) => {
+            run_decode(cmd, &configuration).await?;
         }
 
-        Subcommands::Cfg(mut cmd) => {
-            // if the user has not specified a rpc url, use the default
-            if cmd.rpc_url.as_str() == "" {
-                cmd.rpc_url = configuration.rpc_url;
-            }
-
-            // if the user has passed an output filename, override the default filename
-            let mut filename = "cfg.dot".to_string();
-            let given_name = cmd.name.as_str();
-
-            if !given_name.is_empty() {
-                filename = format!("{given_name}-{filename}");
-            }
-            let cfg = cfg(cmd.clone()).await.map_err(|e| eyre!("failed to generate cfg: {}", e))?;
-            let stringified_dot = cfg.as_dot(cmd.color_edges);
-
-            if cmd.output == "print" {
-                print_with_less(&stringified_dot)
-                    .await
-                    .map_err(|e| eyre!("failed to print cfg: {}", e))?;
-            } else {
-                let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?;
-                write_file(&output_path, &stringified_dot)
-                    .map_err(|e| eyre!("failed to write cfg: {}", e))?;
-            }
+        Subcommands::Cfg(cmd) => {
+            run_cfg(cmd, &configuration).await?;
         }
 
-        Subcommands::Dump(mut cmd) => {
-            // if the user has not specified a rpc url, use the default
-            if cmd.rpc_url.as_str() == "" {
-                cmd.rpc_url = configuration.rpc_url;
-            }
-
-            // if the user has passed an output filename, override the default filename
-            let mut filename = "dump.csv".to_string();
-            let given_name = cmd.name.as_str();
-
-            if !given_name.is_empty() {
-                filename = format!("{given_name}-{filename}");
-            }
-
-            let result =
-                dump(cmd.clone()).await.map_err(|e| eyre!("failed to dump storage: {}", e))?;
-            let mut lines = Vec::new();
-
-            // add header
-            lines.push(String::from("slot,value"));
-
-            // add rows
-            for (slot, value) in result {
-                lines.push(format!("{},{}", slot.to_lower_hex(), value.to_lower_hex()));
-            }
-
-            if cmd.output == "print" {
-                print_with_less(&lines.join("\n"))
-                    .await
-                    .map_err(|e| eyre!("failed to print dump: {}", e))?;
-            } else {
-                let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?;
-
-                write_file(&output_path, &lines.join("\n"))
-                    .map_err(|e| eyre!("failed to write dump: {}", e))?;
-            }
+        Subcommands::Dump(cmd) => {
+            run_dump(cmd, &configuration).await?;
         }
 
-        Subcommands::Inspect(mut cmd) => {
-            // if the user has not specified a rpc url, use the default
-            if cmd.rpc_url.as_str() == "" {
-                cmd.rpc_url = configuration.rpc_url;
-            }
-
-            // if the user has not specified a transpose api key, use the default
-            if cmd.transpose_api_key.is_empty() {
-                cmd.transpose_api_key = configuration.transpose_api_key;
-            }
-
-            // if the user has passed an output filename, override the default filename
-            let mut filename = "decoded_trace.json".to_string();
-            let given_name = cmd.name.as_str();
-
-            if !given_name.is_empty() {
-                filename = format!("{given_name}-{filename}");
-            }
-
-            let inspect_result = inspect(cmd.clone())
-                .await
-                .map_err(|e| eyre!("failed to inspect transaction: {}", e))?;
-            inspect_result.display();
-
-            if cmd.output == "print" {
-                let mut output_str = String::new();
-
-                output_str.push_str(&format!(
-                    "Decoded Trace:\n\n{}\n",
-                    serde_json::to_string_pretty(&inspect_result.decoded_trace)?
-                ));
+        Subcommands::Inspect(cmd) => {
+            run_inspect(cmd, &configuration, true).await?;
+        }
 
-                print_with_less(&output_str)
-                    .await
-                    .map_err(|e| eyre!("failed to print decoded trace: {}", e))?;
-            } else {
-                // write decoded trace with serde
-                let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
-                        .await
-                        .map_err(|e| eyre!("failed to build output path: {}", e))?;
+        Subcommands::Batch(cmd) => {
+            batch(cmd, configuration).await.map_err(|e| eyre!("failed to run batch: {}", e))?;
+        }
 
-                write_file(
-                    &output_path,
-                    &serde_json::to_string_pretty(&inspect_result.decoded_trace)?,
-                )
-                .map_err(|e| eyre!("failed to write decoded trace: {}", e))?;
-            }
+        Subcommands::Serve(cmd) => {
+            serve(cmd, configuration).await.map_err(|e| eyre!("failed to serve: {}", e))?;
         }
 
         Subcommands::Config(cmd) => {
@@ -317,6 +94,17 @@ async fn main() -> Result<()> {
         Subcommands::Cache(cmd) => {
             cache(cmd).map_err(|e| eyre!("failed to manage cache: {}", e))?;
         }
+
+        Subcommands::Signatures(cmd) => {
+            signature_database(cmd)
+                .map_err(|e| eyre!("failed to manage signature database: {}", e))?;
+        }
+    }
+
+    // the cache's hit and miss counters and the recency of its reads aren't logged as they
+    // happen, so they're persisted once the command has finished
+    if let Err(e) = flush_cache() {
+        debug!("failed to flush cache: {}", e);
     }
 
     // check if the version is up to date
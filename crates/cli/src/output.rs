@@ -49,6 +49,15 @@ pub(crate) async fn print_with_less(input: &str) -> Result<()> {
     Ok(())
 }
 
+/// escape a field for inclusion in a csv row, quoting it if it contains a delimiter, quote or
+/// newline
+pub(crate) fn csv_escape(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,773 @@
+//! Serve mode, which exposes the heimdall modules over HTTP, so that services can call them
+//! without shelling out to the CLI. Each module can be called with JSON-RPC 2.0 at `/`, or by
+//! POSTing its params to `/{module}`. Requests share the configuration and the process' caches,
+//! which stay warm between requests.
+
+use std::{collections::BTreeMap, convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+
+use clap::{Args, ValueEnum};
+use eyre::{eyre, Result};
+use heimdall_cache::{flush_cache, with_store};
+use heimdall_common::utils::{hex::ToLowerHex, strings::decode_hex};
+use heimdall_config::Configuration;
+use heimdall_core::{
+    heimdall_cfg::{cfg, CfgArgs, CfgArgsBuilder, CfgFormat},
+    heimdall_decoder::{decode, DecodeArgs, DecodeArgsBuilder},
+    heimdall_decompiler::{decompile, DecompilerArgs, DecompilerArgsBuilder, HardFork},
+    heimdall_disassembler::{disassemble, DisassemblerArgs, DisassemblerArgsBuilder},
+    heimdall_dump::{dump, DumpArgs, DumpArgsBuilder},
+    heimdall_inspect::{inspect, InspectArgs, InspectArgsBuilder},
+};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::CONTENT_TYPE,
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, runtime::Handle, sync::Semaphore, time::timeout_at};
+use tracing::{debug, info, warn};
+
+/// The largest request body accepted, in bytes.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Args)]
+#[clap(override_usage = "heimdall serve [OPTIONS]")]
+pub(crate) struct ServeArgs {
+    /// The address to listen on.
+    ///
+    /// Clients can make the server read any file it has access to, by passing its path as a
+    /// target, and send requests to any URL, by passing it as the `rpc_url`. Only listen on an
+    /// address reachable by untrusted clients with `--restricted`.
+    #[clap(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// The port to listen on.
+    #[clap(long, short, default_value = "8080")]
+    pub port: u16,
+
+    /// The number of requests to run at once. Further requests wait for a slot until they time
+    /// out.
+    #[clap(long, short = 'j', default_value = "4")]
+    pub concurrency: usize,
+
+    /// The time a request may take, including waiting for a slot, in seconds.
+    #[clap(long, short, default_value = "300")]
+    pub timeout: u64,
+
+    /// Reject requests which target files or set the `rpc_url`, so that clients can only run the
+    /// modules over hex encoded targets, using the configured RPC provider.
+    #[clap(long)]
+    pub restricted: bool,
+}
+
+/// The state shared by every request.
+struct ServerState {
+    configuration: Configuration,
+    permits: Arc<Semaphore>,
+    timeout: Duration,
+    restricted: bool,
+}
+
+/// An error handling a request, reported as a JSON-RPC error or an HTTP status.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RequestError {
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("method not found: {0}")]
+    MethodNotFound(String),
+    #[error("invalid params: {0}")]
+    InvalidParams(String),
+    #[error("request timed out after {0}s")]
+    Timeout(u64),
+    #[error("{0}")]
+    Module(String),
+}
+
+impl RequestError {
+    /// The JSON-RPC error code of the error.
+    fn code(&self) -> i64 {
+        match self {
+            Self::Parse(_) => -32700,
+            Self::InvalidRequest(_) => -32600,
+            Self::MethodNotFound(_) => -32601,
+            Self::InvalidParams(_) => -32602,
+            Self::Timeout(_) => -32001,
+            Self::Module(_) => -32000,
+        }
+    }
+
+    /// The HTTP status of the error.
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::Parse(_) | Self::InvalidRequest(_) | Self::InvalidParams(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::MethodNotFound(_) => StatusCode::NOT_FOUND,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::Module(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// A JSON-RPC 2.0 request.
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A call to a module, with the arguments built from the request's params. Arguments which
+/// aren't given fall back to the builder's defaults, or the configuration.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Call {
+    Disassemble(DisassemblerArgs),
+    Decompile(DecompilerArgs),
+    Cfg(CfgArgs, bool),
+    Decode(DecodeArgs),
+    Dump(DumpArgs),
+    Inspect(InspectArgs),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DisassembleParams {
+    target: String,
+    rpc_url: Option<String>,
+    decimal_counter: Option<bool>,
+    json: Option<bool>,
+    hardfork: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DecompileParams {
+    target: String,
+    rpc_url: Option<String>,
+    skip_resolving: Option<bool>,
+    include_solidity: Option<bool>,
+    include_yul: Option<bool>,
+    timeout: Option<u64>,
+    total_timeout: Option<u64>,
+    llm_postprocess: Option<bool>,
+    model: Option<String>,
+    hardfork: Option<String>,
+    contracts: Option<Vec<String>>,
+    fork: Option<bool>,
+    fork_block: Option<u64>,
+    solver: Option<bool>,
+    no_cache: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CfgParams {
+    target: String,
+    rpc_url: Option<String>,
+    format: Option<String>,
+    color_edges: Option<bool>,
+    timeout: Option<u64>,
+    hardfork: Option<String>,
+    solver: Option<bool>,
+    no_cache: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DecodeParams {
+    target: String,
+    rpc_url: Option<String>,
+    explain: Option<bool>,
+    model: Option<String>,
+    constructor: Option<bool>,
+    truncate_calldata: Option<bool>,
+    skip_resolving: Option<bool>,
+    raw: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DumpParams {
+    target: String,
+    rpc_url: Option<String>,
+    from_block: Option<u128>,
+    to_block: Option<u128>,
+    decompile: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InspectParams {
+    target: String,
+    rpc_url: Option<String>,
+    skip_resolving: Option<bool>,
+    to: Option<String>,
+    from: Option<String>,
+    value: Option<String>,
+    block: Option<u64>,
+}
+
+/// Deserializes a module's params, which must be a JSON object.
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RequestError> {
+    serde_json::from_value(params).map_err(|e| RequestError::InvalidParams(e.to_string()))
+}
+
+/// Reports a module's arguments which failed to build as invalid params.
+fn build_error(e: impl std::fmt::Display) -> RequestError {
+    RequestError::InvalidParams(e.to_string())
+}
+
+/// Rejects targets which aren't hex encoded, i.e. file paths, if the server is restricted.
+fn check_target(target: &str, restricted: bool) -> Result<(), RequestError> {
+    if restricted && decode_hex(target.trim()).is_err() {
+        return Err(RequestError::InvalidParams(format!(
+            "target must be hex encoded on a restricted server: {target}"
+        )));
+    }
+    Ok(())
+}
+
+/// Parses the name of a hardfork, as accepted by `--hardfork`.
+fn parse_hardfork(hardfork: &str) -> Result<HardFork, RequestError> {
+    hardfork.parse().map_err(RequestError::InvalidParams)
+}
+
+impl Call {
+    /// Builds the call to `method` from its params, filling in any arguments which aren't given
+    /// from the configuration. If `restricted` is set, params which would read files or choose
+    /// the RPC provider are rejected.
+    fn parse(
+        method: &str,
+        params: Value,
+        configuration: &Configuration,
+        restricted: bool,
+    ) -> Result<Self, RequestError> {
+        let rpc_url = |rpc_url: Option<String>| match rpc_url {
+            Some(_) if restricted => Err(RequestError::InvalidParams(
+                "rpc_url can't be set on a restricted server".to_string(),
+            )),
+            Some(rpc_url) => Ok(rpc_url),
+            None => Ok(configuration.rpc_url.clone()),
+        };
+        let model =
+            |model: Option<String>| model.unwrap_or_else(|| configuration.openrouter_model.clone());
+
+        match method {
+            "disassemble" => {
+                let params: DisassembleParams = parse_params(params)?;
+                check_target(&params.target, restricted)?;
+                let mut builder = DisassemblerArgsBuilder::new();
+                builder
+                    .target(params.target)
+                    .rpc_url(rpc_url(params.rpc_url)?)
+                    .etherscan_api_key(configuration.etherscan_api_key.clone());
+                if let Some(decimal_counter) = params.decimal_counter {
+                    builder.decimal_counter(decimal_counter);
+                }
+                if let Some(json) = params.json {
+                    builder.json(json);
+                }
+                if let Some(hardfork) = params.hardfork {
+                    builder.hardfork(parse_hardfork(&hardfork)?);
+                }
+                Ok(Self::Disassemble(builder.build().map_err(build_error)?))
+            }
+            "decompile" => {
+                let params: DecompileParams = parse_params(params)?;
+                check_target(&params.target, restricted)?;
+                let mut builder = DecompilerArgsBuilder::new();
+                builder
+                    .target(params.target)
+                    .rpc_url(rpc_url(params.rpc_url)?)
+                    .openrouter_api_key(configuration.openrouter_api_key.clone())
+                    .model(model(params.model))
+                    .etherscan_api_key(configuration.etherscan_api_key.clone())
+                    .total_timeout(params.total_timeout)
+                    .fork_block(params.fork_block);
+                if let Some(skip_resolving) = params.skip_resolving {
+                    builder.skip_resolving(skip_resolving);
+                }
+                if let Some(include_solidity) = params.include_solidity {
+                    builder.include_solidity(include_solidity);
+                }
+                if let Some(include_yul) = params.include_yul {
+                    builder.include_yul(include_yul);
+                }
+                if let Some(timeout) = params.timeout {
+                    builder.timeout(timeout);
+                }
+                if let Some(llm_postprocess) = params.llm_postprocess {
+                    builder.llm_postprocess(llm_postprocess);
+                }
+                if let Some(hardfork) = params.hardfork {
+                    builder.hardfork(parse_hardfork(&hardfork)?);
+                }
+                if let Some(contracts) = params.contracts {
+                    for contract in &contracts {
+                        if let Some((_, target)) = contract.split_once('=') {
+                            check_target(target, restricted)?;
+                        }
+                    }
+                    builder.contracts(contracts);
+                }
+                if let Some(fork) = params.fork {
+                    builder.fork(fork);
+                }
+                if let Some(solver) = params.solver {
+                    builder.solver(solver);
+                }
+                if let Some(no_cache) = params.no_cache {
+                    builder.no_cache(no_cache);
+                }
+                Ok(Self::Decompile(builder.build().map_err(build_error)?))
+            }
+            "cfg" => {
+                let params: CfgParams = parse_params(params)?;
+                check_target(&params.target, restricted)?;
+                let mut builder = CfgArgsBuilder::new();
+                builder
+                    .target(params.target)
+                    .rpc_url(rpc_url(params.rpc_url)?)
+                    .etherscan_api_key(configuration.etherscan_api_key.clone());
+                if let Some(format) = params.format {
+                    builder.format(
+                        CfgFormat::from_str(&format, true).map_err(RequestError::InvalidParams)?,
+                    );
+                }
+                if let Some(timeout) = params.timeout {
+                    builder.timeout(timeout);
+                }
+                if let Some(hardfork) = params.hardfork {
+                    builder.hardfork(parse_hardfork(&hardfork)?);
+                }
+                if let Some(solver) = params.solver {
+                    builder.solver(solver);
+                }
+                if let Some(no_cache) = params.no_cache {
+                    builder.no_cache(no_cache);
+                }
+                Ok(Self::Cfg(
+                    builder.build().map_err(build_error)?,
+                    params.color_edges.unwrap_or(false),
+                ))
+            }
+            "decode" => {
+                let params: DecodeParams = parse_params(params)?;
+                check_target(&params.target, restricted)?;
+                let mut builder = DecodeArgsBuilder::new();
+                builder
+                    .target(params.target)
+                    .rpc_url(rpc_url(params.rpc_url)?)
+                    .openrouter_api_key(configuration.openrouter_api_key.clone())
+                    .model(model(params.model));
+                if let Some(explain) = params.explain {
+                    builder.explain(explain);
+                }
+                if let Some(constructor) = params.constructor {
+                    builder.constructor(constructor);
+                }
+                if let Some(truncate_calldata) = params.truncate_calldata {
+                    builder.truncate_calldata(truncate_calldata);
+                }
+                if let Some(skip_resolving) = params.skip_resolving {
+                    builder.skip_resolving(skip_resolving);
+                }
+                if let Some(raw) = params.raw {
+                    builder.raw(raw);
+                }
+                Ok(Self::Decode(builder.build().map_err(build_error)?))
+            }
+            "dump" => {
+                let params: DumpParams = parse_params(params)?;
+                check_target(&params.target, restricted)?;
+                let mut builder = DumpArgsBuilder::new();
+                builder.target(params.target).rpc_url(rpc_url(params.rpc_url)?);
+                if let Some(from_block) = params.from_block {
+                    builder.from_block(from_block);
+                }
+                if let Some(to_block) = params.to_block {
+                    builder.to_block(Some(to_block));
+                }
+                if let Some(decompile) = params.decompile {
+                    builder.decompile(decompile);
+                }
+                Ok(Self::Dump(builder.build().map_err(build_error)?))
+            }
+            "inspect" => {
+                let params: InspectParams = parse_params(params)?;
+                check_target(&params.target, restricted)?;
+                let mut builder = InspectArgsBuilder::new();
+                builder
+                    .target(params.target)
+                    .rpc_url(rpc_url(params.rpc_url)?)
+                    .transpose_api_key(configuration.transpose_api_key.clone())
+                    .to(params.to)
+                    .from(params.from)
+                    .block(params.block);
+                if let Some(skip_resolving) = params.skip_resolving {
+                    builder.skip_resolving(skip_resolving);
+                }
+                if let Some(value) = params.value {
+                    builder.value(value);
+                }
+                Ok(Self::Inspect(builder.build().map_err(build_error)?))
+            }
+            method => Err(RequestError::MethodNotFound(method.to_string())),
+        }
+    }
+
+    /// Bounds the module's symbolic execution to `remaining`, since a module running on a
+    /// blocking thread can't be cancelled once the request times out. Other modules spend their
+    /// time waiting on the RPC provider, so they're cancelled when the request times out.
+    ///
+    /// The bound is rounded down to whole seconds, so that it's usually the same between
+    /// requests and their results can be cached.
+    fn limit_to(&mut self, remaining: Duration) {
+        let remaining = remaining.as_secs() * 1000;
+        match self {
+            Self::Decompile(args) => {
+                args.timeout = args.timeout.min(remaining);
+                args.total_timeout =
+                    Some(args.total_timeout.map_or(remaining, |total| total.min(remaining)));
+            }
+            Self::Cfg(args, _) => args.timeout = args.timeout.min(remaining),
+            _ => {}
+        }
+    }
+
+    /// Runs the module, returning its result as JSON.
+    async fn run(self) -> Result<Value> {
+        match self {
+            Self::Disassemble(args) => {
+                let json = args.json;
+                let assembly = disassemble(args)
+                    .await
+                    .map_err(|e| eyre!("failed to disassemble bytecode: {}", e))?;
+
+                // json disassembly is returned as the instructions, rather than a string of them
+                let assembly = match json {
+                    true => serde_json::from_str(&assembly)?,
+                    false => Value::String(assembly),
+                };
+                Ok(json!({ "assembly": assembly }))
+            }
+            Self::Decompile(args) => {
+                let result = decompile(args)
+                    .await
+                    .map_err(|e| eyre!("failed to decompile bytecode: {}", e))?;
+                Ok(serde_json::to_value(result)?)
+            }
+            Self::Cfg(args, color_edges) => {
+                let format = args.format;
+                let result = cfg(args).await.map_err(|e| eyre!("failed to generate cfg: {}", e))?;
+                Ok(json!({
+                    "format": format.extension(),
+                    "graph": result.render(format, color_edges),
+                }))
+            }
+            Self::Decode(args) => {
+                let result =
+                    decode(args).await.map_err(|e| eyre!("failed to decode calldata: {}", e))?;
+                Ok(serde_json::from_str(&result.to_json()?)?)
+            }
+            Self::Dump(args) => {
+                let result =
+                    dump(args).await.map_err(|e| eyre!("failed to dump storage: {}", e))?;
+                let storage: BTreeMap<_, _> = result
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (slot.to_lower_hex(), value.to_lower_hex()))
+                    .collect();
+                Ok(json!({
+                    "storage": storage,
+                    "layout": result.layout,
+                    "decoded": result.decoded,
+                }))
+            }
+            Self::Inspect(args) => {
+                let result = inspect(args)
+                    .await
+                    .map_err(|e| eyre!("failed to inspect transaction: {}", e))?;
+                Ok(serde_json::to_value(result.decoded_trace)?)
+            }
+        }
+    }
+}
+
+/// Serves the modules until the process is interrupted.
+pub(crate) async fn serve(args: ServeArgs, configuration: Configuration) -> Result<()> {
+    // open the cache up front, so the first request doesn't pay for loading it
+    with_store(|_| Ok(())).map_err(|e| eyre!("failed to open cache: {}", e))?;
+
+    let listener = TcpListener::bind((args.host.as_str(), args.port))
+        .await
+        .map_err(|e| eyre!("failed to listen on {}:{}: {}", args.host, args.port, e))?;
+    let state = Arc::new(ServerState {
+        configuration,
+        permits: Arc::new(Semaphore::new(args.concurrency.max(1))),
+        timeout: Duration::from_secs(args.timeout),
+        restricted: args.restricted,
+    });
+    info!("listening on http://{}", listener.local_addr()?);
+
+    loop {
+        let (stream, remote) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => {
+                info!("shutting down");
+                return Ok(());
+            }
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle(request, remote, state.clone()));
+            if let Err(e) =
+                http1::Builder::new().serve_connection(TokioIo::new(stream), service).await
+            {
+                debug!("connection from {} failed: {}", remote, e);
+            }
+        });
+    }
+}
+
+/// Routes a request to the health check, the JSON-RPC endpoint, or a module's endpoint.
+async fn handle(
+    request: Request<Incoming>,
+    remote: SocketAddr,
+    state: Arc<ServerState>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().trim_matches('/').to_string();
+    debug!("{} {} /{}", remote, method, path);
+
+    let response = match (&method, path.as_str()) {
+        (&Method::GET, "health") => json_response(StatusCode::OK, &json!({ "status": "ok" })),
+        (&Method::POST, "") => {
+            let response = match read_json(request).await {
+                Ok(body) => handle_rpc(body, &state).await,
+                Err(e) => rpc_response(Value::Null, Err(e)),
+            };
+            json_response(StatusCode::OK, &response)
+        }
+        (&Method::POST, module) => {
+            let result = match read_json(request).await {
+                Ok(params) => call(module, params, &state).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(result) => json_response(StatusCode::OK, &result),
+                Err(e) => json_response(e.status(), &json!({ "error": e.to_string() })),
+            }
+        }
+        _ => json_response(StatusCode::NOT_FOUND, &json!({ "error": "not found" })),
+    };
+
+    Ok(response)
+}
+
+/// Handles a JSON-RPC request, returning the JSON-RPC response.
+async fn handle_rpc(body: Value, state: &ServerState) -> Value {
+    let request: RpcRequest = match serde_json::from_value(body) {
+        Ok(request) => request,
+        Err(e) => {
+            return rpc_response(Value::Null, Err(RequestError::InvalidRequest(e.to_string())))
+        }
+    };
+
+    let result = call(&request.method, request.params, state).await;
+    rpc_response(request.id, result)
+}
+
+/// Calls `method` with `params`, waiting for a free slot and timing out once the request has
+/// taken longer than the configured timeout. The module is bounded to the time left once it has
+/// a slot, see [`Call::limit_to`].
+///
+/// Modules are run on a blocking thread, so that cpu-bound modules run in parallel, and hold
+/// their slot until they've finished.
+async fn call(method: &str, params: Value, state: &ServerState) -> Result<Value, RequestError> {
+    let mut call = Call::parse(method, params, &state.configuration, state.restricted)?;
+    let deadline = tokio::time::Instant::now() + state.timeout;
+    let timed_out = || RequestError::Timeout(state.timeout.as_secs());
+
+    let permit = timeout_at(deadline, state.permits.clone().acquire_owned())
+        .await
+        .map_err(|_| timed_out())?
+        .map_err(|e| RequestError::Module(e.to_string()))?;
+    call.limit_to(deadline.saturating_duration_since(tokio::time::Instant::now()));
+
+    let handle = Handle::current();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let result = handle.block_on(timeout_at(deadline, call.run()));
+
+        // persist the cache's counters and recency, which would otherwise be lost when the
+        // server is stopped
+        if let Err(e) = flush_cache() {
+            debug!("failed to flush cache: {}", e);
+        }
+        result
+    })
+    .await
+    .map_err(|e| RequestError::Module(format!("module panicked: {e}")))?
+    .map_err(|_| timed_out())?;
+
+    result.map_err(|e| {
+        warn!("{} failed: {}", method, e);
+        RequestError::Module(e.to_string())
+    })
+}
+
+/// Reads the body of a request as JSON.
+async fn read_json(request: Request<Incoming>) -> Result<Value, RequestError> {
+    let body = Limited::new(request.into_body(), MAX_BODY_SIZE)
+        .collect()
+        .await
+        .map_err(|e| RequestError::Parse(e.to_string()))?
+        .to_bytes();
+
+    serde_json::from_slice(&body).map_err(|e| RequestError::Parse(e.to_string()))
+}
+
+/// Builds a JSON-RPC response with the given id, from the result of the call.
+fn rpc_response(id: Value, result: Result<Value, RequestError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code(), "message": e.to_string() },
+        }),
+    }
+}
+
+/// Builds an HTTP response with the given status and JSON body.
+fn json_response(status: StatusCode, body: &Value) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, "application/json".parse().expect("valid header"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> ServerState {
+        ServerState {
+            configuration: Configuration::default(),
+            permits: Arc::new(Semaphore::new(1)),
+            timeout: Duration::from_secs(30),
+            restricted: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_call() {
+        let configuration = Configuration::default();
+
+        let call = Call::parse(
+            "cfg",
+            json!({ "target": "0x00", "format": "json" }),
+            &configuration,
+            false,
+        )
+        .expect("failed to parse call");
+        assert!(matches!(call, Call::Cfg(args, false) if args.format == CfgFormat::Json));
+
+        assert!(matches!(
+            Call::parse("selfdestruct", json!({ "target": "0x00" }), &configuration, false),
+            Err(RequestError::MethodNotFound(_))
+        ));
+        assert!(matches!(
+            Call::parse(
+                "decompile",
+                json!({ "target": "0x00", "output": "/" }),
+                &configuration,
+                false
+            ),
+            Err(RequestError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            Call::parse(
+                "disassemble",
+                json!({ "target": "0x00", "hardfork": "x" }),
+                &configuration,
+                false
+            ),
+            Err(RequestError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_restricted_call() {
+        let configuration = Configuration::default();
+        let parse = |params| Call::parse("decompile", params, &configuration, true);
+
+        assert!(parse(json!({ "target": "0x6080604052", "include_solidity": true })).is_ok());
+        assert!(matches!(
+            parse(json!({ "target": "/etc/passwd" })),
+            Err(RequestError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            parse(json!({ "target": "0x00", "rpc_url": "http://localhost:8545" })),
+            Err(RequestError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            parse(json!({ "target": "0x00", "contracts": ["0x01=/etc/passwd"] })),
+            Err(RequestError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn test_limit_call() {
+        let configuration = Configuration::default();
+        let mut call = Call::parse("decompile", json!({ "target": "0x00" }), &configuration, false)
+            .expect("failed to parse call");
+
+        call.limit_to(Duration::from_millis(5500));
+        assert!(matches!(
+            call,
+            Call::Decompile(args) if args.timeout == 5000 && args.total_timeout == Some(5000)
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_disassemble() {
+        let response = handle_rpc(
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "disassemble",
+                "params": { "target": "0x6080604052" }
+            }),
+            &state(),
+        )
+        .await;
+
+        assert_eq!(response["id"], 1);
+        assert!(response["result"]["assembly"]
+            .as_str()
+            .expect("missing assembly")
+            .contains("PUSH1"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_error() {
+        let response =
+            handle_rpc(json!({ "jsonrpc": "2.0", "id": "a", "method": "nope" }), &state()).await;
+
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["error"]["code"], -32601);
+    }
+}
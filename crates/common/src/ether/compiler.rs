@@ -2,7 +2,10 @@
 
 use std::fmt::Display;
 
-use crate::{ether::bytecode::remove_pushbytes_from_bytecode, utils::iter::ByteSliceExt};
+use crate::{
+    ether::{bytecode::remove_pushbytes_from_bytecode, metadata::CompilerMetadata},
+    utils::iter::ByteSliceExt,
+};
 use tracing::{debug, trace, warn};
 
 /// Compiler enum to represent the compiler used to compile the contract.
@@ -65,9 +68,12 @@ pub fn detect_compiler(bytecode: &[u8]) -> (Compiler, String) {
         compiler = Compiler::Solc;
     }
 
-    // Remove `PUSHN [u8; n]` bytes so we are left with only operations
+    // Remove `PUSHN [u8; n]` bytes so we are left with only operations. Metadata appended to the
+    // code isn't operations either
+    let metadata = CompilerMetadata::find(bytecode);
+    let code_end = metadata.as_ref().map_or(bytecode.len(), |metadata| metadata.range.start);
     let pruned_bytecode =
-        remove_pushbytes_from_bytecode(alloy::primitives::Bytes::from_iter(bytecode))
+        remove_pushbytes_from_bytecode(alloy::primitives::Bytes::from_iter(&bytecode[..code_end]))
             .expect("invalid bytecode");
 
     // detect minimal proxies
@@ -110,10 +116,14 @@ pub fn detect_compiler(bytecode: &[u8]) -> (Compiler, String) {
         compiler = Compiler::Vyper;
     }
 
-    // Previously known heuristic: check for cbor encoded compiler metadata
-    // check for cbor encoded compiler metadata
+    // check for cbor encoded compiler metadata, which includes the exact version
     // https://cbor.io
-    if bytecode.contains_slice(&[0x73, 0x6f, 0x6c, 0x63, 0x43]) {
+    if let Some(metadata) = metadata.filter(|metadata| metadata.version.is_some()) {
+        compiler = metadata.compiler;
+        version = metadata.version.unwrap_or(version);
+
+        trace!("exact compiler version match found due to cbor encoded metadata: {}", version);
+    } else if bytecode.contains_slice(&[0x73, 0x6f, 0x6c, 0x63, 0x43]) {
         let compiler_version = bytecode.split_by_slice(&[0x73, 0x6f, 0x6c, 0x63, 0x43]);
 
         if compiler_version.len() > 1 {
@@ -233,6 +243,19 @@ mod test_compiler {
         assert_eq!(detect_compiler(bytecode), expected_result);
     }
 
+    #[test]
+    fn test_detect_compiler_decoded_metadata() {
+        // STOP, followed by metadata for solc 0.8.19 with an IPFS hash
+        let bytecode = crate::utils::strings::decode_hex(concat!(
+            "00a2646970667358221220",
+            "7d3cd6e4f6b1a0e6b0a6d1b5a4f8e5c0b0d7e0f3c0a3e1d6a2c4f0b7e8d9c2a164",
+            "736f6c63430008130033"
+        ))
+        .expect("failed to decode bytecode");
+        let expected_result = (Compiler::Solc, "0.8.19".to_string());
+        assert_eq!(detect_compiler(&bytecode), expected_result);
+    }
+
     #[test]
     fn test_detect_compiler_vyper_metadata() {
         let bytecode = &[0x76, 0x79, 0x70, 0x65, 0x72, 0x83, 0x31, 0x35, 0x35, 0x30, 0x30];
@@ -0,0 +1,312 @@
+//! Module for decoding the compiler metadata appended to bytecode.
+//!
+//! Research:
+//! https://docs.soliditylang.org/en/latest/metadata.html#encoding-of-the-metadata-hash-in-the-bytecode
+
+use std::ops::Range;
+
+use alloy::primitives::B256;
+
+use crate::{ether::compiler::Compiler, utils::strings::encode_hex};
+
+/// The keys which identify a CBOR map as compiler metadata.
+const METADATA_KEYS: [&str; 6] = ["ipfs", "bzzr0", "bzzr1", "solc", "vyper", "experimental"];
+
+/// Compiler metadata decoded from the CBOR map appended to the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilerMetadata {
+    /// The range of the metadata, including its two-byte length suffix
+    pub range: Range<usize>,
+
+    /// The compiler which produced the metadata
+    pub compiler: Compiler,
+
+    /// The version of the compiler, if the metadata includes it
+    pub version: Option<String>,
+
+    /// The IPFS hash of the metadata JSON, base58-encoded
+    pub ipfs: Option<String>,
+
+    /// The Swarm hash of the metadata JSON, from either the `bzzr0` or `bzzr1` key
+    pub swarm: Option<B256>,
+
+    /// Whether the contract was compiled with experimental features enabled
+    pub experimental: bool,
+}
+
+impl CompilerMetadata {
+    /// Finds the compiler metadata within `bytecode`. The metadata is followed by its length as a
+    /// two-byte big-endian integer, and is usually at the very end of runtime code. In creation
+    /// code the ABI-encoded constructor arguments follow it, so the end of each of their words is
+    /// tried too, starting from the end.
+    pub fn find(bytecode: &[u8]) -> Option<Self> {
+        (0..=bytecode.len()).rev().step_by(32).find_map(|end| Self::ending_at(bytecode, end))
+    }
+
+    /// Decodes the metadata whose two-byte length suffix ends at `end`.
+    fn ending_at(bytecode: &[u8], end: usize) -> Option<Self> {
+        let length = bytecode.get(end.checked_sub(2)?..end)?;
+        let length = usize::from(u16::from_be_bytes([length[0], length[1]]));
+        let start = (end - 2).checked_sub(length)?;
+
+        // metadata is either a map, or (for newer vyper versions) an array ending with one
+        if !matches!(bytecode[start], 0xa1..=0xa7 | 0x81..=0x87) {
+            return None;
+        }
+
+        let mut offset = start;
+        let value = CborValue::decode(&bytecode[..end - 2], &mut offset, 0)?;
+        if offset != end - 2 {
+            return None;
+        }
+
+        let entries = match value {
+            CborValue::Map(entries) => entries,
+            CborValue::Array(mut items) => match items.pop() {
+                Some(CborValue::Map(entries)) => entries,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Self::from_entries(start..end, entries)
+    }
+
+    /// Builds the metadata from the entries of its CBOR map. Returns `None` if none of the
+    /// entries are known metadata keys.
+    fn from_entries(range: Range<usize>, entries: Vec<(CborValue, CborValue)>) -> Option<Self> {
+        let mut metadata = Self {
+            range,
+            compiler: Compiler::Solc,
+            version: None,
+            ipfs: None,
+            swarm: None,
+            experimental: false,
+        };
+
+        let mut known = false;
+        for (key, value) in entries {
+            let CborValue::Text(key) = key else { return None };
+            known |= METADATA_KEYS.contains(&key.as_str());
+
+            match (key.as_str(), value) {
+                ("ipfs", CborValue::Bytes(hash)) => metadata.ipfs = Some(encode_base58(&hash)),
+                ("bzzr0" | "bzzr1", CborValue::Bytes(hash)) if hash.len() == 32 => {
+                    metadata.swarm = Some(B256::from_slice(&hash))
+                }
+                ("solc", CborValue::Bytes(version)) => {
+                    metadata.version =
+                        Some(version.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("."))
+                }
+
+                // prerelease builds of solc include the full version string
+                ("solc", CborValue::Text(version)) => metadata.version = Some(version),
+                ("vyper", CborValue::Array(version)) => {
+                    metadata.compiler = Compiler::Vyper;
+                    metadata.version = Some(
+                        version
+                            .iter()
+                            .map(|v| match v {
+                                CborValue::Uint(v) => v.to_string(),
+                                _ => String::from("?"),
+                            })
+                            .collect::<Vec<_>>()
+                            .join("."),
+                    );
+                }
+                ("experimental", CborValue::Bool(experimental)) => {
+                    metadata.experimental = experimental
+                }
+                _ => {}
+            }
+        }
+
+        known.then_some(metadata)
+    }
+
+    /// Returns a human-readable description of the metadata, one field per line.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "compiler: {} {}",
+            self.compiler,
+            self.version.as_deref().unwrap_or("unknown")
+        )];
+        if let Some(ipfs) = &self.ipfs {
+            lines.push(format!("ipfs: {ipfs}"));
+        }
+        if let Some(swarm) = &self.swarm {
+            lines.push(format!("swarm: 0x{}", encode_hex(swarm.as_slice())));
+        }
+        if self.experimental {
+            lines.push(String::from("experimental: true"));
+        }
+
+        lines
+    }
+}
+
+/// The subset of CBOR which compilers use to encode metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CborValue {
+    Uint(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    Map(Vec<(CborValue, CborValue)>),
+    Bool(bool),
+}
+
+impl CborValue {
+    /// Decodes the value beginning at `offset` of `bytes`, advancing `offset` past it. Returns
+    /// `None` for malformed values, and values nested deeper than metadata ever is.
+    fn decode(bytes: &[u8], offset: &mut usize, depth: usize) -> Option<Self> {
+        if depth > 3 {
+            return None;
+        }
+
+        let header = *bytes.get(*offset)?;
+        *offset += 1;
+        let (major, info) = (header >> 5, header & 0x1f);
+        let argument = match info {
+            0..=23 => info as u64,
+            24..=27 => {
+                let size = 1 << (info - 24);
+                let argument = bytes.get(*offset..*offset + size)?;
+                *offset += size;
+                argument.iter().fold(0u64, |acc, byte| (acc << 8) | *byte as u64)
+            }
+            _ => return None,
+        };
+
+        // every item is at least one byte, so longer lengths can't be valid
+        let remaining = (bytes.len() - *offset) as u64;
+        match major {
+            0 => Some(Self::Uint(argument)),
+            2 | 3 if argument <= remaining => {
+                let data = bytes[*offset..*offset + argument as usize].to_vec();
+                *offset += argument as usize;
+                match major {
+                    2 => Some(Self::Bytes(data)),
+                    _ => String::from_utf8(data).ok().map(Self::Text),
+                }
+            }
+            4 if argument <= remaining => (0..argument)
+                .map(|_| Self::decode(bytes, offset, depth + 1))
+                .collect::<Option<Vec<_>>>()
+                .map(Self::Array),
+            5 if argument <= remaining => (0..argument)
+                .map(|_| {
+                    Some((
+                        Self::decode(bytes, offset, depth + 1)?,
+                        Self::decode(bytes, offset, depth + 1)?,
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Self::Map),
+            7 if info == 20 || info == 21 => Some(Self::Bool(info == 21)),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes `bytes` with the base58 alphabet used by bitcoin and IPFS.
+fn encode_base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    // repeatedly divide the big-endian number by 58, collecting the remainders
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in bytes {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    // leading zero bytes are encoded as leading ones
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(digits.iter().rev().map(|digit| ALPHABET[*digit as usize] as char))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::strings::decode_hex;
+
+    use super::*;
+
+    /// `PUSH1 0x80, PUSH1 0x40, MSTORE, INVALID`, followed by solc 0.8.19 metadata
+    const SOLC_RUNTIME: &str = concat!(
+        "6080604052fe",
+        "a2646970667358221220",
+        "7d3cd6e4f6b1a0e6b0a6d1b5a4f8e5c0b0d7e0f3c0a3e1d6a2c4f0b7e8d9c2a164",
+        "736f6c63430008130033"
+    );
+
+    #[test]
+    fn test_find_solc_metadata() {
+        let bytecode = decode_hex(SOLC_RUNTIME).expect("failed to decode bytecode");
+        let metadata = CompilerMetadata::find(&bytecode).expect("no metadata");
+
+        assert_eq!(metadata.range, 6..bytecode.len());
+        assert_eq!(metadata.compiler, Compiler::Solc);
+        assert_eq!(metadata.version.as_deref(), Some("0.8.19"));
+        assert!(metadata.ipfs.expect("no ipfs hash").starts_with("Qm"));
+        assert!(!metadata.experimental);
+    }
+
+    #[test]
+    fn test_find_metadata_before_constructor_args() {
+        let bytecode = decode_hex(&format!("{SOLC_RUNTIME}{:064x}{:064x}", 42, 43))
+            .expect("failed to decode bytecode");
+        let metadata = CompilerMetadata::find(&bytecode).expect("no metadata");
+
+        assert_eq!(metadata.range.end, bytecode.len() - 64);
+    }
+
+    #[test]
+    fn test_find_bzzr0_metadata() {
+        // INVALID, LOG1 ... as in contracts compiled with solc 0.4
+        let bytecode = decode_hex(concat!(
+            "00fe",
+            "a165627a7a72305820",
+            "1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8",
+            "0029"
+        ))
+        .expect("failed to decode bytecode");
+        let metadata = CompilerMetadata::find(&bytecode).expect("no metadata");
+
+        assert_eq!(metadata.range.start, 2);
+        assert_eq!(metadata.version, None);
+        assert_eq!(metadata.swarm.expect("no swarm hash").as_slice()[..2], [0x1e, 0x7d]);
+    }
+
+    #[test]
+    fn test_find_vyper_metadata() {
+        // STOP, followed by vyper 0.3.7 metadata
+        let bytecode = decode_hex("00a165767970657283000307000b").expect("failed to decode");
+        let metadata = CompilerMetadata::find(&bytecode).expect("no metadata");
+
+        assert_eq!(metadata.compiler, Compiler::Vyper);
+        assert_eq!(metadata.version.as_deref(), Some("0.3.7"));
+    }
+
+    #[test]
+    fn test_find_no_metadata() {
+        let bytecode = decode_hex("608060405234801561000f575f5ffd5b50600400")
+            .expect("failed to decode bytecode");
+        assert_eq!(CompilerMetadata::find(&bytecode), None);
+    }
+
+    #[test]
+    fn test_encode_base58() {
+        assert_eq!(encode_base58(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(encode_base58(&[0, 0, 1]), "112");
+    }
+}
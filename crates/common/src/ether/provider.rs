@@ -2,11 +2,18 @@
 use alloy::{
     eips::BlockId,
     network::Ethereum,
-    primitives::{Address, TxHash},
-    providers::{ext::TraceApi, Provider, ProviderBuilder, RootProvider},
+    primitives::{Address, TxHash, U256},
+    providers::{
+        ext::{DebugApi, TraceApi},
+        Provider, ProviderBuilder, RootProvider,
+    },
     rpc::types::{
-        trace::parity::{TraceResults, TraceResultsWithTransactionHash, TraceType},
-        Filter, Log, Transaction,
+        trace::{
+            common::TraceResult,
+            geth::{GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace},
+            parity::{TraceResults, TraceResultsWithTransactionHash, TraceType},
+        },
+        Filter, Header, Log, Transaction, TransactionRequest,
     },
 };
 use eyre::Result;
@@ -52,6 +59,26 @@ impl MultiTransportProvider {
         Ok(self.provider.get_code_at(address).block_id(block).await?.to_vec())
     }
 
+    /// Get the value of a storage slot at the given address at a specific block.
+    pub async fn get_storage_at(
+        &self,
+        address: Address,
+        slot: U256,
+        block: BlockId,
+    ) -> Result<U256> {
+        Ok(self.provider.get_storage_at(address, slot).block_id(block).await?)
+    }
+
+    /// Get the balance of the given address at a specific block.
+    pub async fn get_balance(&self, address: Address, block: BlockId) -> Result<U256> {
+        Ok(self.provider.get_balance(address).block_id(block).await?)
+    }
+
+    /// Get the header of a specific block.
+    pub async fn get_block_header(&self, block: BlockId) -> Result<Option<Header>> {
+        Ok(self.provider.get_block(block).await?.map(|block| block.header))
+    }
+
     /// Get the transaction by hash.
     pub async fn get_transaction_by_hash(&self, tx_hash: TxHash) -> Result<Option<Transaction>> {
         Ok(self.provider.get_transaction_by_hash(tx_hash).await?)
@@ -84,6 +111,46 @@ impl MultiTransportProvider {
         Ok(trace_results)
     }
 
+    /// Traces every transaction in the block at the given number, using the tracer configured in
+    /// `trace_options`.
+    pub async fn debug_trace_block_by_number(
+        &self,
+        block_number: u64,
+        trace_options: GethDebugTracingOptions,
+    ) -> Result<Vec<TraceResult<GethTrace, String>>> {
+        Ok(self.provider.debug_trace_block_by_number(block_number.into(), trace_options).await?)
+    }
+
+    /// Simulates the given transaction on top of the given block.
+    /// The `trace_type` parameter is a list of the types of traces to return.
+    pub async fn trace_call(
+        &self,
+        request: &TransactionRequest,
+        block: BlockId,
+        trace_type: &[TraceType],
+    ) -> Result<TraceResults> {
+        Ok(self
+            .provider
+            .trace_call(request)
+            .block_id(block)
+            .trace_types(trace_type.to_vec())
+            .await?)
+    }
+
+    /// Simulates the given transaction on top of the given block, using the tracer configured in
+    /// `trace_options`.
+    pub async fn debug_trace_call(
+        &self,
+        request: TransactionRequest,
+        block: BlockId,
+        trace_options: GethDebugTracingOptions,
+    ) -> Result<GethTrace> {
+        Ok(self
+            .provider
+            .debug_trace_call(request, block, GethDebugTracingCallOptions::new(trace_options))
+            .await?)
+    }
+
     /// Get the logs that match the given filter.
     pub async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
         Ok(self.provider.get_logs(filter).await?)
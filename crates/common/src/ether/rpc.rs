@@ -3,15 +3,28 @@
 use crate::ether::provider::MultiTransportProvider;
 use alloy::{
     eips::{BlockId, BlockNumberOrTag},
-    primitives::{Address, TxHash},
+    primitives::{keccak256, Address, Bytes, TxHash, U256, U64},
     rpc::types::{
-        trace::parity::{TraceResults, TraceResultsWithTransactionHash, TraceType},
-        Filter, FilterBlockOption, FilterSet, Log, Transaction,
+        trace::{
+            common::TraceResult,
+            geth::{
+                CallConfig, CallFrame, DiffMode, GethDebugTracingOptions, GethTrace,
+                PreStateConfig, PreStateFrame,
+            },
+            parity::{
+                AccountDiff, Action, CallAction, CallOutput, CallType, ChangedType, CreateAction,
+                CreateOutput, CreationMethod, Delta, MemoryDelta, StateDiff, TraceOutput,
+                TraceResults, TraceResultsWithTransactionHash, TraceType, TransactionTrace,
+                VmExecutedOperation, VmInstruction, VmTrace,
+            },
+        },
+        Filter, FilterBlockOption, FilterSet, Header, Log, Transaction, TransactionRequest,
     },
 };
 use eyre::{bail, OptionExt, Result};
 use heimdall_cache::with_cache;
 use tokio_retry::{strategy::ExponentialBackoff, Retry};
+use tracing::{debug, warn};
 
 /// Get the chainId of the provided RPC URL
 ///
@@ -75,6 +88,101 @@ pub async fn get_code(contract_address: Address, rpc_url: &str) -> Result<Vec<u8
     .await
 }
 
+/// Get the bytecode of the provided contract address, at the given block (or the latest block if
+/// `None`)
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_code_at;
+///
+/// // let bytecode = get_code_at("0x0", Some(18_000_000), "https://eth.llamarpc.com").await;
+/// // assert!(bytecode.is_ok());
+/// ```
+///
+/// Note: only the bytecode at the latest block is cached
+pub async fn get_code_at(
+    contract_address: Address,
+    block: Option<u64>,
+    rpc_url: &str,
+) -> Result<Vec<u8>> {
+    if block.is_none() {
+        return get_code(contract_address, rpc_url).await;
+    }
+
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        provider.get_code_at_block(contract_address, block_id(block)).await
+    })
+    .await
+}
+
+/// Get the value of a storage slot of the provided contract address, at the given block (or the
+/// latest block if `None`)
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_storage_at;
+///
+/// // let value = get_storage_at("0x0", U256::ZERO, None, "https://eth.llamarpc.com").await;
+/// // assert!(value.is_ok());
+/// ```
+///
+/// Note: storage values are un-cacheable
+pub async fn get_storage_at(
+    contract_address: Address,
+    slot: U256,
+    block: Option<u64>,
+    rpc_url: &str,
+) -> Result<U256> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        provider.get_storage_at(contract_address, slot, block_id(block)).await
+    })
+    .await
+}
+
+/// Get the balance of the provided address, at the given block (or the latest block if `None`)
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_balance;
+///
+/// // let balance = get_balance("0x0", None, "https://eth.llamarpc.com").await;
+/// // assert!(balance.is_ok());
+/// ```
+///
+/// Note: balances are un-cacheable
+pub async fn get_balance(address: Address, block: Option<u64>, rpc_url: &str) -> Result<U256> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        provider.get_balance(address, block_id(block)).await
+    })
+    .await
+}
+
+/// Get the header of the given block (or the latest block if `None`)
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_block_header;
+///
+/// // let header = get_block_header(Some(1), "https://eth.llamarpc.com").await;
+/// // assert!(header.is_ok());
+/// ```
+///
+/// Note: [`Header`] is un-cacheable
+pub async fn get_block_header(block: Option<u64>, rpc_url: &str) -> Result<Header> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        provider.get_block_header(block_id(block)).await?.ok_or_eyre("block not found")
+    })
+    .await
+}
+
+/// Converts an optional block number into a [`BlockId`], defaulting to the latest block.
+fn block_id(block: Option<u64>) -> BlockId {
+    match block {
+        Some(number) => BlockId::Number(BlockNumberOrTag::Number(number)),
+        None => BlockId::Number(BlockNumberOrTag::Latest),
+    }
+}
+
 /// Get the raw transaction data of the provided transaction hash \
 ///
 /// ```no_run
@@ -105,20 +213,158 @@ pub async fn get_transaction(transaction_hash: TxHash, rpc_url: &str) -> Result<
 /// // assert!(trace.is_ok());
 /// ```
 ///
-/// Note: [`TraceResults`] is un-cacheable
+/// Note: traces are cached as JSON, since a mined transaction's trace never changes
 pub async fn get_trace(transaction_hash: &str, rpc_url: &str) -> Result<TraceResults> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+        let trace = with_cache(
+            &format!("trace.{}.{}", &chain_id, transaction_hash.to_lowercase()),
+            || async {
+                let provider = MultiTransportProvider::connect(rpc_url).await?;
+                let trace = provider
+                    .trace_replay_transaction(
+                        transaction_hash,
+                        &[TraceType::Trace, TraceType::VmTrace, TraceType::StateDiff],
+                    )
+                    .await?;
+                Ok(serde_json::to_string(&trace)?)
+            },
+        )
+        .await?;
+        Ok(serde_json::from_str(&trace)?)
+    })
+    .await
+}
+
+/// Simulate a transaction on top of the given block, returning the same traces as [`get_trace`].
+///
+/// `trace_call` is used if the node supports it. Otherwise, the trace and state diff are built
+/// from `debug_traceCall`'s `callTracer` and `prestateTracer`, which don't include a VM trace.
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_call_trace;
+/// use alloy::{eips::BlockId, rpc::types::TransactionRequest};
+///
+/// // let trace = get_call_trace(&TransactionRequest::default(), BlockId::latest(), "https://eth.llamarpc.com").await;
+/// // assert!(trace.is_ok());
+/// ```
+///
+/// Note: [`TraceResults`] is un-cacheable
+pub async fn get_call_trace(
+    request: &TransactionRequest,
+    block: BlockId,
+    rpc_url: &str,
+) -> Result<TraceResults> {
     Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
         let provider = MultiTransportProvider::connect(rpc_url).await?;
-        provider
-            .trace_replay_transaction(
-                transaction_hash,
+        match provider
+            .trace_call(
+                request,
+                block,
                 &[TraceType::Trace, TraceType::VmTrace, TraceType::StateDiff],
             )
             .await
+        {
+            Ok(trace) => return Ok(trace),
+            Err(e) => debug!("trace_call failed, falling back to debug_traceCall: {}", e),
+        }
+
+        let frame = match provider
+            .debug_trace_call(
+                request.clone(),
+                block,
+                GethDebugTracingOptions::call_tracer(CallConfig::default()),
+            )
+            .await?
+        {
+            GethTrace::CallTracer(frame) => frame,
+            _ => bail!("unexpected callTracer frame"),
+        };
+        let diff = match provider
+            .debug_trace_call(
+                request.clone(),
+                block,
+                GethDebugTracingOptions::prestate_tracer(PreStateConfig {
+                    diff_mode: Some(true),
+                    disable_code: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await?
+        {
+            GethTrace::PreStateTracer(PreStateFrame::Diff(diff)) => diff,
+            _ => bail!("unexpected prestateTracer frame"),
+        };
+
+        let mut trace = Vec::new();
+        flatten_call_frame(&frame, Vec::new(), &mut trace);
+        Ok(TraceResults {
+            output: frame.output.unwrap_or_default(),
+            state_diff: Some(prestate_to_state_diff(diff)),
+            trace,
+            vm_trace: None,
+        })
+    })
+    .await
+}
+
+/// Get the logs emitted by a simulated transaction via `debug_traceCall`, along with the trace
+/// address of the call which emitted each log.
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_call_logs;
+/// use alloy::{eips::BlockId, rpc::types::TransactionRequest};
+///
+/// // let logs = get_call_logs(&TransactionRequest::default(), BlockId::latest(), "https://eth.llamarpc.com").await;
+/// // assert!(logs.is_ok());
+/// ```
+pub async fn get_call_logs(
+    request: &TransactionRequest,
+    block: BlockId,
+    rpc_url: &str,
+) -> Result<Vec<(Vec<usize>, Log)>> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        let frame = match provider
+            .debug_trace_call(
+                request.clone(),
+                block,
+                GethDebugTracingOptions::call_tracer(CallConfig {
+                    with_log: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await?
+        {
+            GethTrace::CallTracer(frame) => frame,
+            _ => bail!("unexpected callTracer frame"),
+        };
+
+        let mut logs = Vec::new();
+        collect_call_frame_logs(&frame, Vec::new(), &mut logs);
+        Ok(logs)
     })
     .await
 }
 
+/// Collects the logs emitted by a `callTracer` frame and its children, along with the trace
+/// address of the frame which emitted each.
+fn collect_call_frame_logs(
+    frame: &CallFrame,
+    trace_address: Vec<usize>,
+    logs: &mut Vec<(Vec<usize>, Log)>,
+) {
+    logs.extend(frame.logs.iter().map(|log| {
+        (trace_address.clone(), Log { inner: log.clone().into_log(), ..Default::default() })
+    }));
+
+    for (i, call) in frame.calls.iter().enumerate() {
+        let mut child_address = trace_address.clone();
+        child_address.push(i);
+        collect_call_frame_logs(call, child_address, logs);
+    }
+}
+
 /// Get all logs for the given block number
 ///
 /// ```no_run
@@ -172,6 +418,318 @@ pub async fn get_block_state_diff(
     .await
 }
 
+/// Get all traces for the given block number, including both the state diff and the VM trace of
+/// each transaction
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_block_traces;
+///
+/// // let traces = get_block_traces(1, "https://eth.llamarpc.com").await;
+/// // assert!(traces.is_ok());
+/// ```
+///
+/// Note: [`TraceResultsWithTransactionHash`] is un-cacheable
+pub async fn get_block_traces(
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<Vec<TraceResultsWithTransactionHash>> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        provider
+            .trace_replay_block_transactions(
+                block_number,
+                &[TraceType::VmTrace, TraceType::StateDiff],
+            )
+            .await
+    })
+    .await
+}
+
+/// A javascript tracer which records the 64-byte preimages hashed by a transaction, which is how
+/// solc derives the slots of mapping values.
+const PREIMAGE_TRACER: &str = r#"{
+    preimages: [],
+    step: function(log) {
+        var op = log.op.toString();
+        if ((op == "SHA3" || op == "KECCAK256") && log.stack.peek(1).valueOf() == 64) {
+            var offset = log.stack.peek(0).valueOf();
+            this.preimages.push(toHex(log.memory.slice(offset, offset + 64)));
+        }
+    },
+    fault: function() {},
+    result: function() { return this.preimages; }
+}"#;
+
+/// A source of per-transaction state diffs for a block. Every source returns diffs in the format of
+/// parity's `trace_replayBlockTransactions`, so they can be used interchangeably.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateDiffSource {
+    /// `trace_replayBlockTransactions`, supported by erigon, reth and nethermind.
+    TraceReplay,
+    /// `debug_traceBlockByNumber` with the `prestateTracer` in diff mode, supported by geth and
+    /// most hosted endpoints.
+    PrestateTracer,
+}
+
+impl StateDiffSource {
+    /// Probes the node at `rpc_url` by tracing `block_number`, returning the first source it
+    /// supports.
+    ///
+    /// ```no_run
+    /// use heimdall_common::ether::rpc::StateDiffSource;
+    ///
+    /// // let source = StateDiffSource::detect(1, "https://eth.llamarpc.com").await?;
+    /// ```
+    pub async fn detect(block_number: u64, rpc_url: &str) -> Result<Self> {
+        for source in [Self::TraceReplay, Self::PrestateTracer] {
+            if source.get_block_state_diff(block_number, false, rpc_url).await.is_ok() {
+                return Ok(source);
+            }
+        }
+
+        bail!("rpc supports neither `trace_replayBlockTransactions` nor `debug_traceBlockByNumber`")
+    }
+
+    /// Get the state diff of each transaction in the given block. If `with_vm_trace` is set, the VM
+    /// trace of each transaction is included too, from which the preimages of hashed storage slots
+    /// can be recovered.
+    ///
+    /// Note: [`TraceResultsWithTransactionHash`] is un-cacheable
+    pub async fn get_block_state_diff(
+        &self,
+        block_number: u64,
+        with_vm_trace: bool,
+        rpc_url: &str,
+    ) -> Result<Vec<TraceResultsWithTransactionHash>> {
+        match (self, with_vm_trace) {
+            (Self::TraceReplay, false) => get_block_state_diff(block_number, rpc_url).await,
+            (Self::TraceReplay, true) => get_block_traces(block_number, rpc_url).await,
+            (Self::PrestateTracer, _) => {
+                get_block_prestate_diff(block_number, with_vm_trace, rpc_url).await
+            }
+        }
+    }
+}
+
+/// Get the state diff of each transaction in the given block via `debug_traceBlockByNumber` and the
+/// `prestateTracer`, converted into parity's format. If `with_vm_trace` is set, the block is traced
+/// again with [`PREIMAGE_TRACER`], and the preimages each transaction hashed are included as a VM
+/// trace (see [`preimage_vm_trace`]).
+async fn get_block_prestate_diff(
+    block_number: u64,
+    with_vm_trace: bool,
+    rpc_url: &str,
+) -> Result<Vec<TraceResultsWithTransactionHash>> {
+    Retry::spawn(ExponentialBackoff::from_millis(50).take(2), || async {
+        let provider = MultiTransportProvider::connect(rpc_url).await?;
+        let diffs = provider
+            .debug_trace_block_by_number(
+                block_number,
+                GethDebugTracingOptions::prestate_tracer(PreStateConfig {
+                    diff_mode: Some(true),
+                    disable_code: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        // nodes may not support javascript tracers, in which case no preimages are recovered
+        let preimages = match with_vm_trace {
+            true => provider
+                .debug_trace_block_by_number(
+                    block_number,
+                    GethDebugTracingOptions::js_tracer(PREIMAGE_TRACER),
+                )
+                .await
+                .inspect_err(|e| warn!("failed to trace preimages of block {block_number}: {e}"))
+                .unwrap_or_default(),
+            false => Vec::new(),
+        };
+
+        diffs
+            .into_iter()
+            .enumerate()
+            .map(|(i, diff)| {
+                let (diff, tx_hash) = match diff {
+                    TraceResult::Success {
+                        result: GethTrace::PreStateTracer(PreStateFrame::Diff(diff)),
+                        tx_hash,
+                    } => (diff, tx_hash),
+                    TraceResult::Success { .. } => bail!("unexpected prestateTracer frame"),
+                    TraceResult::Error { error, .. } => bail!("prestateTracer failed: {error}"),
+                };
+
+                let vm_trace = match preimages.get(i) {
+                    Some(TraceResult::Success { result, .. }) => {
+                        let preimages: Vec<Bytes> = serde_json::to_value(result)
+                            .and_then(serde_json::from_value)
+                            .unwrap_or_default();
+                        Some(preimage_vm_trace(&preimages))
+                    }
+                    _ => None,
+                };
+
+                Ok(TraceResultsWithTransactionHash {
+                    full_trace: TraceResults {
+                        output: Default::default(),
+                        state_diff: Some(prestate_to_state_diff(diff)),
+                        trace: Vec::new(),
+                        vm_trace,
+                    },
+                    transaction_hash: tx_hash.unwrap_or_default(),
+                })
+            })
+            .collect()
+    })
+    .await
+}
+
+/// Converts a `prestateTracer` diff into a parity state diff.
+///
+/// In diff mode, `pre` holds the changed accounts and slots before the transaction, while `post`
+/// holds only the fields which changed. Accounts which were deleted are missing from `post`, and
+/// slots which were cleared are missing from `post`'s storage.
+fn prestate_to_state_diff(diff: DiffMode) -> StateDiff {
+    fn delta<T: PartialEq + Clone>(pre: Option<&T>, post: Option<&T>, deleted: bool) -> Delta<T> {
+        match (pre, post) {
+            (Some(from), Some(to)) if from == to => Delta::Unchanged,
+            (Some(from), Some(to)) => {
+                Delta::Changed(ChangedType { from: from.clone(), to: to.clone() })
+            }
+            (None, Some(to)) => Delta::Added(to.clone()),
+            (Some(from), None) if deleted => Delta::Removed(from.clone()),
+            _ => Delta::Unchanged,
+        }
+    }
+
+    let addresses: Vec<Address> = diff.pre.keys().chain(diff.post.keys()).copied().collect();
+    let mut state_diff = StateDiff::default();
+    for address in addresses {
+        let pre = diff.pre.get(&address).cloned().unwrap_or_default();
+        let deleted = !diff.post.contains_key(&address);
+        let post = diff.post.get(&address).cloned().unwrap_or_default();
+
+        let storage = pre
+            .storage
+            .keys()
+            .chain(post.storage.keys())
+            .map(|slot| {
+                // a slot missing from `post` was cleared
+                let cleared = !post.storage.contains_key(slot);
+                (*slot, delta(pre.storage.get(slot), post.storage.get(slot), cleared))
+            })
+            .collect();
+
+        state_diff.insert(
+            address,
+            AccountDiff {
+                balance: delta(pre.balance.as_ref(), post.balance.as_ref(), deleted),
+                code: delta(pre.code.as_ref(), post.code.as_ref(), deleted),
+                nonce: delta(
+                    pre.nonce.map(U64::from).as_ref(),
+                    post.nonce.map(U64::from).as_ref(),
+                    deleted,
+                ),
+                storage,
+            },
+        );
+    }
+
+    state_diff
+}
+
+/// Builds a parity VM trace which hashes each of `preimages`, in order. Each preimage is written to
+/// memory by one instruction and hashed by the next, as if the transaction had been traced with
+/// `trace_replayBlockTransactions`.
+fn preimage_vm_trace(preimages: &[Bytes]) -> VmTrace {
+    let instruction = |op: &str, push: Vec<U256>, mem: Option<MemoryDelta>| VmInstruction {
+        cost: 0,
+        ex: Some(VmExecutedOperation { used: 0, push, mem, store: None }),
+        pc: 0,
+        sub: None,
+        op: Some(op.to_string()),
+        idx: None,
+    };
+
+    VmTrace {
+        code: Bytes::new(),
+        ops: preimages
+            .iter()
+            .flat_map(|preimage| {
+                [
+                    instruction(
+                        "MSTORE",
+                        Vec::new(),
+                        Some(MemoryDelta { off: 0, data: preimage.clone() }),
+                    ),
+                    instruction("KECCAK256", vec![keccak256(preimage).into()], None),
+                ]
+            })
+            .collect(),
+    }
+}
+
+/// Flattens a `callTracer` frame and its children into parity call traces, in depth-first order.
+fn flatten_call_frame(
+    frame: &CallFrame,
+    trace_address: Vec<usize>,
+    traces: &mut Vec<TransactionTrace>,
+) {
+    let gas = frame.gas.saturating_to::<u64>();
+    let gas_used = frame.gas_used.saturating_to::<u64>();
+    let output = frame.output.clone().unwrap_or_default();
+    let value = frame.value.unwrap_or_default();
+
+    let (action, result) = match frame.typ.as_str() {
+        "CREATE" | "CREATE2" => (
+            Action::Create(CreateAction {
+                from: frame.from,
+                gas,
+                init: frame.input.clone(),
+                value,
+                creation_method: match frame.typ.as_str() {
+                    "CREATE2" => CreationMethod::Create2,
+                    _ => CreationMethod::Create,
+                },
+            }),
+            TraceOutput::Create(CreateOutput {
+                address: frame.to.unwrap_or_default(),
+                code: output,
+                gas_used,
+            }),
+        ),
+        typ => (
+            Action::Call(CallAction {
+                from: frame.from,
+                call_type: match typ {
+                    "CALLCODE" => CallType::CallCode,
+                    "DELEGATECALL" => CallType::DelegateCall,
+                    "STATICCALL" => CallType::StaticCall,
+                    _ => CallType::Call,
+                },
+                gas,
+                input: frame.input.clone(),
+                to: frame.to.unwrap_or_default(),
+                value,
+            }),
+            TraceOutput::Call(CallOutput { gas_used, output }),
+        ),
+    };
+
+    traces.push(TransactionTrace {
+        action,
+        error: frame.error.clone(),
+        result: frame.error.is_none().then_some(result),
+        subtraces: frame.calls.len(),
+        trace_address: trace_address.clone(),
+    });
+
+    for (i, call) in frame.calls.iter().enumerate() {
+        let mut child_address = trace_address.clone();
+        child_address.push(i);
+        flatten_call_frame(call, child_address, traces);
+    }
+}
+
 /// Get the block number at which a contract was created using binary search.
 ///
 /// This function performs a binary search to find the earliest block at which
@@ -338,6 +896,55 @@ pub mod tests {
         assert_eq!(creation_block, 4719568);
     }
 
+    #[test]
+    fn test_prestate_to_state_diff() {
+        use alloy::{primitives::B256, rpc::types::trace::geth::AccountState};
+
+        let changed = address!("00000000000000000000000000000000000000aa");
+        let deleted = address!("00000000000000000000000000000000000000bb");
+        let slot = |n: u8| B256::with_last_byte(n);
+
+        let diff = DiffMode {
+            pre: [
+                (
+                    changed,
+                    AccountState {
+                        balance: Some(U256::from(10)),
+                        nonce: Some(1),
+                        storage: [(slot(1), slot(5)), (slot(2), slot(6))].into(),
+                        ..Default::default()
+                    },
+                ),
+                (deleted, AccountState { balance: Some(U256::from(3)), ..Default::default() }),
+            ]
+            .into(),
+            post: [(
+                changed,
+                AccountState {
+                    balance: Some(U256::from(7)),
+                    storage: [(slot(1), slot(9)), (slot(3), slot(4))].into(),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+        };
+
+        let state_diff = prestate_to_state_diff(diff);
+        let account = &state_diff[&changed];
+        assert_eq!(
+            account.balance,
+            Delta::Changed(ChangedType { from: U256::from(10), to: U256::from(7) })
+        );
+        assert_eq!(account.nonce, Delta::Unchanged);
+        assert_eq!(
+            account.storage[&slot(1)],
+            Delta::Changed(ChangedType { from: slot(5), to: slot(9) })
+        );
+        assert_eq!(account.storage[&slot(2)], Delta::Removed(slot(6)));
+        assert_eq!(account.storage[&slot(3)], Delta::Added(slot(4)));
+        assert_eq!(state_diff[&deleted].balance, Delta::Removed(U256::from(3)));
+    }
+
     #[tokio::test]
     async fn test_chain_id_with_ws_rpc() {
         let rpc_url = std::env::var("WS_RPC_URL").unwrap_or_else(|_| {
@@ -0,0 +1,465 @@
+//! A local, on-disk database of text signatures, used to resolve selectors without network
+//! access.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::keccak256;
+use alloy_json_abi::JsonAbi;
+use clap::Parser;
+use eyre::{eyre, Result};
+use heimdall_cache::{delete_cache, store::Namespace, with_store};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use crate::utils::{env::get_env, strings::encode_hex};
+
+use super::sources::SelectorKind;
+
+/// Clap argument parser for the signatures subcommand
+#[derive(Debug, Clone, Parser)]
+#[clap(
+    about = "Manage the local signature database",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    override_usage = "heimdall signatures <SUBCOMMAND>"
+)]
+pub struct SignatureDatabaseArgs {
+    /// Signatures subcommand
+    #[clap(subcommand)]
+    pub sub: SignatureDatabaseSubcommands,
+}
+
+/// Clap subcommand parser for signatures subcommands
+#[derive(Debug, Clone, Parser)]
+pub enum SignatureDatabaseSubcommands {
+    /// Import signatures into the local database
+    #[clap(
+        name = "import",
+        about = "Imports signatures from ABIs, Foundry artifacts, or 4byte/openchain dumps"
+    )]
+    Import(ImportArgs),
+
+    /// Look up a selector in the local database
+    #[clap(name = "lookup", about = "Looks up a function, event, or error selector locally")]
+    Lookup(LookupArgs),
+}
+
+/// Arguments for the `signatures import` subcommand
+#[derive(Debug, Clone, Parser)]
+pub struct ImportArgs {
+    /// The files or directories to import signatures from. Directories are searched recursively.
+    #[clap(required = true)]
+    pub paths: Vec<String>,
+}
+
+/// Arguments for the `signatures lookup` subcommand
+#[derive(Debug, Clone, Parser)]
+pub struct LookupArgs {
+    /// The 4-byte function or error selector, or 32-byte event selector, to look up.
+    #[clap(required = true)]
+    pub selector: String,
+}
+
+/// Signatures subcommand handler
+pub fn signature_database(args: SignatureDatabaseArgs) -> Result<()> {
+    let database = SignatureDatabase::open_default()?;
+
+    match args.sub {
+        SignatureDatabaseSubcommands::Import(args) => {
+            let mut imported = 0;
+            for path in args.paths {
+                imported += database.import(Path::new(&path))?;
+            }
+            info!("imported {} new signatures into {}", imported, database.root.display());
+        }
+        SignatureDatabaseSubcommands::Lookup(args) => {
+            let selector = args.selector.trim_start_matches("0x").to_lowercase();
+            let kinds = match selector.len() {
+                64 => vec![SelectorKind::Event],
+                _ => vec![SelectorKind::Function, SelectorKind::Error],
+            };
+
+            for kind in kinds {
+                for signature in database.lookup(kind, &selector)? {
+                    println!("{kind} {signature}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A local database of text signatures, keyed by selector.
+///
+/// Signatures are stored as `selector\tsignature` lines, sharded by kind and the first byte of the
+/// selector (e.g. `function/a9.tsv`), so a lookup only reads a small file.
+#[derive(Debug, Clone)]
+pub struct SignatureDatabase {
+    /// The directory the database is stored in.
+    pub root: PathBuf,
+}
+
+impl SignatureDatabase {
+    /// Opens the database stored in `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Opens the database at the path in the `SIGNATURE_DATABASE` environment variable, or at
+    /// `~/.bifrost/signatures` if it isn't set.
+    #[allow(deprecated)]
+    pub fn open_default() -> Result<Self> {
+        if let Some(path) = get_env("SIGNATURE_DATABASE").filter(|path| !path.is_empty()) {
+            return Ok(Self::new(path));
+        }
+
+        let mut home = std::env::home_dir().ok_or_else(|| {
+            eyre!("failed to get home directory. does your os support `std::env::home_dir()`?")
+        })?;
+        home.push(".bifrost");
+        home.push("signatures");
+        Ok(Self::new(home))
+    }
+
+    /// Returns the text signatures stored for `selector`, which is hex-encoded without a prefix.
+    pub fn lookup(&self, kind: SelectorKind, selector: &str) -> Result<Vec<String>> {
+        let selector = selector.trim_start_matches("0x").to_lowercase();
+        let Some(shard) = self.shard_path(kind, &selector) else {
+            return Ok(Vec::new());
+        };
+        if !shard.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(fs::read_to_string(&shard)?
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(entry, _)| *entry == selector)
+            .map(|(_, signature)| signature.to_string())
+            .collect())
+    }
+
+    /// Inserts text signatures into the database, computing their selectors. Returns the number of
+    /// signatures which weren't already present.
+    pub fn insert<I>(&self, signatures: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = (SelectorKind, String)>, {
+        // group the new entries by shard, so each shard is rewritten once
+        let mut shards: BTreeMap<PathBuf, BTreeSet<(String, String)>> = BTreeMap::new();
+        for (kind, signature) in signatures {
+            let signature: String = signature.chars().filter(|c| !c.is_whitespace()).collect();
+            let hash = keccak256(signature.as_bytes());
+            let selector = match kind {
+                SelectorKind::Event => encode_hex(hash.as_slice()),
+                _ => encode_hex(&hash[..4]),
+            };
+            if let Some(shard) = self.shard_path(kind, &selector) {
+                shards.entry(shard).or_default().insert((selector, signature));
+            }
+        }
+
+        let mut inserted = 0;
+        let mut selectors = BTreeSet::new();
+        for (shard, entries) in shards {
+            let mut existing: BTreeSet<(String, String)> = match shard.exists() {
+                true => fs::read_to_string(&shard)?
+                    .lines()
+                    .filter_map(|line| line.split_once('\t'))
+                    .map(|(selector, signature)| (selector.to_string(), signature.to_string()))
+                    .collect(),
+                false => BTreeSet::new(),
+            };
+
+            for entry in entries {
+                selectors.insert(entry.0.clone());
+                if existing.insert(entry) {
+                    inserted += 1;
+                }
+            }
+
+            if let Some(parent) = shard.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let contents: String = existing
+                .iter()
+                .map(|(selector, signature)| format!("{selector}\t{signature}\n"))
+                .collect();
+            fs::write(&shard, contents)?;
+        }
+
+        // resolutions are cached by selector, so drop any cached resolutions of the imported
+        // selectors in favor of the new signatures
+        let cached = with_store(|store| store.keys(Some(Namespace::Selectors))).unwrap_or_default();
+        for key in cached {
+            if key.split_once('.').is_some_and(|(_, selector)| selectors.contains(selector)) {
+                delete_cache(&key).ok();
+            }
+        }
+
+        Ok(inserted)
+    }
+
+    /// Imports signatures from a file or directory, returning the number of new signatures.
+    ///
+    /// Supported files are JSON ABIs, Foundry (or Hardhat) artifacts which contain an `abi`, and
+    /// text dumps (e.g. from 4byte or openchain) with one signature per line, which must have a
+    /// `.txt`, `.tsv` or `.csv` extension. Directories are searched recursively, skipping files of
+    /// any other type.
+    pub fn import(&self, path: &Path) -> Result<usize> {
+        if path.is_dir() {
+            let mut imported = 0;
+            let mut entries = fs::read_dir(path)?.filter_map(|e| e.ok()).collect::<Vec<_>>();
+            entries.sort_by_key(|entry| entry.path());
+            for entry in entries {
+                let path = entry.path();
+                if path.is_file() && ImportFormat::of(&path).is_none() {
+                    debug!("skipping '{}', which isn't a supported file type", path.display());
+                    continue;
+                }
+                imported += self.import(&path)?;
+            }
+            return Ok(imported);
+        }
+
+        let format = ImportFormat::of(path).ok_or_else(|| {
+            eyre!("cannot import '{}': expected a .json, .txt, .tsv or .csv file", path.display())
+        })?;
+        let contents = fs::read_to_string(path)
+            .map_err(|e| eyre!("failed to read '{}': {}", path.display(), e))?;
+        let signatures = match format {
+            ImportFormat::Json => match serde_json::from_str::<Value>(&contents) {
+                Ok(json) => match signatures_from_json(&json) {
+                    Some(signatures) => signatures,
+                    None => {
+                        debug!("skipping '{}', which isn't an abi or artifact", path.display());
+                        return Ok(0);
+                    }
+                },
+                Err(_) => {
+                    warn!("skipping '{}', which isn't valid json", path.display());
+                    return Ok(0);
+                }
+            },
+            ImportFormat::Text => signatures_from_text(&contents),
+        };
+
+        debug!("importing {} signatures from '{}'", signatures.len(), path.display());
+        self.insert(signatures)
+    }
+
+    /// Returns the path of the shard which holds `selector`.
+    fn shard_path(&self, kind: SelectorKind, selector: &str) -> Option<PathBuf> {
+        let prefix = selector.get(..2).filter(|p| p.chars().all(|c| c.is_ascii_hexdigit()))?;
+        Some(self.root.join(kind.to_string()).join(format!("{prefix}.tsv")))
+    }
+}
+
+/// The formats signatures can be imported from, identified by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportFormat {
+    /// A JSON ABI, or an artifact which contains one.
+    Json,
+    /// A text dump, with one signature per line.
+    Text,
+}
+
+impl ImportFormat {
+    /// Returns the format of the file at `path`, or `None` if its extension isn't supported.
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "txt" | "tsv" | "csv" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the signatures within a JSON ABI, or an artifact which contains one.
+fn signatures_from_json(json: &Value) -> Option<Vec<(SelectorKind, String)>> {
+    let abi = match json {
+        Value::Array(_) => json,
+        Value::Object(object) => object.get("abi")?,
+        _ => return None,
+    };
+    let abi: JsonAbi = serde_json::from_value(abi.clone()).ok()?;
+
+    Some(
+        abi.functions()
+            .map(|f| (SelectorKind::Function, f.signature()))
+            .chain(abi.events().map(|e| (SelectorKind::Event, e.signature())))
+            .chain(abi.errors().map(|e| (SelectorKind::Error, e.signature())))
+            .collect(),
+    )
+}
+
+/// Returns the signatures within a text dump, one per line. A line may hold other columns, such as
+/// a selector or id, and may name the kind of signature (e.g. `event Transfer(...)`). Signatures
+/// whose kind is unknown are imported as both functions and events.
+fn signatures_from_text(contents: &str) -> Vec<(SelectorKind, String)> {
+    contents
+        .lines()
+        .flat_map(|line| {
+            let Some((start, end)) = find_signature(line) else {
+                return Vec::new();
+            };
+            let signature = &line[start..end];
+
+            // the other columns may hold a selector, whose length gives away the kind
+            let prefix = line[..start].trim();
+            let selectors: Vec<usize> = line[..start]
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .chain(line[end..].split(|c: char| !c.is_ascii_alphanumeric()))
+                .filter_map(|column| column.strip_prefix("0x"))
+                .filter(|column| column.chars().all(|c| c.is_ascii_hexdigit()))
+                .map(str::len)
+                .collect();
+
+            let kind = if prefix.ends_with("event") || selectors.contains(&64) {
+                Some(SelectorKind::Event)
+            } else if prefix.ends_with("error") {
+                Some(SelectorKind::Error)
+            } else if prefix.ends_with("function") || selectors.contains(&8) {
+                Some(SelectorKind::Function)
+            } else {
+                None
+            };
+
+            match kind {
+                Some(kind) => vec![(kind, signature.to_string())],
+                None => vec![
+                    (SelectorKind::Function, signature.to_string()),
+                    (SelectorKind::Event, signature.to_string()),
+                ],
+            }
+        })
+        .collect()
+}
+
+/// Returns the byte range of the first `name(types)` signature in `line`, matching nested tuple
+/// parentheses.
+fn find_signature(line: &str) -> Option<(usize, usize)> {
+    let open = line.find('(')?;
+    let start = line[..open]
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    if start == open || line[start..].starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    for (i, c) in line[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, open + i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_database(name: &str) -> SignatureDatabase {
+        let root = std::env::temp_dir().join(format!(
+            "heimdall-signatures-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::remove_dir_all(&root).ok();
+        SignatureDatabase::new(root)
+    }
+
+    #[test]
+    fn test_import_text_dump() {
+        let database = temp_database("text");
+        let dump = database.root.with_extension("csv");
+        fs::create_dir_all(&database.root).expect("failed to create database");
+        fs::write(
+            &dump,
+            "id,text_signature,hex_signature\n\
+             1,transfer(address,uint256),0xa9059cbb\n\
+             2,\"Transfer(address,address,uint256)\",0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef\n\
+             3,approve(address,uint256)\n",
+        )
+        .expect("failed to write dump");
+
+        assert_eq!(database.import(&dump).expect("failed to import"), 4);
+        assert_eq!(
+            database.lookup(SelectorKind::Function, "a9059cbb").expect("failed to lookup"),
+            vec!["transfer(address,uint256)"]
+        );
+        assert_eq!(
+            database
+                .lookup(
+                    SelectorKind::Event,
+                    "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                )
+                .expect("failed to lookup"),
+            vec!["Transfer(address,address,uint256)"]
+        );
+        assert_eq!(
+            database.lookup(SelectorKind::Function, "0x095ea7b3").expect("failed to lookup"),
+            vec!["approve(address,uint256)"]
+        );
+
+        // importing again doesn't duplicate signatures
+        assert_eq!(database.import(&dump).expect("failed to import"), 0);
+
+        fs::remove_dir_all(&database.root).ok();
+        fs::remove_file(dump).ok();
+    }
+
+    #[test]
+    fn test_find_signature() {
+        let line = "0x1234abcd,swap((address,uint256)[],bytes),extra";
+        let (start, end) = find_signature(line).expect("no signature found");
+        assert_eq!(&line[start..end], "swap((address,uint256)[],bytes)");
+        assert_eq!(find_signature("id,text_signature"), None);
+    }
+
+    #[test]
+    fn test_import_artifact() {
+        let database = temp_database("artifact");
+        let artifacts = database.root.with_extension("out");
+        fs::create_dir_all(artifacts.join("Token.sol")).expect("failed to create artifacts");
+        fs::write(
+            artifacts.join("Token.sol").join("Token.json"),
+            r#"{"abi":[
+                {"type":"function","name":"balanceOf","inputs":[{"name":"a","type":"address"}],"outputs":[],"stateMutability":"view"},
+                {"type":"error","name":"Unauthorized","inputs":[]}
+            ],"bytecode":{"object":"0x"}}"#,
+        )
+        .expect("failed to write artifact");
+
+        // files of other types are skipped
+        fs::write(artifacts.join("Token.sol").join("Token.sol"), "function foo(uint256) {}")
+            .expect("failed to write source");
+
+        assert_eq!(database.import(&artifacts).expect("failed to import"), 2);
+        assert!(database.import(&artifacts.join("Token.sol").join("Token.sol")).is_err());
+        assert_eq!(
+            database.lookup(SelectorKind::Function, "70a08231").expect("failed to lookup"),
+            vec!["balanceOf(address)"]
+        );
+        assert_eq!(
+            database.lookup(SelectorKind::Error, "82b42900").expect("failed to lookup"),
+            vec!["Unauthorized()"]
+        );
+
+        fs::remove_dir_all(&database.root).ok();
+        fs::remove_dir_all(artifacts).ok();
+    }
+}
@@ -9,21 +9,24 @@ use async_trait::async_trait;
 
 use crate::{
     ether::types::{dyn_sol_types_to_strings, inputs_to_abi_format, parse_function_parameters},
-    utils::{
-        http::get_json_from_url,
-        io::{logging::TraceFactory, types::display},
-    },
+    utils::io::{logging::TraceFactory, types::display},
 };
-use eyre::{OptionExt, Result};
+use eyre::Result;
 use heimdall_cache::{store_cache, with_cache};
 use serde::{
     ser::{SerializeMap, Serializer},
     Deserialize, Serialize,
 };
-use tracing::{debug, trace};
+use tracing::debug;
 
 use super::types::DynSolValueExt;
 
+pub mod database;
+pub mod sources;
+
+pub use database::{signature_database, SignatureDatabase, SignatureDatabaseArgs};
+pub use sources::{resolve_text_signatures, SelectorKind, SignatureSource};
+
 /// A resolved function signature. May contain decoded inputs.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct ResolvedFunction {
@@ -155,72 +158,13 @@ pub trait ResolveSelector {
 #[async_trait]
 impl ResolveSelector for ResolvedError {
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>> {
-        with_cache(&format!("selector.{selector}"), || async {
-            // normalize selector
-            let selector = match selector.strip_prefix("0x") {
-                Some(selector) => selector,
-                None => selector,
-            };
-
-            trace!("resolving error selector {}", &selector);
-
-            // get function possibilities from openchain
-            let signatures = match get_json_from_url(
-                &format!(
-                    "https://api.openchain.xyz/signature-database/v1/lookup?filter=false&function=0x{}",
-                    &selector
-                ),
-                10,
-            )
-            .await?
-            {
-                Some(signatures) => signatures,
-                None => return Ok(None),
-            };
-
-            // convert the serde value into a vec of possible functions
-            let results = signatures
-                .get("result")
-                .and_then(|result| result.get("function"))
-                .and_then(|function| function.get(format!("0x{selector}")))
-                .and_then(|item| item.as_array())
-                .map(|array| array.to_vec())
-                .ok_or_eyre("error parsing signatures from openchain")?;
-
-            trace!("found {} possible functions for selector: {}", &results.len(), &selector);
-
-            let mut signature_list: Vec<ResolvedError> = Vec::new();
-
-            for signature in results {
-                // get the function text signature and unwrap it into a string
-                let text_signature = match signature.get("name") {
-                    Some(text_signature) => text_signature.to_string().replace('"', ""),
-                    None => continue,
-                };
-
-                // safely split the text signature into name and inputs
-                let function_parts = match text_signature.split_once('(') {
-                    Some(function_parts) => function_parts,
-                    None => continue,
-                };
-
-                // Parse the inputs using parse_function_parameters
-                let parsed_inputs = match parse_function_parameters(&text_signature) {
-                    Ok(inputs) => inputs,
-                    Err(_) => continue,
-                };
-
-                signature_list.push(ResolvedError {
-                    name: function_parts.0.to_string(),
-                    signature: text_signature.to_string(),
-                    inputs: dyn_sol_types_to_strings(&parsed_inputs),
-                });
-            }
-
-            Ok(match signature_list.len() {
-                0 => None,
-                _ => Some(signature_list),
-            })
+        with_cache(&format!("error.{selector}"), || async {
+            Ok(resolve_signatures(SelectorKind::Error, selector).await?.map(|signatures| {
+                signatures
+                    .into_iter()
+                    .map(|(name, signature, inputs)| ResolvedError { name, signature, inputs })
+                    .collect()
+            }))
         })
         .await
     }
@@ -229,72 +173,13 @@ impl ResolveSelector for ResolvedError {
 #[async_trait]
 impl ResolveSelector for ResolvedLog {
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>> {
-        with_cache(&format!("selector.{selector}"), || async {
-            // normalize selector
-            let selector = match selector.strip_prefix("0x") {
-                Some(selector) => selector,
-                None => selector,
-            };
-
-            trace!("resolving event selector {}", &selector);
-
-            // get function possibilities from openchain
-            let signatures = match get_json_from_url(
-                &format!(
-                "https://api.openchain.xyz/signature-database/v1/lookup?filter=false&event=0x{}",
-                &selector
-            ),
-                10,
-            )
-            .await?
-            {
-                Some(signatures) => signatures,
-                None => return Ok(None),
-            };
-
-            // convert the serde value into a vec of possible functions
-            let results = signatures
-                .get("result")
-                .and_then(|result| result.get("event"))
-                .and_then(|function| function.get(format!("0x{selector}")))
-                .and_then(|item| item.as_array())
-                .map(|array| array.to_vec())
-                .ok_or_eyre("error parsing signatures from openchain")?;
-
-            trace!("found {} possible functions for selector: {}", &results.len(), &selector);
-
-            let mut signature_list: Vec<ResolvedLog> = Vec::new();
-
-            for signature in results {
-                // get the function text signature and unwrap it into a string
-                let text_signature = match signature.get("name") {
-                    Some(text_signature) => text_signature.to_string().replace('"', ""),
-                    None => continue,
-                };
-
-                // safely split the text signature into name and inputs
-                let function_parts = match text_signature.split_once('(') {
-                    Some(function_parts) => function_parts,
-                    None => continue,
-                };
-
-                // Parse the inputs using parse_function_parameters
-                let parsed_inputs = match parse_function_parameters(&text_signature) {
-                    Ok(inputs) => inputs,
-                    Err(_) => continue,
-                };
-
-                signature_list.push(ResolvedLog {
-                    name: function_parts.0.to_string(),
-                    signature: text_signature.to_string(),
-                    inputs: dyn_sol_types_to_strings(&parsed_inputs),
-                });
-            }
-
-            Ok(match signature_list.len() {
-                0 => None,
-                _ => Some(signature_list),
-            })
+        with_cache(&format!("event.{selector}"), || async {
+            Ok(resolve_signatures(SelectorKind::Event, selector).await?.map(|signatures| {
+                signatures
+                    .into_iter()
+                    .map(|(name, signature, inputs)| ResolvedLog { name, signature, inputs })
+                    .collect()
+            }))
         })
         .await
     }
@@ -303,78 +188,57 @@ impl ResolveSelector for ResolvedLog {
 #[async_trait]
 impl ResolveSelector for ResolvedFunction {
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>> {
-        with_cache(&format!("selector.{selector}"), || async {
-            // normalize selector
-            let selector = match selector.strip_prefix("0x") {
-                Some(selector) => selector,
-                None => selector,
-            };
-
-            trace!("resolving function selector {}", &selector);
-
-            // get function possibilities from openchain
-            let signatures = match get_json_from_url(
-                &format!(
-                "https://api.openchain.xyz/signature-database/v1/lookup?filter=false&function=0x{}",
-                &selector
-            ),
-                10,
-            )
-            .await?
-            {
-                Some(signatures) => signatures,
-                None => return Ok(None),
-            };
-
-            // convert the serde value into a vec of possible functions
-            let results = signatures
-                .get("result")
-                .and_then(|result| result.get("function"))
-                .and_then(|function| function.get(format!("0x{selector}")))
-                .and_then(|item| item.as_array())
-                .map(|array| array.to_vec())
-                .ok_or_eyre("error parsing signatures from openchain")?;
-
-            trace!("found {} possible functions for selector: {}", &results.len(), &selector);
-
-            let mut signature_list: Vec<ResolvedFunction> = Vec::new();
-
-            for signature in results {
-                // get the function text signature and unwrap it into a string
-                let text_signature = match signature.get("name") {
-                    Some(text_signature) => text_signature.to_string().replace('"', ""),
-                    None => continue,
-                };
-
-                // safely split the text signature into name and inputs
-                let function_parts = match text_signature.split_once('(') {
-                    Some(function_parts) => function_parts,
-                    None => continue,
-                };
-
-                // Parse the inputs using parse_function_parameters
-                let parsed_inputs = match parse_function_parameters(&text_signature) {
-                    Ok(inputs) => inputs,
-                    Err(_) => continue,
-                };
-
-                signature_list.push(ResolvedFunction {
-                    name: function_parts.0.to_string(),
-                    signature: text_signature.to_string(),
-                    inputs: dyn_sol_types_to_strings(&parsed_inputs),
-                    decoded_inputs: None,
-                });
-            }
-
-            Ok(match signature_list.len() {
-                0 => None,
-                _ => Some(signature_list),
-            })
+        with_cache(&format!("function.{selector}"), || async {
+            Ok(resolve_signatures(SelectorKind::Function, selector).await?.map(|signatures| {
+                signatures
+                    .into_iter()
+                    .map(|(name, signature, inputs)| ResolvedFunction {
+                        name,
+                        signature,
+                        inputs,
+                        decoded_inputs: None,
+                    })
+                    .collect()
+            }))
         })
         .await
     }
 }
 
+/// Resolves `selector` from the configured [`SignatureSource`]s, and parses each text signature
+/// into its name, signature, and inputs. Signatures which fail to parse are skipped.
+async fn resolve_signatures(
+    kind: SelectorKind,
+    selector: &str,
+) -> Result<Option<Vec<(String, String, Vec<String>)>>> {
+    let signatures = match resolve_text_signatures(kind, selector).await? {
+        Some(signatures) => signatures,
+        None => return Ok(None),
+    };
+
+    let signature_list = signatures
+        .into_iter()
+        .filter_map(|text_signature| {
+            // safely split the text signature into name and inputs
+            let (name, _) = text_signature.split_once('(')?;
+
+            // Parse the inputs using parse_function_parameters
+            let parsed_inputs = parse_function_parameters(&text_signature).ok()?;
+
+            Some((
+                name.to_string(),
+                text_signature.clone(),
+                dyn_sol_types_to_strings(&parsed_inputs),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(match signature_list.len() {
+        0 => None,
+        _ => Some(signature_list),
+    })
+}
+
 /// Given the path to an ABI file, parses all [`ResolvedFunction`]s, [`ResolvedError`]s, and
 /// [`ResolvedLog`]s from the ABI and saves them to the cache.
 pub fn cache_signatures_from_abi(path: PathBuf) -> Result<()> {
@@ -403,7 +267,7 @@ pub fn cache_signatures_from_abi(path: PathBuf) -> Result<()> {
             decoded_inputs: None,
         };
 
-        store_cache(&format!("selector.{selector}"), Some(vec![resolved_function]), None).ok();
+        store_cache(&format!("function.{selector}"), Some(vec![resolved_function]), None).ok();
     });
     json_abi.events().for_each(|event| {
         let selector = event.selector().to_string().trim_start_matches("0x").to_string();
@@ -420,7 +284,7 @@ pub fn cache_signatures_from_abi(path: PathBuf) -> Result<()> {
 
         let resolved_log = ResolvedLog { name: event.name.clone(), signature, inputs };
 
-        store_cache(&format!("selector.{selector}"), Some(vec![resolved_log]), None).ok();
+        store_cache(&format!("event.{selector}"), Some(vec![resolved_log]), None).ok();
     });
     json_abi.errors().for_each(|error| {
         let selector = error.selector().to_string().trim_start_matches("0x").to_string();
@@ -437,7 +301,7 @@ pub fn cache_signatures_from_abi(path: PathBuf) -> Result<()> {
 
         let resolved_error = ResolvedError { name: error.name.clone(), signature, inputs };
 
-        store_cache(&format!("selector.{selector}"), Some(vec![resolved_error]), None).ok();
+        store_cache(&format!("error.{selector}"), Some(vec![resolved_error]), None).ok();
     });
 
     debug!(
@@ -558,7 +422,7 @@ mod tests {
     #[tokio::test]
     async fn resolve_function_signature_nominal() {
         let signature = String::from("095ea7b3");
-        let _ = delete_cache(&format!("selector.{}", &signature));
+        let _ = delete_cache(&format!("function.{}", &signature));
         let result = ResolvedFunction::resolve(&signature)
             .await
             .expect("failed to resolve signature")
@@ -569,7 +433,7 @@ mod tests {
     #[tokio::test]
     async fn resolve_multicall_signature() {
         let signature = String::from("1749e1e3");
-        let _ = delete_cache(&format!("selector.{}", &signature));
+        let _ = delete_cache(&format!("function.{}", &signature));
         let result = ResolvedFunction::resolve(&signature)
             .await
             .expect("failed to resolve signature")
@@ -587,7 +451,7 @@ mod tests {
     #[tokio::test]
     async fn resolve_error_signature_nominal() {
         let signature = String::from("30cd7471");
-        let _ = delete_cache(&format!("selector.{}", &signature));
+        let _ = delete_cache(&format!("error.{}", &signature));
         let result = ResolvedError::resolve(&signature)
             .await
             .expect("failed to resolve signature")
@@ -599,7 +463,7 @@ mod tests {
     async fn resolve_event_signature_nominal() {
         let signature =
             String::from("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
-        let _ = delete_cache(&format!("selector.{}", &signature));
+        let _ = delete_cache(&format!("event.{}", &signature));
         let result = ResolvedLog::resolve(&signature)
             .await
             .expect("failed to resolve signature")
@@ -618,7 +482,7 @@ mod tests {
     #[tokio::test]
     async fn resolve_function_signature_should_return_none_when_json_url_returns_empty_signatures()
     {
-        let _ = delete_cache(&format!("selector.{}", "test_signature"));
+        let _ = delete_cache(&format!("function.{}", "test_signature"));
         let signature = String::from("test_signature");
         let result = ResolvedFunction::resolve(&signature).await;
         assert!(result.is_err());
@@ -710,7 +574,7 @@ mod tests {
                                           // num_params = 1, num_dyn_params = 2 + 1 = 3
                                           // Without saturating_sub: 1 - 3 would underflow
                                           // With saturating_sub: 1.saturating_sub(3) = 0
-        let score = score_signature(&signature, Some(0));
+        let score = score_signature(signature, Some(0));
         // Should not panic, should return a valid score (greater than 0)
         assert!(score > 0);
     }
@@ -722,7 +586,7 @@ mod tests {
                                   // num_params = 1, num_dyn_params = 0, num_static_params = 1
                                   // If num_words = 10, then num_words - num_static_params = 9
                                   // This would reduce score by 90
-        let score = score_signature(&signature, Some(10));
+        let score = score_signature(signature, Some(10));
         // Should not panic and should be reduced appropriately
         // Initial score calculation:
         // - Start: 1000
@@ -742,7 +606,7 @@ mod tests {
         // num_dyn_params = bytes(3) + string(1) + [(1) = 5
         // Without saturating_sub: 3 - 5 would underflow
         // With saturating_sub: 3.saturating_sub(5) = 0
-        let score = score_signature(&signature, Some(2));
+        let score = score_signature(signature, Some(2));
         // Should not panic and should return a valid score
         // The score should be positive since we add 10 per param
         assert!(score > 0);
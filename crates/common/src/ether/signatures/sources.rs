@@ -0,0 +1,235 @@
+//! Sources from which text signatures can be resolved, and the chain they're queried in.
+
+use std::fmt::Display;
+
+use async_trait::async_trait;
+use eyre::{eyre, OptionExt, Result};
+use tracing::{debug, trace};
+
+use crate::utils::{env::get_env, http::get_json_from_url};
+
+use super::database::SignatureDatabase;
+
+/// The sources which are queried when `SIGNATURE_SOURCES` isn't set, in order.
+pub const DEFAULT_SIGNATURE_SOURCES: &str = "local,openchain";
+
+/// The kind of selector being resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SelectorKind {
+    /// A 4-byte function selector.
+    Function,
+    /// A 32-byte event selector.
+    Event,
+    /// A 4-byte custom error selector.
+    Error,
+}
+
+impl Display for SelectorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectorKind::Function => write!(f, "function"),
+            SelectorKind::Event => write!(f, "event"),
+            SelectorKind::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A source of text signatures, such as a local database or a public signature API.
+#[async_trait]
+pub trait SignatureSource: Send + Sync {
+    /// The name of the source, as used in the `signature_sources` configuration.
+    fn name(&self) -> &'static str;
+
+    /// Returns the text signatures matching `selector`, which is hex-encoded without a prefix.
+    /// Returns `None` if the source has no matches.
+    async fn lookup(&self, kind: SelectorKind, selector: &str) -> Result<Option<Vec<String>>>;
+}
+
+/// Resolves selectors from the local [`SignatureDatabase`].
+#[derive(Debug, Clone, Default)]
+pub struct LocalSource;
+
+#[async_trait]
+impl SignatureSource for LocalSource {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn lookup(&self, kind: SelectorKind, selector: &str) -> Result<Option<Vec<String>>> {
+        let database = SignatureDatabase::open_default()?;
+        let mut signatures = database.lookup(kind, selector)?;
+
+        // errors share their selector format with functions, and are often imported as such
+        if kind == SelectorKind::Error {
+            signatures.extend(database.lookup(SelectorKind::Function, selector)?);
+            signatures.sort_unstable();
+            signatures.dedup();
+        }
+
+        Ok((!signatures.is_empty()).then_some(signatures))
+    }
+}
+
+/// Resolves selectors from the openchain signature database.
+#[derive(Debug, Clone, Default)]
+pub struct OpenchainSource;
+
+#[async_trait]
+impl SignatureSource for OpenchainSource {
+    fn name(&self) -> &'static str {
+        "openchain"
+    }
+
+    async fn lookup(&self, kind: SelectorKind, selector: &str) -> Result<Option<Vec<String>>> {
+        // openchain stores errors alongside functions
+        let filter = match kind {
+            SelectorKind::Event => "event",
+            _ => "function",
+        };
+
+        let response = match get_json_from_url(
+            &format!(
+                "https://api.openchain.xyz/signature-database/v1/lookup?filter=false&{filter}=0x{selector}"
+            ),
+            10,
+        )
+        .await?
+        {
+            Some(response) => response,
+            None => return Ok(None),
+        };
+
+        let signatures = response
+            .get("result")
+            .and_then(|result| result.get(filter))
+            .and_then(|results| results.get(format!("0x{selector}")))
+            .and_then(|item| item.as_array())
+            .ok_or_eyre("error parsing signatures from openchain")?
+            .iter()
+            .filter_map(|signature| signature.get("name")?.as_str().map(str::to_string))
+            .collect::<Vec<_>>();
+
+        Ok((!signatures.is_empty()).then_some(signatures))
+    }
+}
+
+/// Resolves selectors from the 4byte.directory signature database.
+#[derive(Debug, Clone, Default)]
+pub struct FourByteSource;
+
+#[async_trait]
+impl SignatureSource for FourByteSource {
+    fn name(&self) -> &'static str {
+        "4byte"
+    }
+
+    async fn lookup(&self, kind: SelectorKind, selector: &str) -> Result<Option<Vec<String>>> {
+        let endpoint = match kind {
+            SelectorKind::Event => "event-signatures",
+            _ => "signatures",
+        };
+
+        let response = match get_json_from_url(
+            &format!("https://www.4byte.directory/api/v1/{endpoint}/?hex_signature=0x{selector}"),
+            10,
+        )
+        .await?
+        {
+            Some(response) => response,
+            None => return Ok(None),
+        };
+
+        let signatures = response
+            .get("results")
+            .and_then(|results| results.as_array())
+            .ok_or_eyre("error parsing signatures from 4byte")?
+            .iter()
+            .filter_map(|signature| signature.get("text_signature")?.as_str().map(str::to_string))
+            .collect::<Vec<_>>();
+
+        Ok((!signatures.is_empty()).then_some(signatures))
+    }
+}
+
+/// Returns the source with the given name, if one exists.
+pub fn signature_source(name: &str) -> Option<Box<dyn SignatureSource>> {
+    match name.trim().to_lowercase().as_str() {
+        "local" => Some(Box::new(LocalSource)),
+        "openchain" => Some(Box::new(OpenchainSource)),
+        "4byte" | "fourbyte" => Some(Box::new(FourByteSource)),
+        _ => None,
+    }
+}
+
+/// Returns the configured signature sources, in the order they should be queried. These are read
+/// from the comma-separated `SIGNATURE_SOURCES` environment variable, falling back to
+/// [`DEFAULT_SIGNATURE_SOURCES`].
+pub fn signature_sources() -> Vec<Box<dyn SignatureSource>> {
+    let names = get_env("SIGNATURE_SOURCES").unwrap_or_else(|| DEFAULT_SIGNATURE_SOURCES.into());
+
+    names
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .filter_map(|name| {
+            let source = signature_source(name);
+            if source.is_none() {
+                debug!("ignoring unknown signature source '{}'", name.trim());
+            }
+            source
+        })
+        .collect()
+}
+
+/// Resolves `selector` into text signatures by querying each configured source in order, returning
+/// the matches from the first source which has any. Sources which fail are skipped, though if none
+/// of the sources have a match, the first failure is returned.
+pub async fn resolve_text_signatures(
+    kind: SelectorKind,
+    selector: &str,
+) -> Result<Option<Vec<String>>> {
+    let selector = selector.trim_start_matches("0x").to_lowercase();
+    if selector.is_empty() || !selector.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(eyre!("invalid {} selector '{}'", kind, selector));
+    }
+
+    let mut error = None;
+    for source in signature_sources() {
+        trace!("resolving {} selector {} from {}", kind, selector, source.name());
+
+        match source.lookup(kind, &selector).await {
+            Ok(Some(signatures)) => {
+                trace!(
+                    "found {} possible {}s for selector {} in {}",
+                    signatures.len(),
+                    kind,
+                    selector,
+                    source.name()
+                );
+                return Ok(Some(signatures));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                debug!("failed to resolve {} from {}: {}", selector, source.name(), e);
+                error.get_or_insert(e);
+            }
+        }
+    }
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_source_names() {
+        assert_eq!(signature_source("local").map(|s| s.name()), Some("local"));
+        assert_eq!(signature_source(" OpenChain ").map(|s| s.name()), Some("openchain"));
+        assert_eq!(signature_source("4byte").map(|s| s.name()), Some("4byte"));
+        assert!(signature_source("etherscan").is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use backoff::ExponentialBackoff;
+use heimdall_cache::{read_cache, store_cache};
 use reqwest::header::HeaderMap;
 use serde_json::Value;
 use std::time::{Duration, Instant};
@@ -93,6 +94,12 @@ async fn call_transpose(query: &str, api_key: &str) -> Option<TransposeResponse>
 /// // let label = get_label(address, api_key).await;
 /// ```
 pub async fn get_label(address: &str, api_key: &str) -> Option<String> {
+    // labels rarely change, so they're cached in the labels namespace
+    let key = format!("label.{address}");
+    if let Ok(Some(label)) = read_cache::<String>(&key) {
+        return Some(label);
+    }
+
     // build the SQL query
     let query = format!(
             "{{\"sql\":\"SELECT COALESCE( (SELECT name FROM ethereum.contract_labels WHERE contract_address = '{address}' ), (SELECT ens_name FROM ethereum.ens_names WHERE primary_address = '{address}' LIMIT 1), (SELECT protocol_name FROM ethereum.protocols WHERE contract_address = '{address}' ), (SELECT symbol FROM ethereum.tokens WHERE contract_address = '{address}' ), (SELECT symbol FROM ethereum.collections WHERE contract_address = '{address}' ) ) as label\",\"parameters\":{{}},\"options\":{{\"timeout\": 999999999}}}}",
@@ -104,9 +111,14 @@ pub async fn get_label(address: &str, api_key: &str) -> Option<String> {
     trace!("fetching label for {address} took {:?}", start_time.elapsed());
 
     // parse the results
-    if let Some(result) = response.results.into_iter().next() {
-        return result.get("label").and_then(|label| label.as_str()).map(|label| label.to_string());
-    };
+    let label = response
+        .results
+        .into_iter()
+        .next()?
+        .get("label")
+        .and_then(|label| label.as_str())
+        .map(|label| label.to_string())?;
+    store_cache(&key, &label, None).ok();
 
-    None
+    Some(label)
 }
@@ -2,8 +2,15 @@ use crossbeam_channel::unbounded;
 
 use std::{sync::Arc, thread};
 
-/// A simple thread pool implementation that takes a vector of items, splits them into chunks, and
-/// processes each chunk in a separate thread. The results are collected and returned.
+/// The stack size of each worker thread. Work such as symbolic execution recurses deeply, so
+/// workers are given the stack size of the main thread rather than the smaller default of spawned
+/// threads.
+const WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// A simple thread pool implementation that takes a vector of items and processes them on
+/// `num_threads` worker threads. Workers pull items from a shared queue, so long-running items
+/// don't hold up the rest of the work. The results are collected and returned in no particular
+/// order.
 ///
 /// ```
 /// use heimdall_common::utils::threading::task_pool;
@@ -31,37 +38,38 @@ pub fn task_pool<
         return Vec::new();
     }
 
-    let (tx, rx) = unbounded();
-    let mut handles = Vec::new();
-
-    // Split items into chunks for each thread to process
-    let chunk_size = items.len().div_ceil(num_threads);
-    let chunks = items.chunks(chunk_size);
+    // queue up every item, workers will stop once the queue is drained
+    let (item_tx, item_rx) = unbounded();
+    let (result_tx, result_rx) = unbounded();
+    let item_count = items.len();
+    for item in items {
+        let _ = item_tx.send(item);
+    }
+    drop(item_tx);
 
     // Share ownership of f across threads with Arc
     let shared_f = Arc::new(f);
 
-    for chunk in chunks {
-        let chunk = chunk.to_owned();
-        let tx = tx.clone();
+    let mut handles = Vec::new();
+    for _ in 0..num_threads.clamp(1, item_count) {
+        let item_rx = item_rx.clone();
+        let result_tx = result_tx.clone();
         // Share ownership of shared_f with each thread with Arc
         let shared_f = Arc::clone(&shared_f);
-        let handle = thread::spawn(move || {
-            let chunk_results: Vec<R> = chunk.into_iter().map(|item| shared_f(item)).collect();
-            let _ = tx.send(chunk_results);
-        });
+        let handle = thread::Builder::new()
+            .stack_size(WORKER_STACK_SIZE)
+            .spawn(move || {
+                while let Ok(item) = item_rx.recv() {
+                    let _ = result_tx.send(shared_f(item));
+                }
+            })
+            .expect("failed to spawn worker thread");
         handles.push(handle);
     }
+    drop(result_tx);
 
-    // Wait for all threads to finish and collect the results
-    let mut results = Vec::new();
-    for _ in 0..num_threads {
-        let chunk_results = match rx.recv() {
-            Ok(chunk_results) => chunk_results,
-            Err(_) => continue,
-        };
-        results.extend(chunk_results);
-    }
+    // collect results until every worker has finished and dropped its sender
+    let results = result_rx.iter().collect();
 
     // Wait for all threads to finish
     for handle in handles {
@@ -105,6 +113,17 @@ mod tests {
         assert_eq!(results, expected_results);
     }
 
+    #[test]
+    fn test_task_pool_with_more_threads_than_items() {
+        let items = vec![1, 2, 3, 4, 5];
+        let num_threads = 4;
+        let expected_results = vec![2, 4, 6, 8, 10];
+
+        let mut results = task_pool(items, num_threads, |x: i32| x * 2);
+        results.sort();
+        assert_eq!(results, expected_results);
+    }
+
     #[test]
     fn test_task_pool_with_empty_items() {
         // Test case with empty items vector
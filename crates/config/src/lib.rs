@@ -53,6 +53,24 @@ pub struct Configuration {
     /// The default model to use for OpenRouter LLM calls (e.g., "openai/gpt-4o-mini")
     #[serde(default)]
     pub openrouter_model: String,
+
+    /// The sources queried when resolving selectors, in order (e.g. `["local", "openchain"]`)
+    #[serde(default = "default_signature_sources")]
+    pub signature_sources: Vec<String>,
+
+    /// The directory of the local signature database. Defaults to `~/.bifrost/signatures`
+    #[serde(default)]
+    pub signature_database: String,
+
+    /// The maximum size of the cache in bytes, beyond which the least recently used objects are
+    /// evicted. Defaults to 512 MB if 0
+    #[serde(default)]
+    pub cache_max_size: u64,
+}
+
+/// The default signature sources: the local database, then openchain.
+fn default_signature_sources() -> Vec<String> {
+    vec!["local".to_string(), "openchain".to_string()]
 }
 
 impl Default for Configuration {
@@ -64,6 +82,9 @@ impl Default for Configuration {
             transpose_api_key: "".to_string(),
             openrouter_api_key: "".to_string(),
             openrouter_model: "".to_string(),
+            signature_sources: default_signature_sources(),
+            signature_database: "".to_string(),
+            cache_max_size: 0,
         }
     }
 }
@@ -194,6 +215,23 @@ impl Configuration {
             "openrouter_model" => {
                 self.openrouter_model = value.to_string();
             }
+            "signature_sources" => {
+                self.signature_sources = value
+                    .split(',')
+                    .map(|source| source.trim().to_string())
+                    .filter(|source| !source.is_empty())
+                    .collect();
+            }
+            "signature_database" => {
+                self.signature_database = value.to_string();
+            }
+            "cache_max_size" => {
+                self.cache_max_size = value.parse().map_err(|_| {
+                    Error::ParseError(format!(
+                        "invalid cache_max_size: \'{value}\' is not a number of bytes."
+                    ))
+                })?;
+            }
             _ => {
                 return Err(Error::Generic(format!(
                     "invalid key: \'{key}\' is not a valid configuration key."
@@ -256,6 +294,9 @@ mod tests {
         assert_eq!(config.transpose_api_key, "");
         assert_eq!(config.openrouter_api_key, "");
         assert_eq!(config.openrouter_model, "");
+        assert_eq!(config.signature_sources, vec!["local", "openchain"]);
+        assert_eq!(config.signature_database, "");
+        assert_eq!(config.cache_max_size, 0);
     }
 
     // Test loading configuration from a file
@@ -300,6 +341,41 @@ mod tests {
         assert_eq!(loaded_config.openrouter_model, "");
     }
 
+    // Test updating the signature sources
+    #[test]
+    #[serial]
+    fn test_update_signature_sources() {
+        Configuration::delete().expect("failed to delete config file");
+        let mut config = Configuration::default();
+
+        config
+            .update("signature_sources", "4byte, local,,openchain")
+            .expect("failed to update signature_sources");
+        assert_eq!(config.signature_sources, vec!["4byte", "local", "openchain"]);
+
+        let loaded_config = Configuration::load().expect("failed to load config file");
+        assert_eq!(loaded_config.signature_sources, vec!["4byte", "local", "openchain"]);
+
+        Configuration::delete().expect("failed to delete config file");
+    }
+
+    // Test updating the cache size bound
+    #[test]
+    #[serial]
+    fn test_update_cache_max_size() {
+        Configuration::delete().expect("failed to delete config file");
+        let mut config = Configuration::default();
+
+        config.update("cache_max_size", "1048576").expect("failed to update cache_max_size");
+        assert_eq!(config.cache_max_size, 1048576);
+        assert!(config.update("cache_max_size", "1 MB").is_err());
+
+        let loaded_config = Configuration::load().expect("failed to load config file");
+        assert_eq!(loaded_config.cache_max_size, 1048576);
+
+        Configuration::delete().expect("failed to delete config file");
+    }
+
     // Test deleting configuration file
     #[test]
     #[serial]
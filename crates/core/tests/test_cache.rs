@@ -0,0 +1,64 @@
+//! Integration tests for caching the results of modules.
+
+#[cfg(test)]
+mod integration_tests {
+    use heimdall_cache::exists;
+    use heimdall_common::utils::strings::decode_hex;
+    use heimdall_decompiler::{decompile, DecompilerArgsBuilder, HardFork};
+
+    #[tokio::test]
+    async fn test_decompile_cached_by_bytecode_hash() {
+        // the cache is kept in a temporary directory, rather than the user's
+        let dir = std::env::temp_dir().join(format!("heimdall-test-cache-{}", std::process::id()));
+        std::env::set_var("CACHE_DIR", &dir);
+
+        let bytecode = "0x5f3560e01c63fdf80bda811861005d57602436103417610061576004358060a01c610061576040525f5c6002146100615760025f5d6040515a595f5f36365f8537835f8787f1905090509050610057573d5f5f3e3d5ffd5b60035f5d005b5f5ffd5b5f80fd";
+        let args = DecompilerArgsBuilder::new()
+            .target(bytecode.to_string())
+            .skip_resolving(true)
+            .include_yul(true)
+            .build()
+            .expect("failed to build args");
+
+        // the key depends on the bytecode and arguments, but not on how the target was given
+        let key = args
+            .cache_key(&decode_hex(bytecode).expect("invalid bytecode"), HardFork::Latest, None)
+            .await
+            .expect("caching is enabled");
+        assert!(key.starts_with("decompile."));
+
+        let result = decompile(args.clone()).await.expect("failed to decompile");
+        assert!(exists(&key).expect("failed to check cache"));
+
+        // the second run is read from the cache, and is identical to the first
+        let cached = decompile(args.clone()).await.expect("failed to decompile");
+        assert_eq!(cached.source, result.source);
+        assert_eq!(cached.abi, result.abi);
+        assert_eq!(cached.abi_with_details, result.abi_with_details);
+
+        // bypassing the cache, or changing arguments which affect the result, changes the key
+        let mut bypassed = args.clone();
+        bypassed.no_cache = true;
+        assert_eq!(bypassed.cache_key(&[], HardFork::Latest, None).await, None);
+        let mut resolved = args;
+        resolved.skip_resolving = false;
+        assert_ne!(
+            resolved
+                .cache_key(&decode_hex(bytecode).expect("invalid bytecode"), HardFork::Latest, None)
+                .await,
+            Some(key)
+        );
+
+        // postprocessing by an LLM isn't deterministic, so its output is never cached
+        let mut postprocessed = resolved;
+        postprocessed.llm_postprocess = true;
+        assert_eq!(
+            postprocessed
+                .cache_key(&decode_hex(bytecode).expect("invalid bytecode"), HardFork::Latest, None)
+                .await,
+            None
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
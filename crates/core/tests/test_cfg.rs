@@ -5,7 +5,7 @@ mod integration_tests {
     use memory_stats::memory_stats;
     use std::path::PathBuf;
 
-    use heimdall_cfg::{cfg, CfgArgs, CfgArgsBuilder, HardFork};
+    use heimdall_cfg::{cfg, CfgArgs, CfgArgsBuilder, CfgFormat, HardFork};
     use petgraph::dot::Dot;
     use serde_json::Value;
 
@@ -21,11 +21,14 @@ mod integration_tests {
             rpc_url,
             default: true,
             color_edges: false,
+            format: CfgFormat::Dot,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
             hardfork: HardFork::Latest,
             etherscan_api_key: String::from(""),
+            solver: false,
+            no_cache: true,
         })
         .await
         .expect("failed to generate cfg");
@@ -52,11 +55,14 @@ mod integration_tests {
             rpc_url,
             default: true,
             color_edges: false,
+            format: CfgFormat::Dot,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
             hardfork: HardFork::Latest,
             etherscan_api_key: String::from(""),
+            solver: false,
+            no_cache: true,
         })
         .await
         .expect("failed to generate cfg");
@@ -81,11 +87,14 @@ mod integration_tests {
             rpc_url,
             default: true,
             color_edges: false,
+            format: CfgFormat::Dot,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
             hardfork: HardFork::Auto,
             etherscan_api_key: String::from(""),
+            solver: false,
+            no_cache: true,
         })
         .await
         .expect("failed to generate cfg with auto hardfork");
@@ -107,11 +116,14 @@ mod integration_tests {
             rpc_url: String::from(""),
             default: true,
             color_edges: false,
+            format: CfgFormat::Dot,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
             hardfork: HardFork::Auto,
             etherscan_api_key: String::from(""),
+            solver: false,
+            no_cache: true,
         })
         .await
         .expect("failed to generate cfg with auto hardfork fallback");
@@ -4,7 +4,9 @@
 mod integration_tests {
     use std::{io::Write, path::PathBuf};
 
-    use heimdall_disassembler::{disassemble, DisassemblerArgs, DisassemblerArgsBuilder, HardFork};
+    use heimdall_disassembler::{
+        disassemble, disassemble_instructions, DisassemblerArgs, DisassemblerArgsBuilder, HardFork,
+    };
     use serde_json::Value;
 
     #[tokio::test]
@@ -16,6 +18,7 @@ mod integration_tests {
             target: bytecode.to_owned(),
             rpc_url: String::from(""),
             decimal_counter: false,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Latest,
@@ -36,6 +39,7 @@ mod integration_tests {
             target: bytecode.to_owned(),
             rpc_url: String::from(""),
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Latest,
@@ -56,6 +60,7 @@ mod integration_tests {
             target: bytecode.to_owned(),
             rpc_url: String::from(""),
             decimal_counter: false,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Latest,
@@ -76,6 +81,7 @@ mod integration_tests {
             target: bytecode.to_owned(),
             rpc_url: String::from(""),
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Latest,
@@ -96,6 +102,7 @@ mod integration_tests {
             target: bytecode.to_owned(),
             rpc_url: String::from(""),
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Latest,
@@ -120,6 +127,7 @@ mod integration_tests {
             target: String::from("test_disassemble_from_file"),
             rpc_url: String::from(""),
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Latest,
@@ -141,14 +149,15 @@ mod integration_tests {
             std::process::exit(0);
         });
 
-        // This contract was deployed before Fusaka, so use Pectra hardfork
-        // to show CLZ (0x1e) as unknown (it's part of the contract metadata)
-        let expected = String::from("0 PUSH1 80\n2 PUSH1 40\n4 MSTORE \n5 PUSH20 ffffffffffffffffffffffffffffffffffffffff\n26 PUSH1 00\n28 SLOAD \n29 AND \n30 CALLDATASIZE \n31 PUSH1 00\n33 DUP1 \n34 CALLDATACOPY \n35 PUSH1 00\n37 DUP1 \n38 CALLDATASIZE \n39 PUSH1 00\n41 DUP5 \n42 GAS \n43 DELEGATECALL \n44 RETURNDATASIZE \n45 PUSH1 00\n47 DUP1 \n48 RETURNDATACOPY \n49 PUSH1 00\n51 DUP2 \n52 EQ \n53 ISZERO \n54 PUSH1 3d\n56 JUMPI \n57 RETURNDATASIZE \n58 PUSH1 00\n60 REVERT \n61 JUMPDEST \n62 RETURNDATASIZE \n63 PUSH1 00\n65 RETURN \n66 INVALID \n67 LOG1 \n68 PUSH6 627a7a723058\n75 SHA3 \n76 unknown \n77 PUSH30 648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8\n108 STOP \n109 unknown \n");
+        // This contract was deployed before Fusaka, so use Pectra hardfork. The trailing bzzr0
+        // metadata is decoded rather than disassembled
+        let expected = String::from("0 PUSH1 80\n2 PUSH1 40\n4 MSTORE \n5 PUSH20 ffffffffffffffffffffffffffffffffffffffff\n26 PUSH1 00\n28 SLOAD \n29 AND \n30 CALLDATASIZE \n31 PUSH1 00\n33 DUP1 \n34 CALLDATACOPY \n35 PUSH1 00\n37 DUP1 \n38 CALLDATASIZE \n39 PUSH1 00\n41 DUP5 \n42 GAS \n43 DELEGATECALL \n44 RETURNDATASIZE \n45 PUSH1 00\n47 DUP1 \n48 RETURNDATACOPY \n49 PUSH1 00\n51 DUP2 \n52 EQ \n53 ISZERO \n54 PUSH1 3d\n56 JUMPI \n57 RETURNDATASIZE \n58 PUSH1 00\n60 REVERT \n61 JUMPDEST \n62 RETURNDATASIZE \n63 PUSH1 00\n65 RETURN \n66 INVALID \n\n; metadata (43 bytes)\n; compiler: solc unknown\n; swarm: 0x1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8\n; a165627a7a723058201e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db80029\n");
 
         let assembly = disassemble(DisassemblerArgs {
             target: String::from("0xafc2f2d803479a2af3a72022d54cc0901a0ec0d6"),
             rpc_url,
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Pectra,
@@ -173,6 +182,7 @@ mod integration_tests {
             target: String::from("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
             rpc_url,
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Auto,
@@ -196,6 +206,7 @@ mod integration_tests {
             target: bytecode.to_owned(),
             rpc_url: String::from(""),
             decimal_counter: true,
+            json: false,
             name: String::from(""),
             output: String::from(""),
             hardfork: HardFork::Auto,
@@ -207,6 +218,53 @@ mod integration_tests {
         assert_eq!(expected, assembly);
     }
 
+    #[tokio::test]
+    async fn test_disassemble_json() {
+        let bytecode = "608060405234801561000f575f5ffd5b50600400";
+
+        let assembly = disassemble(
+            DisassemblerArgsBuilder::new()
+                .target(bytecode.to_owned())
+                .json(true)
+                .build()
+                .expect("failed to build args"),
+        )
+        .await
+        .expect("failed to disassemble");
+        let disassembly: Value = serde_json::from_str(&assembly).expect("invalid json");
+        let instructions = &disassembly["instructions"];
+
+        assert_eq!(instructions.as_array().map(Vec::len), Some(15));
+        assert_eq!(instructions[6]["pc"], 0x08);
+        assert_eq!(instructions[6]["name"], "PUSH2");
+        assert_eq!(instructions[6]["immediate"], "0x000f");
+        assert_eq!(instructions[7]["jumpTargets"][0], 0x0f);
+        assert_eq!(instructions[7]["stackInputs"], 2);
+        assert_eq!(instructions[11]["block"], 2);
+        assert_eq!(disassembly["layout"]["code"]["size"], 20);
+        assert!(disassembly["layout"]["metadata"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_instructions() {
+        let bytecode = "366000600037611000600036600073";
+
+        let instructions = disassemble_instructions(
+            DisassemblerArgsBuilder::new()
+                .target(bytecode.to_owned())
+                .build()
+                .expect("failed to build args"),
+        )
+        .await
+        .expect("failed to disassemble");
+
+        // the trailing PUSH20 is cut short, so it isn't an instruction
+        assert_eq!(instructions.len(), 8);
+        assert_eq!(instructions[4].name, "PUSH2");
+        assert_eq!(instructions[4].immediate.to_vec(), vec![0x10, 0x00]);
+        assert!(instructions.iter().all(|instruction| instruction.block == 0));
+    }
+
     #[tokio::test]
     #[ignore]
     async fn heavy_integration_test() {
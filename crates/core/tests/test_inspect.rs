@@ -24,6 +24,10 @@ mod integration_tests {
             name: String::from(""),
             output: String::from("output"),
             skip_resolving: true,
+            to: None,
+            from: None,
+            value: String::from("0"),
+            block: None,
         };
 
         let _ = heimdall_inspect::inspect(args).await.expect("failed to inspect");
@@ -47,11 +51,36 @@ mod integration_tests {
             name: String::from(""),
             output: String::from("output"),
             skip_resolving: true,
+            to: None,
+            from: None,
+            value: String::from("0"),
+            block: None,
         };
 
         let _ = heimdall_inspect::inspect(args).await.expect("failed to inspect");
     }
 
+    #[tokio::test]
+    async fn test_inspect_simulated() {
+        let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| {
+            println!("RPC_URL not set, skipping test");
+            std::process::exit(0);
+        });
+
+        // WETH `totalSupply()`
+        let args = InspectArgsBuilder::new()
+            .target(String::from("0x18160ddd"))
+            .rpc_url(rpc_url)
+            .skip_resolving(true)
+            .to(Some(String::from("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2")))
+            .block(Some(18_000_000))
+            .build()
+            .expect("failed to build args");
+
+        let result = heimdall_inspect::inspect(args).await.expect("failed to inspect");
+        assert!(result.decoded_trace.subtraces.is_empty());
+    }
+
     /// Thorough testing for inspect across a large number of transactions.
     #[test]
     #[ignore]
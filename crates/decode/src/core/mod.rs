@@ -69,6 +69,7 @@ pub async fn decode(mut args: DecodeArgs) -> Result<DecodeResult, Error> {
 
     // if args.constructor is true, we need to extract the constructor arguments and use that
     // as the calldata
+    let mut constructor_match = None;
     if args.constructor {
         debug!("extracting constructor arguments from deployment bytecode.");
         warn!("the --constructor flag is in unstable, and will be improved in future releases.");
@@ -79,6 +80,19 @@ pub async fn decode(mut args: DecodeArgs) -> Result<DecodeResult, Error> {
             encode_hex(&constructor.arguments)
         );
 
+        // decode the arguments as the constructor's parameters, if they could be recovered
+        if !constructor.inputs.is_empty() {
+            let inputs =
+                constructor.inputs.iter().map(|input| input.to_string()).collect::<Vec<_>>();
+            debug!("recovered constructor parameters: ({})", inputs.join(","));
+            constructor_match = Some(ResolvedFunction {
+                name: String::from("constructor"),
+                signature: format!("constructor({})", inputs.join(",")),
+                inputs,
+                decoded_inputs: None,
+            });
+        }
+
         // prefix with four zero bytes to avoid selector issues
         calldata =
             [0x00, 0x00, 0x00, 0x00].into_iter().chain(constructor.arguments.into_iter()).collect();
@@ -112,7 +126,7 @@ pub async fn decode(mut args: DecodeArgs) -> Result<DecodeResult, Error> {
             _ => Vec::new(),
         }
     } else {
-        Vec::new()
+        constructor_match.into_iter().collect()
     };
     debug!("resolving potential matches took {:?}", start_resolve_time.elapsed());
     if !potential_matches.is_empty() {
@@ -1,8 +1,14 @@
+use alloy::primitives::Address;
+use alloy_dyn_abi::DynSolType;
 use eyre::{eyre, OptionExt, Result};
 use heimdall_common::{
     constants::CONSTRUCTOR_REGEX,
     utils::strings::{decode_hex, encode_hex},
 };
+use heimdall_vm::{
+    core::vm::VM,
+    ext::{creation::deploy, layout::BytecodeLayout},
+};
 
 #[derive(Debug, Clone)]
 pub(crate) struct Constructor {
@@ -10,9 +16,38 @@ pub(crate) struct Constructor {
     pub _contract: Vec<u8>,
     pub _metadata: Vec<u8>,
     pub arguments: Vec<u8>,
+
+    /// the types of the constructor's parameters, if they could be recovered by executing it
+    pub inputs: Vec<DynSolType>,
 }
 
 pub(crate) fn parse_deployment_bytecode(input: Vec<u8>) -> Result<Constructor> {
+    // executing the constructor locates the runtime code and arguments regardless of how the
+    // creation bytecode is laid out, and recovers the types of the constructor's parameters
+    let vm = VM::new(
+        &input,
+        &[],
+        Address::default(),
+        Address::default(),
+        Address::default(),
+        0,
+        u128::MAX,
+    );
+    if let Some(deployment) = deploy(&vm) {
+        let metadata = BytecodeLayout::analyze(&deployment.runtime_code)
+            .metadata
+            .map(|metadata| deployment.runtime_code[metadata.range].to_vec())
+            .unwrap_or_default();
+
+        return Ok(Constructor {
+            _constructor: input[..deployment.runtime_offset].to_vec(),
+            _contract: deployment.runtime_code,
+            _metadata: metadata,
+            arguments: deployment.constructor_args,
+            inputs: deployment.constructor_inputs,
+        });
+    }
+
     // convert input to a hex string
     let input = encode_hex(&input);
 
@@ -59,5 +94,6 @@ pub(crate) fn parse_deployment_bytecode(input: Vec<u8>) -> Result<Constructor> {
         _contract: decode_hex(contract)?,
         _metadata: decode_hex(metadata)?,
         arguments: decode_hex(arguments)?,
+        inputs: Vec::new(),
     })
 }
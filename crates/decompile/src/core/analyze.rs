@@ -1,14 +1,14 @@
 use std::{fmt::Display, time::Instant};
 
 use futures::future::BoxFuture;
-use heimdall_vm::ext::exec::VMTrace;
+use heimdall_vm::ext::exec::{LoopInfo, VMTrace};
 use tracing::debug;
 
 use crate::{
     interfaces::AnalyzedFunction,
     utils::heuristics::{
         argument_heuristic, event_heuristic, extcall_heuristic, modifier_heuristic,
-        solidity_heuristic, yul_heuristic, Heuristic,
+        solidity_heuristic, storage_heuristic, yul_heuristic, Heuristic,
     },
     Error,
 };
@@ -59,6 +59,11 @@ pub(crate) struct AnalyzerState {
     pub analyzer_type: AnalyzerType,
     /// Whether to skip resolving internal calls
     pub skip_resolving: bool,
+    /// If the current branch heads a loop, this will hold it so its conditional jump is rendered
+    /// as the loop itself
+    pub current_loop: Option<LoopInfo>,
+    /// The number of loops enclosing the current branch, used for naming induction variables
+    pub loop_depth: usize,
 }
 
 /// The analyzer, which will analyze a [`VMTrace`] generated by symbolic execution and build an
@@ -90,6 +95,7 @@ impl Analyzer {
                 self.heuristics.push(Heuristic::new(solidity_heuristic));
                 self.heuristics.push(Heuristic::new(argument_heuristic));
                 self.heuristics.push(Heuristic::new(modifier_heuristic));
+                self.heuristics.push(Heuristic::new(storage_heuristic));
                 self.heuristics.push(Heuristic::new(extcall_heuristic));
             }
             AnalyzerType::Yul => {
@@ -97,11 +103,13 @@ impl Analyzer {
                 self.heuristics.push(Heuristic::new(yul_heuristic));
                 self.heuristics.push(Heuristic::new(argument_heuristic));
                 self.heuristics.push(Heuristic::new(modifier_heuristic));
+                self.heuristics.push(Heuristic::new(storage_heuristic));
             }
             AnalyzerType::Abi => {
                 self.heuristics.push(Heuristic::new(event_heuristic));
                 self.heuristics.push(Heuristic::new(argument_heuristic));
                 self.heuristics.push(Heuristic::new(modifier_heuristic));
+                self.heuristics.push(Heuristic::new(storage_heuristic));
             }
         };
 
@@ -126,6 +134,8 @@ impl Analyzer {
             conditional_stack: Vec::new(),
             analyzer_type: self.typ,
             skip_resolving: self.skip_resolving,
+            current_loop: None,
+            loop_depth: 0,
         };
 
         // Perform analysis
@@ -150,14 +160,48 @@ impl Analyzer {
             // reset jumped conditional, we dont propagate conditionals across branches
             analyzer_state.jumped_conditional = None;
 
+            analyzer_state.current_loop = branch.loop_info.clone();
+
+            // a back-edge ends by re-evaluating the conditional jump of its loop header, which
+            // has already been analyzed at the header
+            let operations = match branch.back_edge {
+                Some(_) => &branch.operations[..branch.operations.len().saturating_sub(1)],
+                None => &branch.operations[..],
+            };
+
             // for each operation in the current trace branch, peform analysis with registerred
             // heuristics
-            for operation in &branch.operations {
+            for operation in operations {
                 for heuristic in &self.heuristics {
                     heuristic.run(&mut self.function, operation, analyzer_state).await?;
                 }
             }
 
+            // if this branch heads a loop, the body is analyzed within the loop's scope and the
+            // exit is analyzed after it
+            if let Some(loop_info) = &branch.loop_info {
+                analyzer_state.current_loop = None;
+                analyzer_state.loop_depth += 1;
+                for child in
+                    branch.children.iter().filter(|child| child.instruction == loop_info.body)
+                {
+                    self.analyze_inner(child, analyzer_state).await?;
+                }
+                analyzer_state.loop_depth -= 1;
+
+                if self.typ != AnalyzerType::Abi {
+                    self.function.logic.push("}".to_string());
+                }
+
+                for child in
+                    branch.children.iter().filter(|child| child.instruction != loop_info.body)
+                {
+                    self.analyze_inner(child, analyzer_state).await?;
+                }
+
+                return Ok(());
+            }
+
             // recurse into the children of the current trace branch
             for child in &branch.children {
                 self.analyze_inner(child, analyzer_state).await?;
@@ -0,0 +1,96 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use hashbrown::HashMap;
+use heimdall_common::utils::threading::task_pool;
+use heimdall_vm::{core::vm::VM, ext::exec::VMTrace};
+use tracing::{debug, info, warn};
+
+/// Symbolically executes every selector on a pool of `threads` workers, each exploring one
+/// selector at a time on its own clone of `evm`.
+///
+/// Each selector is given `timeout` to explore, which is cut short by `deadline` if given.
+/// Selectors which haven't started by the time `deadline` passes are skipped.
+///
+/// Returns the trace of each selector, and the number of selectors which were skipped or whose
+/// exploration was cut short by a timeout.
+pub(crate) fn symbolic_exec_selectors(
+    evm: &VM,
+    selectors: HashMap<String, u128>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    threads: usize,
+) -> (HashMap<String, VMTrace>, usize) {
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n,
+    };
+    let total = selectors.len();
+    let completed = Arc::new(AtomicUsize::new(0));
+    let incomplete = Arc::new(AtomicUsize::new(0));
+    let evm = evm.clone();
+
+    debug!("symbolically executing {} selectors on {} threads", total, threads.min(total));
+
+    let items: Vec<(String, u128)> = selectors.into_iter().collect();
+    let incomplete_count = incomplete.clone();
+    let results = task_pool(items, threads, move |(selector, entry_point)| {
+        let start_sym_exec_time = Instant::now();
+        let result = match deadline {
+            Some(deadline) if start_sym_exec_time >= deadline => {
+                warn!("skipping symbolic execution of '{}': total timeout reached", selector);
+                incomplete_count.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            _ => {
+                let mut timeout_at =
+                    start_sym_exec_time.checked_add(timeout).expect("invalid timeout");
+                if let Some(deadline) = deadline {
+                    timeout_at = timeout_at.min(deadline);
+                }
+
+                let mut vm = evm.clone();
+                vm.reset();
+                let result = vm.symbolic_exec_selector(&selector, entry_point, timeout_at);
+
+                // exploration stops silently at the timeout, so a selector still running when it
+                // passed has an incomplete trace
+                if Instant::now() >= timeout_at {
+                    warn!("symbolic execution of '{}' timed out", selector);
+                    incomplete_count.fetch_add(1, Ordering::Relaxed);
+                }
+
+                match result {
+                    Ok((map, jumpdest_count)) => {
+                        debug!(
+                            "symbolically executed '{}' in {:?}",
+                            selector,
+                            start_sym_exec_time.elapsed()
+                        );
+                        debug!("'{}' has {} unique branches", selector, jumpdest_count);
+                        Some((selector, map))
+                    }
+                    Err(e) => {
+                        warn!("failed to symbolically execute '{}': {}", selector, e);
+                        None
+                    }
+                }
+            }
+        };
+
+        // report progress roughly every tenth of the way through
+        let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+        if done.is_multiple_of((total / 10).max(1)) || done == total {
+            info!("symbolic execution progress: {}/{} selectors", done, total);
+        }
+
+        result
+    });
+
+    (results.into_iter().flatten().collect(), incomplete.load(Ordering::Relaxed))
+}
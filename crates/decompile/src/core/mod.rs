@@ -1,4 +1,5 @@
 pub(crate) mod analyze;
+pub(crate) mod execute;
 pub(crate) mod out;
 pub(crate) mod postprocess;
 pub(crate) mod resolve;
@@ -8,6 +9,7 @@ use alloy_dyn_abi::{DynSolType, DynSolValue};
 use alloy_json_abi::JsonAbi;
 use eyre::eyre;
 use hashbrown::HashMap;
+use heimdall_cache::{read_cache, store_cache};
 use heimdall_common::{
     ether::{
         compiler::detect_compiler,
@@ -19,26 +21,38 @@ use heimdall_common::{
     },
     utils::strings::{decode_hex, encode_hex, encode_hex_reduced, StringExt},
 };
-use heimdall_disassembler::{disassemble, DisassemblerArgsBuilder};
+use heimdall_disassembler::{disassemble_instructions, DisassemblerArgsBuilder};
 use heimdall_vm::{
     core::vm::VM,
-    ext::selectors::{find_function_selectors, resolve_selectors},
+    ext::{
+        creation::{deploy, Deployment},
+        selectors::{find_function_selectors, resolve_selectors},
+        solver::default_solver,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 
 use crate::{
     core::{
         analyze::{Analyzer, AnalyzerType},
-        out::{build_abi, build_abi_with_details, source::build_source},
+        execute::symbolic_exec_selectors,
+        out::{
+            build_abi, build_abi_with_details, build_storage_layout, layout::StorageLayout,
+            source::build_source,
+        },
         postprocess::PostprocessOrchestrator,
-        resolve::match_parameters,
+        resolve::{match_parameters, rank_signatures},
     },
     error::Error,
     interfaces::{AnalyzedFunction, DecompilerArgs},
 };
 use tracing::{debug, info, warn};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Result of a successful decompile operation
 ///
 /// Contains the decompiled source code (if requested) and the reconstructed ABI
@@ -50,6 +64,13 @@ pub struct DecompileResult {
     pub abi: JsonAbi,
     /// The extended ABI with selector and signature information
     pub abi_with_details: serde_json::Value,
+    /// The reconstructed storage layout of the contract, in solc's `storageLayout` format
+    pub storage_layout: StorageLayout,
+    /// The result of executing the constructor, if the target is creation bytecode. The rest of
+    /// the result describes the runtime code it deploys. It isn't serialized, so cached results
+    /// take it from executing the constructor again, which is cheap and deterministic
+    #[serde(skip)]
+    pub deployment: Option<Deployment>,
 }
 
 /// Decompiles EVM bytecode into higher-level Solidity-like code
@@ -103,7 +124,7 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
 
     // get the bytecode from the target
     let start_fetch_time = Instant::now();
-    let contract_bytecode = args
+    let mut contract_bytecode = args
         .get_bytecode()
         .await
         .map_err(|e| Error::FetchError(format!("fetching target bytecode failed: {e}")))?;
@@ -118,15 +139,35 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
         )));
     }
 
+    // load any additional contracts, so external calls into them can be executed
+    let world = args
+        .get_world_state()
+        .await
+        .map_err(|e| Error::FetchError(format!("fetching contracts failed: {e}")))?;
+
+    // results are cached by the hash of the bytecode, so they're shared between identical contracts
+    let cache_key = args.cache_key(&contract_bytecode, hardfork, world.as_ref()).await;
+
     // perform versioning and compiler heuristics
     let (_compiler, _version) = detect_compiler(&contract_bytecode);
 
+    let state_provider = args
+        .get_state_provider()
+        .map_err(|e| Error::FetchError(format!("loading state provider failed: {e}")))?;
+
+    // when executing against real state, the contract must run at its own address so that its
+    // storage and balance can be read
+    let contract_address = match state_provider {
+        Some(_) => args.target.parse::<Address>().unwrap_or_default(),
+        None => Address::default(),
+    };
+
     // create a new EVM instance. we will use this for finding function selectors,
     // performing symbolic execution, and more.
     let mut evm = VM::new(
         &contract_bytecode,
         &[],
-        Address::default(),
+        contract_address,
         Address::default(),
         Address::default(),
         0,
@@ -134,8 +175,53 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     )
     .with_hardfork(hardfork);
 
+    if args.solver {
+        evm = evm.with_solver(default_solver());
+    }
+
+    if let Some(provider) = state_provider {
+        evm = evm.with_provider(provider);
+    }
+
+    if let Some(world) = world {
+        debug!("loaded {} contracts for executing external calls", world.len());
+        evm = evm.with_world(Arc::new(world));
+    }
+
+    // creation bytecode returns the contract's runtime code from its constructor. the constructor
+    // is decompiled on its own, and the runtime code it deploys is decompiled in its place
+    let deployment = deploy(&evm);
+    if let Some(result) = cache_key.as_deref().and_then(read_cached_result) {
+        info!("using cached decompilation of '{}'", args.target.truncate(64));
+        debug!("decompilation took {:?}", start_time.elapsed());
+        return Ok(DecompileResult { deployment, ..result });
+    }
+
+    // results explored only until a timeout aren't cached, since a later run may explore further
+    let mut timed_out = false;
+    let mut constructor_trace = None;
+    if let Some(deployment) = &deployment {
+        info!("target is creation bytecode, decompiling its constructor and deployed runtime code");
+        debug!("recovered constructor signature '{}'", deployment.constructor_signature());
+
+        let start_sym_exec_time = Instant::now();
+        let timeout_at = Instant::now()
+            .checked_add(Duration::from_millis(args.timeout))
+            .expect("invalid timeout");
+        let (map, jumpdest_count) = evm
+            .symbolic_exec(timeout_at)
+            .map_err(|e| Error::Eyre(eyre!("symbolic execution failed: {}", e)))?;
+        timed_out |= Instant::now() >= timeout_at;
+        debug!("symbolic execution (constructor) took {:?}", start_sym_exec_time.elapsed());
+        debug!("'constructor' has {} unique branches", jumpdest_count);
+
+        constructor_trace = Some(map);
+        contract_bytecode = deployment.runtime_code.clone();
+        evm = deployment.runtime_vm(&evm);
+    }
+
     // disassemble the contract's bytecode
-    let assembly = disassemble(
+    let instructions = disassemble_instructions(
         DisassemblerArgsBuilder::new()
             .target(encode_hex(&contract_bytecode))
             .hardfork(hardfork)
@@ -147,7 +233,7 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
 
     // find all the function selectors in the bytecode
     let start_selectors_time = Instant::now();
-    let selectors = find_function_selectors(&evm, &assembly);
+    let selectors = find_function_selectors(&evm, &instructions);
     debug!("finding function selectors took {:?}", start_selectors_time.elapsed());
 
     // resolve selectors (if enabled)
@@ -162,13 +248,13 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     if selectors.is_empty() {
         warn!("discovered no function selectors in the bytecode.");
         let start_sym_exec_time = Instant::now();
+        let timeout_at = Instant::now()
+            .checked_add(Duration::from_millis(args.timeout))
+            .expect("invalid timeout");
         let (map, jumpdest_count) = evm
-            .symbolic_exec(
-                Instant::now()
-                    .checked_add(Duration::from_millis(args.timeout))
-                    .expect("invalid timeout"),
-            )
+            .symbolic_exec(timeout_at)
             .map_err(|e| Error::Eyre(eyre!("symbolic execution failed: {}", e)))?;
+        timed_out |= Instant::now() >= timeout_at;
 
         symbolic_execution_maps.insert("fallback".to_string(), map);
         debug!("symbolic execution (fallback) took {:?}", start_sym_exec_time.elapsed());
@@ -176,26 +262,19 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     }
 
     let overall_sym_exec_time = Instant::now();
-    for (selector, entry_point) in selectors {
-        let start_sym_exec_time = Instant::now();
-        evm.reset();
-        let (map, jumpdest_count) = match evm.symbolic_exec_selector(
-            &selector,
-            entry_point,
-            Instant::now()
-                .checked_add(Duration::from_millis(args.timeout))
-                .expect("invalid timeout"),
-        ) {
-            Ok(map) => map,
-            Err(e) => {
-                warn!("failed to symbolically execute '{}': {}", selector, e);
-                continue;
-            }
-        };
-        symbolic_execution_maps.insert(selector.clone(), map);
-        debug!("symbolically executed '{}' in {:?}", selector, start_sym_exec_time.elapsed());
-        debug!("'{}' has {} unique branches", selector, jumpdest_count);
-    }
+    let (selector_maps, incomplete) = symbolic_exec_selectors(
+        &evm,
+        selectors,
+        Duration::from_millis(args.timeout),
+        args.total_timeout.map(|total_timeout| {
+            overall_sym_exec_time
+                .checked_add(Duration::from_millis(total_timeout))
+                .expect("invalid total timeout")
+        }),
+        args.threads,
+    );
+    symbolic_execution_maps.extend(selector_maps);
+    timed_out |= incomplete > 0;
     debug!("symbolic execution took {:?}", overall_sym_exec_time.elapsed());
     info!("symbolically executed {} selectors", symbolic_execution_maps.len());
 
@@ -212,8 +291,13 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
             // analyze the symbolic execution trace
             let mut analyzed_function = analyzer.analyze(trace_root).await?;
 
-            // if the function is constant, we can get the exact val
-            if analyzed_function.is_constant() && !analyzed_function.fallback {
+            // if the function is constant, we can get the exact val. with a state provider, the
+            // same holds for any view function without arguments, such as a getter
+            let is_computable = analyzed_function.is_constant() ||
+                (evm_clone.provider.is_some() &&
+                    analyzed_function.view &&
+                    analyzed_function.arguments.is_empty());
+            if is_computable && !analyzed_function.fallback {
                 evm_clone.reset();
                 let x = evm_clone.call(&decode_hex(&selector).expect("invalid selector"), 0)?;
 
@@ -241,6 +325,26 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     });
     let mut analyzed_functions = futures::future::try_join_all(handles).await?;
 
+    // analyze the constructor, whose parameters were recovered when it was deployed
+    let mut constructor = match (constructor_trace, deployment.as_ref()) {
+        (Some(trace_root), Some(deployment)) => {
+            let mut analyzer = Analyzer::new(
+                analyzer_type,
+                args.skip_resolving,
+                AnalyzedFunction::new("constructor", false),
+            );
+            let mut analyzed_constructor = analyzer.analyze(trace_root).await?;
+            analyzed_constructor.resolved_function = Some(ResolvedFunction {
+                name: String::from("constructor"),
+                signature: deployment.constructor_signature(),
+                inputs: deployment.constructor_inputs.iter().map(|ty| ty.to_string()).collect(),
+                decoded_inputs: deployment.decode_arguments(),
+            });
+            Some(analyzed_constructor)
+        }
+        _ => None,
+    };
+
     debug!("analyzing symbolic execution results took {:?}", start_analysis_time.elapsed());
     info!("analyzed {} symbolic execution traces", analyzed_functions.len());
 
@@ -250,6 +354,7 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
         let start_error_resolving_time = Instant::now();
         let mut error_selectors: Vec<String> = analyzed_functions
             .iter()
+            .chain(constructor.as_ref())
             .flat_map(|f| f.errors.iter().map(|e| encode_hex_reduced(*e).replacen("0x", "", 1)))
             .collect();
         error_selectors.dedup();
@@ -281,6 +386,7 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
         let start_event_resolving_time = Instant::now();
         let mut event_selectors: Vec<String> = analyzed_functions
             .iter()
+            .chain(constructor.as_ref())
             .flat_map(|f| f.events.iter().map(|e| encode_hex_reduced(*e).replacen("0x", "", 1)))
             .collect();
         event_selectors.dedup();
@@ -313,24 +419,21 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     analyzed_functions.iter_mut().for_each(|f| {
         let resolve_function_signatures =
             resolved_selectors.get(&f.selector).unwrap_or(&Vec::new()).to_owned();
-        let mut matched_resolved_functions = match_parameters(resolve_function_signatures, f);
+        let matched_resolved_functions = match_parameters(resolve_function_signatures, f);
         debug!(
             "matched {} resolved functions for '{}'",
             matched_resolved_functions.len(),
             f.selector
         );
 
-        matched_resolved_functions.sort_by(|a, b| {
-            let a_score = score_signature(&a.signature, None);
-            let b_score = score_signature(&b.signature, None);
-            b_score.cmp(&a_score)
-        });
-
-        f.resolved_function = matched_resolved_functions.first().cloned();
+        // rank the matches by how well they fit the function's calldata layout and storage accesses
+        f.candidates = rank_signatures(matched_resolved_functions, f);
+        f.resolved_function = f.candidates.first().map(|candidate| candidate.function.clone());
         debug!(
-            "using signature '{}' for '{}'",
+            "using signature '{}' for '{}' (confidence: {})",
             f.resolved_function.as_ref().map(|r| &r.signature).unwrap_or(&String::new()),
-            f.selector
+            f.selector,
+            f.candidates.first().map(|candidate| candidate.confidence).unwrap_or_default()
         );
     });
 
@@ -339,6 +442,7 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     let mut postprocessor = PostprocessOrchestrator::new(analyzer_type)?;
     let states = analyzed_functions
         .iter_mut()
+        .chain(constructor.as_mut())
         .filter_map(|f| {
             postprocessor.postprocess(f).map_err(|e| f.notices.push(e.to_string())).ok()
         })
@@ -351,11 +455,36 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect::<HashMap<String, String>>();
 
+    // reconstruct the storage layout, naming variables as they are named in the source
+    let storage_names = states
+        .iter()
+        .flat_map(|s| s.storage_map.iter())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<HashMap<String, String>>();
+    let storage_types = states
+        .iter()
+        .flat_map(|s| s.storage_type_map.iter())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<HashMap<String, String>>();
+    let storage_layout = build_storage_layout(
+        &analyzed_functions,
+        constructor.as_ref(),
+        &storage_names,
+        &storage_types,
+    );
+    debug!("reconstructed {} storage variables", storage_layout.storage.len());
+
     // construct the abi for the given analyzed functions
-    let abi = build_abi(&analyzed_functions, &all_resolved_errors, &all_resolved_events)?;
+    let abi = build_abi(
+        &analyzed_functions,
+        constructor.as_ref(),
+        &all_resolved_errors,
+        &all_resolved_events,
+    )?;
     let abi_with_details = build_abi_with_details(&abi, &analyzed_functions)?;
     let source = build_source(
         &analyzed_functions,
+        constructor.as_ref(),
         &all_resolved_errors,
         &all_resolved_events,
         &storage_variables,
@@ -365,7 +494,40 @@ pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     )
     .await?;
 
+    let result = DecompileResult { source, abi, abi_with_details, storage_layout, deployment };
+    match &cache_key {
+        Some(_) if timed_out => debug!("not caching decompilation, symbolic execution timed out"),
+        Some(key) => cache_result(key, &result),
+        None => {}
+    }
+
     debug!("decompilation took {:?}", start_time.elapsed());
 
-    Ok(DecompileResult { source, abi, abi_with_details })
+    Ok(result)
+}
+
+/// Reads the [`DecompileResult`] cached under `key`, if any. Results are cached as JSON, since the
+/// ABI can't be encoded with bincode.
+fn read_cached_result(key: &str) -> Option<DecompileResult> {
+    read_cache::<String>(key)
+        .inspect_err(|e| debug!("failed to read cached decompilation: {}", e))
+        .ok()
+        .flatten()
+        .and_then(|result| {
+            serde_json::from_str(&result)
+                .inspect_err(|e| debug!("failed to parse cached decompilation: {}", e))
+                .ok()
+        })
+}
+
+/// Caches `result` under `key`. Failing to cache doesn't fail the decompilation.
+fn cache_result(key: &str, result: &DecompileResult) {
+    match serde_json::to_string(result) {
+        Ok(result) => {
+            if let Err(e) = store_cache(key, result, None) {
+                debug!("failed to cache decompilation: {}", e);
+            }
+        }
+        Err(e) => debug!("failed to serialize decompilation: {}", e),
+    }
 }
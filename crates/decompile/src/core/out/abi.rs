@@ -2,7 +2,9 @@ use alloy_dyn_abi::DynSolType;
 use hashbrown::HashMap;
 use std::time::Instant;
 
-use alloy_json_abi::{Error, Event, EventParam, Function, JsonAbi, Param, StateMutability};
+use alloy_json_abi::{
+    Constructor, Error, Event, EventParam, Function, JsonAbi, Param, StateMutability,
+};
 
 use eyre::Result;
 use heimdall_common::{
@@ -20,6 +22,7 @@ use crate::interfaces::AnalyzedFunction;
 
 pub(crate) fn build_abi(
     functions: &[AnalyzedFunction],
+    constructor: Option<&AnalyzedFunction>,
     all_resolved_errors: &HashMap<String, ResolvedError>,
     all_resolved_logs: &HashMap<String, ResolvedLog>,
 ) -> Result<JsonAbi> {
@@ -27,6 +30,30 @@ pub(crate) fn build_abi(
     let start_time = Instant::now();
     let mut abi = JsonAbi::new();
 
+    // add the constructor, if the target is creation bytecode
+    abi.constructor = constructor.map(|f| Constructor {
+        inputs: f
+            .resolved_function
+            .as_ref()
+            .map(|sig| {
+                sig.inputs()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, input)| Param {
+                        name: format!("arg{i}"),
+                        internal_type: None,
+                        ty: to_abi_string(input),
+                        components: to_components(input),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        state_mutability: match f.payable {
+            true => StateMutability::Payable,
+            false => StateMutability::NonPayable,
+        },
+    });
+
     // add functions
     functions.iter().filter(|f| !f.fallback).for_each(|f| {
         // determine the state mutability of the function
@@ -53,25 +80,24 @@ pub(crate) fn build_abi(
                 .sorted_arguments()
                 .iter()
                 .enumerate()
-                .map(|(i, (_, arg))| Param {
-                    name: format!("arg{i}"),
-                    internal_type: None,
-                    ty: match f.resolved_function {
-                        Some(ref sig) => {
-                            to_abi_string(sig.inputs().get(i).unwrap_or(&DynSolType::Bytes))
-                        }
-                        None => arg
-                            .potential_types()
-                            .first()
-                            .cloned()
-                            .unwrap_or_else(|| "bytes32".to_string()),
-                    },
-                    components: match f.resolved_function {
-                        Some(ref sig) => {
-                            to_components(sig.inputs().get(i).unwrap_or(&DynSolType::Bytes))
-                        }
-                        None => vec![],
-                    },
+                .map(|(i, (_, arg))| {
+                    // recovered dynamic arguments may be tuples, which need their components
+                    let ty = f.resolved_function.as_ref().map_or_else(
+                        || {
+                            arg.potential_types()
+                                .first()
+                                .and_then(|ty| DynSolType::parse(ty).ok())
+                                .unwrap_or(DynSolType::FixedBytes(32))
+                        },
+                        |sig| sig.inputs().get(i).cloned().unwrap_or(DynSolType::Bytes),
+                    );
+
+                    Param {
+                        name: format!("arg{i}"),
+                        internal_type: None,
+                        ty: to_abi_string(&ty),
+                        components: to_components(&ty),
+                    }
                 })
                 .collect(),
             outputs: f
@@ -215,6 +241,24 @@ pub(crate) fn build_abi_with_details(
                                 }
                             };
                             obj.insert("signature".to_string(), json!(signature));
+
+                            // Add the confidence in the resolved signature, and the other
+                            // signatures which matched the function
+                            if let Some((resolved, alternatives)) =
+                                analyzed_func.candidates.split_first()
+                            {
+                                obj.insert("confidence".to_string(), json!(resolved.confidence));
+                                obj.insert(
+                                    "alternatives".to_string(),
+                                    json!(alternatives
+                                        .iter()
+                                        .map(|candidate| json!({
+                                            "signature": candidate.function.signature,
+                                            "confidence": candidate.confidence,
+                                        }))
+                                        .collect::<Vec<_>>()),
+                                );
+                            }
                         }
                     }
                 }
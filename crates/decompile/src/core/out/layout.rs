@@ -0,0 +1,421 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy::primitives::U256;
+use hashbrown::{HashMap, HashSet};
+use heimdall_common::utils::strings::base26_encode;
+use serde::{Deserialize, Serialize};
+
+use crate::interfaces::AnalyzedFunction;
+
+/// The maximum number of hashes followed when tracing a slot back to its base slot.
+const MAX_SLOT_DEPTH: usize = 8;
+
+/// The largest distance from a hashed slot at which a slot is still considered to belong to it,
+/// i.e. a dynamic array element or struct member.
+const MAX_ELEMENT_OFFSET: u64 = 1 << 16;
+
+/// The reconstructed storage layout of a contract, in the same format as solc's `storageLayout`
+/// output.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageLayout {
+    /// The storage variables of the contract, ordered by slot and offset.
+    pub storage: Vec<StorageLayoutEntry>,
+
+    /// The types referenced by the storage variables, keyed by type identifier.
+    pub types: BTreeMap<String, StorageLayoutType>,
+}
+
+/// A single storage variable within a [`StorageLayout`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StorageLayoutEntry {
+    /// The AST node id of the variable's declaration. Always 0, since there is no AST.
+    pub ast_id: u64,
+
+    /// The name of the contract the variable belongs to.
+    pub contract: String,
+
+    /// The name of the variable.
+    pub label: String,
+
+    /// The byte offset of the variable within its slot, counted from the right.
+    pub offset: usize,
+
+    /// The slot the variable is stored in, as a decimal string.
+    pub slot: String,
+
+    /// The identifier of the variable's type, a key into [`StorageLayout::types`].
+    #[serde(rename = "type")]
+    pub type_id: String,
+
+    /// Whether the variable shares its slot with other variables.
+    pub packed: bool,
+
+    /// The selectors of the functions which read the variable.
+    pub read_by: Vec<String>,
+
+    /// The selectors of the functions which write the variable.
+    pub written_by: Vec<String>,
+}
+
+/// A type referenced by a [`StorageLayout`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StorageLayoutType {
+    /// How the type is stored: `inplace`, `mapping` or `dynamic_array`.
+    pub encoding: String,
+
+    /// The canonical name of the type.
+    pub label: String,
+
+    /// The number of bytes the type occupies within its slot, as a decimal string.
+    pub number_of_bytes: String,
+
+    /// The key type identifier, for mappings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    /// The value type identifier, for mappings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+
+    /// The element type identifier, for arrays.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+
+    /// The members of the type, for structs. Never set for reconstructed layouts, but present in
+    /// layouts emitted by solc.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<StorageLayoutEntry>>,
+}
+
+/// A step taken from a storage variable's slot to the slot of one of its elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Step {
+    /// `keccak256(key . slot)`, a mapping value.
+    Mapping,
+    /// `keccak256(slot) + index`, a dynamic array element.
+    Array,
+}
+
+/// All accesses to a single storage variable, gathered across functions.
+#[derive(Default)]
+struct Variable {
+    /// The steps from the variable's slot to the accessed slots.
+    path: Vec<Step>,
+    /// The packed fields of the accessed slots, as (byte offset, size in bytes).
+    fields: BTreeSet<(usize, usize)>,
+    /// The selectors of the functions which read the variable.
+    read_by: BTreeSet<String>,
+    /// The selectors of the functions which write the variable.
+    written_by: BTreeSet<String>,
+}
+
+/// Builds the storage layout of the contract from the storage accesses of its functions, and of
+/// its constructor if it was decompiled, which writes the initial values of its storage.
+///
+/// `storage_names` maps slot accesses (e.g. `storage[0x01]`) to the variable names used in the
+/// decompiled source, and `storage_types` maps those names to their inferred types. Both are empty
+/// if no source was generated.
+pub(crate) fn build_storage_layout(
+    functions: &[AnalyzedFunction],
+    constructor: Option<&AnalyzedFunction>,
+    storage_names: &HashMap<String, String>,
+    storage_types: &HashMap<String, String>,
+) -> StorageLayout {
+    // functions are labelled by their selectors in the layout
+    let functions = functions
+        .iter()
+        .map(|function| match function.fallback {
+            true => (function, "fallback".to_string()),
+            false => (function, format!("0x{}", function.selector)),
+        })
+        .chain(constructor.map(|constructor| (constructor, "constructor".to_string())))
+        .collect::<Vec<_>>();
+    let preimages: HashMap<U256, Vec<u8>> = functions
+        .iter()
+        .flat_map(|(f, _)| {
+            f.storage.preimages.iter().map(|(hash, preimage)| (*hash, preimage.clone()))
+        })
+        .collect();
+
+    // group every accessed slot by the storage variable it belongs to
+    let mut variables: BTreeMap<U256, Variable> = BTreeMap::new();
+    for (function, selector) in functions {
+        let accessed: HashSet<&U256> =
+            function.storage.reads.iter().chain(function.storage.writes.iter()).collect();
+
+        for slot in accessed {
+            let (root, path) = resolve_slot(*slot, &preimages, 0);
+            let variable = variables.entry(root).or_default();
+            if path.len() > variable.path.len() {
+                variable.path = path;
+            }
+            if function.storage.reads.contains(slot) {
+                variable.read_by.insert(selector.clone());
+            }
+            if function.storage.writes.contains(slot) {
+                variable.written_by.insert(selector.clone());
+            }
+            variable.fields.extend(
+                function
+                    .storage
+                    .fields
+                    .iter()
+                    .filter(|(field_slot, ..)| field_slot == slot)
+                    .map(|(_, offset, size)| (*offset, *size)),
+            );
+        }
+    }
+
+    // the names of variables at constant slots, as used in the decompiled source
+    let named_slots: HashMap<U256, &String> = storage_names
+        .iter()
+        .filter_map(|(access, name)| {
+            let slot = access.strip_prefix("storage[")?.strip_suffix(']')?;
+            let slot = U256::from_str_radix(slot.trim_start_matches("0x"), 16).ok()?;
+            Some((slot, name))
+        })
+        .collect();
+    let mut used_labels: HashSet<String> =
+        named_slots.values().map(|name| name.to_string()).collect();
+    let mut next_label = |prefix: &str| {
+        let mut i = used_labels.len() + 1;
+        while used_labels.contains(&format!("{prefix}_{}", base26_encode(i))) {
+            i += 1;
+        }
+        let label = format!("{prefix}_{}", base26_encode(i));
+        used_labels.insert(label.clone());
+        label
+    };
+
+    let mut layout = StorageLayout::default();
+    for (slot, variable) in variables {
+        // a variable at a constant slot may hold several packed fields
+        let fields = match variable.path.is_empty() {
+            true if variable.fields.len() > 1 ||
+                variable.fields.iter().any(|(offset, _)| *offset != 0) =>
+            {
+                disjoint_fields(&variable.fields)
+            }
+            _ => vec![(0, variable.fields.iter().map(|(_, size)| *size).max().unwrap_or(32))],
+        };
+        let packed = fields.len() > 1 || fields.iter().any(|(offset, _)| *offset != 0);
+
+        for (offset, size) in fields {
+            let name = named_slots.get(&slot).filter(|_| !packed).map(|name| name.to_string());
+            let value_type = name
+                .as_ref()
+                .and_then(|name| storage_types.get(name))
+                .filter(|typ| variable.path.is_empty() && !typ.starts_with("mapping"))
+                .cloned()
+                .unwrap_or_else(|| elementary_type(size));
+
+            let label = name.unwrap_or_else(|| match variable.path.first() {
+                Some(Step::Mapping) => next_label("storage_map"),
+                Some(Step::Array) => next_label("storage_array"),
+                None => next_label("store"),
+            });
+
+            layout.storage.push(StorageLayoutEntry {
+                ast_id: 0,
+                contract: "DecompiledContract".to_string(),
+                label,
+                offset,
+                slot: slot.to_string(),
+                type_id: register_type(&mut layout.types, &variable.path, &value_type),
+                packed,
+                read_by: variable.read_by.iter().cloned().collect(),
+                written_by: variable.written_by.iter().cloned().collect(),
+            });
+        }
+    }
+
+    layout
+}
+
+/// Traces `slot` back to the slot of the storage variable it belongs to, returning that slot and
+/// the steps taken from it to `slot`, outermost first.
+fn resolve_slot(slot: U256, preimages: &HashMap<U256, Vec<u8>>, depth: usize) -> (U256, Vec<Step>) {
+    if depth >= MAX_SLOT_DEPTH {
+        return (slot, Vec::new());
+    }
+
+    // mapping values are stored at `keccak256(key . slot)`, and dynamic array elements start at
+    // `keccak256(slot)`
+    if let Some(preimage) = preimages.get(&slot) {
+        let (step, base) = match preimage.len() {
+            32 => (Step::Array, U256::from_be_slice(preimage)),
+            len if len > 32 => (Step::Mapping, U256::from_be_slice(&preimage[len - 32..])),
+            _ => return (slot, Vec::new()),
+        };
+
+        let (root, mut path) = resolve_slot(base, preimages, depth + 1);
+        path.push(step);
+        return (root, path);
+    }
+
+    // slots just past a hashed slot are array elements or struct members
+    if let Some(base) = preimages.keys().find(|hash| {
+        slot.checked_sub(**hash).is_some_and(|distance| distance < U256::from(MAX_ELEMENT_OFFSET))
+    }) {
+        return resolve_slot(*base, preimages, depth + 1);
+    }
+
+    (slot, Vec::new())
+}
+
+/// Splits the packed fields of a slot into disjoint fields, preferring the widest field at each
+/// offset.
+fn disjoint_fields(fields: &BTreeSet<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut disjoint: Vec<(usize, usize)> = Vec::new();
+    for (offset, size) in fields {
+        match disjoint.last_mut() {
+            Some((last_offset, last_size)) if *offset < *last_offset + *last_size => {
+                if *offset == *last_offset {
+                    *last_size = (*last_size).max(*size);
+                }
+            }
+            _ => disjoint.push((*offset, *size)),
+        }
+    }
+    disjoint
+}
+
+/// Returns the elementary type which best fits a field of `size` bytes.
+fn elementary_type(size: usize) -> String {
+    match size {
+        20 => "address".to_string(),
+        32 => "bytes32".to_string(),
+        size => format!("uint{}", size * 8),
+    }
+}
+
+/// Registers the type of a variable with the given `path` to a `value_type`, along with any types
+/// it references, returning its identifier.
+fn register_type(
+    types: &mut BTreeMap<String, StorageLayoutType>,
+    path: &[Step],
+    value_type: &str,
+) -> String {
+    let Some((step, rest)) = path.split_first() else {
+        let id = format!("t_{}", value_type.replace(' ', "_"));
+        let size = match value_type {
+            "address" => 20,
+            "bool" => 1,
+            typ if typ.starts_with("uint") || typ.starts_with("int") => {
+                typ.trim_start_matches(char::is_alphabetic).parse::<usize>().unwrap_or(256) / 8
+            }
+            typ if typ.starts_with("bytes") => {
+                typ.trim_start_matches("bytes").parse::<usize>().unwrap_or(32)
+            }
+            _ => 32,
+        };
+
+        types.entry(id.clone()).or_insert_with(|| StorageLayoutType {
+            encoding: "inplace".to_string(),
+            label: value_type.to_string(),
+            number_of_bytes: size.to_string(),
+            ..Default::default()
+        });
+        return id;
+    };
+
+    let inner = register_type(types, rest, value_type);
+    let inner_label = types.get(&inner).map(|typ| typ.label.clone()).unwrap_or_default();
+    let (id, typ) = match step {
+        Step::Mapping => {
+            let key = register_type(types, &[], "bytes32");
+            (
+                format!("t_mapping({key},{inner})"),
+                StorageLayoutType {
+                    encoding: "mapping".to_string(),
+                    label: format!("mapping(bytes32 => {inner_label})"),
+                    number_of_bytes: "32".to_string(),
+                    key: Some(key),
+                    value: Some(inner),
+                    ..Default::default()
+                },
+            )
+        }
+        Step::Array => (
+            format!("t_array({inner})dyn_storage"),
+            StorageLayoutType {
+                encoding: "dynamic_array".to_string(),
+                label: format!("{inner_label}[]"),
+                number_of_bytes: "32".to_string(),
+                base: Some(inner),
+                ..Default::default()
+            },
+        ),
+    };
+
+    types.entry(id.clone()).or_insert(typ);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::keccak256;
+
+    use super::*;
+
+    #[test]
+    fn test_build_storage_layout() {
+        let mut getter = AnalyzedFunction::new("06fdde03", false);
+        let mut setter = AnalyzedFunction::new("a9059cbb", false);
+        let mut constructor = AnalyzedFunction::new("constructor", false);
+
+        // slot 0 packs an address and a uint8, read by the getter and written by the setter
+        getter.storage.reads.insert(U256::ZERO);
+        getter.storage.fields.insert((U256::ZERO, 0, 20));
+        setter.storage.reads.insert(U256::ZERO);
+        setter.storage.writes.insert(U256::ZERO);
+        setter.storage.fields.insert((U256::ZERO, 20, 1));
+
+        // slot 1 is a mapping, written at `keccak256(key . 1)`
+        let mut preimage = vec![0u8; 32];
+        preimage.extend(U256::from(1).to_be_bytes::<32>());
+        let hash = U256::from_be_bytes(keccak256(&preimage).0);
+        setter.storage.preimages.insert(hash, preimage);
+        setter.storage.writes.insert(hash);
+
+        // slot 2 is a plain variable, named by the postprocessor
+        getter.storage.reads.insert(U256::from(2));
+        constructor.storage.writes.insert(U256::from(2));
+        let storage_names = HashMap::from([("storage[0x02]".to_string(), "store_a".to_string())]);
+        let storage_types = HashMap::from([("store_a".to_string(), "uint256".to_string())]);
+
+        let layout = build_storage_layout(
+            &[getter, setter],
+            Some(&constructor),
+            &storage_names,
+            &storage_types,
+        );
+
+        let summary = layout
+            .storage
+            .iter()
+            .map(|entry| {
+                (entry.label.as_str(), entry.slot.as_str(), entry.offset, entry.type_id.as_str())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("store_b", "0", 0, "t_address"),
+                ("store_c", "0", 20, "t_uint8"),
+                ("storage_map_d", "1", 0, "t_mapping(t_bytes32,t_bytes32)"),
+                ("store_a", "2", 0, "t_uint256"),
+            ]
+        );
+
+        assert!(layout.storage[0].packed);
+        assert_eq!(layout.storage[0].read_by, vec!["0x06fdde03", "0xa9059cbb"]);
+        assert_eq!(layout.storage[1].written_by, vec!["0xa9059cbb"]);
+        assert!(!layout.storage[3].packed);
+        assert_eq!(layout.storage[3].written_by, vec!["constructor"]);
+        assert_eq!(layout.types["t_mapping(t_bytes32,t_bytes32)"].encoding, "mapping");
+        assert_eq!(layout.types["t_uint8"].number_of_bytes, "1");
+    }
+}
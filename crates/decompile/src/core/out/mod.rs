@@ -1,4 +1,6 @@
 pub(crate) mod abi;
+pub(crate) mod layout;
 pub(crate) mod source;
 
 pub(crate) use abi::{build_abi, build_abi_with_details};
+pub(crate) use layout::build_storage_layout;
@@ -7,7 +7,7 @@ use eyre::{OptionExt, Result};
 use heimdall_common::{
     ether::signatures::{ResolvedError, ResolvedLog},
     resources::openrouter::{complete_chat_structured, AnnotatedContractResponse},
-    utils::{hex::ToLowerHex, strings::encode_hex_reduced},
+    utils::{hex::ToLowerHex, io::types::Parameterize, strings::encode_hex_reduced},
 };
 
 use tracing::debug;
@@ -34,8 +34,10 @@ async fn annotate_contract(source: &str, openrouter_api_key: &str, model: &str)
     Ok(response.source)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn build_source(
     functions: &[AnalyzedFunction],
+    constructor: Option<&AnalyzedFunction>,
     all_resolved_errors: &HashMap<String, ResolvedError>,
     all_resolved_logs: &HashMap<String, ResolvedLog>,
     storage_variables: &HashMap<String, String>,
@@ -68,13 +70,31 @@ pub(crate) async fn build_source(
     }
 
     // add event and error declarations
-    let resolved_event_error_map =
-        get_event_and_error_declarations(functions, all_resolved_errors, all_resolved_logs);
+    let resolved_event_error_map = get_event_and_error_declarations(
+        functions,
+        constructor,
+        all_resolved_errors,
+        all_resolved_logs,
+    );
     if analyzer_type == AnalyzerType::Solidity {
         resolved_event_error_map.iter().for_each(|(_, (resolved_name, typ))| {
             source.push(format!("{typ} {resolved_name}"));
         });
 
+        // add the constructor, if the target is creation bytecode. yul output only covers the
+        // runtime code's dispatcher, so the constructor is only included in solidity output
+        if let Some(constructor) = constructor {
+            let mut constructor_source = get_constructor_header(constructor);
+            constructor_source.extend(constructor.logic.clone());
+            constructor_source.push("}".to_string());
+
+            let imbalance = get_indentation_imbalance(&constructor_source);
+            constructor_source.extend(vec!["}".to_string(); imbalance as usize]);
+            constructor_source.push(String::new());
+
+            source.extend(constructor_source);
+        }
+
         // add the fallback function, if it exists
         if let Some(fallback) = functions.iter().find(|f| f.fallback) {
             source.push(String::from("fallback() external payable {"));
@@ -275,6 +295,40 @@ fn get_function_header(f: &AnalyzedFunction) -> Vec<String> {
     }
 }
 
+/// Helper function which will get the header for the constructor of creation bytecode, including
+/// the arguments it was deployed with.
+fn get_constructor_header(f: &AnalyzedFunction) -> Vec<String> {
+    let parameters = f
+        .resolved_function
+        .as_ref()
+        .map(|sig| {
+            sig.inputs()
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| format!("{arg} arg{i}"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+    let constructor_signature = match f.payable {
+        true => format!("constructor({parameters}) payable"),
+        false => format!("constructor({parameters})"),
+    };
+
+    let mut output = vec![format!("/// @custom:signature   {constructor_signature}")];
+    output.extend(f.notices.iter().map(|notice| format!("/// @notice             {notice}")));
+    output.extend(
+        f.resolved_function
+            .iter()
+            .flat_map(|sig| sig.decoded_inputs.iter().flatten())
+            .enumerate()
+            .map(|(i, value)| format!("/// @param              arg{i} {}", value.parameterize())),
+    );
+    output.push(format!("{constructor_signature} {{"));
+
+    output
+}
+
 /// Helper function which will write constant variables to the source code.
 fn get_constants(functions: &[AnalyzedFunction]) -> Vec<String> {
     let mut output: Vec<String> = functions
@@ -317,11 +371,18 @@ fn get_storage_variables(
                     .map(|x| x.name.clone())
                     .unwrap_or_else(|| format!("unresolved_{}", f.selector));
 
-                // TODO: for public getters, we can use `eth_getStorageAt` to get the value
+                // if the getter was executed against real state, include its value
+                let value = f
+                    .constant_value
+                    .as_ref()
+                    .map(|value| format!(" // = {value}"))
+                    .unwrap_or_default();
+
                 return format!(
-                    "{} public {};",
+                    "{} public {};{}",
                     f.returns.as_ref().unwrap_or(typ).replacen("memory", "", 1).trim(),
                     name,
+                    value,
                 );
             }
 
@@ -337,14 +398,17 @@ fn get_storage_variables(
 /// Helper function which will get the event and error declarations for the decompiled source code.
 fn get_event_and_error_declarations(
     functions: &[AnalyzedFunction],
+    constructor: Option<&AnalyzedFunction>,
     all_resolved_errors: &HashMap<String, ResolvedError>,
     all_resolved_logs: &HashMap<String, ResolvedLog>,
 ) -> HashMap<String, (String, String)> {
     let mut output = HashMap::new();
 
     // get all events and errors
-    let all_events = functions.iter().flat_map(|f| f.events.clone()).collect::<HashSet<_>>();
-    let all_errors = functions.iter().flat_map(|f| f.errors.clone()).collect::<HashSet<_>>();
+    let all_events =
+        functions.iter().chain(constructor).flat_map(|f| f.events.clone()).collect::<HashSet<_>>();
+    let all_errors =
+        functions.iter().chain(constructor).flat_map(|f| f.errors.clone()).collect::<HashSet<_>>();
 
     // add event declarations
     all_events.iter().for_each(|event_selector| {
@@ -1,7 +1,30 @@
-use crate::interfaces::AnalyzedFunction;
-use heimdall_common::ether::signatures::ResolvedFunction;
+use std::cmp::Ordering;
+
+use crate::interfaces::{AnalyzedFunction, CalldataFrame, TypeHeuristic};
+use alloy_dyn_abi::DynSolType;
+use heimdall_common::ether::signatures::{score_signature, ResolvedFunction};
 use tracing::trace;
 
+/// How much the calldata layout of a function contributes to a candidate's confidence.
+const ARGUMENTS_WEIGHT: f64 = 0.6;
+
+/// How much the way a function uses its arguments in storage contributes to a candidate's
+/// confidence.
+const STORAGE_WEIGHT: f64 = 0.2;
+
+/// How much the text of a candidate's signature contributes to its confidence.
+const TEXT_WEIGHT: f64 = 0.2;
+
+/// A resolved signature which matches the parameters of an [`AnalyzedFunction`].
+#[derive(Clone, Debug)]
+pub(crate) struct SignatureCandidate {
+    /// the resolved signature, with the same selector as the function
+    pub function: ResolvedFunction,
+
+    /// how confident we are that this is the function's signature, between 0 and 1
+    pub confidence: f64,
+}
+
 /// Given a list of potential [`ResolvedFunction`]s and a [`Snapshot`], return a list of
 /// [`ResolvedFunction`]s (that is, resolved signatures that were found on a 4byte directory) that
 /// match the parameters found during symbolic execution for said [`Snapshot`].
@@ -38,10 +61,10 @@ pub(crate) fn match_parameters(
             trace!("    checking for parameter {} with type {}", &index.to_string(), &input);
             match function.arguments.get(&index) {
                 Some(f) => {
-                    // arrays are typically recorded as bytes by the decompiler's potential
-                    // types
+                    // arrays whose elements weren't accessed are recorded as bytes by the
+                    // decompiler's potential types
                     if input.contains("[]") {
-                        if !f.potential_types().contains(&"bytes".to_string()) {
+                        if !f.potential_types().iter().any(|ty| ty == input || ty == "bytes") {
                             trace!(
                                 "        parameter {} does not match type {} for function {}({})",
                                 &index.to_string(),
@@ -51,7 +74,9 @@ pub(crate) fn match_parameters(
                             );
                             continue;
                         }
-                    } else if !f.potential_types().contains(input) {
+                    } else if !(f.potential_types().contains(input) ||
+                        input == "bool" && f.heuristics.contains(&TypeHeuristic::Boolean))
+                    {
                         matched = false;
                         trace!(
                             "        parameter {} does not match type {} for function {}({})",
@@ -85,3 +110,228 @@ pub(crate) fn match_parameters(
 
     matched_functions
 }
+
+/// Ranks the given [`ResolvedFunction`]s, which must match the parameters of `function` (see
+/// [`match_parameters`]), by how confident we are that each is the function's signature.
+///
+/// Signatures of colliding selectors often match the same number and size of arguments, so each
+/// candidate's confidence combines the calldata layout of the function, how it uses its arguments
+/// in storage, and the text of the signature, which is the weakest evidence.
+pub(crate) fn rank_signatures(
+    resolved_functions: Vec<ResolvedFunction>,
+    function: &AnalyzedFunction,
+) -> Vec<SignatureCandidate> {
+    let best_text_score = resolved_functions
+        .iter()
+        .map(|f| score_signature(&f.signature, None))
+        .max()
+        .unwrap_or_default()
+        .max(1);
+
+    let mut candidates = resolved_functions
+        .into_iter()
+        .map(|resolved_function| {
+            let arguments = arguments_confidence(&resolved_function, function);
+            let storage = storage_confidence(&resolved_function, function);
+            let text =
+                score_signature(&resolved_function.signature, None) as f64 / best_text_score as f64;
+            trace!(
+                "scored {} with arguments: {:.3}, storage: {:.3}, text: {:.3}",
+                &resolved_function.signature,
+                arguments,
+                storage,
+                text
+            );
+
+            let confidence = ARGUMENTS_WEIGHT
+                .mul_add(arguments, STORAGE_WEIGHT.mul_add(storage, TEXT_WEIGHT * text));
+            SignatureCandidate {
+                function: resolved_function,
+                confidence: (confidence * 1000.0).round() / 1000.0,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort_by(|a, b| {
+        b.confidence.partial_cmp(&a.confidence).unwrap_or(Ordering::Equal).then_with(|| {
+            score_signature(&b.function.signature, None)
+                .cmp(&score_signature(&a.function.signature, None))
+        })
+    });
+    candidates
+}
+
+/// Returns how well the inputs of `resolved_function` fit the calldata layout of `function`.
+fn arguments_confidence(resolved_function: &ResolvedFunction, function: &AnalyzedFunction) -> f64 {
+    let inputs = resolved_function.inputs.iter().filter(|x| !x.is_empty()).collect::<Vec<_>>();
+    if inputs.is_empty() {
+        return match function.arguments.is_empty() {
+            true => 1.0,
+            false => 0.0,
+        };
+    }
+
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| argument_confidence(input, function.arguments.get(&index)))
+        .sum::<f64>() /
+        inputs.len() as f64
+}
+
+/// Returns how well `input` fits the argument recovered from calldata, if any.
+fn argument_confidence(input: &str, frame: Option<&CalldataFrame>) -> f64 {
+    let (Some(frame), Ok(ty)) = (frame, DynSolType::parse(input)) else {
+        return 0.0;
+    };
+    let potential_types = frame.potential_types();
+    let position = potential_types.iter().position(|potential| *potential == ty.to_string());
+
+    match (&frame.dynamic, ty.is_dynamic()) {
+        // the structure of dynamic data is only known if it was accessed. otherwise, it's `bytes`
+        (Some(_), true) => match position {
+            Some(_) => 1.0,
+            None if potential_types.iter().any(|potential| potential == "bytes") => 0.6,
+            None => 0.3,
+        },
+
+        // static values aren't offsets to data which is read
+        (Some(_), false) => 0.1,
+
+        // the offset of a dynamic value is followed even if the value isn't used
+        (None, true) => 0.3,
+
+        (None, false) => {
+            if ty == DynSolType::Bool && frame.heuristics.contains(&TypeHeuristic::Boolean) {
+                return 1.0;
+            }
+
+            // potential types are ordered by likelihood
+            let confidence = match position {
+                Some(0) => 1.0,
+                Some(_) => 0.75,
+                None => 0.25,
+            };
+
+            // arithmetic on a value suggests it's a number, while byte operations suggest it isn't,
+            // and a value which is only ever tested for truth is likely a bool
+            let numeric = frame.heuristics.contains(&TypeHeuristic::Numeric);
+            let bytes = frame.heuristics.contains(&TypeHeuristic::Bytes);
+            let boolean = frame.heuristics.contains(&TypeHeuristic::Boolean);
+            let contradicted = match ty {
+                DynSolType::Uint(_) | DynSolType::Int(_) => (bytes || boolean) && !numeric,
+                DynSolType::FixedBytes(_) => (numeric || boolean) && !bytes,
+                DynSolType::Address => boolean,
+                _ => false,
+            };
+            match contradicted {
+                true => confidence * 0.5,
+                false => confidence,
+            }
+        }
+    }
+}
+
+/// Returns how well the inputs of `resolved_function` fit the way `function` uses its arguments
+/// in storage. A dynamic argument's head is only an offset, so arguments which are stored must be
+/// static, and arguments which slots are computed from are array indices, so they're numbers.
+fn storage_confidence(resolved_function: &ResolvedFunction, function: &AnalyzedFunction) -> f64 {
+    let inputs = resolved_function.inputs.iter().filter(|x| !x.is_empty()).collect::<Vec<_>>();
+    if inputs.is_empty() {
+        return 0.5;
+    }
+
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let Ok(ty) = DynSolType::parse(input) else {
+                return 0.0;
+            };
+            let indexes = function.storage.slot_arguments.contains(&index);
+            let stored = function.storage.stored_arguments.contains(&index);
+
+            match ty {
+                _ if (indexes || stored) && ty.is_dynamic() => 0.0,
+                DynSolType::Uint(_) if indexes => 1.0,
+                _ if indexes => 0.5,
+                _ if stored => 1.0,
+
+                // the argument isn't used in storage, so there's no evidence either way
+                _ => 0.5,
+            }
+        })
+        .sum::<f64>() /
+        inputs.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interfaces::{DynamicType, ElementType},
+        test_utils::{argument, rank, resolved},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_rank_by_storage() {
+        // bytes32 and uint256 both fit a word, but the argument is an index into an array
+        let mut function = AnalyzedFunction::new("13af4035", false);
+        function.arguments.insert(0, argument(32, None));
+        function.storage.slot_arguments.insert(0);
+
+        let ranked = rank(&function, &["foo(bytes32)", "foo(uint256)"]);
+        assert_eq!(ranked[0].0, "foo(uint256)");
+        assert!(ranked[0].1 > ranked[1].1);
+    }
+
+    #[test]
+    fn test_rank_by_argument_layout() {
+        // bytes4 and uint32 both fit a 4 byte mask, but arithmetic suggests a number
+        let mut function = AnalyzedFunction::new("00000001", false);
+        let mut frame = argument(4, None);
+        frame.heuristics.insert(TypeHeuristic::Numeric);
+        function.arguments.insert(0, frame);
+
+        let ranked = rank(&function, &["foo(bytes4)", "foo(uint32)"]);
+        assert_eq!(
+            ranked.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>(),
+            vec!["foo(uint32)", "foo(bytes4)"]
+        );
+    }
+
+    #[test]
+    fn test_rank_dynamic_arguments() {
+        // the elements of the array were accessed as words
+        let mut function = AnalyzedFunction::new("00000002", false);
+        let dynamic =
+            DynamicType { element: Some(Box::new(ElementType::Word(32))), ..Default::default() };
+        function.arguments.insert(0, argument(32, Some(dynamic)));
+
+        let ranked = rank(&function, &["bar(bytes32[])", "bar(uint256[])"]);
+        assert_eq!(ranked[0].0, "bar(uint256[])");
+        assert!(ranked.iter().all(|(_, confidence)| (0.0..=1.0).contains(confidence)));
+    }
+
+    #[test]
+    fn test_resolved_tuple_inputs() {
+        let function = resolved("baz((uint256,bytes)[],address)");
+        assert_eq!(function.inputs, vec!["(uint256,bytes)[]", "address"]);
+        assert!(resolved("baz()").inputs.is_empty());
+    }
+
+    #[test]
+    fn test_boolean_heuristic_matches_bool() {
+        let mut function = AnalyzedFunction::new("00000003", false);
+        let mut frame = argument(32, None);
+        frame.heuristics.insert(TypeHeuristic::Boolean);
+        function.arguments.insert(0, frame);
+
+        let ranked = rank(&function, &["setFee(uint256)", "setPaused(bool)"]);
+        assert_eq!(
+            ranked,
+            vec![("setPaused(bool)".to_string(), 0.9), ("setFee(uint256)".to_string(), 0.6)]
+        );
+    }
+}
@@ -1,10 +1,18 @@
-use alloy::primitives::Address;
+use alloy::primitives::{keccak256, Address};
 use clap::Parser;
 use derive_builder::Builder;
-use eyre::Result;
-use heimdall_common::ether::bytecode::get_bytecode_from_target;
+use eyre::{eyre, Result};
+use heimdall_common::{ether::bytecode::get_bytecode_from_target, utils::strings::encode_hex};
 use heimdall_config::parse_url_arg;
-use heimdall_vm::core::hardfork::HardFork;
+use heimdall_vm::{
+    core::{
+        hardfork::HardFork,
+        provider::{RpcStateProvider, SnapshotStateProvider, StateProvider},
+        world::WorldState,
+    },
+    ext::solver::default_solver,
+};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Parser, Builder)]
 #[clap(
@@ -54,6 +62,16 @@ pub struct DecompilerArgs {
     #[clap(long, short, default_value = "10000", hide_default_value = true)]
     pub timeout: u64,
 
+    /// The timeout for symbolic execution of all functions in milliseconds. Functions which
+    /// haven't been explored once it passes are skipped.
+    #[clap(long)]
+    pub total_timeout: Option<u64>,
+
+    /// The number of threads to use for symbolic execution. Defaults to the number of available
+    /// cores.
+    #[clap(long, default_value = "0", hide_default_value = true)]
+    pub threads: usize,
+
     /// Path to an optional ABI file to use for resolving errors, functions, and events.
     #[clap(long, short, default_value = None, hide_default_value = true)]
     pub abi: Option<String>,
@@ -79,6 +97,37 @@ pub struct DecompilerArgs {
     /// will be treated as unknown. Defaults to 'latest'.
     #[clap(long, short = 'f', default_value = "latest")]
     pub hardfork: HardFork,
+
+    /// Additional contracts to load, so that external calls into them are executed rather than
+    /// stubbed. Each entry is either an address, whose code is fetched from the RPC provider, or
+    /// `ADDRESS=TARGET`, where the target is bytecode or a file containing bytecode.
+    #[clap(long, value_delimiter = ',')]
+    pub contracts: Vec<String>,
+
+    /// Whether to execute against the target's on-chain state, fetched lazily from the RPC
+    /// provider. This allows the values of getters to be computed.
+    #[clap(long, conflicts_with = "snapshot")]
+    pub fork: bool,
+
+    /// The block number to read on-chain state at when using `--fork`. Defaults to the latest
+    /// block.
+    #[clap(long, requires = "fork")]
+    pub fork_block: Option<u64>,
+
+    /// Path to a JSON state snapshot to execute against, allowing getters to be computed offline.
+    #[clap(long, default_value = None, hide_default_value = true)]
+    pub snapshot: Option<String>,
+
+    /// Whether to prune branches which can't be reached during symbolic execution, by checking
+    /// the conditions along each path with a constraint solver. The solver is Z3 if heimdall is
+    /// built with the `z3` feature.
+    #[clap(long)]
+    pub solver: bool,
+
+    /// Whether to bypass the cache, always decompiling the target rather than reusing the result
+    /// of a previous run on the same bytecode.
+    #[clap(long = "no-cache")]
+    pub no_cache: bool,
 }
 
 impl DecompilerArgs {
@@ -96,6 +145,113 @@ impl DecompilerArgs {
         get_bytecode_from_target(&self.target, &self.rpc_url, &self.etherscan_api_key).await
     }
 
+    /// Builds the world state of the additional contracts specified with `--contracts`.
+    ///
+    /// # Returns
+    /// The world state, or `None` if no additional contracts were specified
+    pub async fn get_world_state(&self) -> Result<Option<WorldState>> {
+        if self.contracts.is_empty() {
+            return Ok(None);
+        }
+
+        let mut world = WorldState::new();
+        for contract in &self.contracts {
+            match contract.split_once('=') {
+                Some((address, target)) => {
+                    let address: Address = address
+                        .trim()
+                        .parse()
+                        .map_err(|_| eyre!("invalid contract address: {address}"))?;
+                    let code = get_bytecode_from_target(target.trim(), &self.rpc_url, "").await?;
+                    world.insert_code(address, code);
+                }
+                None => {
+                    let address: Address = contract
+                        .trim()
+                        .parse()
+                        .map_err(|_| eyre!("invalid contract address: {contract}"))?;
+                    world.fetch_code(address, &self.rpc_url).await?;
+                }
+            }
+        }
+
+        Ok(Some(world))
+    }
+
+    /// Builds the state provider specified with `--fork` or `--snapshot`.
+    ///
+    /// # Returns
+    /// The state provider, or `None` if neither option was specified
+    pub fn get_state_provider(&self) -> Result<Option<Arc<dyn StateProvider>>> {
+        if let Some(path) = &self.snapshot {
+            return Ok(Some(Arc::new(SnapshotStateProvider::from_file(path)?)));
+        }
+
+        if self.fork {
+            if self.rpc_url.is_empty() {
+                return Err(eyre!("`--fork` requires an RPC provider, set with `--rpc-url`"));
+            }
+            return Ok(Some(Arc::new(RpcStateProvider::new(&self.rpc_url, self.fork_block))));
+        }
+
+        Ok(None)
+    }
+
+    /// Builds the key the decompilation of `bytecode` is cached under, from the hash of the
+    /// bytecode, the hardfork, and the arguments which affect the result. Files passed as
+    /// arguments are keyed by their contents, forked state by its chain and block, and the
+    /// contracts loaded with `--contracts` by their code, as returned by
+    /// [`DecompilerArgs::get_world_state`].
+    ///
+    /// # Returns
+    /// The key, or `None` if caching is disabled with `--no-cache`, the target is executed
+    /// against the latest on-chain state, which changes between runs, or the output is
+    /// postprocessed by an LLM, which isn't deterministic
+    pub async fn cache_key(
+        &self,
+        bytecode: &[u8],
+        hardfork: HardFork,
+        world: Option<&WorldState>,
+    ) -> Option<String> {
+        if self.no_cache || self.llm_postprocess || (self.fork && self.fork_block.is_none()) {
+            return None;
+        }
+
+        // the same block number refers to different state on different chains
+        let fork = match self.fork_block {
+            Some(block) if self.fork => {
+                let chain_id = heimdall_common::ether::rpc::chain_id(&self.rpc_url).await.ok()?;
+                Some((chain_id, block))
+            }
+            _ => None,
+        };
+
+        let file_hash = |path: &String| {
+            std::fs::read(path).map(|contents| encode_hex(keccak256(contents).as_slice())).ok()
+        };
+        let arguments = format!(
+            "{}|{:?}|{}|{}|{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}",
+            env!("CARGO_PKG_VERSION"),
+            self.solver.then(default_solver),
+            self.skip_resolving,
+            self.include_solidity,
+            self.include_yul,
+            self.timeout,
+            self.total_timeout,
+            self.abi.as_ref().map(file_hash),
+            world.map(WorldState::hash),
+            fork,
+            self.snapshot.as_ref().map(file_hash),
+        );
+
+        Some(format!(
+            "decompile.{}.{}.{}",
+            encode_hex(keccak256(bytecode).as_slice()),
+            hardfork,
+            &encode_hex(keccak256(arguments).as_slice())[..16]
+        ))
+    }
+
     /// Gets the hardfork to use for decompilation.
     ///
     /// If `hardfork` is set to `Auto`, attempts to detect the hardfork based on the
@@ -158,12 +314,20 @@ impl DecompilerArgsBuilder {
             output: Some(String::new()),
             name: Some(String::new()),
             timeout: Some(10000),
+            total_timeout: Some(None),
+            threads: Some(0),
             abi: Some(None),
             llm_postprocess: Some(false),
             openrouter_api_key: Some(String::new()),
             model: Some(String::new()),
             etherscan_api_key: Some(String::new()),
             hardfork: Some(HardFork::Latest),
+            contracts: Some(Vec::new()),
+            fork: Some(false),
+            fork_block: Some(None),
+            snapshot: Some(None),
+            solver: Some(false),
+            no_cache: Some(false),
         }
     }
 }
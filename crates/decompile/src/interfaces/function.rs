@@ -1,10 +1,15 @@
+use std::collections::BTreeMap;
+
 use hashbrown::{HashMap, HashSet};
 
 use alloy::primitives::U256;
 use heimdall_common::ether::signatures::ResolvedFunction;
-use heimdall_vm::core::{opcodes::WrappedOpcode, types::byte_size_to_type};
+use heimdall_vm::{
+    core::{opcodes::WrappedOpcode, types::byte_size_to_type},
+    ext::calldata::CalldataLocation,
+};
 
-use crate::core::analyze::AnalyzerType;
+use crate::core::{analyze::AnalyzerType, resolve::SignatureCandidate};
 
 /// The [`AnalyzedFunction`] struct represents a function that has been analyzed by the decompiler.
 #[derive(Clone, Debug)]
@@ -37,6 +42,10 @@ pub(crate) struct AnalyzedFunction {
     /// stores the matched resolved function for this Functon
     pub resolved_function: Option<ResolvedFunction>,
 
+    /// every resolved signature which matches this function, ranked by confidence. the first is
+    /// the `resolved_function`
+    pub candidates: Vec<SignatureCandidate>,
+
     /// stores decompiler notices
     pub notices: Vec<String>,
 
@@ -56,6 +65,35 @@ pub(crate) struct AnalyzedFunction {
 
     /// optional constant value for this function
     pub constant_value: Option<String>,
+
+    /// the storage slots accessed by this function
+    pub storage: StorageAccesses,
+}
+
+/// The storage accesses made by a function, used to reconstruct the contract's storage layout.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StorageAccesses {
+    /// the slots read by the function
+    pub reads: HashSet<U256>,
+
+    /// the slots written by the function
+    pub writes: HashSet<U256>,
+
+    /// the arguments which slots are computed from, i.e. array indices
+    pub slot_arguments: HashSet<usize>,
+
+    /// the arguments whose values are written to storage
+    pub stored_arguments: HashSet<usize>,
+
+    /// the packed fields accessed by the function, as (slot, byte offset, size in bytes)
+    pub fields: HashSet<(U256, usize, usize)>,
+
+    /// the preimages of any hashes computed by the function, used to trace mapping and dynamic
+    /// array slots back to their base slot
+    pub preimages: HashMap<U256, Vec<u8>>,
+
+    /// the concrete slot of each SLOAD, keyed by the operation which produced the slot
+    pub slot_operations: HashMap<WrappedOpcode, U256>,
 }
 
 #[derive(Clone, Debug)]
@@ -69,13 +107,176 @@ pub(crate) struct CalldataFrame {
     pub arg_op: String,
     pub mask_size: usize,
     pub heuristics: HashSet<TypeHeuristic>,
+
+    /// the structure of the argument's data, if the argument is an offset to a dynamic value
+    pub dynamic: Option<DynamicType>,
 }
 
 impl CalldataFrame {
     /// Get the potential types for the given argument
     pub(crate) fn potential_types(&self) -> Vec<String> {
-        // get all potential types that can fit in self.mask_size
-        byte_size_to_type(self.mask_size).1.to_vec()
+        match &self.dynamic {
+            // `bytes` and `string` are encoded identically
+            Some(dynamic) => match dynamic.to_type() {
+                ty if ty == "bytes" => vec![ty, String::from("string")],
+                ty => vec![ty],
+            },
+
+            // get all potential types that can fit in self.mask_size
+            None => byte_size_to_type(self.mask_size).1.to_vec(),
+        }
+    }
+}
+
+/// How the data of a dynamic argument was accessed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CalldataAccess {
+    /// a CALLDATALOAD of a word
+    Load,
+
+    /// a CALLDATALOAD whose result is masked to the given number of bytes
+    Mask(usize),
+
+    /// a CALLDATACOPY into memory, either of whole words or of raw bytes
+    Copy { words: bool },
+
+    /// a multiplication of the length by the size of a word, i.e. when decoding an array into
+    /// memory element by element
+    Scale,
+}
+
+/// The type of a value within the data of a dynamic argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ElementType {
+    /// a static value, masked to the given number of bytes
+    Word(usize),
+
+    /// a dynamic value, which is stored as an offset to its data
+    Dynamic(DynamicType),
+}
+
+impl ElementType {
+    /// Narrows the type of a static value to the size it was masked to. Offsets to dynamic
+    /// values aren't masked.
+    fn narrow(&mut self, access: CalldataAccess) {
+        if let (ElementType::Word(size), CalldataAccess::Mask(mask_size)) = (self, access) {
+            *size = mask_size;
+        }
+    }
+
+    fn to_type(&self) -> String {
+        match self {
+            ElementType::Word(mask_size) => byte_size_to_type(*mask_size).1[0].to_string(),
+            ElementType::Dynamic(dynamic) => dynamic.to_type(),
+        }
+    }
+}
+
+/// The structure of a dynamic argument (or of a dynamic value nested within one), recovered from
+/// the accesses of its data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DynamicType {
+    /// whether the data is indexed byte-wise, i.e. this is `bytes` or `string`
+    pub raw_bytes: bool,
+
+    /// the type of the elements, if the data is indexed word-wise
+    pub element: Option<Box<ElementType>>,
+
+    /// the values accessed at constant offsets from the start of the data, keyed by offset. for
+    /// arrays, `bytes` and `string` the value at offset zero is the length
+    pub fields: BTreeMap<U256, ElementType>,
+}
+
+impl DynamicType {
+    /// Records an access at `location`, which must be within the data of this value.
+    pub(crate) fn record(&mut self, location: &CalldataLocation, access: CalldataAccess) {
+        let mut steps = Vec::new();
+        let mut current = location;
+        while let CalldataLocation::Tail { pointer, offset, index } = current {
+            steps.push((*offset, index.as_ref().map(|index| index.stride)));
+            current = pointer;
+        }
+
+        steps.reverse();
+        self.record_steps(&steps, access);
+    }
+
+    /// Records an access reached by following `steps`, each of which is the offset and the stride
+    /// of the variable index (if any) into the data of the value at the previous step.
+    fn record_steps(&mut self, steps: &[(U256, Option<U256>)], access: CalldataAccess) {
+        let Some((&(offset, stride), steps)) = steps.split_first() else {
+            return;
+        };
+        let word = U256::from(32);
+
+        // the value at this step is the offset of a nested dynamic value
+        if !steps.is_empty() {
+            let value = match stride {
+                Some(stride) if stride == word => {
+                    self.element.get_or_insert_with(|| Box::new(ElementType::Word(32))).as_mut()
+                }
+                Some(_) => return,
+                None => self.fields.entry(offset).or_insert(ElementType::Word(32)),
+            };
+
+            if let ElementType::Word(_) = value {
+                *value = ElementType::Dynamic(DynamicType::default());
+            }
+            if let ElementType::Dynamic(nested) = value {
+                nested.record_steps(steps, access);
+            }
+            return;
+        }
+
+        let words = match (stride, access) {
+            // copies are of the whole data, so they only tell us the size of the elements
+            (_, CalldataAccess::Copy { words }) => words,
+            (_, CalldataAccess::Scale) => true,
+            (Some(stride), _) => stride == word,
+            (None, _) => {
+                self.fields.entry(offset).or_insert(ElementType::Word(32)).narrow(access);
+                return;
+            }
+        };
+
+        if words {
+            self.element.get_or_insert_with(|| Box::new(ElementType::Word(32))).narrow(access);
+        } else {
+            self.raw_bytes = true;
+        }
+    }
+
+    /// Returns the Solidity type of this value, i.e. `bytes`, `uint256[]` or `(uint256,bytes)`.
+    pub(crate) fn to_type(&self) -> String {
+        let word = U256::from(32);
+        if let Some(element) = &self.element {
+            // arrays decoded element by element read their elements at constant offsets, which
+            // are masked rather than the indexed element
+            let element = match (element.as_ref(), self.fields.range(word..).next()) {
+                (ElementType::Word(32), Some((_, field))) => field,
+                (element, _) => element,
+            };
+            return format!("{}[]", element.to_type());
+        }
+        if self.raw_bytes {
+            return String::from("bytes");
+        }
+
+        // tuples are only stored out-of-place when they contain a dynamic value. otherwise, only
+        // the length (and perhaps a slice) of the data was accessed
+        if !self.fields.values().any(|field| matches!(field, ElementType::Dynamic(_))) {
+            return String::from("bytes");
+        }
+
+        let fields = self.fields.keys().next_back().copied().unwrap_or_default() / word;
+        let fields = (0..=fields.try_into().unwrap_or(0usize))
+            .map(|index| match self.fields.get(&(U256::from(index) * word)) {
+                Some(field) => field.to_type(),
+                None => String::from("bytes32"),
+            })
+            .collect::<Vec<String>>();
+
+        format!("({})", fields.join(","))
     }
 }
 
@@ -97,6 +298,7 @@ impl AnalyzedFunction {
             events: HashSet::new(),
             errors: HashSet::new(),
             resolved_function: None,
+            candidates: Vec::new(),
             notices: Vec::new(),
             pure: true,
             view: true,
@@ -105,6 +307,7 @@ impl AnalyzedFunction {
             fallback,
             maybe_getter_for: None,
             constant_value: None,
+            storage: StorageAccesses::default(),
         }
     }
 
@@ -140,3 +343,165 @@ impl AnalyzedFunction {
         arguments
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use heimdall_vm::{
+        core::opcodes::{MUL, SHL},
+        test_utils::{add, counter, load, op, push},
+    };
+
+    use super::*;
+    use crate::test_utils::argument;
+
+    /// the start of the data of the dynamic value whose offset is stored at `pointer`, relative
+    /// to `base`
+    fn data(base: WrappedOpcode, pointer: WrappedOpcode) -> WrappedOpcode {
+        add(base, load(pointer))
+    }
+
+    /// the `i`th word-sized element of the array whose data starts at `data`
+    fn element(data: WrappedOpcode) -> WrappedOpcode {
+        add(add(data, push(0x20)), op(MUL, vec![counter(0x64), push(0x20)]))
+    }
+
+    /// the start of the elements of the array whose data starts at `data`
+    fn elements(data: WrappedOpcode) -> WrappedOpcode {
+        add(data, push(0x20))
+    }
+
+    /// replays calldata reads at the given offsets, returning the recovered type of argument 0
+    fn recover(accesses: Vec<(WrappedOpcode, CalldataAccess)>) -> Vec<String> {
+        let mut frame = argument(32, None);
+
+        for (offset, access) in accesses {
+            let location = CalldataLocation::locate(&offset).expect("failed to locate read");
+            assert_eq!(location.argument(), 0);
+            frame.dynamic.get_or_insert_with(DynamicType::default).record(&location, access);
+        }
+
+        frame.potential_types()
+    }
+
+    #[test]
+    fn test_static_argument() {
+        assert_eq!(recover(Vec::new())[0], "uint256");
+    }
+
+    #[test]
+    fn test_bytes_or_string() {
+        // function f(bytes calldata data) { bytes memory copy = data; }
+        let data = data(push(0x04), push(0x04));
+        let types = recover(vec![
+            (data.clone(), CalldataAccess::Load),
+            (elements(data), CalldataAccess::Copy { words: false }),
+        ]);
+        assert_eq!(types, vec!["bytes", "string"]);
+    }
+
+    #[test]
+    fn test_bytes_indexed() {
+        // function f(bytes calldata data) { data[i]; }
+        let data = data(push(0x04), push(0x04));
+        let types = recover(vec![
+            (data.clone(), CalldataAccess::Load),
+            (add(elements(data), counter(0x64)), CalldataAccess::Load),
+        ]);
+        assert_eq!(types[0], "bytes");
+    }
+
+    #[test]
+    fn test_uint256_array() {
+        // function f(uint256[] calldata values) { values[i]; }
+        let data = data(push(0x04), push(0x04));
+        let types = recover(vec![
+            (data.clone(), CalldataAccess::Load),
+            (element(data), CalldataAccess::Load),
+        ]);
+        assert_eq!(types, vec!["uint256[]"]);
+    }
+
+    #[test]
+    fn test_uint256_array_scaled() {
+        // function f(uint256[] memory values), decoded by solc element by element
+        let data = data(push(0x04), push(0x04));
+        let types =
+            recover(vec![(data.clone(), CalldataAccess::Load), (data, CalldataAccess::Scale)]);
+        assert_eq!(types, vec!["uint256[]"]);
+    }
+
+    #[test]
+    fn test_address_array_scaled() {
+        // function f(address[] memory values), decoded by solc element by element
+        let data = data(push(0x04), push(0x04));
+        let types = recover(vec![
+            (data.clone(), CalldataAccess::Load),
+            (data.clone(), CalldataAccess::Scale),
+            (add(data, push(0x20)), CalldataAccess::Mask(20)),
+        ]);
+        assert_eq!(types, vec!["address[]"]);
+    }
+
+    #[test]
+    fn test_uint256_array_copy() {
+        // function f(uint256[] calldata values) { uint256[] memory copy = values; }
+        let data = data(push(0x04), push(0x04));
+        let types = recover(vec![
+            (data.clone(), CalldataAccess::Load),
+            (elements(data), CalldataAccess::Copy { words: true }),
+        ]);
+        assert_eq!(types, vec!["uint256[]"]);
+    }
+
+    #[test]
+    fn test_address_array() {
+        // function f(address[] calldata owners) { owners[i]; }
+        let data = data(push(0x04), push(0x04));
+        let types = recover(vec![
+            (element(data.clone()), CalldataAccess::Load),
+            (element(data), CalldataAccess::Mask(20)),
+        ]);
+        assert_eq!(types, vec!["address[]"]);
+    }
+
+    #[test]
+    fn test_bytes_array() {
+        // function f(bytes[] calldata items) { items[i].length; }
+        let outer = data(push(0x04), push(0x04));
+        let inner = data(elements(outer.clone()), element(outer.clone()));
+        let types = recover(vec![
+            (element(outer), CalldataAccess::Load),
+            (inner.clone(), CalldataAccess::Load),
+            (add(elements(inner), counter(0x64)), CalldataAccess::Load),
+        ]);
+        assert_eq!(types, vec!["bytes[]"]);
+    }
+
+    #[test]
+    fn test_nested_uint256_array() {
+        // function f(uint256[][] calldata matrix) { matrix[i][j]; }
+        let outer = data(push(0x04), push(0x04));
+        let inner = add(
+            elements(outer.clone()),
+            load(add(elements(outer), op(SHL, vec![push(5), counter(0x64)]))),
+        );
+        let types = recover(vec![
+            (inner.clone(), CalldataAccess::Load),
+            (element(inner), CalldataAccess::Load),
+        ]);
+        assert_eq!(types, vec!["uint256[][]"]);
+    }
+
+    #[test]
+    fn test_tuple_with_bytes() {
+        // function f((address owner, bytes data) calldata s) { s.owner; s.data.length; }
+        let tuple = data(push(0x04), push(0x04));
+        let inner = data(tuple.clone(), add(tuple.clone(), push(0x20)));
+        let types = recover(vec![
+            (tuple.clone(), CalldataAccess::Load),
+            (tuple, CalldataAccess::Mask(20)),
+            (inner, CalldataAccess::Load),
+        ]);
+        assert_eq!(types, vec!["(address,bytes)"]);
+    }
+}
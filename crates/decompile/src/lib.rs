@@ -12,8 +12,16 @@ mod core;
 mod interfaces;
 mod utils;
 
+/// Fixtures for building analyzed functions in tests
+#[cfg(test)]
+mod test_utils;
+
 // re-export the public interface
-pub use core::{decompile, DecompileResult};
+pub use core::{
+    decompile,
+    out::layout::{StorageLayout, StorageLayoutEntry, StorageLayoutType},
+    DecompileResult,
+};
 pub use error::Error;
 pub use heimdall_vm::core::hardfork::HardFork;
 pub use interfaces::{DecompilerArgs, DecompilerArgsBuilder};
@@ -0,0 +1,40 @@
+//! Fixtures for building analyzed functions and resolved signatures in tests.
+
+use alloy_dyn_abi::DynSolType;
+use hashbrown::HashSet;
+use heimdall_common::ether::signatures::ResolvedFunction;
+use heimdall_vm::test_utils::push;
+
+use crate::{
+    core::resolve::{match_parameters, rank_signatures},
+    interfaces::{AnalyzedFunction, CalldataFrame, DynamicType},
+};
+
+/// A resolved function with the given signature, i.e. `transfer(address,uint256)`.
+pub(crate) fn resolved(signature: &str) -> ResolvedFunction {
+    let name = signature.split('(').next().unwrap_or_default().to_string();
+    let inputs = match DynSolType::parse(&signature[name.len()..]) {
+        Ok(DynSolType::Tuple(inputs)) => {
+            inputs.iter().map(|input| input.sol_type_name().to_string()).collect()
+        }
+        _ => panic!("invalid signature: {signature}"),
+    };
+
+    ResolvedFunction { name, signature: signature.to_string(), inputs, decoded_inputs: None }
+}
+
+/// An argument masked to `mask_size` bytes, which is an offset to `dynamic` if given.
+pub(crate) fn argument(mask_size: usize, dynamic: Option<DynamicType>) -> CalldataFrame {
+    CalldataFrame { arg_op: push(0x04).to_string(), mask_size, heuristics: HashSet::new(), dynamic }
+}
+
+/// Matches and ranks `signatures` against `function`, returning each matching signature along
+/// with its confidence, best first.
+pub(crate) fn rank(function: &AnalyzedFunction, signatures: &[&str]) -> Vec<(String, f64)> {
+    let resolved_functions = signatures.iter().map(|signature| resolved(signature)).collect();
+    let candidates = rank_signatures(match_parameters(resolved_functions, function), function);
+    candidates
+        .into_iter()
+        .map(|candidate| (candidate.function.signature, candidate.confidence))
+        .collect()
+}
@@ -4,16 +4,19 @@ use hashbrown::HashSet;
 use alloy::primitives::U256;
 use eyre::eyre;
 use heimdall_common::utils::strings::find_balanced_encapsulator;
-use heimdall_vm::core::{
-    opcodes::{opcode_name, CALLDATALOAD, ISZERO},
-    types::{byte_size_to_type, convert_bitmask},
-    vm::State,
+use heimdall_vm::{
+    core::{
+        opcodes::{opcode_name, WrappedInput, WrappedOpcode, CALLDATALOAD, ISZERO, MUL, SHL},
+        types::{byte_size_to_type, convert_bitmask},
+        vm::State,
+    },
+    ext::calldata::CalldataLocation,
 };
 use tracing::{debug, trace};
 
 use crate::{
     core::analyze::{AnalyzerState, AnalyzerType},
-    interfaces::{AnalyzedFunction, CalldataFrame, TypeHeuristic},
+    interfaces::{AnalyzedFunction, CalldataAccess, CalldataFrame, DynamicType, TypeHeuristic},
     utils::constants::{AND_BITMASK_REGEX, AND_BITMASK_REGEX_2, STORAGE_ACCESS_REGEX},
     Error,
 };
@@ -24,16 +27,38 @@ pub(crate) fn argument_heuristic<'a>(
     analyzer_state: &'a mut AnalyzerState,
 ) -> BoxFuture<'a, Result<(), Error>> {
     Box::pin(async move {
+        // arrays decoded into memory element by element scale their length by the size of a
+        // word, to find the size of their data
+        if let Some(location) = scaled_length(state) {
+            record_dynamic_access(function, &location, CalldataAccess::Scale);
+        }
+
         match state.last_instruction.opcode {
             // CALLDATALOAD
             0x35 => {
-                // calculate the argument index, with the 4byte signature padding removed
-                // for example, CALLDATALOAD(4) -> (4-4)/32 = 0
-                //              CALLDATALOAD(36) -> (36-4)/32 = 1
-                let arg_index = (state.last_instruction.inputs[0].saturating_sub(U256::from(4)) /
-                    U256::from(32))
-                .try_into()
-                .unwrap_or(usize::MAX);
+                let offset = &state.last_instruction.input_operations[0];
+                let arg_index = match CalldataLocation::locate(offset) {
+                    Some(CalldataLocation::Argument(arg_index)) => arg_index,
+
+                    // this is a read of a dynamic argument's data, which is attributed to the
+                    // argument holding its offset
+                    Some(location) => {
+                        record_dynamic_access(function, &location, CalldataAccess::Load);
+                        return Ok(());
+                    }
+
+                    // reads of dynamic data which can't be located would otherwise alias the
+                    // argument heads, since every argument is zero during symbolic execution
+                    None if reads_calldata(offset) => return Ok(()),
+
+                    // calculate the argument index, with the 4byte signature padding removed
+                    // for example, CALLDATALOAD(4) -> (4-4)/32 = 0
+                    //              CALLDATALOAD(36) -> (36-4)/32 = 1
+                    None => (state.last_instruction.inputs[0].saturating_sub(U256::from(4)) /
+                        U256::from(32))
+                    .try_into()
+                    .unwrap_or(usize::MAX),
+                };
 
                 // insert only if this argument is not already in the hashmap
                 function.arguments.entry(arg_index).or_insert_with(|| {
@@ -42,17 +67,29 @@ pub(crate) fn argument_heuristic<'a>(
                         arg_index, state.last_instruction.inputs[0]
                     );
                     CalldataFrame {
-                        arg_op: state.last_instruction.input_operations[0].to_string(),
+                        arg_op: offset.to_string(),
                         mask_size: 32, // init to 32 because all CALLDATALOADs are 32 bytes
                         heuristics: HashSet::new(),
+                        dynamic: None,
                     }
                 });
             }
 
             // CALLDATACOPY
             0x37 => {
-                // TODO: implement CALLDATACOPY support
-                trace!("CALLDATACOPY detected; not implemented");
+                // copies of a dynamic argument's data into memory, i.e. `bytes memory` or
+                // `uint256[] memory` arguments. arrays are copied in words, so their size is a
+                // multiple of the length
+                if let Some(location) =
+                    CalldataLocation::locate(&state.last_instruction.input_operations[1])
+                        .filter(|location| location.depth() > 0)
+                {
+                    let words =
+                        [MUL, SHL].contains(&state.last_instruction.input_operations[2].opcode);
+                    record_dynamic_access(function, &location, CalldataAccess::Copy { words });
+                } else {
+                    trace!("CALLDATACOPY of unknown calldata; skipping");
+                }
             }
 
             // AND | OR
@@ -84,6 +121,18 @@ pub(crate) fn argument_heuristic<'a>(
                         );
 
                         frame.mask_size = mask_size_bytes;
+                    } else if let Some(location) = match &calldataload_op.inputs[0] {
+                        WrappedInput::Opcode(offset) => CalldataLocation::locate(offset),
+                        WrappedInput::Raw(_) => None,
+                    }
+                    .filter(|location| location.depth() > 0)
+                    {
+                        // this is a mask of an element (or field) of a dynamic argument
+                        record_dynamic_access(
+                            function,
+                            &location,
+                            CalldataAccess::Mask(mask_size_bytes),
+                        );
                     }
                 }
             }
@@ -296,3 +345,52 @@ pub(crate) fn argument_heuristic<'a>(
         Ok(())
     })
 }
+
+/// Records an access of a dynamic argument's data in the frame of the argument which holds its
+/// offset, which has already been discovered by the CALLDATALOAD of said offset.
+fn record_dynamic_access(
+    function: &mut AnalyzedFunction,
+    location: &CalldataLocation,
+    access: CalldataAccess,
+) {
+    if let Some(frame) = function.arguments.get_mut(&location.argument()) {
+        debug!(
+            "argument {} is dynamic, found {:?} of {}",
+            location.argument(),
+            access,
+            location.solidify().unwrap_or_else(|| String::from("its data"))
+        );
+        frame.dynamic.get_or_insert_with(DynamicType::default).record(location, access);
+    }
+}
+
+/// Returns the location of the length of a dynamic argument's data, if `state` multiplies it by
+/// the size of a word (either through MUL or SHL).
+fn scaled_length(state: &State) -> Option<CalldataLocation> {
+    let instruction = &state.last_instruction;
+    let word = U256::from(32);
+    let length = match (instruction.opcode, instruction.input_operations.as_slice()) {
+        (MUL, [a, b]) if instruction.inputs[1] == word && b.opcode != CALLDATALOAD => a,
+        (MUL, [a, b]) if instruction.inputs[0] == word && a.opcode != CALLDATALOAD => b,
+        (SHL, [_, value]) if instruction.inputs[0] == U256::from(5) => value,
+        _ => return None,
+    };
+
+    let location = match (length.opcode, length.inputs.as_slice()) {
+        (CALLDATALOAD, [WrappedInput::Opcode(offset)]) => CalldataLocation::locate(offset)?,
+        _ => return None,
+    };
+    match &location {
+        CalldataLocation::Tail { offset, index: None, .. } if offset.is_zero() => Some(location),
+        _ => None,
+    }
+}
+
+/// Returns true if `operation` is derived from a value read from calldata.
+fn reads_calldata(operation: &WrappedOpcode) -> bool {
+    operation.opcode == CALLDATALOAD ||
+        operation.inputs.iter().any(|input| match input {
+            WrappedInput::Opcode(inner) => reads_calldata(inner),
+            WrappedInput::Raw(_) => false,
+        })
+}
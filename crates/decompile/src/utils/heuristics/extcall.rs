@@ -1,6 +1,9 @@
 use alloy::primitives::U256;
 use futures::future::BoxFuture;
-use heimdall_common::utils::{hex::ToLowerHex, strings::encode_hex_reduced};
+use heimdall_common::utils::{
+    hex::ToLowerHex,
+    strings::{encode_hex, encode_hex_reduced},
+};
 use heimdall_vm::{
     core::{opcodes::opcode_name, vm::State},
     w_gas, w_push0,
@@ -213,6 +216,21 @@ pub(crate) fn extcall_heuristic<'a>(
             _ => {}
         };
 
+        // if the call was executed against a loaded contract, include what it returned
+        if let Some(frame) = &state.call {
+            trace!(
+                "instruction {} ({}) was executed against {}",
+                instruction.instruction,
+                opcode_name(instruction.opcode),
+                frame.code_address
+            );
+            function.logic.push(if frame.success() {
+                format!("// ret0 = 0x{}", encode_hex(&frame.result.returndata))
+            } else {
+                format!("// reverted with 0x{}", encode_hex(&frame.result.returndata))
+            });
+        }
+
         Ok(())
     })
 }
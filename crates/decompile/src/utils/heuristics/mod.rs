@@ -9,6 +9,7 @@ mod events;
 mod extcall;
 mod modifiers;
 mod solidity;
+mod storage;
 mod yul;
 
 // re-export heuristics
@@ -17,6 +18,7 @@ pub(crate) use events::event_heuristic;
 pub(crate) use extcall::extcall_heuristic;
 pub(crate) use modifiers::modifier_heuristic;
 pub(crate) use solidity::solidity_heuristic;
+pub(crate) use storage::storage_heuristic;
 pub(crate) use yul::yul_heuristic;
 
 /// A heuristic is a function that takes a function and a state and modifies the function based on
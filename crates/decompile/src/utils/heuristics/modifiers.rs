@@ -1,7 +1,7 @@
 use futures::future::BoxFuture;
 use heimdall_vm::{
     core::{
-        opcodes::{OpCodeInfo, JUMPI},
+        opcodes::{OpCodeInfo, JUMPI, RJUMPI},
         vm::State,
     },
     w_callvalue, w_iszero,
@@ -38,12 +38,14 @@ pub(crate) fn modifier_heuristic<'a>(
             function.view = false;
         }
 
-        // if the instruction is a JUMPI with non-zero CALLVALUE requirement, the function is
-        // non-payable exactly: ISZERO(CALLVALUE())
-        if function.payable &&
-            state.last_instruction.opcode == JUMPI &&
-            state.last_instruction.input_operations[1] == w_iszero!(w_callvalue!())
-        {
+        // if the instruction is a JUMPI (or RJUMPI) with non-zero CALLVALUE requirement, the
+        // function is non-payable exactly: ISZERO(CALLVALUE())
+        let condition = match state.last_instruction.opcode {
+            JUMPI => state.last_instruction.input_operations.get(1),
+            RJUMPI => state.last_instruction.input_operations.first(),
+            _ => None,
+        };
+        if function.payable && condition == Some(&w_iszero!(w_callvalue!())) {
             debug!(
                 "conditional at instruction {} indicates a non-payable function",
                 state.last_instruction.instruction
@@ -2,12 +2,12 @@ use alloy::primitives::U256;
 use alloy_dyn_abi::{DynSolType, DynSolValue};
 use futures::future::BoxFuture;
 use heimdall_common::utils::strings::encode_hex_reduced;
-use heimdall_vm::core::vm::State;
+use heimdall_vm::{core::vm::State, ext::calldata::CalldataLocation};
 
 use crate::{
     core::analyze::AnalyzerState,
     interfaces::{AnalyzedFunction, StorageFrame},
-    utils::constants::VARIABLE_SIZE_CHECK_REGEX,
+    utils::{constants::VARIABLE_SIZE_CHECK_REGEX, loops::solidify_loop},
     Error,
 };
 
@@ -26,6 +26,22 @@ pub(crate) fn solidity_heuristic<'a>(
                 let source_offset = instruction.inputs[1];
                 let size_bytes = instruction.inputs[2];
 
+                // copies of a dynamic argument's data (which starts after its length) are
+                // copies of the argument itself
+                if let Some(CalldataLocation::Tail { pointer, offset, index: None }) =
+                    CalldataLocation::locate(&instruction.input_operations[1])
+                {
+                    if let Some(argument) = pointer.solidify().filter(|_| offset == U256::from(32))
+                    {
+                        function.logic.push(format!(
+                            "memory[{}] = {};",
+                            memory_offset.solidify(),
+                            argument
+                        ));
+                        return Ok(());
+                    }
+                }
+
                 // add the mstore to the function's memory map
                 function.logic.push(format!(
                     "memory[{}] = msg.data[{}:{}];",
@@ -91,10 +107,19 @@ pub(crate) fn solidity_heuristic<'a>(
                 ));
             }
 
-            // JUMPI
-            0x57 => {
-                // this is an if conditional for the children branches
-                let conditional = instruction.input_operations[1].solidify();
+            // JUMPI / RJUMPI
+            0x57 | 0xe1 => {
+                // if this jump heads a loop, render the loop rather than a conditional
+                if let Some(loop_info) = analyzer_state.current_loop.take() {
+                    function.logic.push(solidify_loop(&loop_info, analyzer_state.loop_depth));
+                    return Ok(());
+                }
+
+                // this is an if conditional for the children branches. JUMPI takes its condition
+                // as the second input, while RJUMPI takes it as the only input.
+                let conditional = instruction.input_operations
+                    [if instruction.opcode == 0x57 { 1 } else { 0 }]
+                .solidify();
 
                 // perform a series of checks to determine if the condition
                 // is added by the compiler and can be ignored
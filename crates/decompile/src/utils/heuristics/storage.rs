@@ -0,0 +1,200 @@
+use alloy::primitives::U256;
+use futures::future::BoxFuture;
+use hashbrown::HashSet;
+use heimdall_vm::{
+    core::{
+        opcodes::{
+            WrappedInput, WrappedOpcode, AND, CALLDATALOAD, DIV, EXP, PUSH0, PUSH32, SHA3, SHR,
+            SLOAD, SSTORE,
+        },
+        vm::State,
+    },
+    ext::calldata::CalldataLocation,
+};
+
+use crate::{core::analyze::AnalyzerState, interfaces::AnalyzedFunction, Error};
+
+/// The largest hash preimage which is remembered. Storage slots are hashed from at most a key and
+/// a slot, though string keys may be longer.
+const MAX_PREIMAGE_SIZE: usize = 1024;
+
+pub(crate) fn storage_heuristic<'a>(
+    function: &'a mut AnalyzedFunction,
+    state: &'a State,
+    _: &'a mut AnalyzerState,
+) -> BoxFuture<'a, Result<(), Error>> {
+    Box::pin(async move {
+        let instruction = &state.last_instruction;
+
+        match instruction.opcode {
+            // remember the preimage of each hash, so hashed slots can be traced back to the
+            // mapping or dynamic array they belong to
+            SHA3 => {
+                let offset = instruction.inputs.first().and_then(|x| usize::try_from(*x).ok());
+                let size = instruction.inputs.get(1).and_then(|x| usize::try_from(*x).ok());
+                if let (Some(offset), Some(size), Some(hash)) =
+                    (offset, size, instruction.outputs.first())
+                {
+                    if size <= MAX_PREIMAGE_SIZE {
+                        function.storage.preimages.insert(*hash, state.memory.read(offset, size));
+                    }
+                }
+            }
+            SLOAD => {
+                if let (Some(slot), Some(operation)) =
+                    (instruction.inputs.first(), instruction.input_operations.first())
+                {
+                    function.storage.reads.insert(*slot);
+                    function.storage.slot_operations.insert(operation.clone(), *slot);
+                    record_arguments(&mut function.storage.slot_arguments, operation);
+                }
+            }
+            SSTORE => {
+                if let Some(slot) = instruction.inputs.first() {
+                    function.storage.writes.insert(*slot);
+                }
+                if let [slot, value] = instruction.input_operations.as_slice() {
+                    record_arguments(&mut function.storage.slot_arguments, slot);
+                    record_arguments(&mut function.storage.stored_arguments, value);
+                }
+            }
+            // masking a loaded slot indicates a packed field, either when reading it
+            // (`(slot >> shift) & mask`) or when clearing it before a write (`slot & ~mask`)
+            AND => {
+                for (operation, mask) in
+                    instruction.input_operations.iter().zip(instruction.inputs.iter().rev())
+                {
+                    if let Some(field) = packed_field(function, operation, *mask) {
+                        function.storage.fields.insert(field);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    })
+}
+
+/// Returns the (slot, byte offset, size in bytes) of the packed field selected by masking
+/// `operation` with `mask`, if `operation` loads a storage slot.
+fn packed_field(
+    function: &AnalyzedFunction,
+    operation: &WrappedOpcode,
+    mask: U256,
+) -> Option<(U256, usize, usize)> {
+    // find the loaded slot, and how far it was shifted right
+    let (load, shift) = match operation.opcode {
+        SLOAD => (operation, U256::ZERO),
+        SHR => (as_operation(operation.inputs.get(1)?)?, constant(operation.inputs.first()?)?),
+        DIV => {
+            let divisor = constant(operation.inputs.get(1)?)?;
+            if !divisor.is_power_of_two() {
+                return None;
+            }
+            (as_operation(operation.inputs.first()?)?, U256::from(divisor.trailing_zeros()))
+        }
+        _ => return None,
+    };
+    if load.opcode != SLOAD {
+        return None;
+    }
+
+    let slot = match load.inputs.first()? {
+        WrappedInput::Raw(slot) => *slot,
+        WrappedInput::Opcode(slot) => *function.storage.slot_operations.get(slot.as_ref())?,
+    };
+    let shift =
+        usize::try_from(shift).ok().filter(|shift| shift.is_multiple_of(8) && *shift < 256)?;
+
+    // a mask of the low bytes reads a field, while a mask with a hole clears one
+    let (offset, size) = if let Some(size) = low_byte_mask_size(mask) {
+        (shift / 8, size)
+    } else if shift == 0 {
+        let hole = !mask;
+        let offset = hole.trailing_zeros();
+        (offset / 8, low_byte_mask_size(hole >> offset).filter(|_| offset.is_multiple_of(8))?)
+    } else {
+        return None;
+    };
+
+    (size < 32 && offset + size <= 32).then_some((slot, offset, size))
+}
+
+/// Records the index of every argument whose head is read by `operation`. Reads within the data
+/// of a dynamic argument are skipped, as its head is only an offset.
+fn record_arguments(arguments: &mut HashSet<usize>, operation: &WrappedOpcode) {
+    if operation.opcode == CALLDATALOAD {
+        if let Some(CalldataLocation::Argument(index)) =
+            operation.inputs.first().and_then(as_operation).and_then(CalldataLocation::locate)
+        {
+            arguments.insert(index);
+        }
+        return;
+    }
+
+    for input in operation.inputs.iter().filter_map(as_operation) {
+        record_arguments(arguments, input);
+    }
+}
+
+/// Returns the number of bytes selected by `mask`, if it selects only whole low-order bytes.
+fn low_byte_mask_size(mask: U256) -> Option<usize> {
+    let bits = mask.count_ones();
+    (bits > 0 && bits.is_multiple_of(8) && mask.trailing_ones() == bits).then_some(bits / 8)
+}
+
+/// Returns the operation wrapped by `input`, if any.
+fn as_operation(input: &WrappedInput) -> Option<&WrappedOpcode> {
+    match input {
+        WrappedInput::Opcode(operation) => Some(operation.as_ref()),
+        WrappedInput::Raw(_) => None,
+    }
+}
+
+/// Returns the value of `input`, if it is a constant.
+fn constant(input: &WrappedInput) -> Option<U256> {
+    let operation = match input {
+        WrappedInput::Raw(value) => return Some(*value),
+        WrappedInput::Opcode(operation) => operation,
+    };
+
+    match operation.opcode {
+        PUSH0 => Some(U256::ZERO),
+        op if (PUSH0..=PUSH32).contains(&op) => constant(operation.inputs.first()?),
+        EXP => {
+            let base = constant(operation.inputs.first()?)?;
+            let exponent = constant(operation.inputs.get(1)?)?;
+            base.checked_pow(exponent)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_vm::test_utils::{add, load, push};
+
+    use super::*;
+
+    #[test]
+    fn test_record_arguments() {
+        // arg1 is added to the slot of an array, while arg0's data is read through its offset
+        let slot = add(push(0x02), load(push(0x24)));
+        let length = load(add(push(0x04), load(push(0x04))));
+
+        let mut arguments = HashSet::new();
+        record_arguments(&mut arguments, &slot);
+        record_arguments(&mut arguments, &length);
+        assert_eq!(arguments, HashSet::from_iter([1]));
+    }
+
+    #[test]
+    fn test_low_byte_mask_size() {
+        assert_eq!(low_byte_mask_size(U256::from(0xff)), Some(1));
+        assert_eq!(low_byte_mask_size((U256::from(1) << 160) - U256::from(1)), Some(20));
+        assert_eq!(low_byte_mask_size(U256::from(0xff00)), None);
+        assert_eq!(low_byte_mask_size(U256::from(0x0f)), None);
+        assert_eq!(low_byte_mask_size(U256::ZERO), None);
+    }
+}
@@ -5,6 +5,7 @@ use heimdall_vm::core::{opcodes::opcode_name, vm::State};
 use crate::{
     core::analyze::AnalyzerState,
     interfaces::{AnalyzedFunction, StorageFrame},
+    utils::loops::yulify_loop,
     Error,
 };
 
@@ -33,9 +34,17 @@ pub(crate) fn yul_heuristic<'a>(
                 ));
             }
 
-            // JUMPI
-            0x57 => {
-                let conditional = instruction.input_operations[1].yulify();
+            // JUMPI / RJUMPI
+            0x57 | 0xe1 => {
+                // if this jump heads a loop, render the loop rather than a conditional
+                if let Some(loop_info) = analyzer_state.current_loop.take() {
+                    function.logic.push(yulify_loop(&loop_info, analyzer_state.loop_depth));
+                    return Ok(());
+                }
+
+                let conditional = instruction.input_operations
+                    [if instruction.opcode == 0x57 { 1 } else { 0 }]
+                .yulify();
 
                 function.logic.push(format!("if {conditional} {{"));
                 analyzer_state.jumped_conditional = Some(conditional.clone());
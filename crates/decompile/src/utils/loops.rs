@@ -0,0 +1,142 @@
+use heimdall_vm::ext::exec::LoopInfo;
+
+/// Names of induction variables, indexed by loop depth. Deeper loops fall back to `i{depth}`.
+const LOOP_VARIABLE_NAMES: [&str; 3] = ["i", "j", "k"];
+
+/// Returns the name of the induction variable of a loop nested `depth` loops deep.
+fn loop_variable(depth: usize) -> String {
+    LOOP_VARIABLE_NAMES
+        .get(depth)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("i{depth}"))
+}
+
+/// Replaces every whole occurrence of `expression` in `line` with `name`. Occurrences which are
+/// part of a longer identifier or literal (i.e. `0x01` within `0x0100`) are left untouched.
+fn substitute(line: &str, expression: &str, name: &str) -> Option<String> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    let mut found = false;
+
+    while let Some(index) = rest.find(expression) {
+        let before = rest[..index].chars().last().or_else(|| result.chars().last());
+        let after = rest[index + expression.len()..].chars().next();
+        result.push_str(&rest[..index]);
+
+        if !before.is_some_and(is_word) && !after.is_some_and(is_word) {
+            result.push_str(name);
+            found = true;
+        } else {
+            result.push_str(expression);
+        }
+        rest = &rest[index + expression.len()..];
+    }
+    result.push_str(rest);
+
+    found.then_some(result)
+}
+
+/// Renders the header of a loop as Solidity. Loops with an induction variable the condition
+/// depends on become `for` loops, all others become `while` loops.
+pub(crate) fn solidify_loop(loop_info: &LoopInfo, depth: usize) -> String {
+    let condition = loop_info.continue_condition().solidify();
+
+    if let Some(induction) = &loop_info.induction {
+        let name = loop_variable(depth);
+        let init = induction.init.solidify();
+        if let Some(condition) = substitute(&condition, &init, &name) {
+            return format!(
+                "for (uint256 {name} = {init}; {condition}; {name} {}= {}) {{",
+                if induction.decreasing { "-" } else { "+" },
+                induction.step._solidify()
+            );
+        }
+    }
+
+    format!("while ({condition}) {{")
+}
+
+/// Renders the header of a loop as Yul. Yul has no `while`, so loops without an induction
+/// variable are rendered as a `for` loop with empty init and post blocks.
+pub(crate) fn yulify_loop(loop_info: &LoopInfo, depth: usize) -> String {
+    let condition = loop_info.continue_condition().yulify();
+
+    if let Some(induction) = &loop_info.induction {
+        let name = loop_variable(depth);
+        let init = induction.init.yulify();
+        if let Some(condition) = substitute(&condition, &init, &name) {
+            return format!(
+                "for {{ let {name} := {init} }} {condition} {{ {name} := {}({name}, {}) }} {{",
+                if induction.decreasing { "sub" } else { "add" },
+                induction.step._yulify()
+            );
+        }
+    }
+
+    format!("for {{ }} {condition} {{ }} {{")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+    use heimdall_vm::{
+        core::opcodes::{WrappedInput, CALLDATALOAD, ISZERO, LT},
+        ext::exec::InductionVariable,
+        test_utils::{op, push},
+    };
+
+    use super::*;
+
+    fn counter_loop(induction: bool) -> LoopInfo {
+        let counter = push(0);
+        let condition = op(LT, vec![counter.clone(), op(CALLDATALOAD, vec![push(4)])]);
+
+        LoopInfo {
+            header: 10,
+            body: 11,
+            exit: 20,
+            condition: op(ISZERO, vec![condition]),
+            body_on_jump: false,
+            induction: induction.then(|| InductionVariable {
+                position: 0,
+                init: counter,
+                step: WrappedInput::Raw(U256::from(1)),
+                decreasing: false,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_substitute_whole_words() {
+        assert_eq!(substitute("0x01 < 0x0100", "0x01", "i"), Some("i < 0x0100".to_string()));
+        assert_eq!(substitute("arg0 < arg01", "arg0", "i"), Some("i < arg01".to_string()));
+        assert_eq!(substitute("arg01 < 0x02", "arg0", "i"), None);
+    }
+
+    #[test]
+    fn test_solidify_for_loop() {
+        let line = solidify_loop(&counter_loop(true), 0);
+        assert!(line.starts_with("for (uint256 i = "));
+        assert!(line.contains("; i < "));
+        assert!(line.ends_with("; i += 0x01) {"));
+    }
+
+    #[test]
+    fn test_solidify_while_loop() {
+        let line = solidify_loop(&counter_loop(false), 1);
+        assert!(line.starts_with("while ("));
+        assert!(line.ends_with(") {"));
+    }
+
+    #[test]
+    fn test_yulify_for_loop() {
+        let line = yulify_loop(&counter_loop(true), 1);
+        assert!(line.starts_with("for { let j := "));
+        assert!(line.contains("lt(j, "));
+        assert!(line.ends_with("{ j := add(j, 0x01) } {"));
+
+        let line = yulify_loop(&counter_loop(false), 0);
+        assert!(line.starts_with("for { } lt("));
+    }
+}
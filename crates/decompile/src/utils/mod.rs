@@ -1,4 +1,5 @@
 pub(crate) mod constants;
 pub(crate) mod heuristics;
+pub(crate) mod loops;
 pub(crate) mod postprocessors;
 pub(crate) mod precompile;
@@ -1,16 +1,24 @@
-use std::time::Instant;
+use std::{ops::Range, time::Instant};
 
 use crate::{error::Error, interfaces::DisassemblerArgs};
 use eyre::eyre;
 use heimdall_common::utils::strings::encode_hex;
-use heimdall_vm::core::opcodes::OpCodeInfo;
+use heimdall_vm::{
+    core::eof::EofContainer,
+    ext::{
+        disassembly::{disassemble_bytecode, DisassembledInstruction},
+        layout::BytecodeLayout,
+    },
+};
+use serde_json::{json, Value};
 use tracing::{debug, info};
 
 /// Disassembles EVM bytecode into readable assembly instructions
 ///
 /// This function takes the bytecode of a contract and converts it into a string
-/// representation of the equivalent EVM assembly code. It handles special cases
-/// like PUSH operations which consume additional bytes as data.
+/// representation of the equivalent EVM assembly code, rendered from the instructions
+/// returned by [`disassemble_instructions`]. If `args.json` is set, the instructions are
+/// rendered as JSON instead, along with the layout of the bytecode.
 ///
 /// # Arguments
 ///
@@ -22,9 +30,56 @@ use tracing::{debug, info};
 pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Error> {
     // init
     let start_time = Instant::now();
-    let mut program_counter = 0;
+    let (contract_bytecode, instructions) = fetch_and_disassemble(&args).await?;
+
+    let start_render_time = Instant::now();
     let mut asm = String::new();
+    let byte_count = if args.json {
+        let layout = BytecodeLayout::analyze(&contract_bytecode);
+        asm =
+            serde_json::to_string_pretty(&render_json(&contract_bytecode, &instructions, &layout))
+                .map_err(|e| eyre!("serializing instructions failed: {}", e))?;
+        contract_bytecode.len()
+    } else if EofContainer::is_eof(&contract_bytecode) {
+        let container = EofContainer::parse(&contract_bytecode)
+            .map_err(|e| eyre!("parsing EOF container failed: {}", e))?;
+        render_eof(&container, &instructions, args.decimal_counter, &mut asm)
+    } else {
+        let layout = BytecodeLayout::analyze(&contract_bytecode);
+        render_code(&instructions, &layout, args.decimal_counter, &mut asm) +
+            render_layout(&contract_bytecode, &layout, &mut asm)
+    };
+    debug!("rendering assembly took {:?}", start_render_time.elapsed());
+
+    info!("disassembled {} bytes successfully", byte_count);
+    debug!("disassembly took {:?}", start_time.elapsed());
+    Ok(asm)
+}
 
+/// Disassembles EVM bytecode into typed instructions
+///
+/// Each instruction carries its offset, opcode, immediate bytes, gas and stack effects, along
+/// with the basic block it belongs to and its jump targets if they're constant. EOF containers
+/// are disassembled code section by code section.
+///
+/// # Arguments
+///
+/// * `args` - Arguments specifying the target and disassembly options
+///
+/// # Returns
+///
+/// The disassembled instructions, in order of their offset
+pub async fn disassemble_instructions(
+    args: DisassemblerArgs,
+) -> Result<Vec<DisassembledInstruction>, Error> {
+    let (_, instructions) = fetch_and_disassemble(&args).await?;
+    Ok(instructions)
+}
+
+/// Fetches the target's bytecode and disassembles it at the resolved hardfork.
+async fn fetch_and_disassemble(
+    args: &DisassemblerArgs,
+) -> Result<(Vec<u8>, Vec<DisassembledInstruction>), Error> {
     // Resolve hardfork (handles Auto detection if needed)
     let start_hardfork_resolve = Instant::now();
     let hardfork = args.get_hardfork().await;
@@ -36,47 +91,295 @@ pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Error> {
         args.get_bytecode().await.map_err(|e| eyre!("fetching target bytecode failed: {}", e))?;
     debug!("fetching target bytecode took {:?}", start_fetch_time.elapsed());
 
-    // iterate over the bytecode, disassembling each instruction
     let start_disassemble_time = Instant::now();
-    while program_counter < contract_bytecode.len() {
-        let opcode = contract_bytecode[program_counter];
-        let mut pushed_bytes = String::new();
-
-        // handle PUSH0 -> PUSH32, which require us to push the next N bytes
-        // onto the stack
-        let mut byte_count_to_push_offset = 0;
-        if (0x5f..=0x7f).contains(&opcode) {
-            let byte_count_to_push: u8 = opcode - 0x5f;
-            pushed_bytes = match contract_bytecode
-                .get(program_counter + 1..program_counter + 1 + byte_count_to_push as usize)
-            {
-                Some(bytes) => encode_hex(bytes),
-                None => break,
-            };
-            byte_count_to_push_offset += byte_count_to_push as usize;
-        }
+    let instructions = disassemble_bytecode(&contract_bytecode, hardfork)?;
+    debug!("disassembly took {:?}", start_disassemble_time.elapsed());
 
-        // Get the opcode name, respecting hardfork activation
-        let opcode_name = match OpCodeInfo::for_fork(opcode, hardfork) {
-            Some(info) => info.name(),
-            None => "unknown",
-        };
+    Ok((contract_bytecode, instructions))
+}
 
-        let offset = program_counter;
+/// Renders each section of an EOF container, prefixing each with a header comment.
+/// Instruction offsets are relative to the start of the container.
+///
+/// Returns the number of bytes rendered.
+fn render_eof(
+    container: &EofContainer,
+    instructions: &[DisassembledInstruction],
+    decimal_counter: bool,
+    asm: &mut String,
+) -> usize {
+    let mut byte_count = 0;
+    asm.push_str(&format!("; EOF container (version {})\n", container.version));
+
+    for (index, (code, types)) in
+        container.code_sections.iter().zip(container.types.iter()).enumerate()
+    {
+        asm.push_str(&format!(
+            "\n; code section {} (inputs: {}, outputs: {}, max stack height: {})\n",
+            index,
+            types.inputs,
+            if types.is_non_returning() {
+                String::from("non-returning")
+            } else {
+                types.outputs.to_string()
+            },
+            types.max_stack_height
+        ));
+
+        let start = instructions.partition_point(|instruction| instruction.pc < code.start);
+        let end = instructions.partition_point(|instruction| instruction.pc < code.end);
+        byte_count += render_code(
+            &instructions[start..end],
+            &BytecodeLayout::default(),
+            decimal_counter,
+            asm,
+        );
+    }
+
+    for (index, subcontainer) in container.container_sections.iter().enumerate() {
+        asm.push_str(&format!(
+            "\n; container section {} ({} bytes)\n; {}\n",
+            index,
+            subcontainer.len(),
+            encode_hex(&container.raw[subcontainer.clone()])
+        ));
+        byte_count += subcontainer.len();
+    }
+
+    asm.push_str(&format!(
+        "\n; data section ({} bytes, {} declared)\n",
+        container.data_section.len(),
+        container.declared_data_size
+    ));
+    if !container.data_section.is_empty() {
+        asm.push_str(&format!("; {}\n", encode_hex(container.data())));
+    }
+    byte_count += container.data_section.len();
+
+    byte_count
+}
+
+/// Renders `instructions` as assembly, appending one line per instruction to `asm`. PUSHes of
+/// likely immutables in `layout` are marked with a trailing comment.
+///
+/// Returns the number of bytes rendered.
+fn render_code(
+    instructions: &[DisassembledInstruction],
+    layout: &BytecodeLayout,
+    decimal_counter: bool,
+    asm: &mut String,
+) -> usize {
+    let mut byte_count = 0;
+    for instruction in instructions {
+        let offset = instruction.pc;
         asm.push_str(
             format!(
-                "{} {} {}\n",
-                if args.decimal_counter { offset.to_string() } else { format!("{offset:06x}") },
-                opcode_name,
-                pushed_bytes
+                "{} {} {}{}\n",
+                if decimal_counter { offset.to_string() } else { format!("{offset:06x}") },
+                instruction.name,
+                encode_hex(&instruction.immediate),
+                if layout.is_immutable(offset) { " ; immutable" } else { "" }
             )
             .as_str(),
         );
-        program_counter += 1 + byte_count_to_push_offset;
+        byte_count += 1 + instruction.immediate.len();
     }
-    debug!("disassembly took {:?}", start_disassemble_time.elapsed());
 
-    info!("disassembled {} bytes successfully", program_counter);
-    debug!("disassembly took {:?}", start_time.elapsed());
-    Ok(asm)
+    byte_count
+}
+
+/// Renders the regions of legacy bytecode which follow its code as comments, decoding the
+/// compiler metadata.
+///
+/// Returns the number of bytes rendered.
+fn render_layout(bytecode: &[u8], layout: &BytecodeLayout, asm: &mut String) -> usize {
+    let mut byte_count = 0;
+
+    if let Some(data) = &layout.data {
+        asm.push_str(&format!(
+            "\n; data ({} bytes)\n; {}\n",
+            data.len(),
+            encode_hex(&bytecode[data.clone()])
+        ));
+        byte_count += data.len();
+    }
+
+    if let Some(metadata) = &layout.metadata {
+        asm.push_str(&format!("\n; metadata ({} bytes)\n", metadata.range.len()));
+        for line in metadata.describe() {
+            asm.push_str(&format!("; {line}\n"));
+        }
+        asm.push_str(&format!("; {}\n", encode_hex(&bytecode[metadata.range.clone()])));
+        byte_count += metadata.range.len();
+    }
+
+    if let Some(args) = &layout.constructor_args {
+        asm.push_str(&format!(
+            "\n; constructor arguments ({} bytes)\n; {}\n",
+            args.len(),
+            encode_hex(&bytecode[args.clone()])
+        ));
+        byte_count += args.len();
+    }
+
+    byte_count
+}
+
+/// Renders `instructions` as JSON, along with the regions of `bytecode` which [`render_code`] and
+/// [`render_layout`] annotate: the data, metadata and constructor arguments following the code,
+/// and the likely immutables within it.
+fn render_json(
+    bytecode: &[u8],
+    instructions: &[DisassembledInstruction],
+    layout: &BytecodeLayout,
+) -> Value {
+    let region = |range: &Range<usize>| {
+        json!({
+            "offset": range.start,
+            "size": range.len(),
+            "bytes": format!("0x{}", encode_hex(&bytecode[range.clone()])),
+        })
+    };
+
+    json!({
+        "instructions": instructions,
+        "layout": {
+            "code": region(&layout.code),
+            "data": layout.data.as_ref().map(region),
+            "metadata": layout.metadata.as_ref().map(|metadata| {
+                let mut value = region(&metadata.range);
+                value["compiler"] = json!(metadata.compiler.to_string());
+                value["version"] = json!(metadata.version);
+                value["ipfs"] = json!(metadata.ipfs);
+                value["swarm"] = json!(metadata.swarm);
+                value["experimental"] = json!(metadata.experimental);
+                value
+            }),
+            "constructorArgs": layout.constructor_args.as_ref().map(region),
+            "immutables": layout.immutables.iter().map(region).collect::<Vec<_>>(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_vm::core::hardfork::HardFork;
+
+    use super::*;
+
+    #[test]
+    fn test_render_code_legacy() {
+        let mut asm = String::new();
+        let bytecode = [0x60, 0x80, 0x60, 0x40, 0x52, 0xe0, 0x00];
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        let byte_count =
+            render_code(&instructions, &BytecodeLayout::analyze(&bytecode), false, &mut asm);
+
+        assert_eq!(byte_count, 7);
+        assert_eq!(
+            asm,
+            "000000 PUSH1 80\n000002 PUSH1 40\n000004 MSTORE \n000005 unknown \n000006 STOP \n"
+        );
+    }
+
+    #[test]
+    fn test_render_eof_sections() {
+        // code section 0: PUSH1 0x01, RJUMPI +1, STOP, CALLF 1, STOP
+        // code section 1: DATALOADN 0x0000, RETF
+        let bytecode = heimdall_common::utils::strings::decode_hex(concat!(
+            "ef0001010008020002000a0004ff0020",
+            "00",
+            "0080000100010001",
+            "6001e1000100e3000100",
+            "d10000e4",
+        ))
+        .expect("failed to decode bytecode");
+        let container = EofContainer::parse(&bytecode).expect("failed to parse container");
+
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        let mut asm = String::new();
+        render_eof(&container, &instructions, false, &mut asm);
+
+        assert!(asm.starts_with("; EOF container (version 1)\n"));
+        assert!(asm.contains(
+            "; code section 0 (inputs: 0, outputs: non-returning, max stack height: 1)\n"
+        ));
+        assert!(asm.contains("000019 PUSH1 01\n00001b RJUMPI 0001\n00001e STOP \n"));
+        assert!(asm.contains("00001f CALLF 0001\n"));
+        assert!(asm.contains("; code section 1 (inputs: 0, outputs: 1, max stack height: 1)\n"));
+        assert!(asm.contains("000023 DATALOADN 0000\n000026 RETF \n"));
+        assert!(asm.contains("; data section (0 bytes, 32 declared)\n"));
+    }
+
+    #[test]
+    fn test_render_layout() {
+        // JUMPDEST, STOP, INVALID, data, then solc 0.4 metadata and a constructor argument
+        let bytecode = heimdall_common::utils::strings::decode_hex(concat!(
+            "5b00fe1122",
+            "a165627a7a72305820",
+            "1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8",
+            "0029",
+            "000000000000000000000000000000000000000000000000000000000000002a"
+        ))
+        .expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        let mut asm = String::new();
+        let byte_count = render_code(&instructions, &layout, false, &mut asm) +
+            render_layout(&bytecode, &layout, &mut asm);
+
+        assert_eq!(byte_count, bytecode.len());
+        assert!(asm.starts_with(
+            "000000 JUMPDEST \n000001 STOP \n000002 INVALID \n\n; data (2 bytes)\n; 1122\n"
+        ));
+        assert!(asm.contains("\n; metadata (43 bytes)\n; compiler: solc unknown\n; swarm: 0x1e7d"));
+        assert!(asm.contains("\n; constructor arguments (32 bytes)\n"));
+        assert!(asm.ends_with(&format!("; {:064x}\n", 42)));
+    }
+
+    #[test]
+    fn test_render_json_layout() {
+        // JUMPDEST, STOP, INVALID, data, then solc 0.4 metadata and a constructor argument
+        let bytecode = heimdall_common::utils::strings::decode_hex(concat!(
+            "5b00fe1122",
+            "a165627a7a72305820",
+            "1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8",
+            "0029",
+            "000000000000000000000000000000000000000000000000000000000000002a"
+        ))
+        .expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        let json = render_json(&bytecode, &instructions, &BytecodeLayout::analyze(&bytecode));
+
+        assert_eq!(json["instructions"].as_array().map(Vec::len), Some(3));
+        assert_eq!(json["layout"]["data"]["offset"], 3);
+        assert_eq!(json["layout"]["data"]["bytes"], "0x1122");
+        assert_eq!(json["layout"]["metadata"]["size"], 43);
+        assert_eq!(json["layout"]["metadata"]["compiler"], "solc");
+        assert_eq!(json["layout"]["constructorArgs"]["size"], 32);
+        assert_eq!(json["layout"]["immutables"], json!([]));
+    }
+
+    #[test]
+    fn test_render_code_immutables() {
+        // PUSH32 of an address, which solc only emits for immutables
+        let address = "1bf797219482a29013d804ad96d1c6f84fba4c45";
+        let bytecode = heimdall_common::utils::strings::decode_hex(&format!("7f{address:0>64}"))
+            .expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        let mut asm = String::new();
+        render_code(&instructions, &BytecodeLayout::analyze(&bytecode), false, &mut asm);
+
+        assert!(asm.ends_with(&format!("{address} ; immutable\n")));
+    }
 }
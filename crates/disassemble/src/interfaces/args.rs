@@ -29,6 +29,11 @@ pub struct DisassemblerArgs {
     #[clap(long = "decimal-counter", short = 'd')]
     pub decimal_counter: bool,
 
+    /// Whether to output the instructions as JSON, including their gas and stack effects, basic
+    /// blocks and constant jump targets.
+    #[clap(long)]
+    pub json: bool,
+
     /// Name of the output file.
     #[clap(long, short, default_value = "", hide_default_value = true)]
     pub name: String,
@@ -63,6 +68,9 @@ pub struct DisassemblerArgsBuilder {
     /// Whether to use base-10 for the program counter.
     decimal_counter: Option<bool>,
 
+    /// Whether to output the instructions as JSON.
+    json: Option<bool>,
+
     /// Name of the output file.
     name: Option<String>,
 
@@ -170,6 +178,7 @@ impl DisassemblerArgsBuilder {
             target: Some(String::new()),
             rpc_url: Some(String::new()),
             decimal_counter: Some(false),
+            json: Some(false),
             name: Some(String::new()),
             output: Some(String::new()),
             hardfork: Some(HardFork::Latest),
@@ -195,6 +204,12 @@ impl DisassemblerArgsBuilder {
         self
     }
 
+    /// Sets whether to output the instructions as JSON
+    pub fn json(&mut self, json: bool) -> &mut Self {
+        self.json = Some(json);
+        self
+    }
+
     /// Sets the name for the output file
     pub fn name(&mut self, name: String) -> &mut Self {
         self.name = Some(name);
@@ -230,6 +245,7 @@ impl DisassemblerArgsBuilder {
             decimal_counter: self
                 .decimal_counter
                 .ok_or_else(|| eyre::eyre!("decimal_counter is required"))?,
+            json: self.json.ok_or_else(|| eyre::eyre!("json is required"))?,
             name: self.name.clone().ok_or_else(|| eyre::eyre!("name is required"))?,
             output: self.output.clone().ok_or_else(|| eyre::eyre!("output is required"))?,
             hardfork: self.hardfork.ok_or_else(|| eyre::eyre!("hardfork is required"))?,
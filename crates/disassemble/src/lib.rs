@@ -11,7 +11,7 @@ mod core;
 mod interfaces;
 
 // re-export the public interface
-pub use core::disassemble;
+pub use core::{disassemble, disassemble_instructions};
 pub use error::Error;
-pub use heimdall_vm::core::hardfork::HardFork;
+pub use heimdall_vm::{core::hardfork::HardFork, ext::disassembly::DisassembledInstruction};
 pub use interfaces::{DisassemblerArgs, DisassemblerArgsBuilder};
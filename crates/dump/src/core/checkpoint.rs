@@ -0,0 +1,152 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use alloy::primitives::{Address, FixedBytes, U256};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// A snapshot of a dump in progress, from which it can be resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Checkpoint {
+    /// The contract being dumped.
+    pub target: Address,
+
+    /// The next block to be dumped. Every block before it has been applied to `storage`.
+    pub next_block: u128,
+
+    /// The value of each storage slot as of `next_block - 1`.
+    pub storage: BTreeMap<FixedBytes<32>, FixedBytes<32>>,
+
+    /// The `(key, slot)` preimages of the mapping slots collected so far, if the dump is being
+    /// decoded.
+    pub preimages: BTreeSet<(U256, U256)>,
+
+    /// The length of the append-only output file when the checkpoint was written.
+    pub append_offset: Option<u64>,
+}
+
+impl Checkpoint {
+    /// Loads the checkpoint at `path`.
+    pub(crate) fn load(path: &str) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| eyre!("failed to read checkpoint '{path}': {e}"))?;
+        Ok(serde_json::from_str(&contents)
+            .map_err(|e| eyre!("invalid checkpoint '{path}': {e}"))?)
+    }
+
+    /// Saves the checkpoint to `path`. The checkpoint is written to a temporary file first, so an
+    /// interrupted save never corrupts the previous checkpoint.
+    pub(crate) fn save(&self, path: &str) -> Result<(), Error> {
+        let contents = serde_json::to_string(self)
+            .map_err(|e| eyre!("failed to serialize checkpoint: {e}"))?;
+        let temp_path = format!("{path}.tmp");
+
+        if let Some(parent) = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| eyre!("failed to create checkpoint directory: {e}"))?;
+        }
+        fs::write(&temp_path, contents)
+            .map_err(|e| eyre!("failed to write checkpoint '{temp_path}': {e}"))?;
+        fs::rename(&temp_path, path)
+            .map_err(|e| eyre!("failed to write checkpoint '{path}': {e}"))?;
+        Ok(())
+    }
+}
+
+/// An append-only CSV file of every storage change, as `block,slot,value` rows.
+pub(crate) struct AppendFile {
+    path: String,
+    pending: Vec<String>,
+}
+
+impl AppendFile {
+    /// Opens the file at `path`. When resuming, any rows written after the checkpoint at `offset`
+    /// are discarded, since their blocks will be dumped again. Otherwise the file is started over.
+    pub(crate) fn open(path: &str, offset: Option<u64>) -> Result<Self, Error> {
+        match offset {
+            Some(offset) if Path::new(path).exists() => {
+                OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .and_then(|f| f.set_len(offset))
+                    .map_err(|e| eyre!("failed to truncate '{path}': {e}"))?;
+            }
+            _ => {
+                if let Some(parent) = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty())
+                {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| eyre!("failed to create output directory: {e}"))?;
+                }
+                fs::write(path, "block,slot,value\n")
+                    .map_err(|e| eyre!("failed to write '{path}': {e}"))?;
+            }
+        }
+
+        Ok(Self { path: path.to_string(), pending: Vec::new() })
+    }
+
+    /// Queues a storage change to be written on the next [`AppendFile::flush`].
+    pub(crate) fn push(&mut self, block: u128, slot: &FixedBytes<32>, value: &FixedBytes<32>) {
+        self.pending.push(format!("{block},{slot},{value}\n"));
+    }
+
+    /// Writes the queued changes to the file, returning its new length.
+    pub(crate) fn flush(&mut self) -> Result<u64, Error> {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| eyre!("failed to open '{}': {e}", self.path))?;
+        file.write_all(self.pending.concat().as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| eyre!("failed to write '{}': {e}", self.path))?;
+        self.pending.clear();
+
+        Ok(file.metadata().map_err(|e| eyre!("failed to read '{}': {e}", self.path))?.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let dir = std::env::temp_dir().join(format!("heimdall-dump-{}", std::process::id()));
+        let checkpoint_path = dir.join("dump.checkpoint").to_string_lossy().to_string();
+        let append_path = dir.join("dump.csv").to_string_lossy().to_string();
+
+        // dump a change, checkpoint, then dump another change which is never checkpointed
+        let mut append = AppendFile::open(&append_path, None).expect("failed to open");
+        append.push(1, &FixedBytes::with_last_byte(1), &FixedBytes::with_last_byte(2));
+        let checkpoint = Checkpoint {
+            target: Address::with_last_byte(0xaa),
+            next_block: 2,
+            storage: BTreeMap::from([(
+                FixedBytes::with_last_byte(1),
+                FixedBytes::with_last_byte(2),
+            )]),
+            preimages: BTreeSet::from([(U256::from(7), U256::from(1))]),
+            append_offset: Some(append.flush().expect("failed to flush")),
+        };
+        checkpoint.save(&checkpoint_path).expect("failed to save");
+        append.push(2, &FixedBytes::with_last_byte(1), &FixedBytes::with_last_byte(3));
+        append.flush().expect("failed to flush");
+
+        // resuming restores the checkpoint, and discards the change made after it
+        let resumed = Checkpoint::load(&checkpoint_path).expect("failed to load");
+        assert_eq!(resumed, checkpoint);
+        AppendFile::open(&append_path, resumed.append_offset).expect("failed to open");
+        let rows = fs::read_to_string(&append_path).expect("failed to read");
+        assert_eq!(rows.lines().count(), 2);
+        assert!(rows.lines().last().is_some_and(|row| row.starts_with("1,")));
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
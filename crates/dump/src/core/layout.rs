@@ -0,0 +1,596 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use alloy::{
+    primitives::{keccak256, Address, FixedBytes, I256, U256},
+    rpc::types::trace::parity::{TraceResultsWithTransactionHash, VmTrace},
+};
+use eyre::eyre;
+use hashbrown::{HashMap, HashSet};
+use heimdall_common::utils::strings::encode_hex;
+use heimdall_decompiler::{StorageLayout, StorageLayoutType};
+use serde::Serialize;
+
+/// The maximum number of elements decoded from a single dynamic array or long string.
+const MAX_ARRAY_ELEMENTS: u64 = 4096;
+
+/// The number of slots a mapping value may span, i.e. how far past `keccak256(key . slot)` the
+/// members of a struct stored in a mapping may be.
+const MAX_VALUE_SLOTS: u64 = 256;
+
+/// The `KECCAK256` (formerly `SHA3`) opcode.
+const KECCAK256: u8 = 0x20;
+
+/// A dumped storage value, labelled with the storage variable it belongs to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DecodedSlot {
+    /// The slot the value is stored in.
+    pub slot: FixedBytes<32>,
+
+    /// The byte offset of the value within its slot, counted from the right.
+    pub offset: usize,
+
+    /// The path to the value, e.g. `balances[0x...]` or `config.owner`.
+    pub label: String,
+
+    /// The type of the value, e.g. `uint256`.
+    pub type_label: String,
+
+    /// The rendered value.
+    pub value: String,
+}
+
+/// A storage variable, or an element of one, which is waiting to be decoded.
+struct Variable {
+    slot: U256,
+    offset: usize,
+    label: String,
+    type_id: String,
+}
+
+/// Parses a storage layout, either as a bare `storageLayout` object or an object holding one (e.g.
+/// a solc or foundry artifact).
+pub(crate) fn parse_storage_layout(json: &str) -> Result<StorageLayout, eyre::Report> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| eyre!("invalid storage layout: {e}"))?;
+    let layout = value.get("storageLayout").cloned().unwrap_or(value);
+    serde_json::from_value(layout).map_err(|e| eyre!("invalid storage layout: {e}"))
+}
+
+/// Collects the preimages of the mapping slots hashed by the transaction in `trace`, as
+/// `(key, slot)` pairs, from its VM trace. Solc hashes `key . slot` from scratch memory to derive
+/// the slot of a mapping value, so each 64-byte hash of memory `[0, 64)` is one.
+pub(crate) fn collect_preimages(
+    trace: &TraceResultsWithTransactionHash,
+    preimages: &mut HashSet<(U256, U256)>,
+) {
+    fn walk(trace: &VmTrace, preimages: &mut HashSet<(U256, U256)>) {
+        // only the scratch space is tracked, as mapping slots are hashed from it
+        let mut scratch = [0u8; 64];
+
+        for instruction in &trace.ops {
+            let opcode = trace.code.get(instruction.pc).copied();
+            let is_hash = opcode == Some(KECCAK256) ||
+                matches!(instruction.op.as_deref(), Some("SHA3" | "KECCAK256"));
+
+            if let Some(executed) = &instruction.ex {
+                // the pushed hash confirms that the scratch space was hashed
+                if is_hash && executed.push.first() == Some(&keccak256(scratch).into()) {
+                    preimages.insert((
+                        U256::from_be_slice(&scratch[..32]),
+                        U256::from_be_slice(&scratch[32..]),
+                    ));
+                }
+
+                if let Some(delta) = executed.mem.as_ref().filter(|delta| delta.off < 64) {
+                    let end = (delta.off + delta.data.len()).min(64);
+                    scratch[delta.off..end].copy_from_slice(&delta.data[..end - delta.off]);
+                }
+            }
+
+            // message-calls execute with their own memory
+            if let Some(sub) = &instruction.sub {
+                walk(sub, preimages);
+            }
+        }
+    }
+
+    if let Some(vm_trace) = &trace.full_trace.vm_trace {
+        walk(vm_trace, preimages);
+    }
+}
+
+/// Keeps only the `preimages` which derive one of the `changed` slots: those whose hash is the
+/// slot of a changed value, or of the struct containing it, and those whose hash is the slot of a
+/// nested mapping holding such a value. Transactions hash many slots which aren't the target's,
+/// so the rest would only accumulate.
+pub(crate) fn retain_preimages(preimages: &mut HashSet<(U256, U256)>, changed: &BTreeSet<U256>) {
+    let hashes: HashMap<(U256, U256), U256> = preimages
+        .iter()
+        .map(|(key, slot)| {
+            let mut preimage = [0u8; 64];
+            preimage[..32].copy_from_slice(&key.to_be_bytes::<32>());
+            preimage[32..].copy_from_slice(&slot.to_be_bytes::<32>());
+            ((*key, *slot), U256::from_be_bytes(keccak256(preimage).0))
+        })
+        .collect();
+
+    let derives_changed = |hash: &U256| {
+        changed.range(*hash..hash.saturating_add(U256::from(MAX_VALUE_SLOTS))).next().is_some()
+    };
+    let mut kept: HashSet<(U256, U256)> =
+        hashes.iter().filter(|(_, hash)| derives_changed(hash)).map(|(p, _)| *p).collect();
+
+    // the outer keys of nested mappings are hashed to the slot the inner keys are hashed with
+    loop {
+        let parents: HashSet<U256> = kept.iter().map(|(_, slot)| *slot).collect();
+        let before = kept.len();
+        kept.extend(
+            hashes
+                .iter()
+                .filter(|(_, hash)| parents.contains(*hash))
+                .map(|(preimage, _)| *preimage),
+        );
+        if kept.len() == before {
+            break;
+        }
+    }
+
+    *preimages = kept;
+}
+
+/// Decodes the dumped `storage` of a contract against its storage `layout`. The keys of each
+/// mapping are taken from the `(key, slot)` `preimages` hashed with the mapping's slot (see
+/// [`collect_preimages`]).
+///
+/// Values are returned ordered by slot and offset. Slots which aren't described by the layout
+/// are omitted.
+pub(crate) fn decode_storage(
+    storage: &HashMap<FixedBytes<32>, FixedBytes<32>>,
+    layout: &StorageLayout,
+    preimages: &HashSet<(U256, U256)>,
+) -> Vec<DecodedSlot> {
+    let mut keys: HashMap<U256, Vec<U256>> = HashMap::new();
+    for (key, slot) in preimages {
+        keys.entry(*slot).or_default().push(*key);
+    }
+    keys.values_mut().for_each(|keys| keys.sort_unstable());
+
+    let mut queue: VecDeque<Variable> = layout
+        .storage
+        .iter()
+        .filter_map(|entry| {
+            Some(Variable {
+                slot: entry.slot.parse().ok()?,
+                offset: entry.offset,
+                label: entry.label.clone(),
+                type_id: entry.type_id.clone(),
+            })
+        })
+        .collect();
+
+    let read = |slot: U256| storage.get(&slot_key(slot)).copied();
+    let mut decoded = Vec::new();
+
+    while let Some(variable) = queue.pop_front() {
+        let Some(typ) = layout.types.get(&variable.type_id) else {
+            continue;
+        };
+
+        match typ.encoding.as_str() {
+            "mapping" => {
+                let (Some(key_type), Some(value_type)) = (&typ.key, &typ.value) else {
+                    continue;
+                };
+                let key_label = layout.types.get(key_type).map(|t| t.label.as_str()).unwrap_or("");
+
+                // values are stored at `keccak256(key . slot)`
+                let mut preimage = [0u8; 64];
+                preimage[32..].copy_from_slice(&variable.slot.to_be_bytes::<32>());
+                for key in keys.get(&variable.slot).into_iter().flatten() {
+                    preimage[..32].copy_from_slice(&key.to_be_bytes::<32>());
+                    let slot = U256::from_be_bytes(keccak256(preimage).0);
+                    queue.push_back(Variable {
+                        slot,
+                        offset: 0,
+                        label: format!(
+                            "{}[{}]",
+                            variable.label,
+                            render_value(key_label, &key.to_be_bytes::<32>())
+                        ),
+                        type_id: value_type.clone(),
+                    });
+                }
+            }
+            "dynamic_array" => {
+                let (Some(length), Some(base)) = (read(variable.slot), &typ.base) else {
+                    continue;
+                };
+                let length = U256::from_be_bytes(length.0);
+                decoded.push(DecodedSlot {
+                    slot: slot_key(variable.slot),
+                    offset: 0,
+                    label: format!("{}.length", variable.label),
+                    type_label: "uint256".to_string(),
+                    value: length.to_string(),
+                });
+
+                // elements start at `keccak256(slot)`
+                let start = U256::from_be_bytes(keccak256(variable.slot.to_be_bytes::<32>()).0);
+                let count = length.saturating_to::<u64>().min(MAX_ARRAY_ELEMENTS);
+                queue.extend(elements(layout, start, base, count, &variable.label));
+            }
+            "bytes" => {
+                let Some(value) = read(variable.slot) else {
+                    continue;
+                };
+                let header = U256::from_be_bytes(value.0);
+
+                // short values are stored inline with their length, while long values are stored
+                // from `keccak256(slot)` onwards
+                let bytes = match header.bit(0) {
+                    false => {
+                        let length = (value[31] / 2).min(31) as usize;
+                        value[..length].to_vec()
+                    }
+                    true => {
+                        let length = ((header - U256::from(1)) / U256::from(2))
+                            .saturating_to::<u64>()
+                            .min(MAX_ARRAY_ELEMENTS * 32);
+                        let start =
+                            U256::from_be_bytes(keccak256(variable.slot.to_be_bytes::<32>()).0);
+                        let mut bytes: Vec<u8> = (0..length.div_ceil(32))
+                            .flat_map(|i| read(start + U256::from(i)).unwrap_or_default().0)
+                            .collect();
+                        bytes.truncate(length as usize);
+                        bytes
+                    }
+                };
+
+                decoded.push(DecodedSlot {
+                    slot: slot_key(variable.slot),
+                    offset: 0,
+                    label: variable.label,
+                    type_label: typ.label.clone(),
+                    value: match typ.label.as_str() {
+                        "string" => format!("{:?}", String::from_utf8_lossy(&bytes)),
+                        _ => format!("0x{}", encode_hex(&bytes)),
+                    },
+                });
+            }
+            _ => {
+                // structs are expanded into their members
+                if let Some(members) = &typ.members {
+                    queue.extend(members.iter().filter_map(|member| {
+                        Some(Variable {
+                            slot: variable.slot + member.slot.parse::<U256>().ok()?,
+                            offset: member.offset,
+                            label: format!("{}.{}", variable.label, member.label),
+                            type_id: member.type_id.clone(),
+                        })
+                    }));
+                    continue;
+                }
+
+                // as are fixed-size arrays
+                let size = type_size(typ);
+                if let Some(base) = &typ.base {
+                    let base_size = layout.types.get(base).map(type_size).unwrap_or(32);
+                    let count = match base_size {
+                        0 => 0,
+                        base_size if base_size >= 32 => size / (base_size.div_ceil(32) * 32),
+                        base_size => size / 32 * (32 / base_size),
+                    };
+                    queue.extend(elements(
+                        layout,
+                        variable.slot,
+                        base,
+                        count as u64,
+                        &variable.label,
+                    ));
+                    continue;
+                }
+
+                let Some(value) = read(variable.slot) else {
+                    continue;
+                };
+                let size = size.clamp(1, 32);
+                let Some(end) = 32usize.checked_sub(variable.offset) else {
+                    continue;
+                };
+                let Some(field) = value.get(end.saturating_sub(size)..end) else {
+                    continue;
+                };
+
+                decoded.push(DecodedSlot {
+                    slot: slot_key(variable.slot),
+                    offset: variable.offset,
+                    value: render_value(&typ.label, field),
+                    label: variable.label,
+                    type_label: typ.label.clone(),
+                });
+            }
+        }
+    }
+
+    decoded.sort_by(|a, b| (a.slot, a.offset, &a.label).cmp(&(b.slot, b.offset, &b.label)));
+    decoded
+}
+
+/// Returns the elements of an array of `count` elements of type `base`, starting at `start`.
+/// Elements of less than 32 bytes are packed together.
+fn elements(
+    layout: &StorageLayout,
+    start: U256,
+    base: &str,
+    count: u64,
+    label: &str,
+) -> Vec<Variable> {
+    let size = layout.types.get(base).map(type_size).unwrap_or(32).max(1);
+
+    (0..count)
+        .map(|i| {
+            let (slot, offset) = match size {
+                size if size >= 32 => (start + U256::from(i * size.div_ceil(32) as u64), 0),
+                size => {
+                    let per_slot = (32 / size) as u64;
+                    (start + U256::from(i / per_slot), (i % per_slot) as usize * size)
+                }
+            };
+            Variable { slot, offset, label: format!("{label}[{i}]"), type_id: base.to_string() }
+        })
+        .collect()
+}
+
+/// Returns the number of bytes occupied by `typ`.
+fn type_size(typ: &StorageLayoutType) -> usize {
+    typ.number_of_bytes.parse().unwrap_or(32)
+}
+
+/// Converts a slot number into a dumped slot key.
+fn slot_key(slot: U256) -> FixedBytes<32> {
+    FixedBytes::from(slot.to_be_bytes::<32>())
+}
+
+/// Renders the raw `bytes` of a value as the type named `type_label`.
+fn render_value(type_label: &str, bytes: &[u8]) -> String {
+    let word = U256::from_be_slice(bytes);
+
+    match type_label {
+        "bool" => (!word.is_zero()).to_string(),
+        label if label.starts_with("address") || label.starts_with("contract ") => {
+            let address = word.to_be_bytes::<32>();
+            Address::from_slice(&address[12..]).to_checksum(None)
+        }
+        label if label.starts_with("uint") || label.starts_with("enum ") => word.to_string(),
+        label if label.starts_with("int") => {
+            // sign-extend the value from its size to 256 bits
+            let bits = bytes.len().min(32) * 8;
+            let value = match bits < 256 && word.bit(bits.saturating_sub(1)) {
+                true => word | (U256::MAX << bits),
+                false => word,
+            };
+            I256::from_raw(value).to_string()
+        }
+        _ => format!("0x{}", encode_hex(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use heimdall_common::utils::strings::decode_hex;
+    use heimdall_decompiler::StorageLayoutEntry;
+
+    use super::*;
+
+    fn entry(label: &str, slot: &str, offset: usize, type_id: &str) -> StorageLayoutEntry {
+        StorageLayoutEntry {
+            label: label.to_string(),
+            slot: slot.to_string(),
+            offset,
+            type_id: type_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn inplace(label: &str, size: usize) -> StorageLayoutType {
+        StorageLayoutType {
+            encoding: "inplace".to_string(),
+            label: label.to_string(),
+            number_of_bytes: size.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_storage() {
+        let layout = StorageLayout {
+            storage: vec![
+                entry("owner", "0", 0, "t_address"),
+                entry("paused", "0", 20, "t_bool"),
+                entry("delta", "0", 21, "t_int8"),
+                entry("balances", "1", 0, "t_mapping(t_address,t_uint256)"),
+                entry("name", "2", 0, "t_string_storage"),
+                entry("values", "3", 0, "t_array(t_uint128)dyn_storage"),
+            ],
+            types: BTreeMap::from([
+                ("t_address".to_string(), inplace("address", 20)),
+                ("t_bool".to_string(), inplace("bool", 1)),
+                ("t_int8".to_string(), inplace("int8", 1)),
+                ("t_uint128".to_string(), inplace("uint128", 16)),
+                ("t_uint256".to_string(), inplace("uint256", 32)),
+                (
+                    "t_mapping(t_address,t_uint256)".to_string(),
+                    StorageLayoutType {
+                        encoding: "mapping".to_string(),
+                        label: "mapping(address => uint256)".to_string(),
+                        number_of_bytes: "32".to_string(),
+                        key: Some("t_address".to_string()),
+                        value: Some("t_uint256".to_string()),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "t_string_storage".to_string(),
+                    StorageLayoutType {
+                        encoding: "bytes".to_string(),
+                        label: "string".to_string(),
+                        number_of_bytes: "32".to_string(),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "t_array(t_uint128)dyn_storage".to_string(),
+                    StorageLayoutType {
+                        encoding: "dynamic_array".to_string(),
+                        label: "uint128[]".to_string(),
+                        number_of_bytes: "32".to_string(),
+                        base: Some("t_uint128".to_string()),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+        };
+
+        let holder = U256::from(0xbeefu64);
+        let balance_slot = {
+            let mut preimage = holder.to_be_bytes::<32>().to_vec();
+            preimage.extend(U256::from(1).to_be_bytes::<32>());
+            U256::from_be_bytes(keccak256(preimage).0)
+        };
+        let values_start = U256::from_be_bytes(keccak256(U256::from(3).to_be_bytes::<32>()).0);
+
+        // slot 0 packs `owner`, `paused` and `delta = -2`
+        let mut packed = [0u8; 32];
+        packed[10] = 0xfe;
+        packed[11] = 1;
+        packed[31] = 0xaa;
+        let mut name = [0u8; 32];
+        name[..5].copy_from_slice(b"hello");
+        name[31] = 10;
+        let mut values = [0u8; 32];
+        values[31] = 7;
+        values[15] = 9;
+
+        let storage = HashMap::from([
+            (slot_key(U256::ZERO), FixedBytes::from(packed)),
+            (slot_key(balance_slot), slot_key(U256::from(1000))),
+            (slot_key(U256::from(2)), FixedBytes::from(name)),
+            (slot_key(U256::from(3)), slot_key(U256::from(2))),
+            (slot_key(values_start), FixedBytes::from(values)),
+        ]);
+
+        let decoded = decode_storage(&storage, &layout, &HashSet::from([(holder, U256::from(1))]));
+        let summary = decoded
+            .iter()
+            .map(|slot| (slot.label.as_str(), slot.value.as_str()))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(summary["owner"], Address::with_last_byte(0xaa).to_checksum(None));
+        assert_eq!(summary["paused"], "true");
+        assert_eq!(summary["delta"], "-2");
+        let holder_label =
+            format!("balances[{}]", Address::left_padding_from(&[0xbe, 0xef]).to_checksum(None));
+        assert_eq!(summary[holder_label.as_str()], "1000");
+        assert_eq!(summary["name"], "\"hello\"");
+        assert_eq!(summary["values.length"], "2");
+        assert_eq!(summary["values[0]"], "7");
+        assert_eq!(summary["values[1]"], "9");
+        assert_eq!(decoded.len(), 8);
+    }
+
+    #[test]
+    fn test_collect_preimages() {
+        use alloy::rpc::types::trace::parity::{
+            MemoryDelta, TraceResults, VmExecutedOperation, VmInstruction,
+        };
+
+        let instruction = |pc: usize, push: Vec<U256>, mem: Option<MemoryDelta>| VmInstruction {
+            cost: 0,
+            ex: Some(VmExecutedOperation { used: 0, push, mem, store: None }),
+            pc,
+            sub: None,
+            op: None,
+            idx: None,
+        };
+        let word = |value: u64| U256::from(value).to_be_bytes::<32>().to_vec();
+        let preimage = [word(0xbeef), word(1)].concat();
+
+        // MSTORE(0, 0xbeef), MSTORE(32, 1), KECCAK256(0, 64), then a hash of other memory
+        let code = decode_hex("0x61beef6000526001602052604060002060206040206000")
+            .expect("failed to decode bytecode");
+        let trace = TraceResultsWithTransactionHash {
+            full_trace: TraceResults {
+                output: Default::default(),
+                state_diff: None,
+                trace: Vec::new(),
+                vm_trace: Some(VmTrace {
+                    code: code.into(),
+                    ops: vec![
+                        instruction(
+                            5,
+                            Vec::new(),
+                            Some(MemoryDelta { off: 0, data: word(0xbeef).into() }),
+                        ),
+                        instruction(
+                            10,
+                            Vec::new(),
+                            Some(MemoryDelta { off: 32, data: word(1).into() }),
+                        ),
+                        instruction(15, vec![keccak256(&preimage).into()], None),
+                        instruction(20, vec![U256::from(1)], None),
+                    ],
+                }),
+            },
+            transaction_hash: Default::default(),
+        };
+
+        let mut preimages = HashSet::new();
+        collect_preimages(&trace, &mut preimages);
+
+        assert_eq!(preimages, HashSet::from([(U256::from(0xbeef), U256::from(1))]));
+    }
+
+    #[test]
+    fn test_retain_preimages() {
+        let hash = |key: u64, slot: U256| {
+            let mut preimage = U256::from(key).to_be_bytes::<32>().to_vec();
+            preimage.extend(slot.to_be_bytes::<32>());
+            U256::from_be_bytes(keccak256(preimage).0)
+        };
+
+        // `m[1][2]` is nested in the mapping at slot 3, and `s[4].b` is the second member of a
+        // struct in the mapping at slot 5. the key hashed with slot 6 derives no changed slot
+        let outer = hash(1, U256::from(3));
+        let mut preimages = HashSet::from([
+            (U256::from(1), U256::from(3)),
+            (U256::from(2), outer),
+            (U256::from(4), U256::from(5)),
+            (U256::from(7), U256::from(6)),
+        ]);
+        let changed = BTreeSet::from([hash(2, outer), hash(4, U256::from(5)) + U256::from(1)]);
+
+        retain_preimages(&mut preimages, &changed);
+        assert_eq!(
+            preimages,
+            HashSet::from([
+                (U256::from(1), U256::from(3)),
+                (U256::from(2), outer),
+                (U256::from(4), U256::from(5)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_storage_layout() {
+        let layout = parse_storage_layout(
+            r#"{"storageLayout":{"storage":[{"astId":3,"contract":"A.sol:A","label":"x","offset":0,"slot":"0","type":"t_uint256"}],"types":{"t_uint256":{"encoding":"inplace","label":"uint256","numberOfBytes":"32"}}}}"#,
+        )
+        .expect("failed to parse layout");
+
+        assert_eq!(layout.storage[0].label, "x");
+        assert_eq!(layout.storage[0].type_id, "t_uint256");
+        assert_eq!(layout.types["t_uint256"].number_of_bytes, "32");
+    }
+}
@@ -1,25 +1,65 @@
+mod checkpoint;
+pub(crate) mod layout;
+
 use alloy::{
-    primitives::{Address, FixedBytes},
+    primitives::{Address, FixedBytes, U256},
     rpc::types::trace::parity::Delta,
 };
 use eyre::eyre;
-use futures::future::try_join_all;
-use hashbrown::HashMap;
+use futures::{stream, StreamExt};
+use hashbrown::{HashMap, HashSet};
 use heimdall_common::{
-    ether::rpc::{get_block_state_diff, latest_block_number},
+    ether::rpc::{latest_block_number, StateDiffSource},
     utils::time::{calculate_eta, format_eta},
 };
+use heimdall_decompiler::{decompile, DecompilerArgsBuilder, StorageLayout};
 
-use std::{sync::Arc, time::Instant};
-use tokio::sync::{Mutex, Semaphore};
+use std::time::{Duration, Instant};
+use tokio_retry::{
+    strategy::{jitter, ExponentialBackoff},
+    Retry,
+};
 use tracing::{debug, info};
 
-use crate::{error::Error, interfaces::DumpArgs};
+/// The number of times fetching a block is retried before the dump fails.
+const MAX_BLOCK_RETRIES: usize = 10;
+
+/// The longest delay between retries of a block, in seconds.
+const MAX_RETRY_DELAY_SECS: u64 = 30;
+
+/// The storage changes made within a block, and the `(key, slot)` preimages of the mapping slots
+/// hashed by its transactions.
+type BlockChanges = (Vec<(FixedBytes<32>, Option<FixedBytes<32>>)>, HashSet<(U256, U256)>);
+
+use crate::{
+    core::{
+        checkpoint::{AppendFile, Checkpoint},
+        layout::{
+            collect_preimages, decode_storage, parse_storage_layout, retain_preimages, DecodedSlot,
+        },
+    },
+    error::Error,
+    interfaces::DumpArgs,
+};
+
+/// The result of a storage dump
+#[derive(Debug, Clone)]
+pub struct DumpResult {
+    /// The value of each storage slot which was written within the block range.
+    pub storage: HashMap<FixedBytes<32>, FixedBytes<32>>,
+
+    /// The storage layout the slots were decoded against, if one was provided or recovered.
+    pub layout: Option<StorageLayout>,
+
+    /// The dumped values, labelled and decoded against `layout`. Empty if there is no layout.
+    pub decoded: Vec<DecodedSlot>,
+}
 
 /// Dumps the storage slots for a contract
 ///
 /// This function retrieves storage slots from a contract by analyzing state differences
-/// across multiple blocks. It uses parallel processing to efficiently handle large block ranges.
+/// across multiple blocks. It uses parallel processing to efficiently handle large block ranges,
+/// and can checkpoint its progress to disk so that long dumps can be resumed.
 ///
 /// # Arguments
 ///
@@ -27,87 +67,216 @@ use crate::{error::Error, interfaces::DumpArgs};
 ///
 /// # Returns
 ///
-/// A HashMap containing the storage slots (keys) and their values
-pub async fn dump(args: DumpArgs) -> Result<HashMap<FixedBytes<32>, FixedBytes<32>>, Error> {
+/// A [`DumpResult`] containing the storage slots (keys) and their values, decoded against the
+/// contract's storage layout if one was provided or recovered
+pub async fn dump(args: DumpArgs) -> Result<DumpResult, Error> {
     let start_time = Instant::now();
-    let storage = Arc::new(Mutex::new(HashMap::new()));
-    let completed_count = Arc::new(Mutex::new(0));
+    let decode = args.layout.is_some() || args.decompile;
     let target =
         args.target.parse::<Address>().map_err(|e| eyre!("invalid target address: {e}"))?;
 
+    // resume from the checkpoint, if requested
+    let checkpoint = match (&args.checkpoint, args.resume) {
+        (Some(path), true) => {
+            let checkpoint = Checkpoint::load(path)?;
+            if checkpoint.target != target {
+                return Err(
+                    eyre!("checkpoint '{path}' is for {}, not {target}", checkpoint.target).into()
+                );
+            }
+            info!("resuming dump from block {}", checkpoint.next_block);
+            Some(checkpoint)
+        }
+        _ => None,
+    };
+    let mut storage: HashMap<FixedBytes<32>, FixedBytes<32>> = checkpoint
+        .as_ref()
+        .map(|checkpoint| checkpoint.storage.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    let mut preimages: HashSet<(U256, U256)> = checkpoint
+        .as_ref()
+        .map(|checkpoint| checkpoint.preimages.iter().copied().collect())
+        .unwrap_or_default();
+    let mut append = args
+        .append
+        .as_ref()
+        .map(|path| {
+            AppendFile::open(
+                path,
+                checkpoint.as_ref().and_then(|checkpoint| checkpoint.append_offset),
+            )
+        })
+        .transpose()?;
+
     // build block range
-    let start_block = args.from_block;
+    let start_block = checkpoint
+        .as_ref()
+        .map_or(args.from_block, |checkpoint| checkpoint.next_block.max(args.from_block));
     let to_block = match args.to_block {
         Some(to_block) => to_block,
         None => latest_block_number(&args.rpc_url).await.map_err(|e| eyre!("rpc error: {e}"))?,
     };
     let block_range = start_block..=to_block;
-    let block_count = block_range.end() - block_range.start() + 1;
+    let block_count = (to_block + 1).saturating_sub(start_block);
     debug!("dumping storage from block range: {:?}", block_range);
 
-    // a quick check to see if the rpc supports trace_ namespace
-    // TODO: dump support via `debug_traceBlockByNumber` w/ prestateTracer as another option
-    let _ = get_block_state_diff(
-        (*block_range.start()).try_into().expect("block number overflow"),
-        &args.rpc_url,
-    )
-    .await
-    .map_err(|_| eyre!("failed to `trace_replayBlockTransactions`. does your rpc support it?"))?;
-
-    // create a semaphore with the correct number of permits
-    let semaphore = Arc::new(Semaphore::new(args.threads));
-    let handles = block_range.map(|block_number| {
-        let semaphore = semaphore.clone();
-        let storage = storage.clone();
-        let args = args.clone();
-        let completed_count = completed_count.clone();
-        tokio::spawn(async move {
-            let _permit = semaphore.acquire().await.expect("failed to acquire semaphore permit");
-            let block_trace = get_block_state_diff(block_number as u64, &args.rpc_url)
-                .await
-                .map_err(|e| eyre!("rpc error: {e}"))?;
+    if block_count > 0 {
+        // pick the state diff source by probing which tracing namespace the rpc supports
+        let source = StateDiffSource::detect(
+            start_block.try_into().expect("block number overflow"),
+            &args.rpc_url,
+        )
+        .await
+        .map_err(|e| eyre!("failed to trace block {start_block}: {e}"))?;
+        debug!("using {:?} as the state diff source", source);
+
+        // fetch blocks concurrently, but apply their changes in block order, so each slot ends up
+        // with its latest value and checkpoints are consistent
+        let mut blocks = stream::iter(block_range)
+            .map(|block_number| {
+                let rpc_url = args.rpc_url.clone();
+                tokio::spawn(async move {
+                    let changes =
+                        get_block_changes(source, block_number as u64, target, decode, &rpc_url)
+                            .await;
+                    (block_number, changes)
+                })
+            })
+            .buffered(args.threads.max(1));
+
+        let mut completed_count = 0u128;
+        while let Some(result) = blocks.next().await {
+            let (block_number, changes) = result.map_err(|e| eyre!("failed to join task: {e}"))?;
+            let (changes, block_preimages) =
+                changes.map_err(|e| eyre!("failed to dump block {block_number}: {e}"))?;
 
             // update storage
-            let mut storage = storage.lock().await;
-            block_trace.iter().for_each(|trace| {
-                if let Some(diff) = trace.full_trace.state_diff.as_ref() {
-                    diff.0
-                        .iter()
-                        .filter(|(addr, _)| addr == &&target)
-                        .flat_map(|(_, value)| value.storage.iter())
-                        .for_each(|(slot, diff)| match diff {
-                            Delta::Added(v) => {
-                                storage.insert(*slot, v.to_owned());
-                            }
-                            Delta::Changed(v) => {
-                                storage.insert(*slot, v.to);
-                            }
-                            Delta::Removed(_) => {
-                                storage.remove(slot);
-                            }
-                            _ => {}
-                        });
+            for (slot, value) in changes {
+                if let Some(append) = append.as_mut() {
+                    append.push(block_number, &slot, &value.unwrap_or_default());
                 }
-            });
+                match value {
+                    Some(value) => storage.insert(slot, value),
+                    None => storage.remove(&slot),
+                };
+            }
+            preimages.extend(block_preimages);
+
+            // checkpoint progress periodically, and once the range is complete
+            completed_count += 1;
+            if completed_count.is_multiple_of(args.checkpoint_interval.max(1)) ||
+                block_number == to_block
+            {
+                let append_offset = append.as_mut().map(AppendFile::flush).transpose()?;
+                if let Some(path) = &args.checkpoint {
+                    Checkpoint {
+                        target,
+                        next_block: block_number + 1,
+                        storage: storage.iter().map(|(k, v)| (*k, *v)).collect(),
+                        preimages: preimages.iter().copied().collect(),
+                        append_offset,
+                    }
+                    .save(path)?;
+                    debug!("checkpointed dump at block {}", block_number);
+                }
+            }
 
             // print progress
-            let mut completed_count = completed_count.lock().await;
-            *completed_count += 1;
-            let remaining = block_count - *completed_count;
-            let completed_per_second = *completed_count as f64 / start_time.elapsed().as_secs_f64();
+            let remaining = block_count - completed_count;
+            let completed_per_second = completed_count as f64 / start_time.elapsed().as_secs_f64();
             info!(
                 "completed={}  remaining={}  eta={}",
-                *completed_count,
+                completed_count,
                 remaining,
                 format_eta(calculate_eta(completed_per_second, remaining as usize))
             );
-            Ok::<_, Error>(())
+        }
+    }
+
+    debug!("storage dump took {:?}", start_time.elapsed());
+
+    // load or recover the storage layout, and decode the dumped slots against it
+    let layout = match (&args.layout, args.decompile) {
+        (Some(path), _) => Some(parse_storage_layout(
+            &std::fs::read_to_string(path)
+                .map_err(|e| eyre!("failed to read storage layout '{path}': {e}"))?,
+        )?),
+        (None, true) => {
+            let decompile_args = DecompilerArgsBuilder::new()
+                .target(args.target.clone())
+                .rpc_url(args.rpc_url.clone())
+                .skip_resolving(true)
+                .include_solidity(true)
+                .build()
+                .map_err(|e| eyre!("failed to build decompiler arguments: {e}"))?;
+            let result = decompile(decompile_args)
+                .await
+                .map_err(|e| eyre!("failed to recover storage layout: {e}"))?;
+            Some(result.storage_layout)
+        }
+        (None, false) => None,
+    };
+
+    let decoded = match &layout {
+        Some(layout) => {
+            let decode_time = Instant::now();
+            let decoded = decode_storage(&storage, layout, &preimages);
+            debug!("decoding {} storage values took {:?}", decoded.len(), decode_time.elapsed());
+            decoded
+        }
+        None => Vec::new(),
+    };
+
+    Ok(DumpResult { storage, layout, decoded })
+}
+
+/// Returns the storage changes made to `target` within the block, in transaction order, along
+/// with the preimages of the mapping slots hashed by the block's transactions if `collect` is set.
+/// Only the preimages which derive one of the changed slots are returned.
+/// A slot whose value is `None` was cleared.
+///
+/// Fetching the block is retried with backoff, so that transient rpc failures and rate limits
+/// don't abort the dump.
+async fn get_block_changes(
+    source: StateDiffSource,
+    block_number: u64,
+    target: Address,
+    collect: bool,
+    rpc_url: &str,
+) -> Result<BlockChanges, Error> {
+    let strategy = ExponentialBackoff::from_millis(4)
+        .max_delay(Duration::from_secs(MAX_RETRY_DELAY_SECS))
+        .map(jitter)
+        .take(MAX_BLOCK_RETRIES);
+
+    // vm traces are only needed to find mapping preimages when decoding
+    let block_trace = Retry::spawn(strategy, || async {
+        source.get_block_state_diff(block_number, collect, rpc_url).await.inspect_err(|e| {
+            debug!("failed to fetch block {}, retrying: {}", block_number, e);
         })
-    });
+    })
+    .await
+    .map_err(|e| eyre!("rpc error: {e}"))?;
+
+    let changes: Vec<_> = block_trace
+        .iter()
+        .filter_map(|trace| trace.full_trace.state_diff.as_ref())
+        .filter_map(|diff| diff.0.get(&target))
+        .flat_map(|account| account.storage.iter())
+        .filter_map(|(slot, diff)| match diff {
+            Delta::Added(v) => Some((*slot, Some(*v))),
+            Delta::Changed(v) => Some((*slot, Some(v.to))),
+            Delta::Removed(_) => Some((*slot, None)),
+            _ => None,
+        })
+        .collect();
 
-    // execute all the tasks
-    try_join_all(handles).await.map_err(|e| eyre!("failed to join tasks: {e}"))?;
+    let mut preimages = HashSet::new();
+    if collect {
+        block_trace.iter().for_each(|trace| collect_preimages(trace, &mut preimages));
+        let changed = changes.iter().map(|(slot, _)| U256::from_be_bytes(slot.0)).collect();
+        retain_preimages(&mut preimages, &changed);
+    }
 
-    debug!("storage dump took {:?}", start_time.elapsed());
-    Ok(storage.to_owned().lock().await.to_owned())
+    Ok((changes, preimages))
 }
@@ -41,6 +41,34 @@ pub struct DumpArgs {
     /// The name for the output file
     #[clap(long, short, default_value = "", hide_default_value = true)]
     pub name: String,
+
+    /// Path to a storage layout, in solc's `storageLayout` format, used to label and decode the
+    /// dumped slots.
+    #[clap(long, short)]
+    pub layout: Option<String>,
+
+    /// Recover the storage layout by decompiling the target, and use it to label and decode the
+    /// dumped slots.
+    #[clap(long, conflicts_with = "layout")]
+    pub decompile: bool,
+
+    /// Path to a checkpoint file, which is periodically updated with the dump's progress so it can
+    /// be resumed with `--resume`.
+    #[clap(long)]
+    pub checkpoint: Option<String>,
+
+    /// The number of blocks dumped between checkpoints.
+    #[clap(long, default_value = "1000", hide_default_value = true)]
+    pub checkpoint_interval: u128,
+
+    /// Resume the dump from the checkpoint file, rather than starting over.
+    #[clap(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// Path to a CSV file to which every storage change is appended as a `block,slot,value` row
+    /// as the dump progresses, so partial progress is kept on disk.
+    #[clap(long)]
+    pub append: Option<String>,
 }
 
 impl DumpArgsBuilder {
@@ -54,6 +82,12 @@ impl DumpArgsBuilder {
             from_block: Some(0),
             to_block: Some(None),
             name: Some(String::new()),
+            layout: Some(None),
+            decompile: Some(false),
+            checkpoint: Some(None),
+            checkpoint_interval: Some(1000),
+            resume: Some(false),
+            append: Some(None),
         }
     }
 }
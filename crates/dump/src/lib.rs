@@ -8,6 +8,6 @@ mod core;
 mod interfaces;
 
 // re-export the public interface
-pub use core::dump;
+pub use core::{dump, layout::DecodedSlot, DumpResult};
 pub use error::Error;
 pub use interfaces::{DumpArgs, DumpArgsBuilder};
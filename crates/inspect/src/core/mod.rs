@@ -1,20 +1,24 @@
 use alloy::{
     consensus::Transaction,
+    eips::BlockId,
     network::TransactionResponse,
-    primitives::TxHash,
-    rpc::types::{trace::parity::TransactionTrace, Log},
+    primitives::{Address, TxHash, U256},
+    rpc::types::{
+        trace::parity::{Action, TraceResults, TransactionTrace},
+        Log, TransactionRequest,
+    },
 };
 use eyre::eyre;
 use futures::future::try_join_all;
-use std::{collections::VecDeque, time::Instant};
+use std::{collections::VecDeque, str::FromStr, time::Instant};
 use tracing::{debug, info, trace, warn};
 
 use heimdall_common::{
     ether::{
-        rpc::{get_block_logs, get_trace, get_transaction},
+        rpc::{get_block_logs, get_call_logs, get_call_trace, get_trace, get_transaction},
         signatures::cache_signatures_from_abi,
     },
-    utils::{env::set_env, hex::ToLowerHex, io::logging::TraceFactory},
+    utils::{env::set_env, hex::ToLowerHex, io::logging::TraceFactory, strings::decode_hex},
 };
 
 use crate::{
@@ -45,7 +49,8 @@ impl InspectResult {
 ///
 /// This function retrieves transaction execution data from the blockchain and
 /// decodes it into a human-readable format, showing function calls, events,
-/// and state changes that occurred during the transaction's execution.
+/// and state changes that occurred during the transaction's execution. When
+/// `args.to` is set, the transaction is simulated rather than fetched.
 ///
 /// # Arguments
 ///
@@ -65,45 +70,18 @@ pub async fn inspect(args: InspectArgs) -> Result<InspectResult, Error> {
             .map_err(|e| Error::Eyre(eyre!("caching signatures from ABI failed: {}", e)))?;
     }
 
-    // get calldata from RPC
-    let start_fetch_time = Instant::now();
-    let transaction = get_transaction(
-        args.target
-            .parse::<TxHash>()
-            .map_err(|_| eyre!("invalid transaction hash: '{}'", args.target))?,
-        &args.rpc_url,
-    )
-    .await
-    .map_err(|e| Error::Eyre(eyre!("fetching transaction failed: {}", e)))?;
-    debug!("fetching transaction took {:?}", start_fetch_time.elapsed());
-
-    let block_number = transaction.block_number.unwrap_or(0);
-
-    // get block traces
-    let start_fetch_time = Instant::now();
-    let block_trace = get_trace(&args.target, &args.rpc_url)
-        .await
-        .map_err(|e| Error::Eyre(eyre!("fetching block trace failed: {}", e)))?;
-    debug!("fetching block trace took {:?}", start_fetch_time.elapsed());
-
-    // get transaction logs
-    let start_fetch_time = Instant::now();
-    let transaction_logs = get_block_logs(block_number, &args.rpc_url)
-        .await
-        .map_err(|e| Error::Eyre(eyre!("fetching block logs failed: {}", e)))?
-        .into_iter()
-        .filter(|log| log.transaction_hash == Some(transaction.tx_hash()))
-        .collect::<Vec<_>>();
-    debug!("fetching transaction logs took {:?}", start_fetch_time.elapsed());
+    // fetch the trace and logs of the transaction, simulating it if it hasn't been mined
+    let transaction = match args.to.as_ref() {
+        Some(to) => simulate_transaction(&args, to).await?,
+        None => fetch_transaction(&args).await?,
+    };
+    let block_trace = transaction.trace;
 
     // convert Vec<Log> to Vec<DecodedLog>
     let decode_log_time = Instant::now();
-    let handles =
-        transaction_logs.into_iter().map(<DecodedLog as async_convert::TryFrom<Log>>::try_from);
-    let mut decoded_logs = try_join_all(handles).await?;
-    decoded_logs
-        .sort_by(|a, b| a.log_index.unwrap_or_default().cmp(&b.log_index.unwrap_or_default()));
-    let mut decoded_logs = VecDeque::from(decoded_logs);
+    let (log_addresses, logs): (Vec<_>, Vec<_>) = transaction.logs.into_iter().unzip();
+    let handles = logs.into_iter().map(<DecodedLog as async_convert::TryFrom<Log>>::try_from);
+    let decoded_logs = try_join_all(handles).await?;
     info!("decoded {} logs successfully", decoded_logs.len());
     debug!("decoding logs took {:?}", decode_log_time.elapsed());
 
@@ -135,23 +113,42 @@ pub async fn inspect(args: InspectArgs) -> Result<InspectResult, Error> {
 
     trace!("joining {} decoded logs to trace", decoded_logs.len());
 
+    // logs of simulated transactions are already attributed to the call which emitted them, while
+    // mined logs are matched to their calls using the vm trace
+    let mut decoded_logs = match log_addresses.iter().all(Option::is_some) {
+        true => {
+            let _ = decoded_trace
+                .attach_logs(log_addresses.into_iter().flatten().zip(decoded_logs).collect());
+            VecDeque::new()
+        }
+        false => {
+            let mut decoded_logs = decoded_logs;
+            decoded_logs.sort_by(|a, b| {
+                a.log_index.unwrap_or_default().cmp(&b.log_index.unwrap_or_default())
+            });
+            VecDeque::from(decoded_logs)
+        }
+    };
+
     if let Some(vm_trace) = block_trace.vm_trace {
         // join logs to trace
-        let _ = decoded_trace.join_logs(&mut decoded_logs, &vm_trace, Vec::new()).await;
+        if !decoded_logs.is_empty() {
+            let _ = decoded_trace.join_logs(&mut decoded_logs, &vm_trace, Vec::new()).await;
+        }
         // build state diffs within trace
         let _ = decoded_trace.build_state_diffs(vm_trace, Vec::new()).await;
     } else {
-        warn!("no vm trace found for transaction. skipping joining logs");
+        warn!("no vm trace found for transaction. skipping joining logs and state diffs");
     }
 
     // build trace
     let mut trace = TraceFactory::default();
     let inspect_call = trace.add_call(
         0,
-        transaction.inner.gas_limit().try_into().unwrap_or_default(),
+        transaction.gas_limit.try_into().unwrap_or_default(),
         "heimdall".to_string(),
-        "inspect".to_string(),
-        vec![transaction.tx_hash().to_lower_hex()],
+        transaction.method.to_string(),
+        vec![transaction.description],
         "()".to_string(),
     );
     decoded_trace.add_to_trace(&contracts, &mut trace, inspect_call);
@@ -161,3 +158,108 @@ pub async fn inspect(args: InspectArgs) -> Result<InspectResult, Error> {
 
     Ok(InspectResult { decoded_trace, _trace: trace })
 }
+
+/// The trace and logs of a transaction to be inspected.
+struct InspectedTransaction {
+    /// The trace, state diff and vm trace of the transaction.
+    trace: TraceResults,
+    /// The logs emitted by the transaction, along with the trace address of the call which
+    /// emitted each, if known.
+    logs: Vec<(Option<Vec<usize>>, Log)>,
+    /// The gas limit of the transaction.
+    gas_limit: u64,
+    /// The name of the root call of the displayed trace.
+    method: &'static str,
+    /// A description of the transaction, displayed as the argument of the root call.
+    description: String,
+}
+
+/// Fetches the trace and logs of the mined transaction with the target hash.
+async fn fetch_transaction(args: &InspectArgs) -> Result<InspectedTransaction, Error> {
+    // get calldata from RPC
+    let start_fetch_time = Instant::now();
+    let transaction = get_transaction(
+        args.target
+            .parse::<TxHash>()
+            .map_err(|_| eyre!("invalid transaction hash: '{}'", args.target))?,
+        &args.rpc_url,
+    )
+    .await
+    .map_err(|e| Error::Eyre(eyre!("fetching transaction failed: {}", e)))?;
+    debug!("fetching transaction took {:?}", start_fetch_time.elapsed());
+
+    let block_number = transaction.block_number.unwrap_or(0);
+
+    // get block traces
+    let start_fetch_time = Instant::now();
+    let block_trace = get_trace(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::Eyre(eyre!("fetching block trace failed: {}", e)))?;
+    debug!("fetching block trace took {:?}", start_fetch_time.elapsed());
+
+    // get transaction logs
+    let start_fetch_time = Instant::now();
+    let transaction_logs = get_block_logs(block_number, &args.rpc_url)
+        .await
+        .map_err(|e| Error::Eyre(eyre!("fetching block logs failed: {}", e)))?
+        .into_iter()
+        .filter(|log| log.transaction_hash == Some(transaction.tx_hash()))
+        .map(|log| (None, log))
+        .collect::<Vec<_>>();
+    debug!("fetching transaction logs took {:?}", start_fetch_time.elapsed());
+
+    Ok(InspectedTransaction {
+        trace: block_trace,
+        logs: transaction_logs,
+        gas_limit: transaction.inner.gas_limit(),
+        method: "inspect",
+        description: transaction.tx_hash().to_lower_hex(),
+    })
+}
+
+/// Simulates a transaction to `to` with the target calldata, returning its trace and logs.
+async fn simulate_transaction(args: &InspectArgs, to: &str) -> Result<InspectedTransaction, Error> {
+    let to = to.parse::<Address>().map_err(|_| eyre!("invalid address: '{}'", to))?;
+    let calldata =
+        decode_hex(&args.target).map_err(|_| eyre!("invalid calldata: '{}'", args.target))?;
+    let value =
+        U256::from_str(&args.value).map_err(|_| eyre!("invalid value: '{}'", args.value))?;
+    let mut request = TransactionRequest::default().to(to).value(value).input(calldata.into());
+    if let Some(from) = args.from.as_ref() {
+        request = request
+            .from(from.parse::<Address>().map_err(|_| eyre!("invalid address: '{}'", from))?);
+    }
+    let block = args.block.map(BlockId::number).unwrap_or_else(BlockId::latest);
+
+    // simulate the transaction
+    let start_fetch_time = Instant::now();
+    let trace = get_call_trace(&request, block, &args.rpc_url)
+        .await
+        .map_err(|e| Error::Eyre(eyre!("simulating transaction failed: {}", e)))?;
+    debug!("simulating transaction took {:?}", start_fetch_time.elapsed());
+
+    // get the logs emitted by the simulation, if the rpc supports `debug_traceCall`
+    let start_fetch_time = Instant::now();
+    let logs = match get_call_logs(&request, block, &args.rpc_url).await {
+        Ok(logs) => logs.into_iter().map(|(address, log)| (Some(address), log)).collect(),
+        Err(e) => {
+            warn!("failed to fetch logs of simulated transaction, skipping logs: {}", e);
+            Vec::new()
+        }
+    };
+    debug!("fetching simulated logs took {:?}", start_fetch_time.elapsed());
+
+    let gas_limit = match trace.trace.first().map(|trace| &trace.action) {
+        Some(Action::Call(call)) => call.gas,
+        Some(Action::Create(create)) => create.gas,
+        _ => 0,
+    };
+
+    Ok(InspectedTransaction {
+        trace,
+        logs,
+        gas_limit,
+        method: "simulate",
+        description: to.to_lower_hex(),
+    })
+}
@@ -13,7 +13,8 @@ use heimdall_config::parse_url_arg;
 /// This struct contains all the configuration parameters needed to inspect
 /// a transaction and decode its trace, logs, and state changes.
 pub struct InspectArgs {
-    /// The target transaction hash to inspect.
+    /// The target transaction hash to inspect, or the calldata of a transaction to simulate when
+    /// `--to` is set.
     #[clap(required = true)]
     pub target: String,
 
@@ -45,6 +46,23 @@ pub struct InspectArgs {
     /// Path to an optional ABI file to use for resolving errors, functions, and events.
     #[clap(long, short, default_value = None, hide_default_value = true)]
     pub abi: Option<String>,
+
+    /// The address to send a simulated transaction to. When set, the target is treated as the
+    /// calldata of a transaction to simulate, rather than the hash of a mined transaction.
+    #[clap(long)]
+    pub to: Option<String>,
+
+    /// The address to send the simulated transaction from.
+    #[clap(long, requires = "to")]
+    pub from: Option<String>,
+
+    /// The value to send with the simulated transaction, in wei.
+    #[clap(long, requires = "to", default_value = "0", hide_default_value = true)]
+    pub value: String,
+
+    /// The block to simulate the transaction on top of. Defaults to the latest block.
+    #[clap(long, requires = "to")]
+    pub block: Option<u64>,
 }
 
 impl InspectArgsBuilder {
@@ -59,6 +77,10 @@ impl InspectArgsBuilder {
             output: Some(String::from("output")),
             skip_resolving: Some(false),
             abi: Some(None),
+            to: Some(None),
+            from: Some(None),
+            value: Some(String::from("0")),
+            block: Some(None),
         }
     }
 }
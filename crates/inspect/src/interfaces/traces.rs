@@ -372,6 +372,23 @@ impl DecodedTransactionTrace {
         Ok(())
     }
 
+    /// Attaches each log to the call at its trace address, for logs whose emitting call is
+    /// already known (e.g. from `debug_traceCall`'s call tracer).
+    pub fn attach_logs(&mut self, logs: Vec<(Vec<usize>, DecodedLog)>) -> Result<(), Error> {
+        for (trace_address, log) in logs {
+            let mut current_trace = self.borrow_mut();
+            for &index in trace_address.iter() {
+                current_trace = current_trace
+                    .subtraces
+                    .get_mut(index)
+                    .ok_or(Error::Eyre(eyre!("subtrace not found")))?;
+            }
+            current_trace.logs.push(log);
+        }
+
+        Ok(())
+    }
+
     #[async_recursion]
     pub async fn build_state_diffs(
         &mut self,
@@ -0,0 +1,48 @@
+//! Benchmark for testing symbolic execution performance on an ERC20 transfer.
+
+#![allow(missing_docs)]
+
+use std::time::{Duration, Instant};
+
+use alloy::primitives::Address;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use heimdall_common::utils::strings::decode_hex;
+use heimdall_vm::{core::vm::VM, ext::selectors::resolve_entry_point};
+
+fn test_symbolic_exec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heimdall_vm");
+
+    // build the evm and find the entry point of transfer(address,uint256) once, outside of the
+    // timed closure
+    let evm = VM::new(
+        &decode_hex(include_str!("./testdata/weth9.hex")).expect("invalid bytecode"),
+        &[],
+        Address::default(),
+        Address::default(),
+        Address::default(),
+        0,
+        u128::MAX,
+    );
+    let entry_point = resolve_entry_point(&mut evm.clone(), "a9059cbb");
+
+    group.sample_size(100);
+    group.bench_function(BenchmarkId::from_parameter("symbolic_exec_transfer"), |b| {
+        b.iter_batched(
+            || evm.clone(),
+            |mut evm| {
+                evm.symbolic_exec_selector(
+                    "a9059cbb",
+                    entry_point,
+                    Instant::now() + Duration::from_secs(10),
+                )
+                .expect("symbolic execution failed")
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, test_symbolic_exec);
+criterion_main!(benches);
@@ -0,0 +1,440 @@
+//! EVM Object Format (EOF) container parsing.
+//!
+//! Implements the container layout described by EIP-3540, along with the immediate-size and
+//! relative-jump rules from EIP-3670, EIP-4200, EIP-4750 and EIP-663. The parser only validates
+//! the container structure (header, section sizes and type section), not the contents of the
+//! code sections themselves.
+
+use std::ops::Range;
+
+use eyre::{bail, OptionExt, Result};
+
+use super::opcodes::{
+    CALLF, DATALOADN, DUPN, EOFCREATE, EXCHANGE, JUMPF, PUSH1, PUSH32, RETURNCONTRACT, RJUMP,
+    RJUMPI, RJUMPV, SWAPN,
+};
+
+/// The magic bytes which prefix every EOF container.
+pub const EOF_MAGIC: [u8; 2] = [0xef, 0x00];
+
+/// Section kind marker for the type section.
+const KIND_TYPES: u8 = 0x01;
+/// Section kind marker for the code sections.
+const KIND_CODE: u8 = 0x02;
+/// Section kind marker for the container sections.
+const KIND_CONTAINER: u8 = 0x03;
+/// Section kind marker for the data section.
+const KIND_DATA: u8 = 0xff;
+/// Marker terminating the container header.
+const TERMINATOR: u8 = 0x00;
+
+/// The `outputs` value which marks a code section as non-returning.
+pub const NON_RETURNING_FUNCTION: u8 = 0x80;
+
+/// The maximum depth of the CALLF return stack.
+pub const RETURN_STACK_LIMIT: usize = 1024;
+
+/// A single entry of an EOF container's type section, describing the stack behavior of the code
+/// section with the same index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EofTypeSection {
+    /// The number of stack items the code section consumes.
+    pub inputs: u8,
+
+    /// The number of stack items the code section returns, or [`NON_RETURNING_FUNCTION`].
+    pub outputs: u8,
+
+    /// The maximum stack height reached while executing the code section.
+    pub max_stack_height: u16,
+}
+
+impl EofTypeSection {
+    /// Returns true if the code section never returns to its caller via RETF.
+    pub fn is_non_returning(&self) -> bool {
+        self.outputs == NON_RETURNING_FUNCTION
+    }
+}
+
+/// A parsed EOF container.
+///
+/// All section ranges are absolute offsets into [`EofContainer::raw`], so instruction offsets
+/// within a code section can be shared with legacy tooling which indexes into the full bytecode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EofContainer {
+    /// The EOF version of the container.
+    pub version: u8,
+
+    /// The type section, one entry per code section.
+    pub types: Vec<EofTypeSection>,
+
+    /// The byte ranges of each code section.
+    pub code_sections: Vec<Range<usize>>,
+
+    /// The byte ranges of each nested container section.
+    pub container_sections: Vec<Range<usize>>,
+
+    /// The byte range of the data section present in the container.
+    pub data_section: Range<usize>,
+
+    /// The data section size declared in the header. Deploy-time containers may carry less data
+    /// than declared, with the remainder appended by RETURNCONTRACT.
+    pub declared_data_size: u16,
+
+    /// The raw bytes of the container.
+    pub raw: Vec<u8>,
+}
+
+impl EofContainer {
+    /// Returns true if the given bytecode starts with the EOF magic bytes.
+    ///
+    /// ```
+    /// use heimdall_vm::core::eof::EofContainer;
+    ///
+    /// assert!(EofContainer::is_eof(&[0xef, 0x00, 0x01]));
+    /// assert!(!EofContainer::is_eof(&[0x60, 0x80]));
+    /// ```
+    pub fn is_eof(bytecode: &[u8]) -> bool {
+        bytecode.starts_with(&EOF_MAGIC)
+    }
+
+    /// Parses an EOF container from the given bytes.
+    ///
+    /// ```
+    /// use heimdall_vm::core::eof::EofContainer;
+    ///
+    /// // a container with a single code section containing `STOP`
+    /// let bytecode = [
+    ///     0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x01, 0xff, 0x00, 0x00,
+    ///     0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
+    /// ];
+    /// let container = EofContainer::parse(&bytecode).expect("failed to parse container");
+    ///
+    /// assert_eq!(container.code_sections.len(), 1);
+    /// assert_eq!(container.code(0), Some(&[0x00u8][..]));
+    /// ```
+    pub fn parse(bytecode: &[u8]) -> Result<Self> {
+        let mut reader = HeaderReader { bytes: bytecode, offset: 0 };
+
+        if reader.read_bytes(2)? != EOF_MAGIC {
+            bail!("invalid EOF magic");
+        }
+        let version = reader.read_u8()?;
+        if version != 1 {
+            bail!("unsupported EOF version: {}", version);
+        }
+
+        // type section header
+        reader.expect_kind(KIND_TYPES)?;
+        let types_size = reader.read_u16()? as usize;
+        if types_size == 0 || !types_size.is_multiple_of(4) {
+            bail!("invalid type section size: {}", types_size);
+        }
+
+        // code section headers
+        reader.expect_kind(KIND_CODE)?;
+        let code_count = reader.read_u16()? as usize;
+        if code_count == 0 || code_count * 4 != types_size {
+            bail!("code section count {} does not match type section size", code_count);
+        }
+        let code_sizes = (0..code_count)
+            .map(|_| match reader.read_u16()? {
+                0 => bail!("empty code section"),
+                size => Ok(size as usize),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // optional container section headers
+        let mut container_sizes = Vec::new();
+        if reader.peek_u8()? == KIND_CONTAINER {
+            reader.expect_kind(KIND_CONTAINER)?;
+            let container_count = reader.read_u16()? as usize;
+            if container_count == 0 {
+                bail!("container section header declares no containers");
+            }
+            for _ in 0..container_count {
+                match reader.read_u32()? {
+                    0 => bail!("empty container section"),
+                    size => container_sizes.push(size as usize),
+                }
+            }
+        }
+
+        // data section header
+        reader.expect_kind(KIND_DATA)?;
+        let declared_data_size = reader.read_u16()?;
+        reader.expect_kind(TERMINATOR)?;
+
+        // type section body
+        let types = (0..code_count)
+            .map(|_| {
+                Ok(EofTypeSection {
+                    inputs: reader.read_u8()?,
+                    outputs: reader.read_u8()?,
+                    max_stack_height: reader.read_u16()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if types[0].inputs != 0 || !types[0].is_non_returning() {
+            bail!("first code section must have 0 inputs and be non-returning");
+        }
+
+        // code and container section bodies
+        let code_sections = code_sizes
+            .into_iter()
+            .map(|size| reader.read_range(size))
+            .collect::<Result<Vec<_>>>()?;
+        let container_sections = container_sizes
+            .into_iter()
+            .map(|size| reader.read_range(size))
+            .collect::<Result<Vec<_>>>()?;
+
+        // data section body, which may be truncated in deploy-time containers
+        let remaining = bytecode.len() - reader.offset;
+        if remaining > declared_data_size as usize {
+            bail!("data section is larger than declared: {} > {}", remaining, declared_data_size);
+        }
+        let data_section = reader.read_range(remaining)?;
+
+        Ok(Self {
+            version,
+            types,
+            code_sections,
+            container_sections,
+            data_section,
+            declared_data_size,
+            raw: bytecode.to_vec(),
+        })
+    }
+
+    /// Returns the bytes of the code section at the given index.
+    pub fn code(&self, index: usize) -> Option<&[u8]> {
+        self.code_sections.get(index).map(|range| &self.raw[range.clone()])
+    }
+
+    /// Returns the bytes of the nested container at the given index.
+    pub fn container(&self, index: usize) -> Option<&[u8]> {
+        self.container_sections.get(index).map(|range| &self.raw[range.clone()])
+    }
+
+    /// Returns the bytes of the data section.
+    pub fn data(&self) -> &[u8] {
+        &self.raw[self.data_section.clone()]
+    }
+
+    /// Returns the index of the code section containing the given absolute offset.
+    pub fn code_section_at(&self, offset: usize) -> Option<usize> {
+        self.code_sections.iter().position(|range| range.contains(&offset))
+    }
+}
+
+/// Returns the number of immediate bytes following the opcode at `offset` in EOF code.
+///
+/// ```
+/// use heimdall_vm::core::eof::immediate_size;
+///
+/// // PUSH2 0x0102
+/// assert_eq!(immediate_size(&[0x61, 0x01, 0x02], 0), 2);
+///
+/// // RJUMPV with a 2-entry jump table
+/// assert_eq!(immediate_size(&[0xe2, 0x01, 0x00, 0x00, 0x00, 0x01], 0), 5);
+/// ```
+pub fn immediate_size(code: &[u8], offset: usize) -> usize {
+    match code.get(offset).copied().unwrap_or_default() {
+        opcode @ PUSH1..=PUSH32 => (opcode - PUSH1 + 1) as usize,
+        RJUMP | RJUMPI | CALLF | JUMPF | DATALOADN => 2,
+        DUPN | SWAPN | EXCHANGE | EOFCREATE | RETURNCONTRACT => 1,
+        RJUMPV => {
+            let max_index = code.get(offset + 1).copied().unwrap_or_default() as usize;
+            1 + 2 * (max_index + 1)
+        }
+        _ => 0,
+    }
+}
+
+/// Returns the absolute jump targets of the RJUMP, RJUMPI or RJUMPV instruction at `offset`.
+///
+/// The fallthrough destination of RJUMPI and RJUMPV is not included. Any other opcode has no
+/// relative jump targets.
+///
+/// ```
+/// use heimdall_vm::core::eof::relative_jump_targets;
+///
+/// // RJUMP +2, skipping two bytes after the instruction
+/// assert_eq!(relative_jump_targets(&[0xe0, 0x00, 0x02], 0), vec![5]);
+///
+/// // RJUMPI -3, jumping back to itself
+/// assert_eq!(relative_jump_targets(&[0xe1, 0xff, 0xfd], 0), vec![0]);
+/// ```
+pub fn relative_jump_targets(code: &[u8], offset: usize) -> Vec<usize> {
+    let read_i16 = |at: usize| -> Option<i16> {
+        code.get(at..at + 2).map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+    let next = offset + 1 + immediate_size(code, offset);
+
+    let relative_offsets = match code.get(offset).copied() {
+        Some(RJUMP | RJUMPI) => read_i16(offset + 1).into_iter().collect::<Vec<_>>(),
+        Some(RJUMPV) => {
+            let count = code.get(offset + 1).copied().unwrap_or_default() as usize + 1;
+            (0..count).filter_map(|i| read_i16(offset + 2 + 2 * i)).collect()
+        }
+        _ => Vec::new(),
+    };
+
+    relative_offsets
+        .into_iter()
+        .filter_map(|relative| usize::try_from(next as isize + relative as isize).ok())
+        .collect()
+}
+
+/// A cursor over the container header.
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl HeaderReader<'_> {
+    fn read_bytes(&mut self, size: usize) -> Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + size)
+            .ok_or_eyre(format!("unexpected end of EOF container at offset {}", self.offset))?;
+        self.offset += size;
+        Ok(bytes)
+    }
+
+    fn read_range(&mut self, size: usize) -> Result<Range<usize>> {
+        let start = self.offset;
+        self.read_bytes(size)?;
+        Ok(start..self.offset)
+    }
+
+    fn peek_u8(&self) -> Result<u8> {
+        self.bytes
+            .get(self.offset)
+            .copied()
+            .ok_or_eyre(format!("unexpected end of EOF container at offset {}", self.offset))
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn expect_kind(&mut self, kind: u8) -> Result<()> {
+        let offset = self.offset;
+        match self.read_u8()? {
+            found if found == kind => Ok(()),
+            found => bail!(
+                "expected EOF section kind {:#04x} at offset {}, found {:#04x}",
+                kind,
+                offset,
+                found
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::utils::strings::decode_hex;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_container_with_multiple_sections() {
+        // 2 code sections, 1 nested container, 2 bytes of data
+        let bytecode = decode_hex(concat!(
+            "ef0001",
+            "010008",
+            "0200020003",
+            "0002",
+            "030001",
+            "00000014",
+            "ff0002",
+            "00",
+            "00800001",
+            "01010001",
+            "e30001",
+            "5fe4",
+            "ef00010100040200010001ff00000000800000",
+            "00",
+            "abcd",
+        ))
+        .expect("failed to decode bytecode");
+
+        let container = EofContainer::parse(&bytecode).expect("failed to parse container");
+
+        assert_eq!(container.version, 1);
+        assert_eq!(container.types.len(), 2);
+        assert!(container.types[0].is_non_returning());
+        assert_eq!(
+            container.types[1],
+            EofTypeSection { inputs: 1, outputs: 1, max_stack_height: 1 }
+        );
+        assert_eq!(container.code(0), Some(&[0xe3, 0x00, 0x01][..]));
+        assert_eq!(container.code(1), Some(&[0x5f, 0xe4][..]));
+        assert_eq!(container.container_sections.len(), 1);
+        assert!(EofContainer::is_eof(container.container(0).expect("missing container")));
+        assert_eq!(container.data(), &[0xab, 0xcd]);
+        assert_eq!(container.code_section_at(container.code_sections[1].start), Some(1));
+    }
+
+    #[test]
+    fn test_parse_truncated_data_section() {
+        let bytecode = decode_hex("ef00010100040200010001ff0004000080000000ab")
+            .expect("failed to decode bytecode");
+
+        let container = EofContainer::parse(&bytecode).expect("failed to parse container");
+
+        assert_eq!(container.declared_data_size, 4);
+        assert_eq!(container.data(), &[0xab]);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_containers() {
+        // legacy bytecode
+        assert!(EofContainer::parse(&[0x60, 0x80, 0x60, 0x40]).is_err());
+
+        // unsupported version
+        assert!(EofContainer::parse(
+            &decode_hex("ef00020100040200010001ff00000000800000").expect("failed to decode")
+        )
+        .is_err());
+
+        // type section size doesn't match the code section count
+        assert!(EofContainer::parse(
+            &decode_hex("ef00010100080200010001ff00000000800000").expect("failed to decode")
+        )
+        .is_err());
+
+        // truncated code section
+        assert!(EofContainer::parse(
+            &decode_hex("ef00010100040200010002ff000000008000").expect("failed to decode")
+        )
+        .is_err());
+
+        // data section larger than declared
+        assert!(EofContainer::parse(
+            &decode_hex("ef00010100040200010001ff0000000080000000ab").expect("failed to decode")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rjumpv_targets() {
+        // RJUMPV with 3 targets: +0, +1, -7
+        let code = [0xe2, 0x02, 0x00, 0x00, 0x00, 0x01, 0xff, 0xf9];
+
+        assert_eq!(immediate_size(&code, 0), 7);
+        assert_eq!(relative_jump_targets(&code, 0), vec![8, 9, 1]);
+    }
+}
@@ -4,6 +4,9 @@ pub mod chains;
 /// Constants used throughout the VM implementation
 pub mod constants;
 
+/// EVM Object Format (EOF) container parsing
+pub mod eof;
+
 /// Ethereum hard fork definitions
 pub mod hardfork;
 
@@ -16,6 +19,9 @@ pub mod memory;
 /// Opcode definitions and implementations
 pub mod opcodes;
 
+/// State providers which supply the VM with chain state
+pub mod provider;
+
 /// Stack implementation for the VM
 pub mod stack;
 
@@ -28,5 +34,8 @@ pub mod types;
 /// Core virtual machine implementation
 pub mod vm;
 
+/// World state of deployed contracts, used to execute message-calls
+pub mod world;
+
 pub use hardfork::HardFork;
 pub use vm::{ExecutionResult, Instruction, State, VM};
@@ -34,6 +34,10 @@ pub struct OpCodeInfo {
     pure: bool,
     /// The hard fork that activated this opcode.
     activated: HardFork,
+    /// Whether the opcode is only valid inside EOF code sections.
+    eof_only: bool,
+    /// Whether the opcode is only valid in legacy bytecode (deprecated by EIP-3670).
+    legacy_only: bool,
 }
 
 impl OpCodeInfo {
@@ -48,6 +52,8 @@ impl OpCodeInfo {
             view: true,
             pure: true,
             activated: HardFork::Frontier,
+            eof_only: false,
+            legacy_only: false,
         }
     }
 
@@ -104,6 +110,18 @@ impl OpCodeInfo {
     pub const fn is_active_at(&self, fork: HardFork) -> bool {
         fork.is_active(self.activated)
     }
+
+    /// Returns whether the opcode is only valid inside EOF code sections.
+    #[inline]
+    pub const fn is_eof_only(&self) -> bool {
+        self.eof_only
+    }
+
+    /// Returns whether the opcode is only valid in legacy bytecode.
+    #[inline]
+    pub const fn is_legacy_only(&self) -> bool {
+        self.legacy_only
+    }
 }
 
 /// Unknown opcode info returned when an opcode is not recognized.
@@ -116,6 +134,8 @@ pub const UNKNOWN_OPCODE: OpCodeInfo = OpCodeInfo {
     view: false,
     pure: false,
     activated: HardFork::Latest,
+    eof_only: false,
+    legacy_only: false,
 };
 
 impl From<u8> for OpCodeInfo {
@@ -127,10 +147,20 @@ impl From<u8> for OpCodeInfo {
 
 impl OpCodeInfo {
     /// Gets opcode info for a specific hard fork.
-    /// Returns None if the opcode is not active at the given fork.
+    /// Returns None if the opcode is not active at the given fork, or is only valid in EOF code.
     #[inline]
     pub fn for_fork(opcode: u8, fork: HardFork) -> Option<Self> {
-        OPCODE_INFO_TABLE[opcode as usize].filter(|info| info.is_active_at(fork))
+        OPCODE_INFO_TABLE[opcode as usize]
+            .filter(|info| info.is_active_at(fork) && !info.is_eof_only())
+    }
+
+    /// Gets opcode info for a specific hard fork within an EOF code section.
+    /// Returns None if the opcode is not active at the given fork, or is only valid in legacy
+    /// bytecode.
+    #[inline]
+    pub fn for_eof(opcode: u8, fork: HardFork) -> Option<Self> {
+        OPCODE_INFO_TABLE[opcode as usize]
+            .filter(|info| info.is_active_at(fork) && !info.is_legacy_only())
     }
 }
 
@@ -177,6 +207,20 @@ pub const fn activated(mut op: OpCodeInfo, fork: HardFork) -> OpCodeInfo {
     op
 }
 
+/// Marks the opcode as only valid inside EOF code sections.
+#[inline]
+pub const fn eof_only(mut op: OpCodeInfo) -> OpCodeInfo {
+    op.eof_only = true;
+    op
+}
+
+/// Marks the opcode as only valid in legacy bytecode.
+#[inline]
+pub const fn legacy_only(mut op: OpCodeInfo) -> OpCodeInfo {
+    op.legacy_only = true;
+    op
+}
+
 macro_rules! opcodes {
     ($($val:literal => $name:ident => $($modifier:ident $(( $($modifier_arg:expr),* ))?),*);* $(;)?) => {
         // create a constant for each opcode
@@ -354,16 +398,16 @@ opcodes! {
     0x35 => CALLDATALOAD => stack_io(1, 1), min_gas(3);
     0x36 => CALLDATASIZE => stack_io(0, 1), min_gas(2);
     0x37 => CALLDATACOPY => stack_io(3, 0), min_gas(3);
-    0x38 => CODESIZE => stack_io(0, 1), min_gas(2);
-    0x39 => CODECOPY => stack_io(3, 0), min_gas(3);
+    0x38 => CODESIZE => stack_io(0, 1), min_gas(2), legacy_only;
+    0x39 => CODECOPY => stack_io(3, 0), min_gas(3), legacy_only;
     0x3a => GASPRICE => stack_io(0, 1), min_gas(2), non_pure;
-    0x3b => EXTCODESIZE => stack_io(1, 1), min_gas(100), non_pure;
-    0x3c => EXTCODECOPY => stack_io(4, 0), min_gas(100), non_pure;
+    0x3b => EXTCODESIZE => stack_io(1, 1), min_gas(100), non_pure, legacy_only;
+    0x3c => EXTCODECOPY => stack_io(4, 0), min_gas(100), non_pure, legacy_only;
     // Byzantium (EIP-211)
     0x3d => RETURNDATASIZE => stack_io(0, 1), min_gas(2), activated(HardFork::Byzantium);
     0x3e => RETURNDATACOPY => stack_io(3, 0), min_gas(3), activated(HardFork::Byzantium);
     // Constantinople (EIP-1052)
-    0x3f => EXTCODEHASH => stack_io(1, 1), min_gas(100), non_pure, activated(HardFork::Constantinople);
+    0x3f => EXTCODEHASH => stack_io(1, 1), min_gas(100), non_pure, activated(HardFork::Constantinople), legacy_only;
     0x40 => BLOCKHASH => stack_io(1, 1), min_gas(20), non_pure;
     0x41 => COINBASE => stack_io(0, 1), min_gas(2), non_pure;
     0x42 => TIMESTAMP => stack_io(0, 1), min_gas(2), non_pure;
@@ -386,11 +430,11 @@ opcodes! {
     0x53 => MSTORE8 => stack_io(2, 0), min_gas(3);
     0x54 => SLOAD => stack_io(1, 1), min_gas(0), non_pure;
     0x55 => SSTORE => stack_io(2, 0), non_pure, non_view;
-    0x56 => JUMP => stack_io(1, 0), min_gas(8);
-    0x57 => JUMPI => stack_io(2, 0), min_gas(10);
-    0x58 => PC => stack_io(0, 1), min_gas(2);
+    0x56 => JUMP => stack_io(1, 0), min_gas(8), legacy_only;
+    0x57 => JUMPI => stack_io(2, 0), min_gas(10), legacy_only;
+    0x58 => PC => stack_io(0, 1), min_gas(2), legacy_only;
     0x59 => MSIZE => stack_io(0, 1), min_gas(2);
-    0x5a => GAS => stack_io(0, 1), min_gas(2);
+    0x5a => GAS => stack_io(0, 1), min_gas(2), legacy_only;
     0x5b => JUMPDEST => min_gas(1);
     // Cancun (EIP-1153)
     0x5c => TLOAD => stack_io(1, 1), min_gas(100), activated(HardFork::Cancun);
@@ -473,20 +517,48 @@ opcodes! {
     0xa3 => LOG3 => stack_io(5, 0), min_gas(1500);
     0xa4 => LOG4 => stack_io(6, 0), min_gas(1875);
 
-    0xf0 => CREATE => stack_io(3, 1), min_gas(32000), non_pure, non_view;
-    0xf1 => CALL => stack_io(7, 1), min_gas(100), non_pure, non_view;
-    0xf2 => CALLCODE => stack_io(7, 1), min_gas(100), non_pure, non_view;
+    // EOF (EIP-7480)
+    0xd0 => DATALOAD => stack_io(1, 1), min_gas(4), eof_only;
+    0xd1 => DATALOADN => stack_io(0, 1), min_gas(3), eof_only;
+    0xd2 => DATASIZE => stack_io(0, 1), min_gas(2), eof_only;
+    0xd3 => DATACOPY => stack_io(3, 0), min_gas(3), eof_only;
+
+    // EOF (EIP-4200)
+    0xe0 => RJUMP => min_gas(2), eof_only;
+    0xe1 => RJUMPI => stack_io(1, 0), min_gas(4), eof_only;
+    0xe2 => RJUMPV => stack_io(1, 0), min_gas(4), eof_only;
+    // EOF (EIP-4750, EIP-6206)
+    0xe3 => CALLF => min_gas(5), eof_only;
+    0xe4 => RETF => min_gas(3), eof_only;
+    0xe5 => JUMPF => min_gas(5), eof_only;
+    // EOF (EIP-663)
+    0xe6 => DUPN => stack_io(0, 1), min_gas(3), eof_only;
+    0xe7 => SWAPN => min_gas(3), eof_only;
+    0xe8 => EXCHANGE => min_gas(3), eof_only;
+    // EOF (EIP-7620)
+    0xec => EOFCREATE => stack_io(4, 1), min_gas(32000), non_pure, non_view, eof_only;
+    0xee => RETURNCONTRACT => stack_io(2, 0), terminating, eof_only;
+
+    0xf0 => CREATE => stack_io(3, 1), min_gas(32000), non_pure, non_view, legacy_only;
+    0xf1 => CALL => stack_io(7, 1), min_gas(100), non_pure, non_view, legacy_only;
+    0xf2 => CALLCODE => stack_io(7, 1), min_gas(100), non_pure, non_view, legacy_only;
     0xf3 => RETURN => stack_io(2, 0), terminating;
     // Homestead (EIP-7)
-    0xf4 => DELEGATECALL => stack_io(6, 1), min_gas(100), non_pure, non_view, activated(HardFork::Homestead);
+    0xf4 => DELEGATECALL => stack_io(6, 1), min_gas(100), non_pure, non_view, activated(HardFork::Homestead), legacy_only;
     // Constantinople (EIP-1014)
-    0xf5 => CREATE2 => stack_io(4, 1), min_gas(32000), non_pure, non_view, activated(HardFork::Constantinople);
+    0xf5 => CREATE2 => stack_io(4, 1), min_gas(32000), non_pure, non_view, activated(HardFork::Constantinople), legacy_only;
+    // EOF (EIP-7069)
+    0xf7 => RETURNDATALOAD => stack_io(1, 1), min_gas(3), eof_only;
+    0xf8 => EXTCALL => stack_io(4, 1), min_gas(100), non_pure, non_view, eof_only;
+    0xf9 => EXTDELEGATECALL => stack_io(3, 1), min_gas(100), non_pure, non_view, eof_only;
     // Byzantium (EIP-214)
-    0xfa => STATICCALL => stack_io(6, 1), min_gas(100), non_pure, non_view, activated(HardFork::Byzantium);
+    0xfa => STATICCALL => stack_io(6, 1), min_gas(100), non_pure, non_view, activated(HardFork::Byzantium), legacy_only;
+    // EOF (EIP-7069)
+    0xfb => EXTSTATICCALL => stack_io(3, 1), min_gas(100), non_pure, eof_only;
     // Byzantium (EIP-140)
     0xfd => REVERT => stack_io(2, 0), terminating, activated(HardFork::Byzantium);
     0xfe => INVALID => terminating;
-    0xff => SELFDESTRUCT => stack_io(1, 0), min_gas(5000), terminating, non_pure, non_view;
+    0xff => SELFDESTRUCT => stack_io(1, 0), min_gas(5000), terminating, non_pure, non_view, legacy_only;
 }
 
 #[cfg(test)]
@@ -542,6 +614,29 @@ mod tests {
         assert!(clz_info_pectra.is_none());
     }
 
+    #[test]
+    fn test_eof_opcode_info_scope() {
+        // EOF-only opcodes are unknown in legacy bytecode
+        assert!(OpCodeInfo::for_fork(RJUMP, HardFork::Latest).is_none());
+        assert!(OpCodeInfo::for_fork(DATALOADN, HardFork::Latest).is_none());
+        assert!(OpCodeInfo::for_fork(EXTCALL, HardFork::Latest).is_none());
+
+        // ...but valid inside EOF code sections
+        let rjumpi_info = OpCodeInfo::for_eof(RJUMPI, HardFork::Latest);
+        assert!(rjumpi_info.is_some());
+        assert_eq!(rjumpi_info.unwrap().name(), "RJUMPI");
+        assert!(OpCodeInfo::for_eof(EOFCREATE, HardFork::Latest).is_some());
+
+        // legacy-only opcodes are rejected inside EOF code sections
+        assert!(OpCodeInfo::for_eof(JUMPI, HardFork::Latest).is_none());
+        assert!(OpCodeInfo::for_eof(SELFDESTRUCT, HardFork::Latest).is_none());
+        assert!(OpCodeInfo::for_fork(JUMPI, HardFork::Latest).is_some());
+
+        // opcodes valid in both remain subject to hardfork activation
+        assert!(OpCodeInfo::for_eof(ADD, HardFork::Latest).is_some());
+        assert!(OpCodeInfo::for_eof(PUSH0, HardFork::London).is_none());
+    }
+
     #[test]
     fn test_hardfork_latest_resolves_to_fusaka() {
         // Latest should include all opcodes up to Fusaka
@@ -0,0 +1,65 @@
+//! State providers, which supply the VM with chain state (storage, balances, code and block
+//! values) that would otherwise be stubbed.
+
+mod rpc;
+mod snapshot;
+
+use std::fmt::Debug;
+
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+pub use rpc::RpcStateProvider;
+pub use snapshot::{AccountSnapshot, SnapshotStateProvider, StateSnapshot};
+
+/// The [`BlockEnv`] struct holds the block values exposed to the EVM by the block information
+/// opcodes. Any value which is `None` is stubbed by the VM.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockEnv {
+    /// The block number (NUMBER).
+    pub number: Option<u64>,
+
+    /// The block timestamp (TIMESTAMP).
+    pub timestamp: Option<u64>,
+
+    /// The block's beneficiary address (COINBASE).
+    pub coinbase: Option<Address>,
+
+    /// The previous block's RANDAO mix (PREVRANDAO).
+    pub prevrandao: Option<U256>,
+
+    /// The block's gas limit (GASLIMIT).
+    pub gas_limit: Option<u64>,
+
+    /// The chain ID (CHAINID).
+    pub chain_id: Option<u64>,
+
+    /// The block's base fee (BASEFEE).
+    pub base_fee: Option<U256>,
+
+    /// The block's blob base fee (BLOBBASEFEE).
+    pub blob_base_fee: Option<U256>,
+}
+
+/// A [`StateProvider`] supplies the [`VM`](crate::core::vm::VM) with chain state. \
+/// \
+/// The VM consults its provider for SLOAD, BALANCE, SELFBALANCE, EXTCODE*, BLOCKHASH and the block
+/// information opcodes. Any value the provider doesn't know (`None`) falls back to the VM's
+/// default, stubbed behavior.
+pub trait StateProvider: Debug + Send + Sync {
+    /// Returns the value of a storage slot of the given account.
+    fn storage(&self, address: Address, slot: U256) -> Option<U256>;
+
+    /// Returns the balance of the given account.
+    fn balance(&self, address: Address) -> Option<U256>;
+
+    /// Returns the code deployed at the given account.
+    fn code(&self, address: Address) -> Option<Vec<u8>>;
+
+    /// Returns the hash of the given block.
+    fn block_hash(&self, number: u64) -> Option<U256>;
+
+    /// Returns the values of the block being executed in.
+    fn block(&self) -> BlockEnv;
+}
@@ -0,0 +1,177 @@
+use std::{
+    future::Future,
+    sync::{Mutex, OnceLock},
+};
+
+use alloy::primitives::{Address, U256};
+use hashbrown::HashMap;
+use heimdall_common::ether::rpc::{get_balance, get_block_header, get_code_at, get_storage_at};
+use tokio::runtime::Runtime;
+use tracing::warn;
+
+use super::{BlockEnv, StateProvider};
+
+/// A [`StateProvider`] which lazily fetches state from an RPC provider, via `eth_getStorageAt`,
+/// `eth_getBalance`, `eth_getCode` and `eth_getBlockByNumber`. \
+/// \
+/// Every value is fetched at most once, and then cached for the lifetime of the provider.
+#[derive(Debug)]
+pub struct RpcStateProvider {
+    rpc_url: String,
+    block: Option<u64>,
+    storage: Mutex<HashMap<(Address, U256), Option<U256>>>,
+    balances: Mutex<HashMap<Address, Option<U256>>>,
+    code: Mutex<HashMap<Address, Option<Vec<u8>>>>,
+    block_hashes: Mutex<HashMap<u64, Option<U256>>>,
+    block_env: OnceLock<BlockEnv>,
+}
+
+impl RpcStateProvider {
+    /// Creates a new [`RpcStateProvider`] which reads state at the given block, or the latest block
+    /// if `None`.
+    pub fn new(rpc_url: &str, block: Option<u64>) -> Self {
+        Self {
+            rpc_url: rpc_url.to_string(),
+            block,
+            storage: Mutex::new(HashMap::new()),
+            balances: Mutex::new(HashMap::new()),
+            code: Mutex::new(HashMap::new()),
+            block_hashes: Mutex::new(HashMap::new()),
+            block_env: OnceLock::new(),
+        }
+    }
+}
+
+/// Runs a future to completion from synchronous code. The VM is synchronous but is often driven
+/// from within an async runtime, so the future is spawned onto a runtime shared by every provider,
+/// and the calling thread waits for its result.
+///
+/// Selectors are symbolically executed on a worker per core, so the runtime has a thread per core
+/// too, and one worker's fetches don't queue behind another's.
+fn block_on<F>(future: F) -> Option<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static, {
+    static RUNTIME: OnceLock<Option<Runtime>> = OnceLock::new();
+
+    let runtime = RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(std::thread::available_parallelism().map_or(1, |n| n.get()))
+                .thread_name("heimdall-rpc-state")
+                .enable_all()
+                .build()
+                .map_err(|e| warn!("failed to start rpc state runtime: {e}"))
+                .ok()
+        })
+        .as_ref()?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    runtime.spawn(async move {
+        let _ = sender.send(future.await);
+    });
+    receiver.recv().ok()
+}
+
+/// Returns the cached value for `key`, fetching and caching it if it isn't present. Failed fetches
+/// are cached as `None`, so they aren't retried.
+fn cached<K, V, F>(cache: &Mutex<HashMap<K, Option<V>>>, key: K, fetch: F) -> Option<V>
+where
+    K: std::hash::Hash + Eq,
+    V: Clone,
+    F: FnOnce() -> Option<V>, {
+    if let Some(value) = cache.lock().ok()?.get(&key) {
+        return value.clone();
+    }
+
+    let value = fetch();
+    cache.lock().ok()?.insert(key, value.clone());
+    value
+}
+
+impl StateProvider for RpcStateProvider {
+    fn storage(&self, address: Address, slot: U256) -> Option<U256> {
+        cached(&self.storage, (address, slot), || {
+            let (block, rpc_url) = (self.block, self.rpc_url.clone());
+            block_on(async move { get_storage_at(address, slot, block, &rpc_url).await })?
+                .map_err(|e| warn!("failed to fetch storage slot {slot} of {address}: {e}"))
+                .ok()
+        })
+    }
+
+    fn balance(&self, address: Address) -> Option<U256> {
+        cached(&self.balances, address, || {
+            let (block, rpc_url) = (self.block, self.rpc_url.clone());
+            block_on(async move { get_balance(address, block, &rpc_url).await })?
+                .map_err(|e| warn!("failed to fetch balance of {address}: {e}"))
+                .ok()
+        })
+    }
+
+    fn code(&self, address: Address) -> Option<Vec<u8>> {
+        cached(&self.code, address, || {
+            let (block, rpc_url) = (self.block, self.rpc_url.clone());
+            block_on(async move { get_code_at(address, block, &rpc_url).await })?
+                .map_err(|e| warn!("failed to fetch code of {address}: {e}"))
+                .ok()
+        })
+    }
+
+    fn block_hash(&self, number: u64) -> Option<U256> {
+        cached(&self.block_hashes, number, || {
+            let rpc_url = self.rpc_url.clone();
+            block_on(async move { get_block_header(Some(number), &rpc_url).await })?
+                .map_err(|e| warn!("failed to fetch block {number}: {e}"))
+                .ok()
+                .map(|header| U256::from_be_bytes(header.hash.0))
+        })
+    }
+
+    fn block(&self) -> BlockEnv {
+        self.block_env
+            .get_or_init(|| {
+                let (block, rpc_url) = (self.block, self.rpc_url.clone());
+                let header = match block_on(async move { get_block_header(block, &rpc_url).await })
+                {
+                    Some(Ok(header)) => header,
+                    Some(Err(e)) => {
+                        warn!("failed to fetch block header: {e}");
+                        return BlockEnv::default();
+                    }
+                    None => return BlockEnv::default(),
+                };
+                let rpc_url = self.rpc_url.clone();
+                let chain_id =
+                    block_on(async move { heimdall_common::ether::rpc::chain_id(&rpc_url).await })
+                        .and_then(Result::ok);
+
+                BlockEnv {
+                    number: Some(header.number),
+                    timestamp: Some(header.timestamp),
+                    coinbase: Some(header.beneficiary),
+                    prevrandao: Some(U256::from_be_bytes(header.mix_hash.0)),
+                    gas_limit: Some(header.gas_limit),
+                    chain_id,
+                    base_fee: header.base_fee_per_gas.map(U256::from),
+                    blob_base_fee: None,
+                }
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_on_outside_runtime() {
+        assert_eq!(block_on(async { 1 }), Some(1));
+        assert_eq!(block_on(async { 2 }), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_block_on_within_runtime() {
+        assert_eq!(block_on(async { 1 }), Some(1));
+    }
+}
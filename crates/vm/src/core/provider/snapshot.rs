@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use alloy::primitives::{Address, Bytes, U256};
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use super::{BlockEnv, StateProvider};
+
+/// The [`AccountSnapshot`] struct holds the state of a single account within a [`StateSnapshot`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountSnapshot {
+    /// The account's balance, in wei.
+    pub balance: Option<U256>,
+
+    /// The code deployed at the account.
+    pub code: Option<Bytes>,
+
+    /// The account's storage, mapping slots to values.
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// The [`StateSnapshot`] struct is a serializable snapshot of chain state, stored as JSON:
+///
+/// ```json
+/// {
+///   "block": { "number": 19000000, "timestamp": 1705173443, "chainId": 1 },
+///   "blockHashes": { "18999999": "0x..." },
+///   "accounts": {
+///     "0x...": { "balance": "0x1", "code": "0x...", "storage": { "0x0": "0x1" } }
+///   }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StateSnapshot {
+    /// The values of the block being executed in.
+    pub block: BlockEnv,
+
+    /// The hashes of previous blocks, keyed by block number.
+    pub block_hashes: BTreeMap<u64, U256>,
+
+    /// The state of each account in the snapshot.
+    pub accounts: BTreeMap<Address, AccountSnapshot>,
+}
+
+/// A [`StateProvider`] backed by a local [`StateSnapshot`], allowing calls to be replayed offline.
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotStateProvider {
+    snapshot: StateSnapshot,
+}
+
+impl SnapshotStateProvider {
+    /// Creates a new [`SnapshotStateProvider`] from the given snapshot.
+    pub fn new(snapshot: StateSnapshot) -> Self {
+        Self { snapshot }
+    }
+
+    /// Reads a [`StateSnapshot`] from the JSON file at the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read state snapshot {}", path.display()))?;
+        Self::from_json(&contents)
+    }
+
+    /// Parses a [`StateSnapshot`] from a JSON string.
+    ///
+    /// ```
+    /// use heimdall_vm::core::provider::{SnapshotStateProvider, StateProvider};
+    /// use alloy::primitives::{Address, U256};
+    ///
+    /// let provider = SnapshotStateProvider::from_json(
+    ///     r#"{ "accounts": { "0x1111111111111111111111111111111111111111": { "storage": { "0x0": "0x2a" } } } }"#,
+    /// )
+    /// .expect("failed to parse snapshot");
+    ///
+    /// assert_eq!(provider.storage(Address::repeat_byte(0x11), U256::ZERO), Some(U256::from(0x2a)));
+    /// ```
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let snapshot = serde_json::from_str(contents).wrap_err("failed to parse state snapshot")?;
+        Ok(Self::new(snapshot))
+    }
+
+    /// Returns the underlying [`StateSnapshot`].
+    pub fn snapshot(&self) -> &StateSnapshot {
+        &self.snapshot
+    }
+}
+
+impl StateProvider for SnapshotStateProvider {
+    fn storage(&self, address: Address, slot: U256) -> Option<U256> {
+        // accounts present in the snapshot have a fully-known storage, so missing slots are zero
+        self.snapshot
+            .accounts
+            .get(&address)
+            .map(|account| account.storage.get(&slot).copied().unwrap_or_default())
+    }
+
+    fn balance(&self, address: Address) -> Option<U256> {
+        self.snapshot.accounts.get(&address).and_then(|account| account.balance)
+    }
+
+    fn code(&self, address: Address) -> Option<Vec<u8>> {
+        self.snapshot
+            .accounts
+            .get(&address)
+            .and_then(|account| account.code.clone())
+            .map(Into::into)
+    }
+
+    fn block_hash(&self, number: u64) -> Option<U256> {
+        self.snapshot.block_hashes.get(&number).copied()
+    }
+
+    fn block(&self) -> BlockEnv {
+        self.snapshot.block.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut snapshot = StateSnapshot::default();
+        snapshot.block.number = Some(100);
+        snapshot.block_hashes.insert(99, U256::from(0xff));
+        snapshot.accounts.insert(
+            Address::repeat_byte(0x11),
+            AccountSnapshot {
+                balance: Some(U256::from(1000)),
+                code: Some(Bytes::from_static(&[0x00])),
+                storage: BTreeMap::from([(U256::from(1), U256::from(2))]),
+            },
+        );
+
+        let json = serde_json::to_string(&snapshot).expect("failed to serialize snapshot");
+        let provider = SnapshotStateProvider::from_json(&json).expect("failed to parse snapshot");
+
+        assert_eq!(provider.snapshot(), &snapshot);
+        assert_eq!(provider.block().number, Some(100));
+        assert_eq!(provider.block_hash(99), Some(U256::from(0xff)));
+        assert_eq!(provider.balance(Address::repeat_byte(0x11)), Some(U256::from(1000)));
+        assert_eq!(provider.code(Address::repeat_byte(0x11)), Some(vec![0x00]));
+        assert_eq!(
+            provider.storage(Address::repeat_byte(0x11), U256::from(1)),
+            Some(U256::from(2))
+        );
+        assert_eq!(provider.storage(Address::repeat_byte(0x11), U256::from(3)), Some(U256::ZERO));
+        assert_eq!(provider.storage(Address::repeat_byte(0x22), U256::from(1)), None);
+    }
+}
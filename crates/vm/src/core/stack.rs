@@ -1,7 +1,7 @@
 use std::{
-    collections::VecDeque,
     fmt::Display,
-    hash::{BuildHasher, Hash},
+    hash::{BuildHasher, Hash, Hasher},
+    sync::Arc,
 };
 
 use alloy::primitives::U256;
@@ -11,13 +11,124 @@ use hashbrown::hash_map::DefaultHashBuilder;
 use super::opcodes::WrappedOpcode;
 
 /// The [`Stack`] struct represents the EVM stack.
-/// It is a LIFO data structure that holds a VecDeque of [`StackFrame`]s.
+/// It is a LIFO data structure that holds a persistent list of [`StackFrame`]s.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Stack {
     /// The collection of stack frames in LIFO order.
     ///
-    /// The front of the deque represents the top of the stack.
-    pub stack: VecDeque<StackFrame>,
+    /// The front of the list represents the top of the stack.
+    pub stack: Frames,
+}
+
+/// A persistent, singly linked list of [`StackFrame`]s, top first. \
+/// \
+/// Clones share every frame with the original, so cloning is O(1) and the stacks snapshotted on
+/// every step of symbolic execution only own the frames pushed since the previous snapshot.
+#[derive(Clone, Default)]
+pub struct Frames {
+    head: Option<Arc<Node>>,
+    len: usize,
+}
+
+/// A node of [`Frames`], holding a frame and the rest of the list below it.
+struct Node {
+    frame: StackFrame,
+    next: Option<Arc<Node>>,
+}
+
+impl Frames {
+    /// Pushes a frame onto the front of the list.
+    pub fn push_front(&mut self, frame: StackFrame) {
+        self.head = Some(Arc::new(Node { frame, next: self.head.take() }));
+        self.len += 1;
+    }
+
+    /// Removes the frame at the front of the list. The frame is only cloned if it's shared with
+    /// another list.
+    pub fn pop_front(&mut self) -> Option<StackFrame> {
+        let node = self.head.take()?;
+        self.len -= 1;
+        match Arc::try_unwrap(node) {
+            Ok(node) => {
+                self.head = node.next;
+                Some(node.frame)
+            }
+            Err(node) => {
+                self.head = node.next.clone();
+                Some(node.frame.clone())
+            }
+        }
+    }
+
+    /// Returns the frame at `index`, counting from the front of the list.
+    pub fn get(&self, index: usize) -> Option<&StackFrame> {
+        self.iter().nth(index)
+    }
+
+    /// Swaps the frames at `a` and `b`, rebuilding the list above the deeper of the two.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        let depth = a.max(b);
+        if depth >= self.len {
+            return;
+        }
+
+        let mut frames = (0..=depth).filter_map(|_| self.pop_front()).collect::<Vec<_>>();
+        frames.swap(a, b);
+        for frame in frames.into_iter().rev() {
+            self.push_front(frame);
+        }
+    }
+
+    /// Returns an iterator over the frames, from the front of the list.
+    pub fn iter(&self) -> impl Iterator<Item = &StackFrame> {
+        std::iter::successors(self.head.as_deref(), |node| node.next.as_deref())
+            .map(|node| &node.frame)
+    }
+
+    /// Returns the number of frames in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the list holds no frames.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Drop for Frames {
+    /// Drops the nodes no other list shares iteratively, rather than recursively, so dropping a
+    /// deep stack can't overflow the call stack.
+    fn drop(&mut self) {
+        let mut next = self.head.take();
+        while let Some(node) = next {
+            match Arc::try_unwrap(node) {
+                Ok(mut node) => next = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Frames {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Frames {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Frames {}
+
+impl Hash for Frames {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        self.iter().for_each(|frame| frame.hash(state));
+    }
 }
 
 /// The [`StackFrame`] struct represents a single frame on the stack.
@@ -54,7 +165,7 @@ impl Stack {
     /// assert_eq!(stack.size(), 0);
     /// ```
     pub fn new() -> Stack {
-        Stack { stack: VecDeque::with_capacity(1024) }
+        Stack { stack: Frames::default() }
     }
 
     /// Push a value onto the stack.
@@ -116,7 +227,7 @@ impl Stack {
         if n > self.stack.len() {
             return Err(eyre::eyre!("stack underflow"));
         }
-        Ok(self.stack.drain(0..n).collect::<Vec<StackFrame>>())
+        Ok((0..n).filter_map(|_| self.stack.pop_front()).collect::<Vec<StackFrame>>())
     }
 
     /// Swap the top value and the nth value on the stack.
@@ -137,7 +248,7 @@ impl Stack {
     /// assert_eq!(stack.pop().unwrap().value, U256::from(0x01));
     /// ```
     pub fn swap(&mut self, n: usize) -> bool {
-        if self.stack.get_mut(n).is_some() {
+        if n < self.stack.len() {
             self.stack.swap(0, n);
             true
         } else {
@@ -145,6 +256,34 @@ impl Stack {
         }
     }
 
+    /// Exchange the nth and mth values on the stack.
+    ///
+    /// ```
+    /// use heimdall_vm::core::{opcodes::WrappedOpcode, stack::Stack};
+    /// use alloy::primitives::U256;
+    ///
+    /// let mut stack = Stack::new();
+    /// stack.push(U256::from(0x00), WrappedOpcode::default());
+    /// stack.push(U256::from(0x01), WrappedOpcode::default());
+    /// stack.push(U256::from(0x02), WrappedOpcode::default());
+    ///
+    /// // stack is now [0x02, 0x01, 0x00]
+    /// stack.exchange(1, 2);
+    ///
+    /// // stack is now [0x02, 0x00, 0x01]
+    /// assert_eq!(stack.pop().unwrap().value, U256::from(0x02));
+    /// assert_eq!(stack.pop().unwrap().value, U256::from(0x00));
+    /// assert_eq!(stack.pop().unwrap().value, U256::from(0x01));
+    /// ```
+    pub fn exchange(&mut self, n: usize, m: usize) -> bool {
+        if n < self.stack.len() && m < self.stack.len() {
+            self.stack.swap(n, m);
+            true
+        } else {
+            false
+        }
+    }
+
     /// Duplicate the nth value on the stack.
     ///
     /// ```
@@ -164,9 +303,9 @@ impl Stack {
     /// // stack is now []
     /// ```
     pub fn dup(&mut self, n: usize) -> bool {
-        match self.stack.get(n - 1) {
+        match self.stack.get(n - 1).cloned() {
             Some(item) => {
-                self.stack.push_front(item.clone());
+                self.stack.push_front(item);
                 true
             }
             None => false,
@@ -346,6 +485,27 @@ mod tests {
         assert!(stack.is_empty());
     }
 
+    #[test]
+    fn test_clones_share_frames() {
+        let mut stack = Stack::new();
+        stack.push(U256::from(1), WrappedOpcode::default());
+        stack.push(U256::from(2), WrappedOpcode::default());
+
+        // changes to either stack aren't visible through the other
+        let mut snapshot = stack.clone();
+        stack.push(U256::from(3), WrappedOpcode::default());
+        assert_eq!(snapshot.pop().unwrap().value, U256::from(2));
+        assert!(stack.swap(2));
+
+        assert_eq!(snapshot.size(), 1);
+        assert_eq!(snapshot.peek(0).value, U256::from(1));
+        assert_eq!(
+            stack.peek_n(3).iter().map(|f| f.value).collect::<Vec<_>>(),
+            vec![U256::from(1), U256::from(2), U256::from(3)]
+        );
+        assert_ne!(stack, snapshot);
+    }
+
     #[test]
     fn test_peek() {
         let mut stack = Stack::new();
@@ -9,9 +9,15 @@ use std::time::Instant;
 #[cfg(feature = "step-tracing")]
 use tracing::trace;
 
-use crate::core::{
-    hardfork::HardFork,
-    opcodes::{self, OpCodeInfo, WrappedInput, WrappedOpcode},
+use crate::{
+    core::{
+        eof::EofContainer,
+        hardfork::HardFork,
+        opcodes::{self, OpCodeInfo, WrappedInput, WrappedOpcode},
+        provider::StateProvider,
+        world::WorldState,
+    },
+    ext::solver::Solver,
 };
 
 use super::super::{
@@ -22,8 +28,9 @@ use super::super::{
 };
 
 use super::{
-    execution::{ExecutionResult, Instruction, State},
+    execution::{CallFrame, ExecutionResult, Instruction, State},
     handlers,
+    snapshot::Snapshots,
 };
 
 /// The [`VM`] struct represents an EVM instance. \
@@ -82,6 +89,44 @@ pub struct VM {
     /// The hard fork to use for opcode activation.
     pub hardfork: HardFork,
 
+    /// The parsed EOF container, if the bytecode is an EOF container.
+    pub eof: Option<Arc<EofContainer>>,
+
+    /// The index of the EOF code section currently being executed.
+    pub code_section: usize,
+
+    /// The EOF return stack of `(code_section, instruction)` pairs pushed by CALLF.
+    pub return_stack: Vec<(usize, u128)>,
+
+    /// The contracts that message-calls may be executed against. If `None`, message-calls are
+    /// stubbed and always succeed.
+    pub world: Option<Arc<WorldState>>,
+
+    /// The provider consulted for chain state, such as storage, balances and block values. If
+    /// `None`, this state is stubbed.
+    pub provider: Option<Arc<dyn StateProvider>>,
+
+    /// The solver used to prune infeasible branches during symbolic execution. If `None`, which is
+    /// the default, every branch is explored.
+    pub solver: Option<Arc<dyn Solver>>,
+
+    /// The depth of this frame in the message-call stack.
+    pub depth: usize,
+
+    /// The returndata of the most recent message-call executed in a child frame, if any.
+    pub call_returndata: Option<Vec<u8>>,
+
+    /// Whether this frame is executing in a static context (within a STATICCALL), in which
+    /// instructions that modify state fail.
+    pub is_static: bool,
+
+    /// The message-call executed by the current instruction, if any.
+    pub(crate) last_call: Option<Arc<CallFrame>>,
+
+    /// Snapshots of the stack, memory, storage and events shared by the [`State`]s returned from
+    /// [`VM::step`] until an instruction mutates them.
+    pub(crate) snapshots: Snapshots,
+
     /// Counter for operations executed (only available with step-tracing feature).
     #[cfg(feature = "step-tracing")]
     pub operation_count: u128,
@@ -118,11 +163,17 @@ impl VM {
         value: u128,
         gas_limit: u128,
     ) -> VM {
+        let eof = if EofContainer::is_eof(bytecode) {
+            EofContainer::parse(bytecode).ok().map(Arc::new)
+        } else {
+            None
+        };
+
         VM {
             stack: Stack::new(),
             memory: Memory::new(),
             storage: Storage::new(),
-            instruction: 1,
+            instruction: Self::entry_instruction(eof.as_deref()),
             bytecode: bytecode.to_vec(),
             calldata: calldata.to_vec(),
             address,
@@ -136,6 +187,17 @@ impl VM {
             exitcode: 255,
             address_access_set: HashSet::new(),
             hardfork: HardFork::default(),
+            eof,
+            code_section: 0,
+            return_stack: Vec::new(),
+            world: None,
+            provider: None,
+            solver: None,
+            depth: 0,
+            call_returndata: None,
+            is_static: false,
+            last_call: None,
+            snapshots: Snapshots::default(),
             #[cfg(feature = "step-tracing")]
             operation_count: 0,
             #[cfg(feature = "step-tracing")]
@@ -149,6 +211,51 @@ impl VM {
         self
     }
 
+    /// Sets the world state that message-calls are executed against.
+    pub fn with_world(mut self, world: Arc<WorldState>) -> Self {
+        self.world = Some(world);
+        self
+    }
+
+    /// Sets the provider consulted for chain state.
+    pub fn with_provider(mut self, provider: Arc<dyn StateProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Sets the solver used to prune infeasible branches during symbolic execution.
+    pub fn with_solver(mut self, solver: Arc<dyn Solver>) -> Self {
+        self.solver = Some(solver);
+        self
+    }
+
+    /// Returns the code deployed at the given address, if it is loaded into the world state or
+    /// known to the state provider.
+    pub(crate) fn account_code(&self, address: &Address) -> Option<Vec<u8>> {
+        self.world
+            .as_ref()
+            .and_then(|world| world.code(address).map(|code| code.to_vec()))
+            .or_else(|| self.provider.as_ref().and_then(|provider| provider.code(*address)))
+            .filter(|code| !code.is_empty())
+    }
+
+    /// Fails if this frame is executing in a static context, in which `opcode` may not modify
+    /// state.
+    pub(crate) fn ensure_mutable(&self, opcode: u8) -> Result<()> {
+        if self.is_static {
+            return Err(eyre::eyre!("{} in a static context", opcodes::opcode_name(opcode)));
+        }
+        Ok(())
+    }
+
+    /// Returns the instruction at which execution begins. For EOF containers, this is the start of
+    /// the first code section.
+    fn entry_instruction(eof: Option<&EofContainer>) -> u128 {
+        eof.and_then(|container| container.code_sections.first())
+            .map(|code| code.start as u128 + 1)
+            .unwrap_or(1)
+    }
+
     /// Exits current execution with the given code and returndata.
     ///
     /// ```
@@ -318,11 +425,25 @@ impl VM {
         #[cfg(feature = "step-tracing")]
         let start_time = Instant::now();
 
+        // EOF code sections are isolated, so execution must stay within the current section
+        let in_code_section = match &self.eof {
+            Some(container) => container
+                .code_sections
+                .get(self.code_section)
+                .is_some_and(|code| code.contains(&((last_instruction - 1) as usize))),
+            None => true,
+        };
+
         // add the opcode to the trace
-        let opcode_info = match OpCodeInfo::for_fork(opcode, self.hardfork) {
+        let opcode_info = match self.eof {
+            Some(_) => OpCodeInfo::for_eof(opcode, self.hardfork),
+            None => OpCodeInfo::for_fork(opcode, self.hardfork),
+        };
+        let opcode_info = match opcode_info.filter(|_| in_code_section) {
             Some(info) => info,
             None => {
-                // Opcode not active at this hardfork - treat as invalid
+                // Opcode not active at this hardfork (or outside of the code section) - treat as
+                // invalid
                 self.exit(1, Vec::new());
                 return Ok(Instruction {
                     instruction: last_instruction,
@@ -441,7 +562,7 @@ impl VM {
             opcodes::COINBASE => handlers::block::coinbase(self, operation)?,
             opcodes::TIMESTAMP => handlers::block::timestamp(self, operation)?,
             (opcodes::NUMBER..=opcodes::BLOBBASEFEE) => {
-                handlers::block::block_info_stub(self, operation)?
+                handlers::block::block_info(self, operation)?
             }
 
             opcodes::POP => handlers::stack::pop(self)?,
@@ -504,6 +625,30 @@ impl VM {
             opcodes::STATICCALL => handlers::system::staticcall(self, operation)?,
             opcodes::REVERT => handlers::system::revert(self)?,
 
+            opcodes::DATALOAD => handlers::eof::dataload(self, operation)?,
+            opcodes::DATALOADN => handlers::eof::dataloadn(self, operation)?,
+            opcodes::DATASIZE => handlers::eof::datasize(self, operation)?,
+            opcodes::DATACOPY => handlers::eof::datacopy(
+                self,
+                #[cfg(feature = "experimental")]
+                operation,
+            )?,
+            opcodes::RJUMP => handlers::eof::rjump(self)?,
+            opcodes::RJUMPI => handlers::eof::rjumpi(self)?,
+            opcodes::RJUMPV => handlers::eof::rjumpv(self)?,
+            opcodes::CALLF => handlers::eof::callf(self)?,
+            opcodes::RETF => handlers::eof::retf(self)?,
+            opcodes::JUMPF => handlers::eof::jumpf(self)?,
+            opcodes::DUPN => handlers::eof::dupn(self)?,
+            opcodes::SWAPN => handlers::eof::swapn(self)?,
+            opcodes::EXCHANGE => handlers::eof::exchange(self)?,
+            opcodes::EOFCREATE => handlers::eof::eofcreate(self, operation)?,
+            opcodes::RETURNCONTRACT => handlers::eof::returncontract(self)?,
+            opcodes::RETURNDATALOAD => handlers::eof::returndataload(self, operation)?,
+            opcodes::EXTCALL => handlers::eof::extcall(self, operation)?,
+            opcodes::EXTDELEGATECALL => handlers::eof::extdelegatecall(self, operation)?,
+            opcodes::EXTSTATICCALL => handlers::eof::extstaticcall(self, operation)?,
+
             _ => {
                 self.exit(1, Vec::new());
             }
@@ -570,19 +715,35 @@ impl VM {
     /// assert_eq!(vm.exitcode, 10);
     /// ```
     pub fn step(&mut self) -> Result<State> {
-        let instruction = self._step()?;
+        let instruction = self.advance()?;
 
         Ok(State {
             last_instruction: instruction,
             gas_used: self.gas_used,
             gas_remaining: self.gas_remaining,
-            stack: self.stack.clone(),
-            memory: self.memory.clone(),
-            storage: self.storage.clone(),
-            events: self.events.clone(),
+            stack: self.snapshots.stack(&self.stack),
+            memory: self.snapshots.memory(&self.memory),
+            storage: self.snapshots.storage(&self.storage),
+            events: self.snapshots.events(&self.events),
+            call: self.last_call.take(),
         })
     }
 
+    /// Executes the next instruction, dropping the snapshots of anything it may have mutated.
+    fn advance(&mut self) -> Result<Instruction> {
+        match self._step() {
+            Ok(instruction) => {
+                self.snapshots.invalidate(instruction.opcode);
+                Ok(instruction)
+            }
+            Err(e) => {
+                // the instruction may have partially executed, so no snapshot can be trusted
+                self.snapshots.clear();
+                Err(e)
+            }
+        }
+    }
+
     /// View the next n instructions without executing them
     ///
     /// ```
@@ -644,7 +805,12 @@ impl VM {
     pub fn reset(&mut self) {
         self.stack = Stack::new();
         self.memory = Memory::new();
-        self.instruction = 1;
+        self.instruction = Self::entry_instruction(self.eof.as_deref());
+        self.code_section = 0;
+        self.return_stack = Vec::new();
+        self.call_returndata = None;
+        self.last_call = None;
+        self.snapshots.clear();
         self.gas_remaining = (self.gas_used + self.gas_remaining).max(21000) - 21000;
         self.gas_used = 21000;
         self.events = Vec::new();
@@ -673,7 +839,9 @@ impl VM {
     /// ```
     pub fn execute(&mut self) -> Result<ExecutionResult> {
         while self.bytecode.len() >= self.instruction as usize {
-            self.step()?;
+            // the intermediate states aren't needed, so don't build them
+            self.advance()?;
+            self.last_call = None;
 
             if self.exitcode != 255 || !self.returndata.is_empty() {
                 break;
@@ -721,13 +889,13 @@ impl VM {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{str::FromStr, sync::Arc};
 
     use alloy::primitives::{Address, U256};
     use heimdall_common::utils::strings::decode_hex;
 
     use super::VM;
-    use crate::core::hardfork::HardFork;
+    use crate::core::{hardfork::HardFork, provider::SnapshotStateProvider, world::WorldState};
 
     // creates a new test VM with calldata.
     fn new_test_vm(bytecode: &str) -> VM {
@@ -1585,4 +1753,304 @@ mod tests {
 
         assert_eq!(vm.stack.peek(0).value, U256::from(3));
     }
+
+    #[test]
+    fn test_eof_rjumpi_dataloadn() {
+        // PUSH1 0x01, RJUMPI +2, PUSH0, STOP, DATALOADN 0x0000, STOP
+        let mut vm = new_test_vm(concat!(
+            "0xef0001010004020001000bff002000",
+            "00800002",
+            "6001e100025f00d1000000",
+            "000000000000000000000000000000000000000000000000000000000000002a",
+        ));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.size(), 1);
+        assert_eq!(vm.stack.peek(0).value, U256::from(0x2a));
+        assert_eq!(vm.exitcode, 10);
+    }
+
+    #[test]
+    fn test_eof_rjumpv() {
+        // PUSH1 0x01, RJUMPV [+0, +2], STOP, STOP, PUSH1 0x07, STOP
+        let mut vm = new_test_vm(concat!(
+            "0xef0001010004020001000dff000000",
+            "00800001",
+            "6001e201000000020000600700",
+        ));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(7));
+        assert_eq!(vm.exitcode, 10);
+    }
+
+    #[test]
+    fn test_eof_callf_retf() {
+        // section 0: PUSH1 0x05, CALLF 1, STOP
+        // section 1: PUSH1 0x03, ADD, RETF
+        let mut vm = new_test_vm(concat!(
+            "0xef000101000802000200060004ff000000",
+            "0080000201010002",
+            "6005e3000100",
+            "600301e4",
+        ));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(8));
+        assert!(vm.return_stack.is_empty());
+        assert_eq!(vm.code_section, 0);
+        assert_eq!(vm.exitcode, 10);
+    }
+
+    #[test]
+    fn test_eof_extcall_executes_loaded_code() {
+        // callee: MSTORE(0, 0x2a), RETURN(0, 32)
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x602a60005260206000f3").expect("failed to decode bytecode"),
+        );
+
+        // EXTCALL(0x11..11, 0, 0, 0), RETURNDATALOAD(0), STOP
+        let mut vm = new_test_vm(concat!(
+            "0xef00010100040200010020ff000000",
+            "00800004",
+            "60006000600073",
+            "1111111111111111111111111111111111111111",
+            "f86000f700",
+        ))
+        .with_world(Arc::new(world));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(0x2a));
+        assert_eq!(vm.stack.peek(1).value, U256::ZERO);
+        assert_eq!(vm.exitcode, 10);
+    }
+
+    #[test]
+    fn test_eof_stack_immediates() {
+        // PUSH1 0x01, PUSH1 0x02, PUSH1 0x03, DUPN 2, EXCHANGE 0x00, SWAPN 1, STOP
+        let mut vm = new_test_vm(concat!(
+            "0xef0001010004020001000dff000000",
+            "00800004",
+            "600160026003e602e800e70100",
+        ));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(3));
+        assert_eq!(vm.stack.peek(1).value, U256::from(2));
+        assert_eq!(vm.stack.peek(2).value, U256::from(1));
+        assert_eq!(vm.stack.peek(3).value, U256::from(1));
+        assert_eq!(vm.exitcode, 10);
+    }
+
+    #[test]
+    fn test_eof_returncontract() {
+        // PUSH0, PUSH0, RETURNCONTRACT 0
+        let subcontainer = "ef00010100040200010001ff0000000080000000";
+        let mut vm = new_test_vm(&format!(
+            "0xef00010100040200010004030001000000{:02x}ff00000000800002{}{}",
+            subcontainer.len() / 2,
+            "5f5fee00",
+            subcontainer
+        ));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.returndata, decode_hex(subcontainer).expect("failed to decode"));
+        assert_eq!(vm.exitcode, 0);
+    }
+
+    #[test]
+    fn test_eof_rejects_legacy_opcodes() {
+        // PUSH1 0x01, PUSH1 0x00, JUMPI
+        let mut vm = new_test_vm("0xef00010100040200010005ff000000008000026001600057");
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.exitcode, 1);
+    }
+
+    #[test]
+    fn test_call_executes_loaded_code() {
+        // callee: MSTORE(0, 0x2a), RETURN(0, 32)
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x602a60005260206000f3").expect("failed to decode bytecode"),
+        );
+
+        // CALL(GAS, 0x11..11, 0, 0, 0, 0, 32), RETURNDATASIZE, MLOAD(0)
+        let mut vm = new_test_vm(
+            "0x602060006000600060007311111111111111111111111111111111111111115af13d60005100",
+        )
+        .with_world(Arc::new(world));
+        let frame = loop {
+            let state = vm.step().expect("step failed!");
+            if let Some(frame) = state.call {
+                break frame;
+            }
+        };
+        assert!(frame.success());
+        assert_eq!(frame.result.returndata, U256::from(0x2a).to_be_bytes_vec());
+
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(0x2a));
+        assert_eq!(vm.stack.peek(1).value, U256::from(32));
+        assert_eq!(vm.stack.peek(2).value, U256::from(1));
+    }
+
+    #[test]
+    fn test_delegatecall_writes_caller_storage() {
+        // callee: SSTORE(1, 0xff)
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x60ff600155").expect("failed to decode bytecode"),
+        );
+
+        // DELEGATECALL(GAS, 0x11..11, 0, 0, 0, 0)
+        let mut vm =
+            new_test_vm("0x60006000600060007311111111111111111111111111111111111111115af400")
+                .with_world(Arc::new(world));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+        assert_eq!(vm.storage.storage.get(&U256::from(1)), Some(&U256::from(0xff)));
+    }
+
+    #[test]
+    fn test_staticcall_rejects_state_modification() {
+        // callees: SSTORE(1, 0xff) and LOG0(0, 0)
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x60ff600155").expect("failed to decode bytecode"),
+        );
+        world.insert_code(
+            Address::repeat_byte(0x22),
+            decode_hex("0x60006000a0").expect("failed to decode bytecode"),
+        );
+
+        // STATICCALL(GAS, 0x11..11, 0, 0, 0, 0), STATICCALL(GAS, 0x22..22, 0, 0, 0, 0)
+        let mut vm = new_test_vm(
+            "0x60006000600060007311111111111111111111111111111111111111115afa\
+             60006000600060007322222222222222222222222222222222222222225afa00",
+        )
+        .with_world(Arc::new(world));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::ZERO);
+        assert_eq!(vm.stack.peek(1).value, U256::ZERO);
+        assert!(vm.storage.storage.is_empty());
+        assert!(vm.events.is_empty());
+    }
+
+    #[test]
+    fn test_call_to_empty_account_clears_returndata() {
+        // callee: MSTORE(0, 0x2a), RETURN(0, 32)
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x602a60005260206000f3").expect("failed to decode bytecode"),
+        );
+        let provider = SnapshotStateProvider::from_json(
+            r#"{ "accounts": { "0x2222222222222222222222222222222222222222": { "code": "0x" } } }"#,
+        )
+        .expect("failed to parse snapshot");
+
+        // CALL(GAS, 0x11..11, 0, 0, 0, 0, 0), CALL(GAS, 0x22..22, 0, 0, 0, 0, 0), RETURNDATASIZE
+        let mut vm = new_test_vm(
+            "0x600060006000600060007311111111111111111111111111111111111111115af1\
+             600060006000600060007322222222222222222222222222222222222222225af13d00",
+        )
+        .with_world(Arc::new(world))
+        .with_provider(Arc::new(provider));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::ZERO);
+        assert_eq!(vm.stack.peek(1).value, U256::from(1));
+        assert_eq!(vm.call_returndata, Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_call_outside_world_is_stubbed() {
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x602a60005260206000f3").expect("failed to decode bytecode"),
+        );
+
+        // CALL(GAS, 0x22..22, 0, 0, 0, 0, 0), RETURNDATASIZE
+        let mut vm =
+            new_test_vm("0x600060006000600060007322222222222222222222222222222222222222225af13d00")
+                .with_world(Arc::new(world));
+        vm.execute().expect("execution failed!");
+
+        // nothing is known about the callee, so its returndata is still stubbed
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+        assert!(vm.call_returndata.is_none());
+    }
+
+    #[test]
+    fn test_call_keeps_callee_storage() {
+        // callee: increments slot 0 and returns its new value
+        let mut world = WorldState::new();
+        world.insert_code(
+            Address::repeat_byte(0x11),
+            decode_hex("0x6000546001018060005560005260206000f3")
+                .expect("failed to decode bytecode"),
+        );
+
+        // CALL(GAS, 0x11..11, 0, 0, 0, 0, 32) twice, MLOAD(0)
+        let mut vm = new_test_vm(
+            "0x602060006000600060007311111111111111111111111111111111111111115af1\
+             602060006000600060007311111111111111111111111111111111111111115af160005100",
+        )
+        .with_world(Arc::new(world));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(2));
+        let storage =
+            vm.world.as_ref().and_then(|world| world.storage(&Address::repeat_byte(0x11)));
+        assert_eq!(storage.and_then(|s| s.storage.get(&U256::ZERO)), Some(&U256::from(2)));
+    }
+
+    #[test]
+    fn test_call_without_world_is_stubbed() {
+        // CALL(GAS, 0x11..11, 0, 0, 0, 0, 0), RETURNDATASIZE
+        let mut vm =
+            new_test_vm("0x600060006000600060007311111111111111111111111111111111111111115af13d00");
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+        assert_eq!(vm.stack.peek(1).value, U256::from(1));
+        assert!(vm.call_returndata.is_none());
+    }
+
+    #[test]
+    fn test_state_provider_snapshot() {
+        let provider = SnapshotStateProvider::from_json(
+            r#"{
+                "block": { "number": 100 },
+                "accounts": {
+                    "0x6865696d64616c6c000000000061646472657373": {
+                        "balance": "0x5",
+                        "storage": { "0x0": "0x2a" }
+                    }
+                }
+            }"#,
+        )
+        .expect("failed to parse snapshot");
+
+        // SLOAD(0), SLOAD(1), NUMBER, SELFBALANCE, GASLIMIT
+        let mut vm = new_test_vm("0x600054600154434745").with_provider(Arc::new(provider));
+        vm.execute().expect("execution failed!");
+
+        assert_eq!(vm.stack.peek(4).value, U256::from(0x2a));
+        assert_eq!(vm.stack.peek(3).value, U256::ZERO);
+        assert_eq!(vm.stack.peek(2).value, U256::from(100));
+        assert_eq!(vm.stack.peek(1).value, U256::from(5));
+        // unknown block values are still stubbed
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+    }
 }
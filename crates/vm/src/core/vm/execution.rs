@@ -1,4 +1,6 @@
-use alloy::primitives::U256;
+use std::sync::Arc;
+
+use alloy::primitives::{Address, U256};
 
 use super::super::{
     log::Log, memory::Memory, opcodes::WrappedOpcode, stack::Stack, storage::Storage,
@@ -40,16 +42,57 @@ pub struct State {
     pub gas_remaining: u128,
 
     /// The current state of the EVM stack.
-    pub stack: Stack,
+    pub stack: Arc<Stack>,
 
-    /// The current state of the EVM memory.
-    pub memory: Memory,
+    /// The current state of the EVM memory. Shared with neighbouring states until an instruction
+    /// mutates memory.
+    pub memory: Arc<Memory>,
 
-    /// The current state of the contract storage.
-    pub storage: Storage,
+    /// The current state of the contract storage. Shared with neighbouring states until an
+    /// instruction mutates storage.
+    pub storage: Arc<Storage>,
 
-    /// The events (logs) emitted so far during execution.
-    pub events: Vec<Log>,
+    /// The events (logs) emitted so far during execution. Shared with neighbouring states until
+    /// an instruction emits an event.
+    pub events: Arc<Vec<Log>>,
+
+    /// The message-call executed by this instruction in a child frame, if any.
+    pub call: Option<Arc<CallFrame>>,
+}
+
+/// [`CallFrame`] is a message-call (CALL, CALLCODE, DELEGATECALL or STATICCALL) which was executed
+/// in a child frame against code loaded into the VM's
+/// [`WorldState`](crate::core::world::WorldState).
+#[derive(Clone, Debug)]
+pub struct CallFrame {
+    /// The opcode which created this frame.
+    pub opcode: u8,
+
+    /// The address whose code was executed.
+    pub code_address: Address,
+
+    /// The calldata passed to the frame.
+    pub calldata: Vec<u8>,
+
+    /// The result of executing the frame.
+    pub result: ExecutionResult,
+
+    /// The instruction at which execution of the frame began.
+    pub entry: u128,
+
+    /// The number of instructions executed within the frame. The states themselves aren't
+    /// retained, as a single frame may execute up to `MAX_CALL_STEPS` instructions.
+    pub steps: usize,
+
+    /// The message-calls made by the frame, in the order they were executed.
+    pub calls: Vec<Arc<CallFrame>>,
+}
+
+impl CallFrame {
+    /// Returns true if the frame halted successfully (via STOP or RETURN).
+    pub fn success(&self) -> bool {
+        matches!(self.result.exitcode, 0 | 10)
+    }
 }
 
 /// [`Instruction`] is a single EVM instruction. It is returned by the [`VM::step`] function, and
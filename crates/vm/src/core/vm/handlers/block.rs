@@ -3,26 +3,55 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use alloy::primitives::U256;
 use eyre::Result;
 
-use crate::core::{constants::COINBASE_ADDRESS, opcodes::WrappedOpcode};
+use crate::core::{
+    constants::COINBASE_ADDRESS,
+    opcodes::{self, WrappedOpcode},
+};
 
 use super::super::core::VM;
 
 /// COINBASE - Get the block's beneficiary address
 pub fn coinbase(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    vm.stack.push(*COINBASE_ADDRESS, operation);
+    let coinbase = vm
+        .provider
+        .as_ref()
+        .and_then(|provider| provider.block().coinbase)
+        .map(|coinbase| VM::address_to_u256(&coinbase))
+        .unwrap_or(*COINBASE_ADDRESS);
+
+    vm.stack.push(coinbase, operation);
     Ok(())
 }
 
 /// TIMESTAMP - Get the block's timestamp
 pub fn timestamp(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let timestamp =
+        vm.provider.as_ref().and_then(|provider| provider.block().timestamp).unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+        });
+
     vm.stack.push(U256::from(timestamp), operation);
     Ok(())
 }
 
-/// Generic handler for block info opcodes that return 1
-/// (NUMBER, PREVRANDAO, GASLIMIT, CHAINID, SELFBALANCE, BASEFEE, BLOBHASH, BLOBBASEFEE)
-pub fn block_info_stub(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    vm.stack.push(U256::from(1u8), operation);
+/// Generic handler for block info opcodes
+/// (NUMBER, PREVRANDAO, GASLIMIT, CHAINID, SELFBALANCE, BASEFEE, BLOBHASH, BLOBBASEFEE). \
+/// Values are read from the state provider if possible, and are otherwise stubbed to 1.
+pub fn block_info(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let value = vm.provider.as_ref().and_then(|provider| {
+        let block = provider.block();
+        match operation.opcode {
+            opcodes::NUMBER => block.number.map(U256::from),
+            opcodes::PREVRANDAO => block.prevrandao,
+            opcodes::GASLIMIT => block.gas_limit.map(U256::from),
+            opcodes::CHAINID => block.chain_id.map(U256::from),
+            opcodes::SELFBALANCE => provider.balance(vm.address),
+            opcodes::BASEFEE => block.base_fee,
+            opcodes::BLOBBASEFEE => block.blob_base_fee,
+            _ => None,
+        }
+    });
+
+    vm.stack.push(value.unwrap_or_else(|| U256::from(1u8)), operation);
     Ok(())
 }
@@ -1,4 +1,4 @@
-use alloy::primitives::U256;
+use alloy::primitives::{keccak256, Address, U256};
 use eyre::Result;
 
 use crate::core::opcodes::WrappedOpcode;
@@ -23,8 +23,15 @@ pub fn balance(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
         vm.consume_gas(100);
     }
 
-    // balance is set to 1 wei because we won't run into div by 0 errors
-    vm.stack.push(U256::from(1), operation);
+    // unless the state provider knows the balance, it is set to 1 wei because we won't run into
+    // div by 0 errors
+    let balance = vm
+        .provider
+        .as_ref()
+        .and_then(|provider| provider.balance(Address::from_word(address.into())))
+        .unwrap_or_else(|| U256::from(1));
+
+    vm.stack.push(balance, operation);
     Ok(())
 }
 
@@ -161,7 +168,11 @@ pub fn extcodesize(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
         vm.consume_gas(100);
     }
 
-    vm.stack.push(U256::from(1), operation);
+    // use the size of the account's code if it is known
+    let size =
+        vm.account_code(&Address::from_word(address.into())).map(|code| code.len()).unwrap_or(1);
+
+    vm.stack.push(U256::from(size), operation);
     Ok(())
 }
 
@@ -172,16 +183,24 @@ pub fn extcodecopy(
 ) -> Result<()> {
     let address = vm.stack.pop()?.value;
     let dest_offset = vm.stack.pop()?.value;
-    vm.stack.pop()?;
+    let offset = vm.stack.pop()?.value;
     let size = vm.stack.pop()?.value;
 
     // Safely convert U256 to usize
     let dest_offset: usize = dest_offset.try_into().unwrap_or(0);
-    let mut size: usize = size.try_into().unwrap_or(256);
-    size = size.max(256);
+    let offset: usize = offset.try_into().unwrap_or(usize::MAX);
+    let size: usize = size.try_into().unwrap_or(256);
 
-    let mut value = Vec::with_capacity(size);
-    value.fill(0xff);
+    // copy the account's code if it is known
+    let (size, value) = match vm.account_code(&Address::from_word(address.into())) {
+        Some(code) => (size, VM::safe_copy_data(&code, offset, size)),
+        None => {
+            let size = size.max(256);
+            let mut value = Vec::with_capacity(size);
+            value.fill(0xff);
+            (size, value)
+        }
+    };
 
     // consume dynamic gas
     let minimum_word_size = size.div_ceil(32) as u128;
@@ -206,7 +225,8 @@ pub fn extcodecopy(
 
 /// RETURNDATASIZE - Get size of output data from the previous call
 pub fn returndatasize(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    vm.stack.push(U256::from(1u8), operation);
+    let size = vm.call_returndata.as_ref().map(|returndata| returndata.len()).unwrap_or(1);
+    vm.stack.push(U256::from(size), operation);
     Ok(())
 }
 
@@ -216,15 +236,23 @@ pub fn returndatacopy(
     #[cfg(feature = "experimental")] operation: WrappedOpcode,
 ) -> Result<()> {
     let dest_offset = vm.stack.pop()?.value;
-    vm.stack.pop()?;
+    let offset = vm.stack.pop()?.value;
     let size = vm.stack.pop()?.value;
 
     // Safely convert U256 to usize
     let dest_offset: usize = dest_offset.try_into().unwrap_or(0);
+    let offset: usize = offset.try_into().unwrap_or(usize::MAX);
     let size: usize = size.try_into().unwrap_or(256);
 
-    let mut value = Vec::with_capacity(size);
-    value.fill(0xff);
+    // copy the returndata of the last executed message-call, if there was one
+    let value = match &vm.call_returndata {
+        Some(returndata) => VM::safe_copy_data(returndata, offset, size),
+        None => {
+            let mut value = Vec::with_capacity(size);
+            value.fill(0xff);
+            value
+        }
+    };
 
     // consume dynamic gas
     let minimum_word_size = size.div_ceil(32) as u128;
@@ -253,13 +281,25 @@ pub fn extcodehash(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
         vm.consume_gas(100);
     }
 
-    vm.stack.push(U256::ZERO, operation);
+    // hash the account's code if it is known
+    let hash = vm
+        .account_code(&Address::from_word(address.into()))
+        .map(|code| U256::from_be_bytes(keccak256(code).0))
+        .unwrap_or(U256::ZERO);
+
+    vm.stack.push(hash, operation);
     Ok(())
 }
 
 /// BLOCKHASH - Get the hash of one of the 256 most recent complete blocks
 pub fn blockhash(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    vm.stack.pop()?;
-    vm.stack.push(U256::ZERO, operation);
+    let number = vm.stack.pop()?.value;
+
+    let hash = match (&vm.provider, u64::try_from(number)) {
+        (Some(provider), Ok(number)) => provider.block_hash(number).unwrap_or(U256::ZERO),
+        _ => U256::ZERO,
+    };
+
+    vm.stack.push(hash, operation);
     Ok(())
 }
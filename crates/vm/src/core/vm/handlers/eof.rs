@@ -0,0 +1,283 @@
+use alloy::primitives::U256;
+use eyre::{OptionExt, Result};
+use std::sync::Arc;
+
+use crate::core::{
+    constants::CREATE2_ADDRESS,
+    eof::{EofContainer, RETURN_STACK_LIMIT},
+    opcodes::{WrappedInput, WrappedOpcode, EOFCREATE, EXTCALL, EXTDELEGATECALL, EXTSTATICCALL},
+};
+
+use super::{super::core::VM, system::message_call};
+
+/// Reads `size` immediate bytes following the current opcode, advancing the instruction pointer
+/// past them.
+fn read_immediate(vm: &mut VM, size: usize) -> Result<Vec<u8>> {
+    let start = (vm.instruction - 1) as usize;
+    let bytes = vm
+        .bytecode
+        .get(start..start + size)
+        .ok_or_eyre(format!("truncated immediate at offset {start}"))?
+        .to_vec();
+    vm.instruction += size as u128;
+    Ok(bytes)
+}
+
+/// Returns the EOF container being executed.
+fn container(vm: &VM) -> Result<Arc<EofContainer>> {
+    vm.eof.clone().ok_or_eyre("EOF opcode executed outside of an EOF container")
+}
+
+/// Moves the instruction pointer by a signed offset relative to the current instruction pointer.
+fn relative_jump(vm: &mut VM, relative: i16) {
+    vm.instruction = (vm.instruction as i128 + relative as i128).max(0) as u128;
+}
+
+/// RJUMP - Jump to a relative offset
+pub fn rjump(vm: &mut VM) -> Result<()> {
+    let immediate = read_immediate(vm, 2)?;
+    relative_jump(vm, i16::from_be_bytes([immediate[0], immediate[1]]));
+    Ok(())
+}
+
+/// RJUMPI - Conditionally jump to a relative offset
+pub fn rjumpi(vm: &mut VM) -> Result<()> {
+    let immediate = read_immediate(vm, 2)?;
+    let condition = vm.stack.pop()?.value;
+
+    if !condition.is_zero() {
+        relative_jump(vm, i16::from_be_bytes([immediate[0], immediate[1]]));
+    }
+    Ok(())
+}
+
+/// RJUMPV - Jump to one of a table of relative offsets, falling through if out of range
+pub fn rjumpv(vm: &mut VM) -> Result<()> {
+    let max_index = read_immediate(vm, 1)?[0] as usize;
+    let table = read_immediate(vm, 2 * (max_index + 1))?;
+    let case = vm.stack.pop()?.value;
+
+    if case <= U256::from(max_index) {
+        let case: usize = case.try_into().unwrap_or(0);
+        relative_jump(vm, i16::from_be_bytes([table[2 * case], table[2 * case + 1]]));
+    }
+    Ok(())
+}
+
+/// CALLF - Call a code section, pushing the return location onto the return stack
+pub fn callf(vm: &mut VM) -> Result<()> {
+    let immediate = read_immediate(vm, 2)?;
+    let section = u16::from_be_bytes([immediate[0], immediate[1]]) as usize;
+    let container = container(vm)?;
+    let code = container
+        .code_sections
+        .get(section)
+        .ok_or_eyre(format!("CALLF to missing code section {section}"))?;
+
+    if vm.return_stack.len() >= RETURN_STACK_LIMIT {
+        vm.exit(1, Vec::new());
+        return Ok(());
+    }
+
+    vm.return_stack.push((vm.code_section, vm.instruction));
+    vm.code_section = section;
+    vm.instruction = code.start as u128 + 1;
+    Ok(())
+}
+
+/// RETF - Return from a code section to the location on top of the return stack
+pub fn retf(vm: &mut VM) -> Result<()> {
+    match vm.return_stack.pop() {
+        Some((section, instruction)) => {
+            vm.code_section = section;
+            vm.instruction = instruction;
+        }
+        None => vm.exit(1, Vec::new()),
+    }
+    Ok(())
+}
+
+/// JUMPF - Jump to a code section without pushing onto the return stack
+pub fn jumpf(vm: &mut VM) -> Result<()> {
+    let immediate = read_immediate(vm, 2)?;
+    let section = u16::from_be_bytes([immediate[0], immediate[1]]) as usize;
+    let container = container(vm)?;
+    let code = container
+        .code_sections
+        .get(section)
+        .ok_or_eyre(format!("JUMPF to missing code section {section}"))?;
+
+    vm.code_section = section;
+    vm.instruction = code.start as u128 + 1;
+    Ok(())
+}
+
+/// DATALOAD - Load a word from the data section
+pub fn dataload(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let offset = vm.stack.pop()?.value;
+    let offset: usize = offset.try_into().unwrap_or(usize::MAX);
+    let container = container(vm)?;
+
+    let value = VM::safe_copy_data(container.data(), offset, 32);
+    vm.stack.push(U256::from_be_slice(&value), operation);
+    Ok(())
+}
+
+/// DATALOADN - Load a word from the data section at an immediate offset
+pub fn dataloadn(vm: &mut VM, mut operation: WrappedOpcode) -> Result<()> {
+    let immediate = read_immediate(vm, 2)?;
+    let offset = u16::from_be_bytes([immediate[0], immediate[1]]) as usize;
+    let container = container(vm)?;
+
+    // record the offset so lexers can render the load
+    operation.inputs = vec![WrappedInput::Raw(U256::from(offset))];
+
+    let value = VM::safe_copy_data(container.data(), offset, 32);
+    vm.stack.push(U256::from_be_slice(&value), operation);
+    Ok(())
+}
+
+/// DATASIZE - Get the size of the data section
+pub fn datasize(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let container = container(vm)?;
+    vm.stack.push(U256::from(container.data().len()), operation);
+    Ok(())
+}
+
+/// DATACOPY - Copy a slice of the data section to memory
+pub fn datacopy(
+    vm: &mut VM,
+    #[cfg(feature = "experimental")] operation: WrappedOpcode,
+) -> Result<()> {
+    let dest_offset = vm.stack.pop()?.value;
+    let offset = vm.stack.pop()?.value;
+    let size = vm.stack.pop()?.value;
+    let container = container(vm)?;
+
+    let dest_offset: usize = dest_offset.try_into().unwrap_or(usize::MAX);
+    let offset: usize = offset.try_into().unwrap_or(usize::MAX);
+    let data_size = container.data().len();
+    let size: usize = size.try_into().unwrap_or(data_size).min(data_size);
+
+    let value = VM::safe_copy_data(container.data(), offset, size);
+
+    // consume dynamic gas
+    let minimum_word_size = size.div_ceil(32) as u128;
+    let gas_cost = 3 * minimum_word_size + vm.memory.expansion_cost(dest_offset, size);
+    vm.consume_gas(gas_cost);
+
+    vm.memory.store_with_opcode(
+        dest_offset,
+        size,
+        &value,
+        #[cfg(feature = "experimental")]
+        operation,
+    );
+    Ok(())
+}
+
+/// DUPN - Duplicate the stack item at an immediate depth
+pub fn dupn(vm: &mut VM) -> Result<()> {
+    let index = read_immediate(vm, 1)?[0] as usize;
+    vm.stack.dup(index + 1);
+    Ok(())
+}
+
+/// SWAPN - Exchange the top stack item with the item at an immediate depth
+pub fn swapn(vm: &mut VM) -> Result<()> {
+    let index = read_immediate(vm, 1)?[0] as usize;
+    vm.stack.swap(index + 1);
+    Ok(())
+}
+
+/// EXCHANGE - Exchange two stack items below the top of the stack
+pub fn exchange(vm: &mut VM) -> Result<()> {
+    let immediate = read_immediate(vm, 1)?[0] as usize;
+    let n = (immediate >> 4) + 1;
+    let m = (immediate & 0x0f) + 1;
+    vm.stack.exchange(n, n + m);
+    Ok(())
+}
+
+/// EOFCREATE - Create a new account from one of the container's subcontainers
+pub fn eofcreate(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    vm.ensure_mutable(EOFCREATE)?;
+    read_immediate(vm, 1)?;
+    vm.stack.pop_n(4)?;
+    vm.stack.push(*CREATE2_ADDRESS, operation);
+    Ok(())
+}
+
+/// RETURNCONTRACT - Halt execution, returning a subcontainer with appended auxiliary data
+pub fn returncontract(vm: &mut VM) -> Result<()> {
+    let index = read_immediate(vm, 1)?[0] as usize;
+    let offset = vm.stack.pop()?.value;
+    let size = vm.stack.pop()?.value;
+    let container = container(vm)?;
+
+    // Safely convert U256 to usize
+    let offset: usize = offset.try_into().unwrap_or(usize::MAX);
+    let size: usize = size.try_into().unwrap_or(usize::MAX);
+
+    // consume dynamic gas
+    let gas_cost = vm.memory.expansion_cost(offset, size);
+    vm.consume_gas(gas_cost);
+
+    let mut returndata = container
+        .container(index)
+        .ok_or_eyre(format!("RETURNCONTRACT of missing container {index}"))?
+        .to_vec();
+    returndata.extend(vm.memory.read(offset, size));
+
+    vm.exit(0, returndata);
+    Ok(())
+}
+
+/// RETURNDATALOAD - Load a word from the output data of the previous call
+pub fn returndataload(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let offset: usize = vm.stack.pop()?.value.try_into().unwrap_or(usize::MAX);
+
+    // the returndata of a stubbed call is unknown, so it's read as 0. otherwise, reads past the
+    // end of the returndata are zero-padded
+    let mut word = [0u8; 32];
+    if let Some(returndata) = &vm.call_returndata {
+        let available = returndata.get(offset..).unwrap_or_default();
+        let size = available.len().min(32);
+        word[..size].copy_from_slice(&available[..size]);
+    }
+
+    vm.stack.push(U256::from_be_bytes(word), operation);
+    Ok(())
+}
+
+/// Pops the target address and `(offset, size)` input range of an EXT*CALL from the stack.
+fn pop_ext_call(vm: &mut VM) -> Result<(U256, (usize, usize))> {
+    let address = vm.stack.pop()?.value;
+    let offset = vm.stack.pop()?.value;
+    let size = vm.stack.pop()?.value;
+
+    Ok((address, (offset.try_into().unwrap_or(usize::MAX), size.try_into().unwrap_or(usize::MAX))))
+}
+
+/// EXTCALL - Message-call into an account
+pub fn extcall(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let (address, args) = pop_ext_call(vm)?;
+    let value = vm.stack.pop()?.value;
+
+    // EXT*CALLs forward all but one 64th of the remaining gas, and have no output range
+    message_call(vm, EXTCALL, U256::MAX, address, value, args, (0, 0), operation)
+}
+
+/// EXTDELEGATECALL - Message-call into this account with an alternative account's code
+pub fn extdelegatecall(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let (address, args) = pop_ext_call(vm)?;
+
+    message_call(vm, EXTDELEGATECALL, U256::MAX, address, U256::ZERO, args, (0, 0), operation)
+}
+
+/// EXTSTATICCALL - Static message-call into an account
+pub fn extstaticcall(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let (address, args) = pop_ext_call(vm)?;
+
+    message_call(vm, EXTSTATICCALL, U256::MAX, address, U256::ZERO, args, (0, 0), operation)
+}
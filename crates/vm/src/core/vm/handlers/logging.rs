@@ -1,11 +1,12 @@
 use eyre::Result;
 
-use crate::core::log::Log;
+use crate::core::{log::Log, opcodes::LOG0};
 
 use super::super::core::VM;
 
 /// LOG0-LOG4 - Append log record with N topics
 pub fn log_n(vm: &mut VM, topic_count: u8) -> Result<()> {
+    vm.ensure_mutable(LOG0 + topic_count)?;
     let offset = vm.stack.pop()?.value;
     let size = vm.stack.pop()?.value;
     let topics = vm.stack.pop_n(topic_count as usize)?.iter().map(|x| x.value).collect();
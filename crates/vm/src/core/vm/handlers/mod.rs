@@ -20,6 +20,9 @@ pub mod control;
 /// Cryptographic operations: SHA3
 pub mod crypto;
 
+/// EOF operations: RJUMP, RJUMPI, RJUMPV, CALLF, RETF, JUMPF, DATALOAD, DUPN, EOFCREATE, etc.
+pub mod eof;
+
 /// Environment information: ADDRESS, BALANCE, CALLER, CALLVALUE, CALLDATALOAD, etc.
 pub mod environment;
 
@@ -1,7 +1,7 @@
 use alloy::primitives::U256;
 use eyre::Result;
 
-use crate::core::opcodes::WrappedOpcode;
+use crate::core::opcodes::{WrappedOpcode, SSTORE, TSTORE};
 
 use super::super::core::VM;
 
@@ -13,12 +13,22 @@ pub fn sload(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
     let gas_cost = vm.storage.access_cost(key);
     vm.consume_gas(gas_cost);
 
-    vm.stack.push(U256::from(vm.storage.load(key)), operation);
+    // slots which haven't been written to are read from the state provider, if there is one
+    let value = vm.storage.load(key);
+    let value = match &vm.provider {
+        Some(provider) if !vm.storage.storage.contains_key(&key) => {
+            provider.storage(vm.address, key).unwrap_or(value)
+        }
+        _ => value,
+    };
+
+    vm.stack.push(value, operation);
     Ok(())
 }
 
 /// SSTORE - Save word to storage
 pub fn sstore(vm: &mut VM) -> Result<()> {
+    vm.ensure_mutable(SSTORE)?;
     let key = vm.stack.pop()?.value;
     let value = vm.stack.pop()?.value;
 
@@ -39,6 +49,7 @@ pub fn tload(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
 
 /// TSTORE - Save word to transient storage
 pub fn tstore(vm: &mut VM) -> Result<()> {
+    vm.ensure_mutable(TSTORE)?;
     let key = vm.stack.pop()?.value;
     let value = vm.stack.pop()?.value;
     vm.storage.tstore(key, value);
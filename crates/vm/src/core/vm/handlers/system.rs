@@ -1,25 +1,84 @@
-use alloy::primitives::U256;
+use alloy::primitives::{Address, U256};
 use eyre::Result;
+use std::sync::Arc;
 
 use crate::core::{
     constants::{CREATE2_ADDRESS, CREATE_ADDRESS},
-    opcodes::WrappedOpcode,
+    opcodes::{
+        WrappedOpcode, CALL, CALLCODE, CREATE, CREATE2, DELEGATECALL, EXTCALL, EXTDELEGATECALL,
+        EXTSTATICCALL, STATICCALL,
+    },
 };
 
-use super::super::core::VM;
+use super::super::{
+    core::VM,
+    execution::{CallFrame, ExecutionResult},
+};
 
-/// CREATE - Create a new account with associated code
-pub fn create(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    vm.stack.pop_n(3)?;
-    vm.stack.push(*CREATE_ADDRESS, operation);
-    Ok(())
+/// The maximum depth of nested message-calls.
+const MAX_CALL_DEPTH: usize = 1024;
+
+/// The maximum number of instructions executed within a single child frame. Symbolic execution
+/// runs with an effectively unlimited gas limit, so this bounds calls into looping code.
+const MAX_CALL_STEPS: usize = 100_000;
+
+/// Pops an `(offset, size)` memory range from the stack.
+fn pop_range(vm: &mut VM) -> Result<(usize, usize)> {
+    let offset = vm.stack.pop()?.value;
+    let size = vm.stack.pop()?.value;
+
+    // Safely convert U256 to usize
+    Ok((offset.try_into().unwrap_or(usize::MAX), size.try_into().unwrap_or(usize::MAX)))
 }
 
-/// CALL - Message-call into an account
-pub fn call(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
-    let address = vm.stack.pop()?.value;
-    vm.stack.pop_n(6)?;
+/// The status of a message-call, as pushed by the calling opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CallStatus {
+    /// The call halted successfully, or was stubbed
+    Success,
+    /// The call reverted
+    Revert,
+    /// The call failed without reverting, e.g. by exceeding the call depth
+    Failure,
+}
 
+/// Pushes the status of a message-call made by `opcode`. Legacy calls push 1 on success and 0
+/// otherwise, while EXT*CALLs push 0 on success, 1 on revert and 2 on failure.
+fn push_status(vm: &mut VM, opcode: u8, status: CallStatus, operation: WrappedOpcode) {
+    match opcode {
+        EXTCALL | EXTDELEGATECALL | EXTSTATICCALL => {
+            let status = match status {
+                CallStatus::Success => 0u8,
+                CallStatus::Revert => 1,
+                CallStatus::Failure => 2,
+            };
+            vm.stack.push(U256::from(status), operation);
+        }
+        _ => vm.push_boolean(status == CallStatus::Success, operation),
+    }
+}
+
+/// Performs a message-call. If the target's code is known to the VM's
+/// [`WorldState`](crate::core::world::WorldState) or state provider, the call is executed in a
+/// child frame and its returndata is copied into memory. Otherwise, the call is stubbed and always
+/// succeeds.
+///
+/// A CALL into an account of the world state runs against the storage it was left with by earlier
+/// calls, and its writes are kept in the world state if it succeeds.
+///
+/// EXTCALL, EXTDELEGATECALL and EXTSTATICCALL are performed as CALL, DELEGATECALL and STATICCALL
+/// respectively, without an output memory range.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn message_call(
+    vm: &mut VM,
+    opcode: u8,
+    gas: U256,
+    address: U256,
+    value: U256,
+    args: (usize, usize),
+    ret: (usize, usize),
+    operation: WrappedOpcode,
+) -> Result<()> {
     // consume dynamic gas
     if !vm.address_access_set.contains(&address) {
         vm.consume_gas(2600);
@@ -28,25 +87,193 @@ pub fn call(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
         vm.consume_gas(100);
     }
 
-    vm.stack.push(U256::from(1u8), operation);
+    let kind = match opcode {
+        EXTCALL => CALL,
+        EXTDELEGATECALL => DELEGATECALL,
+        EXTSTATICCALL => STATICCALL,
+        opcode => opcode,
+    };
+
+    // transferring value is a state modification
+    if kind == CALL && !value.is_zero() {
+        vm.ensure_mutable(opcode)?;
+    }
+
+    let code_address = Address::from_word(address.into());
+    let code = match vm.account_code(&code_address) {
+        Some(code) => code,
+        None => {
+            // an account the provider confirms has no code returns nothing, while a stubbed
+            // call's returndata is unknown
+            vm.call_returndata = vm
+                .provider
+                .as_ref()
+                .and_then(|provider| provider.code(code_address))
+                .filter(|code| code.is_empty());
+            push_status(vm, opcode, CallStatus::Success, operation);
+            return Ok(());
+        }
+    };
+
+    let gas_cost =
+        vm.memory.expansion_cost(args.0, args.1) + vm.memory.expansion_cost(ret.0, ret.1);
+    vm.consume_gas(gas_cost);
+
+    // the call fails without executing if the call stack is too deep
+    if vm.depth >= MAX_CALL_DEPTH {
+        vm.call_returndata = Some(Vec::new());
+        push_status(vm, opcode, CallStatus::Failure, operation);
+        return Ok(());
+    }
+
+    // CALLCODE and DELEGATECALL execute the callee's code in the context of this account
+    let calldata = vm.memory.read(args.0, args.1);
+    let value: u128 = value.try_into().unwrap_or(u128::MAX);
+    let (address, caller, value, storage) = match kind {
+        CALLCODE => (vm.address, vm.address, value, vm.storage.clone()),
+        DELEGATECALL => (vm.address, vm.caller, vm.value, vm.storage.clone()),
+        _ => (
+            code_address,
+            vm.address,
+            value,
+            vm.world
+                .as_ref()
+                .and_then(|world| world.storage(&code_address).cloned())
+                .unwrap_or_default(),
+        ),
+    };
+
+    // all but one 64th of the remaining gas may be forwarded to the child frame
+    let gas: u128 = gas.try_into().unwrap_or(u128::MAX);
+    let gas = gas.min(vm.gas_remaining - vm.gas_remaining / 64);
+
+    let mut child =
+        VM::new(&code, &calldata, address, vm.origin, caller, value, 0).with_hardfork(vm.hardfork);
+    child.gas_remaining = gas;
+    child.gas_used = 0;
+    child.storage = storage;
+    child.world = vm.world.clone();
+    child.provider = vm.provider.clone();
+    child.solver = vm.solver.clone();
+    child.depth = vm.depth + 1;
+    child.is_static = vm.is_static || kind == STATICCALL;
+
+    // only the nested message-calls of each state are kept, as a frame may execute up to
+    // MAX_CALL_STEPS instructions
+    let entry = child.instruction;
+    let mut steps = 0;
+    let mut calls = Vec::new();
+    while child.bytecode.len() >= child.instruction as usize {
+        if steps >= MAX_CALL_STEPS {
+            child.exit(9, Vec::new());
+            break;
+        }
+
+        steps += 1;
+        match child.step() {
+            Ok(state) => calls.extend(state.call),
+            Err(_) => {
+                child.exit(1, Vec::new());
+                break;
+            }
+        }
+
+        if child.exitcode != 255 || !child.returndata.is_empty() {
+            break;
+        }
+    }
+
+    // running off the end of the code is an implicit STOP
+    if child.exitcode == 255 {
+        child.exit(10, Vec::new());
+    }
+
+    vm.consume_gas(child.gas_used);
+
+    let frame = CallFrame {
+        opcode,
+        code_address,
+        calldata,
+        result: ExecutionResult {
+            gas_used: child.gas_used,
+            gas_remaining: child.gas_remaining,
+            returndata: child.returndata.clone(),
+            exitcode: child.exitcode,
+            events: child.events.clone(),
+            instruction: child.instruction,
+        },
+        entry,
+        steps,
+        calls,
+    };
+    let success = frame.success();
+    let status = match frame.result.exitcode {
+        _ if success => CallStatus::Success,
+        1 => CallStatus::Revert,
+        _ => CallStatus::Failure,
+    };
+
+    // storage changes persist if the call succeeded, either in this account's context or in the
+    // world state, along with those made by any calls nested in it
+    if success {
+        vm.world = child.world;
+        match kind {
+            CALLCODE | DELEGATECALL => vm.storage = child.storage,
+            CALL => {
+                Arc::make_mut(vm.world.get_or_insert_with(Default::default))
+                    .set_storage(code_address, child.storage);
+            }
+            _ => {}
+        }
+    }
+    if success && kind != STATICCALL {
+        vm.events.extend(child.events);
+    }
+
+    // copy the returndata into the output memory range
+    let size = ret.1.min(child.returndata.len());
+    vm.memory.store_with_opcode(
+        ret.0,
+        size,
+        &child.returndata[..size],
+        #[cfg(feature = "experimental")]
+        operation.clone(),
+    );
+
+    vm.call_returndata = Some(child.returndata);
+    vm.last_call = Some(Arc::new(frame));
+    push_status(vm, opcode, status, operation);
     Ok(())
 }
 
+/// CREATE - Create a new account with associated code
+pub fn create(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    vm.ensure_mutable(CREATE)?;
+    vm.stack.pop_n(3)?;
+    vm.stack.push(*CREATE_ADDRESS, operation);
+    Ok(())
+}
+
+/// CALL - Message-call into an account
+pub fn call(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let gas = vm.stack.pop()?.value;
+    let address = vm.stack.pop()?.value;
+    let value = vm.stack.pop()?.value;
+    let args = pop_range(vm)?;
+    let ret = pop_range(vm)?;
+
+    message_call(vm, CALL, gas, address, value, args, ret, operation)
+}
+
 /// CALLCODE - Message-call into this account with alternative account's code
 pub fn callcode(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let gas = vm.stack.pop()?.value;
     let address = vm.stack.pop()?.value;
-    vm.stack.pop_n(6)?;
+    let value = vm.stack.pop()?.value;
+    let args = pop_range(vm)?;
+    let ret = pop_range(vm)?;
 
-    // consume dynamic gas
-    if !vm.address_access_set.contains(&address) {
-        vm.consume_gas(2600);
-        vm.address_access_set.insert(address);
-    } else {
-        vm.consume_gas(100);
-    }
-
-    vm.stack.push(U256::from(1u8), operation);
-    Ok(())
+    message_call(vm, CALLCODE, gas, address, value, args, ret, operation)
 }
 
 /// RETURN - Halt execution returning output data
@@ -68,40 +295,27 @@ pub fn op_return(vm: &mut VM) -> Result<()> {
 
 /// DELEGATECALL - Message-call into this account with an alternative account's code
 pub fn delegatecall(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let gas = vm.stack.pop()?.value;
     let address = vm.stack.pop()?.value;
-    vm.stack.pop_n(5)?;
-
-    // consume dynamic gas
-    if !vm.address_access_set.contains(&address) {
-        vm.consume_gas(2600);
-        vm.address_access_set.insert(address);
-    } else {
-        vm.consume_gas(100);
-    }
+    let args = pop_range(vm)?;
+    let ret = pop_range(vm)?;
 
-    vm.stack.push(U256::from(1u8), operation);
-    Ok(())
+    message_call(vm, DELEGATECALL, gas, address, U256::ZERO, args, ret, operation)
 }
 
 /// STATICCALL - Static message-call into an account
 pub fn staticcall(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    let gas = vm.stack.pop()?.value;
     let address = vm.stack.pop()?.value;
-    vm.stack.pop_n(5)?;
-
-    // consume dynamic gas
-    if !vm.address_access_set.contains(&address) {
-        vm.consume_gas(2600);
-        vm.address_access_set.insert(address);
-    } else {
-        vm.consume_gas(100);
-    }
+    let args = pop_range(vm)?;
+    let ret = pop_range(vm)?;
 
-    vm.stack.push(U256::from(1u8), operation);
-    Ok(())
+    message_call(vm, STATICCALL, gas, address, U256::ZERO, args, ret, operation)
 }
 
 /// CREATE2 - Create a new account with associated code at a predictable address
 pub fn create2(vm: &mut VM, operation: WrappedOpcode) -> Result<()> {
+    vm.ensure_mutable(CREATE2)?;
     vm.stack.pop_n(4)?;
     vm.stack.push(*CREATE2_ADDRESS, operation);
     Ok(())
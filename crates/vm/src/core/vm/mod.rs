@@ -5,9 +5,10 @@
 
 mod core;
 mod execution;
+mod snapshot;
 
 /// Opcode handlers organized by category.
 pub mod handlers;
 
 pub use self::core::VM;
-pub use execution::{ExecutionResult, Instruction, State};
+pub use execution::{CallFrame, ExecutionResult, Instruction, State};
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::core::{
+    log::Log,
+    memory::Memory,
+    opcodes::{
+        CALL, CALLCODE, CALLDATACOPY, CODECOPY, CREATE, CREATE2, DATACOPY, DELEGATECALL, EOFCREATE,
+        EXTCALL, EXTCODECOPY, EXTDELEGATECALL, EXTSTATICCALL, JUMPDEST, LOG0, LOG4, MCOPY, MSTORE,
+        MSTORE8, RETURNDATACOPY, SLOAD, SSTORE, STATICCALL, STOP, TLOAD, TSTORE,
+    },
+    stack::Stack,
+    storage::Storage,
+};
+
+/// Opcodes which execute a child frame, and may therefore write to memory, storage and events.
+const MESSAGE_CALL_OPCODES: [u8; 10] = [
+    CALL,
+    CALLCODE,
+    DELEGATECALL,
+    STATICCALL,
+    CREATE,
+    CREATE2,
+    EXTCALL,
+    EXTDELEGATECALL,
+    EXTSTATICCALL,
+    EOFCREATE,
+];
+
+/// Opcodes which write to memory.
+const MEMORY_WRITE_OPCODES: [u8; 8] =
+    [MSTORE, MSTORE8, MCOPY, CALLDATACOPY, CODECOPY, EXTCODECOPY, RETURNDATACOPY, DATACOPY];
+
+/// Copy-on-write snapshots of a [`VM`](super::VM)'s stack, memory, storage and events.
+///
+/// Every [`State`](super::State) returned by [`VM::step`](super::VM::step) holds a snapshot of
+/// each. Most instructions only touch the stack, so rather than deep-cloning memory, storage and
+/// events on every step, consecutive states share one snapshot until an instruction which may
+/// mutate it is executed. The stack is retaken after almost every instruction, but its snapshots
+/// share their frames with one another (see [`Frames`](crate::core::stack::Frames)), so each only
+/// costs the frames pushed since the last.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Snapshots {
+    stack: Option<Arc<Stack>>,
+    memory: Option<Arc<Memory>>,
+    storage: Option<Arc<Storage>>,
+    events: Option<Arc<Vec<Log>>>,
+}
+
+impl Snapshots {
+    /// Drops every snapshot, so each is taken again on the next step.
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Drops the snapshots of everything the given opcode may have mutated.
+    pub(crate) fn invalidate(&mut self, opcode: u8) {
+        let message_call = MESSAGE_CALL_OPCODES.contains(&opcode);
+
+        if !matches!(opcode, JUMPDEST | STOP) {
+            self.stack = None;
+        }
+        if message_call || MEMORY_WRITE_OPCODES.contains(&opcode) {
+            self.memory = None;
+        }
+
+        // SLOAD and TLOAD warm the accessed slot, and SLOAD may cache a value from the provider
+        if message_call || matches!(opcode, SLOAD | SSTORE | TLOAD | TSTORE) {
+            self.storage = None;
+        }
+        if message_call || (LOG0..=LOG4).contains(&opcode) {
+            self.events = None;
+        }
+    }
+
+    /// Returns the snapshot of `stack`, taking a new one if it was invalidated.
+    pub(crate) fn stack(&mut self, stack: &Stack) -> Arc<Stack> {
+        snapshot(&mut self.stack, stack)
+    }
+
+    /// Returns the snapshot of `memory`, taking a new one if it was invalidated.
+    pub(crate) fn memory(&mut self, memory: &Memory) -> Arc<Memory> {
+        snapshot(&mut self.memory, memory)
+    }
+
+    /// Returns the snapshot of `storage`, taking a new one if it was invalidated.
+    pub(crate) fn storage(&mut self, storage: &Storage) -> Arc<Storage> {
+        snapshot(&mut self.storage, storage)
+    }
+
+    /// Returns the snapshot of `events`, taking a new one if it was invalidated.
+    pub(crate) fn events(&mut self, events: &[Log]) -> Arc<Vec<Log>> {
+        self.events.get_or_insert_with(|| Arc::new(events.to_vec())).clone()
+    }
+}
+
+fn snapshot<T: Clone>(slot: &mut Option<Arc<T>>, value: &T) -> Arc<T> {
+    slot.get_or_insert_with(|| Arc::new(value.clone())).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+
+    use crate::core::opcodes::{WrappedOpcode, ADD};
+
+    use super::*;
+
+    #[test]
+    fn test_snapshots_are_shared_until_invalidated() {
+        let mut snapshots = Snapshots::default();
+        let mut memory = Memory::new();
+
+        let first = snapshots.memory(&memory);
+        snapshots.invalidate(ADD);
+        let second = snapshots.memory(&memory);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        memory.store(0, 32, &[1u8; 32]);
+        snapshots.invalidate(MSTORE);
+        let third = snapshots.memory(&memory);
+        assert!(!Arc::ptr_eq(&second, &third));
+        assert_eq!(third.read(0, 32), vec![1u8; 32]);
+        assert_eq!(first.read(0, 32), vec![0u8; 32]);
+    }
+
+    #[test]
+    fn test_stack_snapshot_invalidated_by_stack_operations() {
+        let mut snapshots = Snapshots::default();
+        let mut stack = Stack::new();
+
+        let first = snapshots.stack(&stack);
+        snapshots.invalidate(JUMPDEST);
+        assert!(Arc::ptr_eq(&first, &snapshots.stack(&stack)));
+
+        stack.push(U256::from(1), WrappedOpcode::default());
+        snapshots.invalidate(ADD);
+        assert_eq!(snapshots.stack(&stack).size(), 1);
+        assert_eq!(first.size(), 0);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut snapshots = Snapshots::default();
+        let storage = Storage::new();
+
+        let first = snapshots.storage(&storage);
+        snapshots.clear();
+        assert!(!Arc::ptr_eq(&first, &snapshots.storage(&storage)));
+    }
+}
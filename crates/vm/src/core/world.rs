@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use alloy::primitives::{keccak256, Address, B256};
+use eyre::Result;
+use hashbrown::HashMap;
+use heimdall_common::ether::rpc::get_code;
+
+use crate::core::storage::Storage;
+
+/// The [`WorldState`] struct maps account addresses to their deployed bytecode, and to the storage
+/// written by message-calls into them. \
+/// \
+/// When a [`VM`](crate::core::vm::VM) holds a world state, message-calls into any of its accounts
+/// are executed in a child frame rather than stubbed.
+#[derive(Clone, Debug, Default)]
+pub struct WorldState {
+    /// The deployed bytecode of each known account.
+    code: HashMap<Address, Arc<Vec<u8>>>,
+    /// The storage of each account which has been written to by a message-call.
+    storage: HashMap<Address, Storage>,
+}
+
+impl WorldState {
+    /// Creates a new, empty [`WorldState`].
+    ///
+    /// ```
+    /// use heimdall_vm::core::world::WorldState;
+    ///
+    /// let world = WorldState::new();
+    /// assert!(world.is_empty());
+    /// ```
+    pub fn new() -> WorldState {
+        WorldState { code: HashMap::new(), storage: HashMap::new() }
+    }
+
+    /// Sets the bytecode deployed at the given address, replacing any existing code.
+    ///
+    /// ```
+    /// use heimdall_vm::core::world::WorldState;
+    /// use alloy::primitives::Address;
+    ///
+    /// let mut world = WorldState::new();
+    /// world.insert_code(Address::repeat_byte(0x11), vec![0x00]);
+    ///
+    /// assert_eq!(world.code(&Address::repeat_byte(0x11)), Some(&[0x00][..]));
+    /// ```
+    pub fn insert_code(&mut self, address: Address, code: Vec<u8>) {
+        self.code.insert(address, Arc::new(code));
+    }
+
+    /// Fetches the bytecode deployed at the given address from the RPC provider and adds it to the
+    /// world state.
+    pub async fn fetch_code(&mut self, address: Address, rpc_url: &str) -> Result<()> {
+        let code = get_code(address, rpc_url).await?;
+        self.insert_code(address, code);
+        Ok(())
+    }
+
+    /// Returns the bytecode deployed at the given address, if it is known and non-empty.
+    pub fn code(&self, address: &Address) -> Option<&[u8]> {
+        self.code.get(address).map(|code| code.as_slice()).filter(|code| !code.is_empty())
+    }
+
+    /// Returns the storage of the given account, if a message-call has written to it.
+    pub fn storage(&self, address: &Address) -> Option<&Storage> {
+        self.storage.get(address)
+    }
+
+    /// Sets the storage of the given account, replacing any existing storage.
+    ///
+    /// ```
+    /// use heimdall_vm::core::{storage::Storage, world::WorldState};
+    /// use alloy::primitives::{Address, U256};
+    ///
+    /// let mut world = WorldState::new();
+    /// let mut storage = Storage::new();
+    /// storage.storage.insert(U256::from(1), U256::from(2));
+    /// world.set_storage(Address::repeat_byte(0x11), storage);
+    ///
+    /// assert_eq!(
+    ///     world.storage(&Address::repeat_byte(0x11)).and_then(|s| s.storage.get(&U256::from(1))),
+    ///     Some(&U256::from(2))
+    /// );
+    /// ```
+    pub fn set_storage(&mut self, address: Address, storage: Storage) {
+        self.storage.insert(address, storage);
+    }
+
+    /// Returns a hash of every account's address and bytecode, which doesn't depend on the order
+    /// the accounts were added in.
+    ///
+    /// ```
+    /// use heimdall_vm::core::world::WorldState;
+    /// use alloy::primitives::Address;
+    ///
+    /// let mut world = WorldState::new();
+    /// world.insert_code(Address::repeat_byte(0x11), vec![0x00]);
+    /// let hash = world.hash();
+    ///
+    /// world.insert_code(Address::repeat_byte(0x11), vec![0x01]);
+    /// assert_ne!(world.hash(), hash);
+    /// ```
+    pub fn hash(&self) -> B256 {
+        let mut accounts = self.code.iter().collect::<Vec<_>>();
+        accounts.sort_by_key(|(address, _)| **address);
+
+        let mut preimage = Vec::new();
+        for (address, code) in accounts {
+            preimage.extend_from_slice(address.as_slice());
+            preimage.extend_from_slice(keccak256(code.as_slice()).as_slice());
+        }
+        keccak256(preimage)
+    }
+
+    /// Returns the number of accounts in the world state.
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    /// Returns true if the world state contains no accounts.
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+}
@@ -0,0 +1,270 @@
+//! Splits bytecode into basic blocks: maximal runs of instructions which are only entered at
+//! their first instruction and only left after their last.
+//!
+//! Blocks begin at the start of the code, at every `JUMPDEST` (or EOF relative jump target), and
+//! after every instruction which jumps or halts.
+
+use std::{collections::HashSet, ops::Range};
+
+use alloy::primitives::U256;
+
+use crate::core::{
+    eof::{immediate_size, relative_jump_targets},
+    opcodes::{
+        OpCodeInfo, JUMP, JUMPDEST, JUMPF, JUMPI, PUSH0, PUSH1, PUSH32, RETF, RJUMP, RJUMPI, RJUMPV,
+    },
+};
+
+/// A single decoded instruction.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    /// The offset of the opcode within the bytecode
+    pub offset: usize,
+
+    /// The opcode of the instruction
+    pub opcode: u8,
+
+    /// The immediate bytes following the opcode, i.e. PUSH data or EOF jump offsets. PUSH data
+    /// which is cut short by the end of the code is truncated
+    pub immediate: Vec<u8>,
+}
+
+impl Instruction {
+    /// Returns the offset of the instruction following this one.
+    pub fn next(&self) -> usize {
+        self.offset + 1 + self.immediate.len()
+    }
+
+    /// Returns the value pushed by this instruction, if it's a PUSH.
+    pub fn push_value(&self) -> Option<U256> {
+        (PUSH0..=PUSH32).contains(&self.opcode).then(|| U256::from_be_slice(&self.immediate))
+    }
+}
+
+/// How control leaves a [`BasicBlock`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockExit {
+    /// Execution continues into the next block, since it begins with a jump target
+    Fallthrough,
+
+    /// An unconditional jump, i.e. JUMP or RJUMP
+    Jump,
+
+    /// A conditional jump, i.e. JUMPI, RJUMPI or RJUMPV, which falls through to the next block if
+    /// it isn't taken
+    Branch,
+
+    /// Execution of the code section ends, i.e. STOP, RETURN, REVERT, RETF or JUMPF
+    Halt,
+}
+
+/// A maximal run of instructions which is only entered at its first instruction and only left
+/// after its last.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BasicBlock {
+    /// The offset of the block's first instruction
+    pub start: usize,
+
+    /// The offset immediately after the block's last instruction
+    pub end: usize,
+
+    /// The instructions within the block, in order
+    pub instructions: Vec<Instruction>,
+
+    /// How control leaves the block
+    pub exit: BlockExit,
+
+    /// The targets of the block's jump which are known without executing it, i.e. a JUMP
+    /// immediately preceded by a PUSH of a valid JUMPDEST, or an EOF relative jump
+    pub static_targets: Vec<usize>,
+}
+
+impl BasicBlock {
+    /// Returns the instruction which ends the block.
+    pub fn terminator(&self) -> Option<&Instruction> {
+        self.instructions.last()
+    }
+
+    /// Returns the offset of the block which execution falls through to, if any.
+    pub fn fallthrough(&self) -> Option<usize> {
+        matches!(self.exit, BlockExit::Fallthrough | BlockExit::Branch).then_some(self.end)
+    }
+
+    /// Returns true if the instruction at `offset` is within this block.
+    pub fn contains(&self, offset: usize) -> bool {
+        (self.start..self.end).contains(&offset)
+    }
+}
+
+/// Decodes the instructions within `range` of `bytecode`. If `eof` is set, EOF immediates are
+/// consumed alongside PUSH data.
+pub fn decode_instructions(bytecode: &[u8], range: Range<usize>, eof: bool) -> Vec<Instruction> {
+    let end = range.end.min(bytecode.len());
+    let mut instructions = Vec::new();
+    let mut offset = range.start;
+
+    while offset < end {
+        let opcode = bytecode[offset];
+        let immediate_count = if eof {
+            immediate_size(bytecode, offset)
+        } else if (PUSH1..=PUSH32).contains(&opcode) {
+            (opcode - PUSH0) as usize
+        } else {
+            0
+        };
+        let immediate = bytecode[offset + 1..(offset + 1 + immediate_count).min(end)].to_vec();
+
+        let instruction = Instruction { offset, opcode, immediate };
+        offset = instruction.next();
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+/// Splits the instructions within `range` of `bytecode` into basic blocks, in order of their
+/// offset. If `eof` is set, `range` must be a single EOF code section.
+pub fn find_basic_blocks(bytecode: &[u8], range: Range<usize>, eof: bool) -> Vec<BasicBlock> {
+    let instructions = decode_instructions(bytecode, range, eof);
+    let jumpdests = instructions
+        .iter()
+        .filter(|instruction| !eof && instruction.opcode == JUMPDEST)
+        .map(|instruction| instruction.offset)
+        .collect::<HashSet<_>>();
+
+    // EOF jumps are relative and don't land on JUMPDESTs, so their targets are leaders too
+    let relative_targets = instructions
+        .iter()
+        .filter(|_| eof)
+        .flat_map(|instruction| relative_jump_targets(bytecode, instruction.offset))
+        .collect::<HashSet<_>>();
+
+    let mut blocks = Vec::new();
+    let mut current: Vec<Instruction> = Vec::new();
+    for instruction in instructions {
+        let is_leader = jumpdests.contains(&instruction.offset) ||
+            relative_targets.contains(&instruction.offset);
+        if is_leader && !current.is_empty() {
+            let block = std::mem::take(&mut current);
+            blocks.push(build_block(bytecode, block, BlockExit::Fallthrough, &jumpdests));
+        }
+
+        let exit = exit_of(instruction.opcode, eof);
+        current.push(instruction);
+        if let Some(exit) = exit {
+            blocks.push(build_block(bytecode, std::mem::take(&mut current), exit, &jumpdests));
+        }
+    }
+
+    // code which runs off the end of the range halts, as if followed by STOP
+    if !current.is_empty() {
+        blocks.push(build_block(bytecode, current, BlockExit::Halt, &jumpdests));
+    }
+
+    blocks
+}
+
+/// Returns how control leaves a block ending with `opcode`, or `None` if `opcode` doesn't end a
+/// block.
+fn exit_of(opcode: u8, eof: bool) -> Option<BlockExit> {
+    // opcodes which aren't valid in this kind of code halt, just like INVALID
+    let info = OpCodeInfo::from(opcode);
+    if (eof && info.is_legacy_only()) || (!eof && info.is_eof_only()) {
+        return Some(BlockExit::Halt);
+    }
+
+    match opcode {
+        JUMP | RJUMP => Some(BlockExit::Jump),
+        JUMPI | RJUMPI | RJUMPV => Some(BlockExit::Branch),
+        RETF | JUMPF => Some(BlockExit::Halt),
+        _ if info.terminating() => Some(BlockExit::Halt),
+        _ => None,
+    }
+}
+
+fn build_block(
+    bytecode: &[u8],
+    instructions: Vec<Instruction>,
+    exit: BlockExit,
+    jumpdests: &HashSet<usize>,
+) -> BasicBlock {
+    let start = instructions.first().map(|instruction| instruction.offset).unwrap_or_default();
+    let end = instructions.last().map(|instruction| instruction.next()).unwrap_or(start);
+
+    let static_targets = match instructions.as_slice() {
+        // legacy jumps are only static if their target is pushed immediately before them
+        [.., push, jump] if [JUMP, JUMPI].contains(&jump.opcode) => push
+            .push_value()
+            .and_then(|target| usize::try_from(target).ok())
+            .filter(|target| jumpdests.contains(target))
+            .into_iter()
+            .collect(),
+        [.., jump] if exit != BlockExit::Halt => relative_jump_targets(bytecode, jump.offset),
+        _ => Vec::new(),
+    };
+
+    BasicBlock { start, end, instructions, exit, static_targets }
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::utils::strings::decode_hex;
+
+    use super::*;
+
+    #[test]
+    fn test_find_basic_blocks_legacy() {
+        // CALLDATASIZE, PUSH1 0x08, JUMPI, PUSH0, PUSH0, REVERT, STOP, JUMPDEST, STOP
+        let bytecode = decode_hex("366008575f5ffd005b00").expect("failed to decode bytecode");
+        let blocks = find_basic_blocks(&bytecode, 0..bytecode.len(), false);
+
+        assert_eq!(blocks.len(), 4);
+        assert_eq!((blocks[0].start, blocks[0].end), (0, 4));
+        assert_eq!(blocks[0].exit, BlockExit::Branch);
+        assert_eq!(blocks[0].static_targets, vec![8]);
+        assert_eq!(blocks[0].fallthrough(), Some(4));
+
+        // the STOP after REVERT is never reached, but is still a block
+        assert_eq!((blocks[1].start, blocks[1].exit), (4, BlockExit::Halt));
+        assert_eq!((blocks[2].start, blocks[2].end), (7, 8));
+        assert_eq!(blocks[3].start, 8);
+    }
+
+    #[test]
+    fn test_static_jump_targets() {
+        // PUSH1 0x04, JUMP, INVALID, JUMPDEST, PUSH1 0x00, JUMP
+        let bytecode = decode_hex("600456fe5b600056").expect("failed to decode bytecode");
+        let blocks = find_basic_blocks(&bytecode, 0..bytecode.len(), false);
+
+        assert_eq!(blocks[0].static_targets, vec![4]);
+        assert_eq!(blocks[0].fallthrough(), None);
+
+        // offset 0 isn't a JUMPDEST, so the jump isn't a valid static target
+        let last = blocks.last().expect("no blocks");
+        assert_eq!(last.start, 4);
+        assert!(last.static_targets.is_empty());
+    }
+
+    #[test]
+    fn test_jumpdest_within_push_data() {
+        // PUSH2 0x5b5b, STOP
+        let bytecode = decode_hex("615b5b00").expect("failed to decode bytecode");
+        let blocks = find_basic_blocks(&bytecode, 0..bytecode.len(), false);
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].instructions[0].push_value(), Some(U256::from(0x5b5b)));
+    }
+
+    #[test]
+    fn test_find_basic_blocks_eof() {
+        // CALLDATASIZE, RJUMPI +1, STOP, PUSH0, PUSH0, REVERT
+        let code = decode_hex("36e10001005f5ffd").expect("failed to decode bytecode");
+        let blocks = find_basic_blocks(&code, 0..code.len(), true);
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].exit, BlockExit::Branch);
+        assert_eq!(blocks[0].static_targets, vec![5]);
+        assert_eq!(blocks[0].fallthrough(), Some(4));
+        assert_eq!(blocks[2].start, 5);
+    }
+}
@@ -0,0 +1,318 @@
+//! Recovers where in the ABI-encoded arguments a calldata access reads from, given the operation
+//! which computed its offset.
+//!
+//! Selectors are symbolically executed with calldata holding only the selector, so every argument
+//! is zero and reads of dynamic data alias the argument heads. Their operations still record how
+//! each offset was derived though, i.e. `CALLDATALOAD(ADD(0x04, CALLDATALOAD(0x04)))` reads the
+//! length of the dynamic value whose offset is the first argument.
+
+use alloy::primitives::U256;
+
+use crate::{
+    core::opcodes::{WrappedInput, WrappedOpcode, ADD, CALLDATALOAD, MUL, SHL},
+    ext::solver::constant_value,
+};
+
+/// The size of the function selector which precedes the ABI-encoded arguments.
+const SELECTOR_SIZE: u64 = 4;
+
+/// The size of an ABI-encoded head, length, or array element.
+const WORD_SIZE: u64 = 32;
+
+/// A variable index into the data of a dynamic value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalldataIndex {
+    /// The index, before it's scaled by `stride`
+    pub operation: WrappedInput,
+
+    /// The number of bytes between consecutive indices, i.e. 32 for an array of words and 1 for
+    /// `bytes`
+    pub stride: U256,
+}
+
+/// A position within the ABI-encoded arguments of a call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalldataLocation {
+    /// The head of the argument at the given index, i.e. `msg.data[4 + 32 * index]`
+    Argument(usize),
+
+    /// A position within the data of a dynamic value
+    Tail {
+        /// Where the offset to the dynamic value's data is stored
+        pointer: Box<CalldataLocation>,
+
+        /// The constant number of bytes from the start of the data. For arrays, `bytes` and
+        /// `string`, the length is stored at offset zero
+        offset: U256,
+
+        /// The variable index added to `offset`, if any
+        index: Option<CalldataIndex>,
+    },
+}
+
+impl CalldataLocation {
+    /// Locates the calldata read at `offset`, i.e. the offset operation of a CALLDATALOAD or
+    /// CALLDATACOPY. Returns `None` for reads of the selector, and offsets which can't be
+    /// attributed to a single position.
+    pub fn locate(offset: &WrappedOpcode) -> Option<Self> {
+        let mut sum = OffsetSum::default();
+        sum.add(offset)?;
+
+        if sum.pointers.is_empty() {
+            if sum.index.is_some() {
+                return None;
+            }
+
+            let head = sum.constant.checked_sub(U256::from(SELECTOR_SIZE))?;
+            return (head / U256::from(WORD_SIZE)).try_into().ok().map(Self::Argument);
+        }
+
+        // when reading nested data, every ancestor's offset is summed too. the read is relative
+        // to the offset stored deepest within the calldata
+        let pointer = sum
+            .pointers
+            .iter()
+            .filter_map(|pointer| match pointer.inputs.first() {
+                Some(WrappedInput::Opcode(offset)) => Self::locate(offset),
+                _ => None,
+            })
+            .max_by_key(|location| location.depth())?;
+        let offset = sum.constant.checked_sub(pointer.data_start())?;
+
+        Some(Self::Tail { pointer: Box::new(pointer), offset, index: sum.index })
+    }
+
+    /// Returns the index of the argument this location is within.
+    pub fn argument(&self) -> usize {
+        match self {
+            Self::Argument(index) => *index,
+            Self::Tail { pointer, .. } => pointer.argument(),
+        }
+    }
+
+    /// Returns the number of dynamic values this location is nested within.
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::Argument(_) => 0,
+            Self::Tail { pointer, .. } => pointer.depth() + 1,
+        }
+    }
+
+    /// Returns the constant part of the position an offset stored at this location is relative
+    /// to. Argument offsets are relative to the start of the arguments, offsets stored in an
+    /// array to the start of its elements (after the length), and offsets stored in a tuple to the
+    /// start of the tuple.
+    fn data_start(&self) -> U256 {
+        match self {
+            Self::Argument(_) => U256::from(SELECTOR_SIZE),
+            Self::Tail { pointer, index: Some(_), .. } => {
+                pointer.data_start() + U256::from(WORD_SIZE)
+            }
+            Self::Tail { pointer, index: None, .. } => pointer.data_start(),
+        }
+    }
+
+    /// Returns the Solidity representation of the value read at this location, i.e. `arg0`,
+    /// `arg0.length` or `arg1[i]`. Returns `None` for reads which don't line up with a word.
+    pub fn solidify(&self) -> Option<String> {
+        let word = U256::from(WORD_SIZE);
+
+        match self {
+            Self::Argument(index) => Some(format!("arg{index}")),
+            Self::Tail { pointer, offset, index: None } => {
+                let value = pointer.solidify()?;
+                match offset.checked_sub(word) {
+                    None if offset.is_zero() => Some(format!("{value}.length")),
+                    Some(element) if (element % word).is_zero() => {
+                        Some(format!("{value}[{}]", element / word))
+                    }
+                    _ => None,
+                }
+            }
+            Self::Tail { pointer, offset, index: Some(index) } => {
+                let value = pointer.solidify()?;
+                let element = offset.checked_sub(word)?;
+                if index.stride != word || !(element % word).is_zero() {
+                    return None;
+                }
+
+                match element / word {
+                    first if first.is_zero() => {
+                        Some(format!("{value}[{}]", index.operation._solidify()))
+                    }
+                    first => Some(format!("{value}[{} + {first}]", index.operation._solidify())),
+                }
+            }
+        }
+    }
+}
+
+/// An offset, decomposed into the sum of a constant, the offsets read from calldata, and at most
+/// one variable index.
+#[derive(Debug, Default)]
+struct OffsetSum<'a> {
+    constant: U256,
+    pointers: Vec<&'a WrappedOpcode>,
+    index: Option<CalldataIndex>,
+}
+
+impl<'a> OffsetSum<'a> {
+    /// Adds `operation` to the sum. Returns `None` if the sum has more than one variable index.
+    fn add(&mut self, operation: &'a WrappedOpcode) -> Option<()> {
+        if let Some(value) = constant_value(operation) {
+            self.constant = self.constant.wrapping_add(value);
+            return Some(());
+        }
+
+        match (operation.opcode, operation.inputs.as_slice()) {
+            (ADD, [a, b]) => {
+                self.add_input(a)?;
+                self.add_input(b)
+            }
+            (CALLDATALOAD, _) => {
+                self.pointers.push(operation);
+                Some(())
+            }
+            (MUL, [a, b]) => match (input_constant(a), input_constant(b)) {
+                (Some(stride), None) => self.set_index(b, stride),
+                (None, Some(stride)) => self.set_index(a, stride),
+                _ => self.set_index(&wrap(operation), U256::from(1)),
+            },
+            (SHL, [shift, value]) => match input_constant(shift) {
+                Some(shift) if shift < U256::from(256) => {
+                    self.set_index(value, U256::from(1) << shift.to::<usize>())
+                }
+                _ => self.set_index(&wrap(operation), U256::from(1)),
+            },
+
+            // anything else is an unscaled index, i.e. into `bytes`
+            _ => self.set_index(&wrap(operation), U256::from(1)),
+        }
+    }
+
+    fn add_input(&mut self, input: &'a WrappedInput) -> Option<()> {
+        match input {
+            WrappedInput::Raw(value) => {
+                self.constant = self.constant.wrapping_add(*value);
+                Some(())
+            }
+            WrappedInput::Opcode(operation) => self.add(operation),
+        }
+    }
+
+    fn set_index(&mut self, operation: &WrappedInput, stride: U256) -> Option<()> {
+        if self.index.is_some() {
+            return None;
+        }
+
+        self.index = Some(CalldataIndex { operation: operation.clone(), stride });
+        Some(())
+    }
+}
+
+fn input_constant(input: &WrappedInput) -> Option<U256> {
+    match input {
+        WrappedInput::Raw(value) => Some(*value),
+        WrappedInput::Opcode(operation) => constant_value(operation),
+    }
+}
+
+fn wrap(operation: &WrappedOpcode) -> WrappedInput {
+    WrappedInput::Opcode(std::sync::Arc::new(operation.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{add, counter, load, op, push};
+
+    use super::*;
+
+    #[test]
+    fn test_locate_argument_heads() {
+        assert_eq!(CalldataLocation::locate(&push(0x04)), Some(CalldataLocation::Argument(0)));
+        assert_eq!(CalldataLocation::locate(&push(0x44)), Some(CalldataLocation::Argument(2)));
+        assert_eq!(CalldataLocation::locate(&push(0x00)), None);
+    }
+
+    #[test]
+    fn test_locate_bytes() {
+        // function f(bytes calldata data): data.length, then the data itself
+        let pointer = load(push(0x04));
+        let length = CalldataLocation::locate(&add(push(0x04), pointer.clone())).unwrap();
+        assert_eq!(length.argument(), 0);
+        assert_eq!(length.solidify().as_deref(), Some("arg0.length"));
+
+        let data = CalldataLocation::locate(&add(add(pointer, push(0x04)), push(0x20))).unwrap();
+        match data {
+            CalldataLocation::Tail { offset, index: None, .. } => {
+                assert_eq!(offset, U256::from(0x20))
+            }
+            _ => panic!("expected the start of the data"),
+        }
+    }
+
+    #[test]
+    fn test_locate_array_element() {
+        // function f(uint256 a, uint256[] calldata b): b[i]
+        let pointer = load(push(0x24));
+        let element = add(
+            add(add(push(0x04), pointer), push(0x20)),
+            op(MUL, vec![counter(0x44), push(0x20)]),
+        );
+        let location = CalldataLocation::locate(&element).unwrap();
+
+        assert_eq!(location.argument(), 1);
+        assert_eq!(location.depth(), 1);
+        assert!(location.solidify().unwrap().starts_with("arg1["));
+    }
+
+    #[test]
+    fn test_locate_nested_array_element() {
+        // function f(uint256[][] calldata a): a[i][j]
+        let outer = load(push(0x04));
+        let outer_data = add(add(push(0x04), outer), push(0x20));
+        let inner = load(add(outer_data.clone(), op(SHL, vec![push(5), counter(0x44)])));
+        let element =
+            add(add(add(outer_data, inner), push(0x20)), op(MUL, vec![push(0x20), counter(0x44)]));
+        let location = CalldataLocation::locate(&element).unwrap();
+
+        assert_eq!(location.depth(), 2);
+        match location {
+            CalldataLocation::Tail { pointer, offset, index: Some(index) } => {
+                assert_eq!(offset, U256::from(0x20));
+                assert_eq!(index.stride, U256::from(0x20));
+                assert!(matches!(*pointer, CalldataLocation::Tail { index: Some(_), .. }));
+            }
+            _ => panic!("expected an indexed element"),
+        }
+    }
+
+    #[test]
+    fn test_locate_tuple_field() {
+        // function f((uint256 a, bytes b) calldata s): s.b, which is the second field of s
+        let pointer = load(push(0x04));
+        let field = CalldataLocation::locate(&add(add(push(0x04), pointer), push(0x20))).unwrap();
+        let data = load(add(add(push(0x04), load(push(0x04))), push(0x20)));
+        let length =
+            CalldataLocation::locate(&add(add(push(0x04), load(push(0x04))), data)).unwrap();
+
+        assert_eq!(field.depth(), 1);
+        assert_eq!(length.depth(), 2);
+        match length {
+            CalldataLocation::Tail { pointer, offset, index: None } => {
+                assert!(offset.is_zero());
+                assert_eq!(*pointer, field);
+            }
+            _ => panic!("expected the length of the tuple's bytes"),
+        }
+    }
+
+    #[test]
+    fn test_locate_rejects_multiple_indices() {
+        let offset = add(
+            add(load(push(0x04)), op(MUL, vec![counter(0x44), push(0x20)])),
+            op(MUL, vec![counter(0x44), push(0x40)]),
+        );
+        assert_eq!(CalldataLocation::locate(&offset), None);
+    }
+}
@@ -0,0 +1,438 @@
+//! Executes the constructor of creation bytecode, recovering the runtime code it deploys, the
+//! storage it initializes, and the types of its parameters.
+//!
+//! Solidity and Vyper constructors copy the runtime code out of the creation bytecode and return
+//! it, while any constructor arguments are appended to the end of the creation bytecode. Solidity
+//! copies the arguments into memory before decoding them, so the types of the constructor's
+//! parameters can be recovered from how each word of that copy is cleaned up after it's loaded.
+
+use std::collections::{BTreeMap, HashSet};
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue},
+    primitives::U256,
+};
+
+use crate::{
+    core::{
+        opcodes::{
+            WrappedInput, WrappedOpcode, AND, CODECOPY, DUP1, ISZERO, MLOAD, POP, RETURN,
+            SIGNEXTEND, SSTORE, SWAP16,
+        },
+        vm::{Instruction, VM},
+    },
+    ext::blocks::decode_instructions,
+};
+
+/// The gas available to the constructor, i.e. a block's gas limit.
+const DEPLOYMENT_GAS_LIMIT: u128 = 30_000_000;
+
+/// The size of an ABI-encoded head.
+const WORD_SIZE: usize = 32;
+
+/// The result of executing the constructor of creation bytecode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deployment {
+    /// The runtime code returned by the constructor
+    pub runtime_code: Vec<u8>,
+
+    /// The offset within the creation bytecode which the runtime code was copied from. Runtime
+    /// code with immutables is patched before it's returned, so it may not match the bytes at
+    /// this offset exactly
+    pub runtime_offset: usize,
+
+    /// The storage slots written by the constructor, and the values they hold once it returns
+    pub storage: BTreeMap<U256, U256>,
+
+    /// The ABI-encoded constructor arguments appended to the creation bytecode
+    pub constructor_args: Vec<u8>,
+
+    /// The types of the constructor's parameters, recovered from how the constructor decodes its
+    /// arguments. Empty if the constructor has no parameters, or they couldn't be recovered
+    pub constructor_inputs: Vec<DynSolType>,
+}
+
+impl Deployment {
+    /// Returns the recovered signature of the constructor, i.e. `constructor(address,uint256)`.
+    pub fn constructor_signature(&self) -> String {
+        format!(
+            "constructor({})",
+            self.constructor_inputs.iter().map(|ty| ty.to_string()).collect::<Vec<_>>().join(",")
+        )
+    }
+
+    /// Decodes the constructor arguments using the recovered parameter types. Returns `None` if
+    /// no parameters were recovered, or the arguments don't decode as them.
+    pub fn decode_arguments(&self) -> Option<Vec<DynSolValue>> {
+        if self.constructor_inputs.is_empty() {
+            return None;
+        }
+
+        DynSolType::Tuple(self.constructor_inputs.clone())
+            .abi_decode_params(&self.constructor_args)
+            .ok()
+            .and_then(|decoded| decoded.as_tuple().map(|values| values.to_vec()))
+    }
+
+    /// Returns a copy of `vm` which executes the deployed runtime code, starting from the storage
+    /// initialized by the constructor.
+    pub fn runtime_vm(&self, vm: &VM) -> VM {
+        let mut runtime = vm.clone();
+        runtime.bytecode = self.runtime_code.clone();
+        runtime.eof = None;
+        runtime.storage.storage =
+            self.storage.iter().map(|(slot, value)| (*slot, *value)).collect();
+        runtime.reset();
+        runtime
+    }
+}
+
+/// Executes `vm`'s bytecode as creation bytecode, i.e. without calldata. Returns `None` unless
+/// the bytecode returns code which it copied from itself, which is how creation bytecode deploys
+/// its runtime code.
+pub fn deploy(vm: &VM) -> Option<Deployment> {
+    // EOF contracts are deployed with EOFCREATE, which doesn't execute creation bytecode
+    if vm.eof.is_some() || !is_creation_code(&vm.bytecode) {
+        return None;
+    }
+
+    let mut vm = vm.clone();
+    vm.calldata.clear();
+    vm.reset();
+    vm.gas_remaining = DEPLOYMENT_GAS_LIMIT;
+
+    let mut instructions = Vec::new();
+    while vm.bytecode.len() >= vm.instruction as usize {
+        instructions.push(vm.step().ok()?.last_instruction);
+
+        if vm.exitcode != 255 || !vm.returndata.is_empty() {
+            break;
+        }
+    }
+
+    // the constructor must RETURN the code it copied
+    if vm.exitcode != 0 || vm.returndata.is_empty() {
+        return None;
+    }
+    let copies = instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| instruction.opcode == CODECOPY)
+        .filter_map(|(index, instruction)| {
+            let [dest, offset, size] = instruction.inputs[..] else {
+                return None;
+            };
+            Some((index, dest.try_into().ok()?, offset.try_into().ok()?, size.try_into().ok()?))
+        })
+        .collect::<Vec<(usize, usize, usize, usize)>>();
+    let (_, _, runtime_offset, runtime_size) =
+        copies.iter().rev().find(|(_, _, _, size)| *size == vm.returndata.len()).copied()?;
+
+    // the arguments are copied from the end of the bytecode, after the runtime code
+    let runtime_end = runtime_offset.saturating_add(runtime_size);
+    let arguments_copy = copies.iter().find(|(_, _, offset, size)| {
+        *size > 0 && *offset >= runtime_end && offset.saturating_add(*size) == vm.bytecode.len()
+    });
+    let constructor_args = match arguments_copy {
+        Some((_, _, offset, _)) => vm.bytecode[*offset..].to_vec(),
+        None => vm.bytecode.get(runtime_end..).map(|args| args.to_vec()).unwrap_or_default(),
+    };
+    let constructor_inputs = arguments_copy
+        .map(|(index, dest, _, size)| {
+            recover_constructor_inputs(&instructions[*index..], *dest, &constructor_args[..*size])
+        })
+        .unwrap_or_default();
+
+    let storage = instructions
+        .iter()
+        .filter(|instruction| instruction.opcode == SSTORE)
+        .filter_map(|instruction| instruction.inputs.first())
+        .map(|slot| (*slot, vm.storage.storage.get(slot).copied().unwrap_or_default()))
+        .collect();
+
+    Some(Deployment {
+        runtime_code: vm.returndata.clone(),
+        runtime_offset,
+        storage,
+        constructor_args,
+        constructor_inputs,
+    })
+}
+
+/// Returns true if `bytecode` could be creation bytecode, i.e. it has both a CODECOPY to copy the
+/// runtime code out of itself and a RETURN to deploy it. Runtime code lacking either isn't
+/// executed as a constructor at all.
+pub fn is_creation_code(bytecode: &[u8]) -> bool {
+    let opcodes = decode_instructions(bytecode, 0..bytecode.len(), false)
+        .into_iter()
+        .map(|instruction| instruction.opcode)
+        .collect::<HashSet<_>>();
+
+    opcodes.contains(&CODECOPY) && opcodes.contains(&RETURN)
+}
+
+/// A load of an ABI-encoded word from the copy of the constructor arguments in memory.
+struct ArgumentRead {
+    /// The index of the MLOAD within the executed instructions
+    index: usize,
+
+    /// The offset of the word, relative to the start of the arguments
+    offset: usize,
+
+    /// The MLOAD operation, which later instructions take as an input
+    operation: WrappedOpcode,
+}
+
+/// Recovers the types of the constructor's parameters from `instructions`, which begin with the
+/// CODECOPY of `arguments` to `dest` in memory.
+///
+/// Each parameter's head is loaded from memory and then cleaned up according to its type, i.e.
+/// an `address` is masked to 20 bytes and a `bool` is normalized with ISZERO. The head of a
+/// dynamic value is an offset, which is added to the start of the arguments to load its length.
+fn recover_constructor_inputs(
+    instructions: &[Instruction],
+    dest: usize,
+    arguments: &[u8],
+) -> Vec<DynSolType> {
+    let reads = instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| instruction.opcode == MLOAD)
+        .filter_map(|(index, instruction)| {
+            let offset = usize::try_from(*instruction.inputs.first()?).ok()?.checked_sub(dest)?;
+            let operation = instruction.output_operations.first()?.clone();
+            (offset < arguments.len() && offset % WORD_SIZE == 0).then_some(ArgumentRead {
+                index,
+                offset,
+                operation,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // the offsets of heads are constant, while every read within the data of a dynamic value is
+    // offset by the value of its head
+    let (heads, tails): (Vec<&ArgumentRead>, Vec<&ArgumentRead>) = reads.iter().partition(|read| {
+        !reads.iter().any(|head| {
+            head.index < read.index &&
+                instructions[read.index]
+                    .input_operations
+                    .first()
+                    .is_some_and(|offset| contains(offset, &head.operation))
+        })
+    });
+
+    let Some(last_head) = heads.iter().map(|read| read.offset).max() else {
+        return Vec::new();
+    };
+    (0..=last_head / WORD_SIZE)
+        .map(|position| {
+            let Some(head) = heads.iter().find(|read| read.offset == position * WORD_SIZE) else {
+                return DynSolType::Uint(256);
+            };
+
+            let data = tails
+                .iter()
+                .filter(|tail| {
+                    instructions[tail.index]
+                        .input_operations
+                        .first()
+                        .is_some_and(|offset| contains(offset, &head.operation))
+                })
+                .collect::<Vec<_>>();
+            if data.is_empty() {
+                return cleanup_type(instructions, head).unwrap_or(DynSolType::Uint(256));
+            }
+
+            // arrays clean up each of their elements, while `bytes` and `string` are copied
+            if let Some(element) = data.iter().find_map(|tail| cleanup_type(instructions, tail)) {
+                return DynSolType::Array(Box::new(element));
+            }
+            match dynamic_data(arguments, head.offset).map(std::str::from_utf8) {
+                Some(Ok(_)) => DynSolType::String,
+                _ => DynSolType::Bytes,
+            }
+        })
+        .collect()
+}
+
+/// Returns the type which the first instruction to use `read` cleans it up as, if any.
+fn cleanup_type(instructions: &[Instruction], read: &ArgumentRead) -> Option<DynSolType> {
+    let (position, consumer) = instructions[read.index + 1..].iter().find_map(|instruction| {
+        // stack manipulation doesn't use the value
+        if instruction.opcode == POP || (DUP1..=SWAP16).contains(&instruction.opcode) {
+            return None;
+        }
+
+        instruction
+            .input_operations
+            .iter()
+            .position(|operation| operation == &read.operation)
+            .map(|position| (position, instruction))
+    })?;
+
+    match consumer.opcode {
+        AND => mask_type(*consumer.inputs.get(1 - position)?),
+        SIGNEXTEND if position == 1 => {
+            let size =
+                usize::try_from(*consumer.inputs.first()?).ok().filter(|size| *size < WORD_SIZE)?;
+            Some(DynSolType::Int((size + 1) * 8))
+        }
+        ISZERO => Some(DynSolType::Bool),
+        _ => None,
+    }
+}
+
+/// Returns the type which a value masked with `mask` is cleaned up as, i.e. `address` for a mask
+/// of the low 20 bytes.
+fn mask_type(mask: U256) -> Option<DynSolType> {
+    let bits = mask.count_ones();
+    if bits == 0 || !bits.is_multiple_of(8) {
+        return None;
+    }
+
+    if mask == U256::MAX >> (256 - bits) {
+        return Some(match bits {
+            160 => DynSolType::Address,
+            _ => DynSolType::Uint(bits),
+        });
+    }
+    (mask == U256::MAX << (256 - bits)).then_some(DynSolType::FixedBytes(bits / 8))
+}
+
+/// Returns the data of the dynamic value whose head is at `head` within `arguments`.
+fn dynamic_data(arguments: &[u8], head: usize) -> Option<&[u8]> {
+    let word = |offset: usize| -> Option<usize> {
+        U256::from_be_slice(arguments.get(offset..offset.checked_add(WORD_SIZE)?)?).try_into().ok()
+    };
+
+    let start = word(head)?;
+    let length = word(start)?;
+    let data = start.checked_add(WORD_SIZE)?;
+    arguments.get(data..data.checked_add(length)?)
+}
+
+/// Whether `operation` is, or is computed from, `target`.
+fn contains(operation: &WrappedOpcode, target: &WrappedOpcode) -> bool {
+    // an operation can't be computed from one which is deeper than itself
+    if operation.depth() < target.depth() {
+        return false;
+    }
+
+    operation == target ||
+        operation.inputs.iter().any(|input| match input {
+            WrappedInput::Opcode(input) => contains(input, target),
+            WrappedInput::Raw(_) => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, U256};
+    use heimdall_common::utils::strings::decode_hex;
+
+    use super::*;
+
+    const RUNTIME_CODE: &str = "602a60005260206000f3";
+
+    fn creation_vm(bytecode: &str) -> VM {
+        VM::new(
+            &decode_hex(bytecode).expect("failed to decode bytecode"),
+            &[],
+            Address::default(),
+            Address::default(),
+            Address::default(),
+            0,
+            u128::MAX,
+        )
+    }
+
+    #[test]
+    fn test_deploy_static_arguments() {
+        // constructor(address owner, bool enabled) { slot0 = owner; slot1 = enabled; }
+        let bytecode = [
+            "60443803604460803960805173ffffffffffffffffffffffffffffffffffffffff16600055",
+            "60a0511515600155",
+            "600a603a600039600a6000f3fe",
+            RUNTIME_CODE,
+            "000000000000000000000000000000000000000000000000000000000000dead",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        ]
+        .concat();
+        let deployment = deploy(&creation_vm(&bytecode)).expect("failed to deploy");
+
+        assert_eq!(deployment.runtime_code, decode_hex(RUNTIME_CODE).expect("invalid hex"));
+        assert_eq!(deployment.runtime_offset, 0x3a);
+        assert_eq!(deployment.constructor_args.len(), 64);
+        assert_eq!(deployment.constructor_inputs, vec![DynSolType::Address, DynSolType::Bool]);
+        assert_eq!(deployment.constructor_signature(), "constructor(address,bool)");
+        assert_eq!(
+            deployment.storage,
+            BTreeMap::from([(U256::ZERO, U256::from(0xdead)), (U256::from(1), U256::from(1))])
+        );
+        assert_eq!(
+            deployment.decode_arguments(),
+            Some(vec![
+                DynSolValue::Address(Address::from_word(U256::from(0xdead).into())),
+                DynSolValue::Bool(true)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_deploy_dynamic_argument() {
+        // constructor(string name) { slot0 = bytes(name).length; }
+        let bytecode = [
+            "602a3803602a60803960805160800151600055",
+            "600a6020600039600a6000f3fe",
+            RUNTIME_CODE,
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "68656c6c6f000000000000000000000000000000000000000000000000000000",
+        ]
+        .concat();
+        let deployment = deploy(&creation_vm(&bytecode)).expect("failed to deploy");
+
+        assert_eq!(deployment.constructor_inputs, vec![DynSolType::String]);
+        assert_eq!(deployment.storage, BTreeMap::from([(U256::ZERO, U256::from(5))]));
+        assert_eq!(
+            deployment.decode_arguments(),
+            Some(vec![DynSolValue::String(String::from("hello"))])
+        );
+    }
+
+    #[test]
+    fn test_deploy_runtime_code() {
+        // runtime code returns data it didn't copy from itself
+        assert_eq!(deploy(&creation_vm(RUNTIME_CODE)), None);
+    }
+
+    #[test]
+    fn test_deploy_solc_creation_code() {
+        // `contract C {}` compiled with solc 0.8.24, with the metadata stripped
+        let bytecode = "6080604052348015600e575f5ffd5b50603e80601a5f395ff3fe60806040525f5ffd";
+        assert!(is_creation_code(&decode_hex(bytecode).expect("invalid hex")));
+
+        let deployment = deploy(&creation_vm(bytecode)).expect("failed to deploy");
+        assert_eq!(deployment.runtime_offset, 0x1a);
+        assert_eq!(deployment.runtime_code.len(), 0x3e);
+        assert!(deployment.runtime_code.starts_with(&[0x60, 0x80, 0x60, 0x40, 0x52]));
+        assert!(deployment.constructor_inputs.is_empty());
+
+        // the deployed runtime code has no CODECOPY, so it's never executed as a constructor
+        assert!(!is_creation_code(&deployment.runtime_code));
+    }
+
+    #[test]
+    fn test_runtime_vm() {
+        let bytecode = ["600a600c600039600a6000f3", RUNTIME_CODE].concat();
+        let vm = creation_vm(&bytecode);
+        let deployment = deploy(&vm).expect("failed to deploy");
+        assert!(deployment.constructor_args.is_empty());
+        assert!(deployment.constructor_inputs.is_empty());
+        assert_eq!(deployment.decode_arguments(), None);
+
+        let mut runtime = deployment.runtime_vm(&vm);
+        let result = runtime.call(&[], 0).expect("failed to execute runtime code");
+        assert_eq!(U256::from_be_slice(&result.returndata), U256::from(42));
+    }
+}
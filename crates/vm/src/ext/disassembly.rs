@@ -0,0 +1,228 @@
+//! Decodes bytecode into typed instructions, annotated with the static information which
+//! downstream analyses need: gas and stack effects, basic blocks, and constant jump targets.
+
+use std::ops::Range;
+
+use alloy::primitives::Bytes;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        eof::{immediate_size, EofContainer},
+        hardfork::HardFork,
+        opcodes::{OpCodeInfo, PUSH0, PUSH1, PUSH32},
+    },
+    ext::{blocks::find_basic_blocks, layout::BytecodeLayout},
+};
+
+/// A single instruction of disassembled bytecode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisassembledInstruction {
+    /// The offset of the opcode within the bytecode. For EOF containers, offsets are relative to
+    /// the start of the container
+    pub pc: usize,
+
+    /// The opcode of the instruction
+    pub opcode: u8,
+
+    /// The name of the opcode, or `unknown` if it isn't active at the hardfork used for
+    /// disassembly
+    pub name: String,
+
+    /// The immediate bytes following the opcode, i.e. PUSH data or EOF immediates
+    pub immediate: Bytes,
+
+    /// The minimum gas the instruction costs
+    pub gas: u16,
+
+    /// The number of stack items the instruction consumes
+    pub stack_inputs: u8,
+
+    /// The number of stack items the instruction produces
+    pub stack_outputs: u8,
+
+    /// The index of the basic block containing the instruction. Blocks are numbered in order of
+    /// their offset, across every code section
+    pub block: usize,
+
+    /// The targets of the instruction which are known without executing it, if it's a jump. A
+    /// JUMP or JUMPI only has one if its target is pushed immediately before it, while RJUMPV may
+    /// have several
+    pub jump_targets: Vec<usize>,
+}
+
+/// Disassembles `bytecode` into instructions, resolving opcode names and stack effects at
+/// `hardfork`. EOF containers are disassembled code section by code section, skipping their
+/// headers, container sections and data. Legacy bytecode is disassembled up to the end of its
+/// code, skipping the metadata and data which follow it (see [`BytecodeLayout`]).
+///
+/// PUSH data which is cut short by the end of the code isn't included, since it doesn't form a
+/// complete instruction.
+pub fn disassemble_bytecode(
+    bytecode: &[u8],
+    hardfork: HardFork,
+) -> Result<Vec<DisassembledInstruction>> {
+    if !EofContainer::is_eof(bytecode) {
+        let layout = BytecodeLayout::analyze(bytecode);
+        return Ok(disassemble_code(bytecode, layout.code, false, hardfork, 0));
+    }
+
+    let container =
+        EofContainer::parse(bytecode).map_err(|e| eyre!("parsing EOF container failed: {}", e))?;
+    let mut instructions = Vec::new();
+    for code in &container.code_sections {
+        let first_block = instructions
+            .last()
+            .map(|instruction: &DisassembledInstruction| instruction.block + 1)
+            .unwrap_or_default();
+        instructions.extend(disassemble_code(
+            &container.raw,
+            code.clone(),
+            true,
+            hardfork,
+            first_block,
+        ));
+    }
+
+    Ok(instructions)
+}
+
+/// Disassembles the instructions within `range` of `bytecode`, numbering their blocks from
+/// `first_block`.
+fn disassemble_code(
+    bytecode: &[u8],
+    range: Range<usize>,
+    eof: bool,
+    hardfork: HardFork,
+    first_block: usize,
+) -> Vec<DisassembledInstruction> {
+    let end = range.end.min(bytecode.len());
+
+    find_basic_blocks(bytecode, range, eof)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, block)| {
+            let terminator = block.terminator().map(|instruction| instruction.offset);
+            let static_targets = block.static_targets;
+
+            block.instructions.into_iter().map(move |instruction| {
+                let info = match eof {
+                    true => OpCodeInfo::for_eof(instruction.opcode, hardfork),
+                    false => OpCodeInfo::for_fork(instruction.opcode, hardfork),
+                };
+                let jump_targets = match terminator == Some(instruction.offset) {
+                    true => static_targets.clone(),
+                    false => Vec::new(),
+                };
+
+                DisassembledInstruction {
+                    pc: instruction.offset,
+                    opcode: instruction.opcode,
+                    name: info.map_or("unknown", |info| info.name()).to_string(),
+                    immediate: Bytes::from(instruction.immediate),
+                    gas: info.map(|info| info.min_gas()).unwrap_or_default(),
+                    stack_inputs: info.map(|info| info.inputs()).unwrap_or_default(),
+                    stack_outputs: info.map(|info| info.outputs()).unwrap_or_default(),
+                    block: first_block + index,
+                    jump_targets,
+                }
+            })
+        })
+        .take_while(|instruction| {
+            let immediate_count = if eof {
+                immediate_size(bytecode, instruction.pc)
+            } else if (PUSH1..=PUSH32).contains(&instruction.opcode) {
+                (instruction.opcode - PUSH0) as usize
+            } else {
+                0
+            };
+
+            instruction.pc + 1 + immediate_count <= end
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::utils::strings::decode_hex;
+
+    use super::*;
+
+    #[test]
+    fn test_disassemble_bytecode_legacy() {
+        // CALLDATASIZE, PUSH1 0x08, JUMPI, PUSH0, PUSH0, REVERT, STOP, JUMPDEST, PUSH2 (truncated)
+        let bytecode = decode_hex("366008575f5ffd005b6100").expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        assert_eq!(instructions.len(), 8);
+        assert_eq!(instructions[1].name, "PUSH1");
+        assert_eq!(instructions[1].immediate, Bytes::from(vec![0x08]));
+        assert_eq!((instructions[2].stack_inputs, instructions[2].stack_outputs), (2, 0));
+        assert_eq!(instructions[2].jump_targets, vec![8]);
+        assert!(instructions[1].jump_targets.is_empty());
+
+        // the block after the REVERT begins at the unreachable STOP
+        assert_eq!(instructions[5].block, 1);
+        assert_eq!(instructions[6].block, 2);
+        assert_eq!((instructions[7].pc, instructions[7].block), (8, 3));
+    }
+
+    #[test]
+    fn test_disassemble_bytecode_skips_metadata() {
+        // JUMPDEST, STOP, INVALID, followed by solc 0.4 metadata
+        let bytecode = decode_hex(concat!(
+            "5b00fe",
+            "a165627a7a72305820",
+            "1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8",
+            "0029"
+        ))
+        .expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(
+            instructions.last().map(|instruction| instruction.name.as_str()),
+            Some("INVALID")
+        );
+    }
+
+    #[test]
+    fn test_disassemble_bytecode_hardfork() {
+        // PUSH0 wasn't introduced until shanghai
+        let bytecode = decode_hex("5f00").expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::London).expect("failed to disassemble");
+
+        assert_eq!(instructions[0].name, "unknown");
+        assert_eq!(instructions[0].gas, 0);
+    }
+
+    #[test]
+    fn test_disassemble_bytecode_eof() {
+        // code section 0: PUSH1 0x01, RJUMPI +1, STOP, CALLF 1, STOP
+        // code section 1: DATALOADN 0x0000, RETF
+        let bytecode = decode_hex(concat!(
+            "ef0001010008020002000a0004ff0020",
+            "00",
+            "0080000100010001",
+            "6001e1000100e3000100",
+            "d10000e4",
+        ))
+        .expect("failed to decode bytecode");
+        let instructions =
+            disassemble_bytecode(&bytecode, HardFork::Latest).expect("failed to disassemble");
+
+        assert_eq!(instructions.len(), 7);
+        assert_eq!((instructions[0].pc, instructions[0].name.as_str()), (0x19, "PUSH1"));
+        assert_eq!(instructions[1].jump_targets, vec![0x1f]);
+
+        // blocks are numbered across both sections
+        let last = instructions.last().expect("no instructions");
+        assert_eq!((last.pc, last.name.as_str()), (0x26, "RETF"));
+        assert!(last.block > instructions[0].block);
+    }
+}
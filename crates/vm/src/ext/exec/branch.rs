@@ -0,0 +1,80 @@
+use alloy::primitives::U256;
+
+use crate::core::{
+    eof::{immediate_size, relative_jump_targets},
+    opcodes::{JUMPI, RJUMPI, RJUMPV},
+    vm::Instruction,
+};
+
+/// A conditional branch encountered during symbolic execution.
+#[derive(Clone, Debug)]
+pub(crate) struct Branch {
+    /// The solidified branch condition, if any.
+    pub(crate) condition: Option<String>,
+    /// The (first) jump destination of the branch, used to identify the jump.
+    pub(crate) jumpdest: U256,
+    /// Whether the VM took the jump, rather than falling through.
+    pub(crate) taken: bool,
+    /// The instructions of every successor the VM did not continue to.
+    pub(crate) alternatives: Vec<u128>,
+}
+
+impl Branch {
+    /// Builds a [`Branch`] from an executed JUMPI, RJUMPI or RJUMPV instruction. Returns `None`
+    /// for any other instruction.
+    pub(crate) fn from_instruction(instruction: &Instruction, bytecode: &[u8]) -> Option<Self> {
+        match instruction.opcode {
+            JUMPI => {
+                let jumpdest = *instruction.inputs.first()?;
+                let taken = instruction.inputs.get(1).map(|op| !op.is_zero()).unwrap_or(true);
+                let alternative = if taken {
+                    instruction.instruction + 1
+                } else {
+                    jumpdest.try_into().unwrap_or(u128::MAX).saturating_add(1)
+                };
+
+                Some(Self {
+                    condition: instruction.input_operations.get(1).map(|op| op.solidify()),
+                    jumpdest,
+                    taken,
+                    alternatives: vec![alternative],
+                })
+            }
+            RJUMPI | RJUMPV => {
+                let offset = (instruction.instruction - 1) as usize;
+                let fallthrough = (offset + 1 + immediate_size(bytecode, offset)) as u128 + 1;
+                let targets = relative_jump_targets(bytecode, offset)
+                    .into_iter()
+                    .map(|target| target as u128 + 1)
+                    .collect::<Vec<_>>();
+
+                // RJUMPI jumps on a non-zero condition, RJUMPV jumps on an in-range case
+                let selector = *instruction.inputs.first()?;
+                let followed = match instruction.opcode {
+                    RJUMPI if !selector.is_zero() => targets.first().copied(),
+                    RJUMPV => {
+                        usize::try_from(selector).ok().and_then(|case| targets.get(case).copied())
+                    }
+                    _ => None,
+                };
+
+                let mut alternatives = Vec::new();
+                for successor in targets.iter().copied().chain(std::iter::once(fallthrough)) {
+                    if Some(successor) != followed.or(Some(fallthrough)) &&
+                        !alternatives.contains(&successor)
+                    {
+                        alternatives.push(successor);
+                    }
+                }
+
+                Some(Self {
+                    condition: instruction.input_operations.first().map(|op| op.solidify()),
+                    jumpdest: U256::from(*targets.first()? - 1),
+                    taken: followed.is_some(),
+                    alternatives,
+                })
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use tracing::trace;
+
+use crate::core::{
+    opcodes::{WrappedInput, WrappedOpcode, ADD, ISZERO, PUSH0, PUSH32, SUB},
+    stack::Stack,
+};
+
+use super::{jump_frame::JumpFrame, VMTrace};
+
+/// A loop recovered during symbolic execution.
+///
+/// Loops are recorded on the [`VMTrace`] of their header, i.e. the branch whose conditional jump
+/// is re-entered through a back-edge from one of its own descendants.
+#[derive(Clone, Debug)]
+pub struct LoopInfo {
+    /// The instruction of the conditional jump heading the loop
+    pub header: u128,
+
+    /// The instruction at which the loop body begins
+    pub body: u128,
+
+    /// The instruction at which execution continues once the loop exits
+    pub exit: u128,
+
+    /// The raw condition of the conditional jump heading the loop
+    pub condition: WrappedOpcode,
+
+    /// Whether the loop body is entered when the jump is taken. If false, the body is the
+    /// fallthrough and the loop continues while `condition` is zero.
+    pub body_on_jump: bool,
+
+    /// The induction variable of the loop, if one could be identified
+    pub induction: Option<InductionVariable>,
+}
+
+impl LoopInfo {
+    /// Returns the condition under which the loop body is executed.
+    pub fn continue_condition(&self) -> WrappedOpcode {
+        if self.body_on_jump {
+            return self.condition.clone();
+        }
+
+        // avoid double negation when the header jumps out of the loop on `ISZERO(condition)`
+        match (self.condition.opcode, self.condition.inputs.as_slice()) {
+            (ISZERO, [WrappedInput::Opcode(inner)]) => inner.as_ref().clone(),
+            _ => WrappedOpcode::new(
+                ISZERO,
+                vec![WrappedInput::Opcode(Arc::new(self.condition.clone()))],
+            ),
+        }
+    }
+}
+
+/// A stack value which is incremented or decremented by a constant step on every iteration of a
+/// loop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InductionVariable {
+    /// The position of the variable on the stack at the loop header, where 0 is the top
+    pub position: usize,
+
+    /// The operation producing the value of the variable upon entering the loop
+    pub init: WrappedOpcode,
+
+    /// The amount the variable changes by on every iteration
+    pub step: WrappedInput,
+
+    /// Whether the variable is decremented, rather than incremented, by `step`
+    pub decreasing: bool,
+}
+
+/// Find the induction variable of a loop, given the stack at its header and the stack upon
+/// reaching the header again through a back-edge.
+///
+/// A stack position is considered an induction variable if its value at the back-edge is the
+/// result of adding to (or subtracting from) its value at the header. Since the VM folds
+/// arithmetic on constants, a constant at the header which was folded into a different constant
+/// by the back-edge is also considered an induction variable, stepping by the difference. Positions
+/// whose initial value appears in `condition` are preferred, since they are most likely the loop
+/// counter.
+pub(super) fn find_induction_variable(
+    header_stack: &Stack,
+    back_edge_stack: &Stack,
+    condition: Option<&WrappedOpcode>,
+) -> Option<InductionVariable> {
+    let mut candidates = Vec::new();
+
+    for (position, (initial, current)) in
+        header_stack.stack.iter().zip(back_edge_stack.stack.iter()).enumerate()
+    {
+        if initial == current {
+            continue;
+        }
+
+        let init = WrappedInput::Opcode(Arc::new(initial.operation.clone()));
+        let operation = &current.operation;
+        let (step, decreasing) = match (operation.opcode, operation.inputs.as_slice()) {
+            (ADD, [a, b]) if a == &init => (b.clone(), false),
+            (ADD, [a, b]) if b == &init => (a.clone(), false),
+            (SUB, [a, b]) if a == &init => (b.clone(), true),
+            _ if is_constant(&initial.operation) && is_folded(operation) => {
+                match current.value.cmp(&initial.value) {
+                    Ordering::Greater => (WrappedInput::Raw(current.value - initial.value), false),
+                    Ordering::Less => (WrappedInput::Raw(initial.value - current.value), true),
+                    Ordering::Equal => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        candidates.push(InductionVariable {
+            position,
+            init: initial.operation.clone(),
+            step,
+            decreasing,
+        });
+    }
+
+    // prefer a candidate which the loop condition depends on
+    let referenced = condition.and_then(|condition| {
+        candidates.iter().position(|candidate| references(condition, &candidate.init))
+    });
+
+    match referenced {
+        Some(index) => Some(candidates.swap_remove(index)),
+        None => candidates.into_iter().next(),
+    }
+}
+
+/// Returns true if `operation` is a constant pushed onto the stack.
+fn is_constant(operation: &WrappedOpcode) -> bool {
+    (PUSH0..=PUSH32).contains(&operation.opcode) &&
+        operation.inputs.iter().all(|input| matches!(input, WrappedInput::Raw(_)))
+}
+
+/// Returns true if `operation` is the result of the VM folding arithmetic on constants, which is
+/// always represented as a `PUSH32`.
+fn is_folded(operation: &WrappedOpcode) -> bool {
+    operation.opcode == PUSH32 && is_constant(operation)
+}
+
+/// Returns true if `operation` is, or is derived from, `target`.
+fn references(operation: &WrappedOpcode, target: &WrappedOpcode) -> bool {
+    operation == target ||
+        operation.inputs.iter().any(|input| match input {
+            WrappedInput::Opcode(inner) => references(inner, target),
+            WrappedInput::Raw(_) => false,
+        })
+}
+
+/// Ends `trace` at a jump which matched a loop-detection heuristic. If the jump heads a loop the
+/// current path is already inside of, the trace is kept as a back-edge to that header so the loop
+/// body isn't lost. Otherwise, the path is discarded.
+pub(super) fn terminate_at_jump(
+    mut trace: VMTrace,
+    jump_frame: &JumpFrame,
+    path: &[JumpFrame],
+) -> Option<VMTrace> {
+    let is_back_edge = path.iter().any(|frame| {
+        frame.pc == jump_frame.pc &&
+            frame.jumpdest == jump_frame.jumpdest &&
+            frame.stack_depth == jump_frame.stack_depth
+    });
+
+    if !is_back_edge {
+        return None;
+    }
+
+    trace!("recording back-edge to loop header at instruction {}", jump_frame.pc);
+    trace.back_edge = Some(jump_frame.pc);
+    Some(trace)
+}
+
+/// Find the first trace in `trace`'s subtree which ends with a back-edge to `header`. Nested
+/// loops headed by the same instruction are not searched, as their back-edges belong to them.
+pub(super) fn find_back_edge(trace: &VMTrace, header: u128) -> Option<&VMTrace> {
+    if trace.back_edge == Some(header) {
+        return Some(trace);
+    }
+
+    trace
+        .children
+        .iter()
+        .filter(|child| child.loop_info.as_ref().map(|info| info.header) != Some(header))
+        .find_map(|child| find_back_edge(child, header))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+
+    use crate::core::opcodes::{CALLDATALOAD, LT};
+
+    use super::*;
+    use crate::test_utils::push;
+
+    #[test]
+    fn test_find_induction_variable_increment() {
+        let counter = push(0);
+        let bound = WrappedOpcode::new(CALLDATALOAD, vec![WrappedInput::Raw(U256::from(4))]);
+        let next = WrappedOpcode::new(
+            ADD,
+            vec![WrappedInput::Opcode(Arc::new(counter.clone())), WrappedInput::Raw(U256::from(1))],
+        );
+
+        let mut header_stack = Stack::new();
+        header_stack.push(U256::from(0), bound.clone());
+        header_stack.push(U256::from(0), counter.clone());
+
+        let mut back_edge_stack = Stack::new();
+        back_edge_stack.push(U256::from(0), bound.clone());
+        back_edge_stack.push(U256::from(1), next);
+
+        let condition = WrappedOpcode::new(
+            LT,
+            vec![
+                WrappedInput::Opcode(Arc::new(counter.clone())),
+                WrappedInput::Opcode(Arc::new(bound)),
+            ],
+        );
+        let induction =
+            find_induction_variable(&header_stack, &back_edge_stack, Some(&condition)).unwrap();
+
+        assert_eq!(induction.position, 0);
+        assert_eq!(induction.init, counter);
+        assert_eq!(induction.step, WrappedInput::Raw(U256::from(1)));
+        assert!(!induction.decreasing);
+    }
+
+    #[test]
+    fn test_find_induction_variable_decrement() {
+        let counter = push(10);
+        let next = WrappedOpcode::new(
+            SUB,
+            vec![WrappedInput::Opcode(Arc::new(counter.clone())), WrappedInput::Raw(U256::from(2))],
+        );
+
+        let mut header_stack = Stack::new();
+        header_stack.push(U256::from(10), counter.clone());
+
+        let mut back_edge_stack = Stack::new();
+        back_edge_stack.push(U256::from(8), next);
+
+        let induction = find_induction_variable(&header_stack, &back_edge_stack, None).unwrap();
+
+        assert_eq!(induction.init, counter);
+        assert!(induction.decreasing);
+    }
+
+    #[test]
+    fn test_find_induction_variable_folded_constant() {
+        let mut header_stack = Stack::new();
+        header_stack.push(U256::from(0), push(0));
+        header_stack.push(U256::from(0), push(0));
+
+        // the VM folds `ADD(1, 0)` into a constant, and the accumulator is unchanged
+        let mut back_edge_stack = Stack::new();
+        back_edge_stack
+            .push(U256::from(0), WrappedOpcode::new(PUSH32, vec![WrappedInput::Raw(U256::ZERO)]));
+        back_edge_stack.push(
+            U256::from(1),
+            WrappedOpcode::new(PUSH32, vec![WrappedInput::Raw(U256::from(1))]),
+        );
+
+        let induction = find_induction_variable(&header_stack, &back_edge_stack, None).unwrap();
+
+        assert_eq!(induction.position, 0);
+        assert_eq!(induction.step, WrappedInput::Raw(U256::from(1)));
+        assert!(!induction.decreasing);
+    }
+
+    #[test]
+    fn test_continue_condition_strips_negation() {
+        let condition = WrappedOpcode::new(LT, vec![WrappedInput::Raw(U256::from(1))]);
+        let mut loop_info = LoopInfo {
+            header: 1,
+            body: 2,
+            exit: 3,
+            condition: WrappedOpcode::new(
+                ISZERO,
+                vec![WrappedInput::Opcode(Arc::new(condition.clone()))],
+            ),
+            body_on_jump: false,
+            induction: None,
+        };
+        assert_eq!(loop_info.continue_condition(), condition);
+
+        loop_info.body_on_jump = true;
+        assert_eq!(loop_info.continue_condition().opcode, ISZERO);
+    }
+
+    #[test]
+    fn test_find_induction_variable_none() {
+        let mut header_stack = Stack::new();
+        header_stack.push(U256::from(1), push(1));
+
+        let mut back_edge_stack = Stack::new();
+        back_edge_stack.push(U256::from(2), push(2));
+
+        assert!(find_induction_variable(&header_stack, &back_edge_stack, None).is_none());
+    }
+}
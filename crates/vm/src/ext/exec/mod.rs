@@ -1,21 +1,31 @@
+pub(crate) mod branch;
 mod jump_frame;
+mod loops;
 mod util;
 
+pub use loops::{InductionVariable, LoopInfo};
+
 use crate::{
     core::{
+        opcodes::{opcode_name, JUMPI, RJUMPI},
         stack::Stack,
-        vm::{State, VM},
+        vm::{CallFrame, State, VM},
     },
-    ext::exec::{
-        jump_frame::JumpFrame,
-        util::{
-            historical_diffs_approximately_equal, jump_condition_appears_recursive,
-            jump_condition_contains_mutated_memory_access,
-            jump_condition_contains_mutated_storage_access,
-            jump_stack_depth_less_than_max_stack_depth, stack_contains_too_many_items,
-            stack_contains_too_many_of_the_same_item, stack_diff, stack_item_source_depth_too_deep,
-            stack_position_shows_pattern,
+    ext::{
+        exec::{
+            branch::Branch,
+            jump_frame::JumpFrame,
+            loops::{find_back_edge, find_induction_variable, terminate_at_jump},
+            util::{
+                historical_diffs_approximately_equal, jump_condition_appears_recursive,
+                jump_condition_contains_mutated_memory_access,
+                jump_condition_contains_mutated_storage_access,
+                jump_stack_depth_less_than_max_stack_depth, stack_contains_too_many_items,
+                stack_contains_too_many_of_the_same_item, stack_diff,
+                stack_item_source_depth_too_deep, stack_position_shows_pattern,
+            },
         },
+        solver::{is_constant, PathConstraint, Satisfiability},
     },
 };
 use eyre::Result;
@@ -39,8 +49,45 @@ pub struct VMTrace {
     /// The sequence of VM states recorded during execution
     pub operations: Vec<State>,
 
-    /// Child traces resulting from internal calls (CALL, DELEGATECALL, etc.)
+    /// Child traces resulting from branching paths (JUMPI, RJUMPI, etc.)
     pub children: Vec<VMTrace>,
+
+    /// Traces of the message-calls (CALL, DELEGATECALL, etc.) executed in child frames, in the
+    /// order they were made
+    pub calls: Vec<VMTrace>,
+
+    /// The loop headed by this trace's conditional jump, if symbolic execution found a back-edge
+    /// to it
+    pub loop_info: Option<LoopInfo>,
+
+    /// The instruction of the loop header this trace jumps back to, if this trace ends in a
+    /// back-edge rather than continuing execution
+    pub back_edge: Option<u128>,
+
+    /// The branch condition which holds along this trace, or `None` if it isn't the successor of
+    /// a conditional jump
+    pub constraint: Option<PathConstraint>,
+
+    /// Whether the VM's solver proved this trace reachable, given the constraints of every branch
+    /// leading to it. Traces proven unreachable are pruned, so this is never
+    /// [`Satisfiability::Unsat`]
+    pub satisfiability: Satisfiability,
+}
+
+impl From<&CallFrame> for VMTrace {
+    fn from(frame: &CallFrame) -> Self {
+        VMTrace {
+            instruction: frame.entry,
+            gas_used: frame.result.gas_used,
+            calls: frame.calls.iter().map(|call| VMTrace::from(call.as_ref())).collect(),
+            operations: Vec::new(),
+            children: Vec::new(),
+            loop_info: None,
+            back_edge: None,
+            constraint: None,
+            satisfiability: Satisfiability::default(),
+        }
+    }
 }
 
 impl VM {
@@ -74,7 +121,13 @@ impl VM {
 
         // the VM is at the function entry point, begin tracing
         let mut branch_count = 0;
-        let trace = match self.recursive_map(&mut branch_count, &mut HashMap::new(), &timeout)? {
+        let trace = match self.recursive_map(
+            &mut branch_count,
+            &mut HashMap::new(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &timeout,
+        )? {
             Some(trace) => trace,
             None => {
                 warn!("symbolic execution returned no valid traces for selector 0x{}", selector);
@@ -83,6 +136,11 @@ impl VM {
                     gas_used: self.gas_used,
                     operations: Vec::new(),
                     children: Vec::new(),
+                    calls: Vec::new(),
+                    loop_info: None,
+                    back_edge: None,
+                    constraint: None,
+                    satisfiability: Satisfiability::default(),
                 }
             }
         };
@@ -107,7 +165,13 @@ impl VM {
 
         // the VM is at the function entry point, begin tracing
         let mut branch_count = 0;
-        let trace = match self.recursive_map(&mut branch_count, &mut HashMap::new(), &timeout)? {
+        let trace = match self.recursive_map(
+            &mut branch_count,
+            &mut HashMap::new(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &timeout,
+        )? {
             Some(trace) => trace,
             None => {
                 warn!("symbolic execution returned no valid traces");
@@ -116,6 +180,11 @@ impl VM {
                     gas_used: self.gas_used,
                     operations: Vec::new(),
                     children: Vec::new(),
+                    calls: Vec::new(),
+                    loop_info: None,
+                    back_edge: None,
+                    constraint: None,
+                    satisfiability: Satisfiability::default(),
                 }
             }
         };
@@ -126,6 +195,8 @@ impl VM {
         &mut self,
         branch_count: &mut u32,
         handled_jumps: &mut HashMap<JumpFrame, Vec<Stack>>,
+        path: &mut Vec<JumpFrame>,
+        constraints: &mut Vec<PathConstraint>,
         timeout_at: &Instant,
     ) -> Result<Option<VMTrace>> {
         let vm = self;
@@ -138,6 +209,17 @@ impl VM {
             gas_used: 0,
             operations: Vec::new(),
             children: Vec::new(),
+            calls: Vec::new(),
+            loop_info: None,
+            back_edge: None,
+            constraint: None,
+            // a path without any constraints is always reachable. otherwise, the trace is
+            // annotated by its parent branch
+            satisfiability: if constraints.is_empty() {
+                Satisfiability::Sat
+            } else {
+                Satisfiability::Unknown
+            },
         };
 
         // step through the bytecode until we find a JUMPI instruction
@@ -157,51 +239,58 @@ impl VM {
             };
             let last_instruction = state.last_instruction.clone();
 
+            // record any message-call executed in a child frame
+            if let Some(frame) = &state.call {
+                vm_trace.calls.push(VMTrace::from(frame.as_ref()));
+            }
+
             // update vm_trace
             vm_trace.operations.push(state);
             vm_trace.gas_used = vm.gas_used;
 
-            // if we encounter a JUMP(I), create children taking both paths and break
-            if last_instruction.opcode == 0x57 {
+            // if we encounter a JUMPI, RJUMPI or RJUMPV, create children taking all paths and break
+            if let Some(branch) = Branch::from_instruction(&last_instruction, &vm.bytecode) {
                 trace!(
-                    "found branch due to JUMP{} instruction at {}",
-                    if last_instruction.opcode == 0x57 { "I" } else { "" },
+                    "found branch due to {} instruction at {}",
+                    opcode_name(last_instruction.opcode),
                     last_instruction.instruction
                 );
 
-                let jump_condition: Option<String> =
-                    last_instruction.input_operations.get(1).map(|op| op.solidify());
-                let jump_taken =
-                    last_instruction.inputs.get(1).map(|op| !op.is_zero()).unwrap_or(true);
+                let jump_condition = branch.condition;
+                let jump_taken = branch.taken;
 
                 // build hashable jump frame
                 let jump_frame = JumpFrame::new(
                     last_instruction.instruction,
-                    last_instruction.inputs[0],
+                    branch.jumpdest,
                     vm.stack.size(),
                     jump_taken,
                 );
 
+                // each of the heuristics below ends the path at this jump. if the jump heads a
+                // loop the path is already inside of, the trace is kept as a back-edge so the loop
+                // body is recovered, rather than being cut off along with the rest of the path
+
                 // if the stack contains too many items, it's probably a loop
                 if stack_contains_too_many_items(&vm.stack) {
-                    return Ok(None);
+                    return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                 }
 
                 // if the stack has over 16 items of the same source, it's probably a loop
                 if stack_contains_too_many_of_the_same_item(&vm.stack) {
-                    return Ok(None);
+                    return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                 }
 
                 // if any item on the stack has a depth > 16, it's probably a loop (because of stack
                 // too deep)
                 if stack_item_source_depth_too_deep(&vm.stack) {
-                    return Ok(None);
+                    return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                 }
 
                 // if the jump stack depth is less than the max stack depth of all previous matching
                 // jumps, it's probably a loop
                 if jump_stack_depth_less_than_max_stack_depth(&jump_frame, handled_jumps) {
-                    return Ok(None);
+                    return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                 }
 
                 // perform heuristic checks on historical stacks
@@ -266,7 +355,7 @@ impl VM {
 
                             // this key exists, but the stack is different, so the jump is new
                             historical_stacks.push(vm.stack.clone());
-                            return Ok(None);
+                            return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                         }
 
                         // check if any stack position shows a consistent pattern
@@ -281,7 +370,7 @@ impl VM {
 
                             // this key exists, but the stack is different, so the jump is new
                             historical_stacks.push(vm.stack.clone());
-                            return Ok(None);
+                            return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                         }
 
                         if historical_diffs_approximately_equal(&vm.stack, historical_stacks) {
@@ -294,7 +383,7 @@ impl VM {
 
                             // this key exists, but the stack is different, so the jump is new
                             historical_stacks.push(vm.stack.clone());
-                            return Ok(None);
+                            return Ok(terminate_at_jump(vm_trace, &jump_frame, path));
                         }
 
                         {
@@ -317,71 +406,111 @@ impl VM {
                     None => {
                         // this key doesnt exist, so the jump is new
                         trace!("added new jump frame: {:?}", jump_frame);
-                        handled_jumps.insert(jump_frame, vec![vm.stack.clone()]);
+                        handled_jumps.insert(jump_frame.clone(), vec![vm.stack.clone()]);
                     }
                 }
 
-                if last_instruction.opcode == 0x56 {
-                    continue;
-                }
-
                 // we didnt break out, so now we crate branching paths to cover all possibilities
                 *branch_count += 1;
                 trace!(
-                    "creating branching paths at instructions {} (JUMPDEST) and {} (CONTINUE)",
-                    last_instruction.inputs[0],
-                    last_instruction.instruction + 1
+                    "creating branching paths at instructions {:?} (JUMPDEST) and {} (CONTINUE)",
+                    branch.alternatives,
+                    vm.instruction
                 );
 
-                // we need to create a trace for the path that wasn't taken.
-                if !jump_taken {
+                // keep track of the jumps on the current path, so back-edges can be recognized
+                let header_stack = vm.stack.clone();
+                path.push(jump_frame);
+
+                // JUMPI and RJUMPI constrain their condition along both successors. RJUMPV
+                // conditions select a case rather than being truthy, so they aren't constrained
+                let condition = match last_instruction.opcode {
+                    JUMPI => last_instruction.input_operations.get(1),
+                    RJUMPI => last_instruction.input_operations.first(),
+                    _ => None,
+                };
+                let constraint = |holds: bool| {
+                    condition.map(|condition| PathConstraint::new(condition.clone(), holds))
+                };
+
+                // we need to create a trace for each path that wasn't taken.
+                for alternative in branch.alternatives {
                     // push a new vm trace to the children
                     let mut trace_vm = vm.clone();
-                    trace_vm.instruction =
-                        last_instruction.inputs[0].try_into().unwrap_or(u128::MAX) + 1;
-                    match trace_vm.recursive_map(branch_count, handled_jumps, timeout_at) {
+                    trace_vm.instruction = alternative;
+                    match trace_vm.map_branch(
+                        constraint(!jump_taken),
+                        branch_count,
+                        handled_jumps,
+                        path,
+                        constraints,
+                        timeout_at,
+                    ) {
                         Ok(Some(child_trace)) => vm_trace.children.push(child_trace),
                         Ok(None) => {}
                         Err(e) => {
                             warn!("error executing branch: {:?}", e);
+                            path.pop();
                             return Ok(None);
                         }
                     }
+                }
 
-                    // push the current path onto the stack
-                    match vm.recursive_map(branch_count, handled_jumps, timeout_at) {
-                        Ok(Some(child_trace)) => vm_trace.children.push(child_trace),
-                        Ok(None) => {}
-                        Err(e) => {
-                            warn!("error executing branch: {:?}", e);
-                            return Ok(None);
-                        }
-                    }
-                    break;
-                } else {
-                    // push a new vm trace to the children
-                    let mut trace_vm = vm.clone();
-                    trace_vm.instruction = last_instruction.instruction + 1;
-                    match trace_vm.recursive_map(branch_count, handled_jumps, timeout_at) {
-                        Ok(Some(child_trace)) => vm_trace.children.push(child_trace),
-                        Ok(None) => {}
-                        Err(e) => {
-                            warn!("error executing branch: {:?}", e);
-                            return Ok(None);
-                        }
+                // push the current path onto the stack
+                let result = vm.map_branch(
+                    constraint(jump_taken),
+                    branch_count,
+                    handled_jumps,
+                    path,
+                    constraints,
+                    timeout_at,
+                );
+                path.pop();
+                match result {
+                    Ok(Some(child_trace)) => vm_trace.children.push(child_trace),
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!("error executing branch: {:?}", e);
+                        return Ok(None);
                     }
+                }
 
-                    // push the current path onto the stack
-                    match vm.recursive_map(branch_count, handled_jumps, timeout_at) {
-                        Ok(Some(child_trace)) => vm_trace.children.push(child_trace),
-                        Ok(None) => {}
-                        Err(e) => {
-                            warn!("error executing branch: {:?}", e);
-                            return Ok(None);
-                        }
+                // if a descendant jumped back to this branch, it heads a loop. JUMPI and RJUMPI
+                // have exactly two successors, so the one containing the back-edge is the body
+                // and the other is the exit
+                if let (Some(condition), [first, second]) =
+                    (condition, vm_trace.children.as_slice())
+                {
+                    let header = last_instruction.instruction;
+                    let found = find_back_edge(first, header)
+                        .map(|back_edge| (first, second, back_edge))
+                        .or_else(|| {
+                            find_back_edge(second, header)
+                                .map(|back_edge| (second, first, back_edge))
+                        });
+
+                    if let Some((body, exit, back_edge)) = found {
+                        trace!("found loop with header at instruction {}", header);
+                        let induction = back_edge.operations.last().and_then(|state| {
+                            find_induction_variable(&header_stack, &state.stack, Some(condition))
+                        });
+                        let body_on_jump = branch
+                            .jumpdest
+                            .try_into()
+                            .map(|jumpdest: u128| jumpdest.saturating_add(1) == body.instruction)
+                            .unwrap_or(false);
+
+                        vm_trace.loop_info = Some(LoopInfo {
+                            header,
+                            body: body.instruction,
+                            exit: exit.instruction,
+                            condition: condition.clone(),
+                            body_on_jump,
+                            induction,
+                        });
                     }
-                    break;
                 }
+                break;
             }
 
             // when the vm exits, this path is complete
@@ -392,9 +521,107 @@ impl VM {
 
         Ok(Some(vm_trace))
     }
+
+    /// Symbolically executes the successor of a branch, which is followed when `constraint` holds.
+    /// If the VM's solver proves the successor unreachable given the constraints of the current
+    /// path, it's pruned without being executed.
+    fn map_branch(
+        &mut self,
+        constraint: Option<PathConstraint>,
+        branch_count: &mut u32,
+        handled_jumps: &mut HashMap<JumpFrame, Vec<Stack>>,
+        path: &mut Vec<JumpFrame>,
+        constraints: &mut Vec<PathConstraint>,
+        timeout_at: &Instant,
+    ) -> Result<Option<VMTrace>> {
+        // the VM folds the state of the loops it unrolls into constants, which differ between
+        // iterations, so a constant condition doesn't constrain the path and is never checked
+        let constant =
+            constraint.as_ref().is_some_and(|constraint| is_constant(&constraint.condition));
+        let constrained = constraint.is_some() && !constant;
+        if constrained {
+            constraints.extend(constraint.clone());
+        }
+
+        let satisfiability = match &self.solver {
+            _ if constant => Satisfiability::Unknown,
+            _ if constraints.is_empty() => Satisfiability::Sat,
+            Some(solver) if constrained => solver.check(constraints),
+            _ => Satisfiability::Unknown,
+        };
+
+        let result = if satisfiability.is_feasible() {
+            self.recursive_map(branch_count, handled_jumps, path, constraints, timeout_at).map(
+                |trace| {
+                    trace.map(|mut trace| {
+                        trace.constraint = constraint;
+                        trace.satisfiability = satisfiability;
+                        trace
+                    })
+                },
+            )
+        } else {
+            trace!("pruning infeasible branch at instruction {}", self.instruction);
+            Ok(None)
+        };
+
+        if constrained {
+            constraints.pop();
+        }
+        result
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO: add tests for symbolic execution & recursive_map
+    use std::{sync::Arc, time::Duration};
+
+    use alloy::primitives::Address;
+
+    use super::*;
+    use crate::ext::solver::{IntervalSolver, Solver};
+
+    /// Requires `x > 5` and then `x < 3`, where `x` is the first calldata word, before reaching the
+    /// JUMPDEST at 0x15.
+    const CONTRADICTORY_BRANCHES: &str = "600560003511600a57005b600360003510601557005b00";
+
+    fn reaches(trace: &VMTrace, instruction: u128) -> bool {
+        trace.instruction == instruction ||
+            trace.children.iter().any(|child| reaches(child, instruction))
+    }
+
+    fn symbolic_exec(solver: Option<Arc<dyn Solver>>) -> VMTrace {
+        let mut vm = VM::new(
+            &decode_hex(CONTRADICTORY_BRANCHES).expect("invalid bytecode"),
+            &[],
+            Address::default(),
+            Address::default(),
+            Address::default(),
+            0,
+            u128::MAX,
+        );
+        if let Some(solver) = solver {
+            vm = vm.with_solver(solver);
+        }
+
+        let (trace, _) = vm
+            .symbolic_exec(Instant::now() + Duration::from_secs(10))
+            .expect("symbolic execution failed");
+        trace
+    }
+
+    #[test]
+    fn test_symbolic_exec_explores_every_branch_without_solver() {
+        // the trace of the JUMPDEST at 0x15 starts at the instruction after it
+        assert!(reaches(&symbolic_exec(None), 0x16));
+    }
+
+    #[test]
+    fn test_symbolic_exec_prunes_infeasible_branch() {
+        let trace = symbolic_exec(Some(Arc::new(IntervalSolver)));
+
+        // the first branch is still taken, but `x < 3` can't hold once `x > 5` does
+        assert!(reaches(&trace, 0x0b));
+        assert!(!reaches(&trace, 0x16));
+    }
 }
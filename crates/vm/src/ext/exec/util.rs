@@ -14,8 +14,9 @@ use super::jump_frame::JumpFrame;
 pub(super) fn stack_diff(a: &Stack, b: &Stack) -> Vec<StackFrame> {
     let mut diff = Vec::new();
 
-    for (i, frame) in a.stack.iter().enumerate() {
-        if b.stack.len() <= i || frame != &b.stack[i] {
+    let mut b_frames = b.stack.iter();
+    for frame in a.stack.iter() {
+        if b_frames.next() != Some(frame) {
             diff.push(frame.clone());
         }
     }
@@ -0,0 +1,245 @@
+//! Analyzes the layout of legacy bytecode, separating the executable code from the metadata, data
+//! and constructor arguments the compiler and deployer append to it.
+
+use std::ops::Range;
+
+use heimdall_common::ether::metadata::CompilerMetadata;
+
+use crate::core::opcodes::{
+    OpCodeInfo, INVALID, JUMP, JUMPDEST, JUMPI, LOG0, LOG4, PUSH1, PUSH32, SLOAD, SSTORE,
+};
+
+/// The layout of a contract's bytecode.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BytecodeLayout {
+    /// The range of executable code, which always begins at the start of the bytecode
+    pub code: Range<usize>,
+
+    /// Data which follows the code, i.e. bytes after an `INVALID` which no `JUMPDEST` follows
+    pub data: Option<Range<usize>>,
+
+    /// The CBOR-encoded compiler metadata, if it could be found and decoded
+    pub metadata: Option<CompilerMetadata>,
+
+    /// Bytes following the metadata. For creation code, these are the ABI-encoded constructor
+    /// arguments
+    pub constructor_args: Option<Range<usize>>,
+
+    /// The ranges of `PUSH32` values within the code which are likely immutables, see
+    /// [`is_likely_immutable`]
+    pub immutables: Vec<Range<usize>>,
+}
+
+impl BytecodeLayout {
+    /// Analyzes the layout of `bytecode`. EOF containers declare their own sections, so their
+    /// bytecode is considered to be code in its entirety.
+    pub fn analyze(bytecode: &[u8]) -> Self {
+        if bytecode.starts_with(&[0xef, 0x00]) {
+            return Self { code: 0..bytecode.len(), ..Default::default() };
+        }
+
+        let metadata = CompilerMetadata::find(bytecode);
+        let code_end = metadata.as_ref().map_or(bytecode.len(), |metadata| metadata.range.start);
+        let constructor_args = metadata
+            .as_ref()
+            .map(|metadata| metadata.range.end..bytecode.len())
+            .filter(|args| !args.is_empty());
+
+        // code after the last JUMPDEST can only be reached by falling through, so nothing after
+        // the first INVALID which follows it is executable
+        let instructions = instruction_offsets(&bytecode[..code_end]);
+        let last_jumpdest = instructions
+            .iter()
+            .rposition(|offset| bytecode[*offset] == JUMPDEST)
+            .unwrap_or_default();
+        let data = instructions[last_jumpdest..]
+            .iter()
+            .find(|offset| bytecode[**offset] == INVALID)
+            .map(|offset| offset + 1..code_end)
+            .filter(|data| !data.is_empty());
+        let code = 0..data.as_ref().map_or(code_end, |data| data.start);
+
+        let instructions =
+            instructions.into_iter().take_while(|offset| *offset < code.end).collect::<Vec<_>>();
+        let immutables = (0..instructions.len())
+            .filter(|index| is_likely_immutable(&bytecode[..code.end], &instructions, *index))
+            .map(|index| instructions[index] + 1..instructions[index] + 33)
+            .collect();
+
+        Self { code, data, metadata, constructor_args, immutables }
+    }
+
+    /// Returns true if the PUSH32 at `offset` pushes a likely immutable.
+    pub fn is_immutable(&self, offset: usize) -> bool {
+        self.immutables.iter().any(|immutable| immutable.start == offset + 1)
+    }
+}
+
+/// Returns the offset of every instruction in `code`, skipping PUSH data. A PUSH whose data is
+/// cut short by the end of the code is still included.
+fn instruction_offsets(code: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    while offset < code.len() {
+        offsets.push(offset);
+        offset += match code[offset] {
+            opcode @ PUSH1..=PUSH32 => (opcode - PUSH1) as usize + 2,
+            _ => 1,
+        };
+    }
+
+    offsets
+}
+
+/// Returns true if the instruction at `instructions[index]` is a `PUSH32` of a likely immutable.
+///
+/// Compilers push constants with the smallest `PUSH` that fits them, while immutables are always
+/// reserved 32 bytes, so a value with a leading zero byte is one. Values which fill the word are
+/// immutables unless they look like one of the constants compilers push in full: masks, selectors
+/// and strings padded to a word, and the event topics and storage slots consumed within the same
+/// block.
+fn is_likely_immutable(bytecode: &[u8], instructions: &[usize], index: usize) -> bool {
+    let offset = instructions[index];
+    let Some(value) = bytecode.get(offset + 1..offset + 33) else {
+        return false;
+    };
+    if bytecode[offset] != PUSH32 {
+        return false;
+    }
+    if value[0] == 0 {
+        return true;
+    }
+
+    let is_mask = value.iter().all(|byte| *byte == 0 || *byte == 0xff);
+    let is_padded = value.ends_with(&[0; 4]);
+    let is_text = value.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' ');
+    if is_mask || is_padded || is_text {
+        return false;
+    }
+
+    // an event topic or storage slot is consumed before the block it's pushed in ends
+    !instructions[index + 1..]
+        .iter()
+        .map(|offset| bytecode[*offset])
+        .take_while(|opcode| {
+            ![JUMPDEST, JUMP, JUMPI].contains(opcode) && !OpCodeInfo::from(*opcode).terminating()
+        })
+        .any(|opcode| (LOG0..=LOG4).contains(&opcode) || opcode == SLOAD || opcode == SSTORE)
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::{ether::compiler::Compiler, utils::strings::decode_hex};
+
+    use super::*;
+
+    /// `PUSH1 0x80, PUSH1 0x40, MSTORE, INVALID`, followed by solc 0.8.19 metadata
+    const SOLC_RUNTIME: &str = concat!(
+        "6080604052fe",
+        "a2646970667358221220",
+        "7d3cd6e4f6b1a0e6b0a6d1b5a4f8e5c0b0d7e0f3c0a3e1d6a2c4f0b7e8d9c2a164",
+        "736f6c63430008130033"
+    );
+
+    #[test]
+    fn test_analyze_solc_metadata() {
+        let bytecode = decode_hex(SOLC_RUNTIME).expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert_eq!(layout.code, 0..6);
+        assert_eq!(layout.data, None);
+        assert_eq!(layout.constructor_args, None);
+
+        let metadata = layout.metadata.expect("no metadata");
+        assert_eq!(metadata.range, 6..bytecode.len());
+        assert_eq!(metadata.compiler, Compiler::Solc);
+    }
+
+    #[test]
+    fn test_analyze_constructor_args() {
+        let bytecode =
+            decode_hex(&format!("{SOLC_RUNTIME}{:064x}", 42)).expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        let metadata = layout.metadata.expect("no metadata");
+        assert_eq!(layout.constructor_args, Some(metadata.range.end..bytecode.len()));
+    }
+
+    #[test]
+    fn test_analyze_bzzr0_metadata() {
+        // INVALID, LOG1 ... as in contracts compiled with solc 0.4
+        let bytecode = decode_hex(concat!(
+            "00fe",
+            "a165627a7a72305820",
+            "1e7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8",
+            "0029"
+        ))
+        .expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert!(layout.metadata.is_some());
+        assert_eq!(layout.code, 0..2);
+    }
+
+    #[test]
+    fn test_analyze_data_after_invalid() {
+        // JUMPDEST, STOP, INVALID, then data which isn't reachable
+        let bytecode = decode_hex("5b00fe11223344").expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert_eq!(layout.code, 0..3);
+        assert_eq!(layout.data, Some(3..7));
+        assert_eq!(layout.metadata, None);
+    }
+
+    #[test]
+    fn test_analyze_immutables() {
+        // PUSH32 of an address, which solc would push with PUSH20 unless it's an immutable
+        let address = "1bf797219482a29013d804ad96d1c6f84fba4c45";
+        let bytecode =
+            decode_hex(&format!("7f{address:0>64}00")).expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert_eq!(layout.immutables, vec![1..33]);
+        assert!(layout.is_immutable(0));
+    }
+
+    #[test]
+    fn test_analyze_full_width_immutables() {
+        // PUSH32 of a hash, i.e. an immutable domain separator, which is returned
+        let hash = "8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f";
+        let bytecode = decode_hex(&format!("7f{hash}5f5260205ff3")).expect("failed to decode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert_eq!(layout.immutables, vec![1..33]);
+    }
+
+    #[test]
+    fn test_analyze_full_width_constants() {
+        // a mask, a padded selector, a string, and an event topic, none of which are immutables
+        let topic = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+        let bytecode = decode_hex(&format!(
+            "7f{}7f08c379a0{}7f{}7f{topic}5f5fa100",
+            "ff".repeat(32),
+            "00".repeat(28),
+            hex_text("Ownable: new owner is the zero a"),
+        ))
+        .expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert!(layout.immutables.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_plain_code() {
+        let bytecode = decode_hex("608060405234801561000f575f5ffd5b50600400")
+            .expect("failed to decode bytecode");
+        let layout = BytecodeLayout::analyze(&bytecode);
+
+        assert_eq!(layout, BytecodeLayout { code: 0..bytecode.len(), ..Default::default() });
+    }
+
+    fn hex_text(text: &str) -> String {
+        text.bytes().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
@@ -6,13 +6,17 @@ use heimdall_common::{
     utils::strings::encode_hex_reduced,
 };
 
-use crate::core::opcodes::{
-    WrappedInput, WrappedOpcode, ADD, ADDMOD, ADDRESS, AND, BALANCE, BASEFEE, BLOCKHASH, BYTE,
-    CALL, CALLCODE, CALLDATALOAD, CALLDATASIZE, CALLER, CALLVALUE, CHAINID, CLZ, CODESIZE,
-    COINBASE, DELEGATECALL, DIV, EQ, EXP, EXTCODEHASH, EXTCODESIZE, GAS, GASLIMIT, GASPRICE, GT,
-    ISZERO, LT, MLOAD, MOD, MSIZE, MUL, MULMOD, NOT, NUMBER, OR, ORIGIN, PREVRANDAO, PUSH0,
-    RETURNDATASIZE, SAR, SDIV, SELFBALANCE, SGT, SHA3, SHL, SHR, SLOAD, SLT, SMOD, STATICCALL, SUB,
-    TIMESTAMP, TLOAD, XOR,
+use crate::{
+    core::opcodes::{
+        WrappedInput, WrappedOpcode, ADD, ADDMOD, ADDRESS, AND, BALANCE, BASEFEE, BLOCKHASH, BYTE,
+        CALL, CALLCODE, CALLDATALOAD, CALLDATASIZE, CALLER, CALLVALUE, CHAINID, CLZ, CODESIZE,
+        COINBASE, DATALOAD, DATALOADN, DATASIZE, DELEGATECALL, DIV, EQ, EXP, EXTCALL, EXTCODEHASH,
+        EXTCODESIZE, EXTDELEGATECALL, EXTSTATICCALL, GAS, GASLIMIT, GASPRICE, GT, ISZERO, LT,
+        MLOAD, MOD, MSIZE, MUL, MULMOD, NOT, NUMBER, OR, ORIGIN, PREVRANDAO, PUSH0, RETURNDATALOAD,
+        RETURNDATASIZE, SAR, SDIV, SELFBALANCE, SGT, SHA3, SHL, SHR, SLOAD, SLT, SMOD, STATICCALL,
+        SUB, TIMESTAMP, TLOAD, XOR,
+    },
+    ext::calldata::CalldataLocation,
 };
 
 /// Checks if a given address is a supported precompiled contract address
@@ -225,8 +229,18 @@ impl WrappedOpcode {
             CALLDATALOAD => {
                 let solidified_slot = self.inputs[0]._solidify();
 
-                // are dealing with a slot that is a constant, we can just use the slot directly
-                if WORD_REGEX.is_match(&solidified_slot).unwrap_or(false) {
+                // reads of dynamic data are rendered relative to the argument they're within
+                let dynamic_value = match &self.inputs[0] {
+                    WrappedInput::Opcode(offset) => CalldataLocation::locate(offset)
+                        .filter(|location| location.depth() > 0)
+                        .and_then(|location| location.solidify()),
+                    WrappedInput::Raw(_) => None,
+                };
+
+                if let Some(dynamic_value) = dynamic_value {
+                    solidified_wrapped_opcode.push_str(&dynamic_value);
+                } else if WORD_REGEX.is_match(&solidified_slot).unwrap_or(false) {
+                    // are dealing with a slot that is a constant, we can just use the slot directly
                     // convert to usize
                     match usize::from_str_radix(&solidified_slot.replacen("0x", "", 1), 16) {
                         Ok(slot) => {
@@ -396,9 +410,23 @@ impl WrappedOpcode {
                     }
                 };
             }
+            EXTCALL | EXTDELEGATECALL | EXTSTATICCALL => {
+                solidified_wrapped_opcode.push_str("success");
+            }
             RETURNDATASIZE => {
                 solidified_wrapped_opcode.push_str("ret0.length");
             }
+            RETURNDATALOAD => {
+                solidified_wrapped_opcode
+                    .push_str(&format!("ret0[{}]", self.inputs[0]._solidify()));
+            }
+            DATALOAD | DATALOADN => {
+                solidified_wrapped_opcode
+                    .push_str(&format!("data[{}]", self.inputs[0]._solidify()));
+            }
+            DATASIZE => {
+                solidified_wrapped_opcode.push_str("data.length");
+            }
             PUSH0 => {
                 solidified_wrapped_opcode.push('0');
             }
@@ -20,8 +20,8 @@ impl WrappedOpcode {
 }
 
 impl WrappedInput {
-    /// Returns a WrappedInput's solidity representation.
-    fn _yulify(&self) -> String {
+    /// Returns a WrappedInput's yul representation.
+    pub fn _yulify(&self) -> String {
         let mut solidified_wrapped_input = String::new();
 
         match self {
@@ -1,12 +1,31 @@
+/// Basic-block analysis of bytecode
+pub mod blocks;
+
+/// Recovery of the ABI structure of calldata accesses
+pub mod calldata;
+
+/// Execution of creation bytecode, recovering the runtime code and constructor it deploys
+pub mod creation;
+
+/// Typed disassembly of bytecode
+pub mod disassembly;
+
 /// Execution utilities for running and analyzing VM operations
 pub mod exec;
 
+/// Layout analysis of legacy bytecode, separating code from metadata, data and constructor
+/// arguments
+pub mod layout;
+
 /// Language lexers for translating EVM bytecode to higher-level languages
 pub mod lexers;
 
 /// Utilities for working with function and event selectors
 pub mod selectors;
 
+/// Constraint solvers used to prune infeasible paths during symbolic execution
+pub mod solver;
+
 /// Experimental range mapping implementation
 #[cfg(feature = "experimental")]
 pub mod range_map;
@@ -0,0 +1,310 @@
+use alloy::primitives::U256;
+use heimdall_common::utils::strings::encode_hex;
+
+use crate::core::{
+    opcodes::{
+        WrappedInput, WrappedOpcode, CALLDATALOAD, CODECOPY, EQ, GT, ISZERO, JUMP, JUMPI, LT, MOD,
+        PUSH0, PUSH32, SGT, SLT, SMOD, SUB, XOR,
+    },
+    vm::{Instruction, VM},
+};
+
+/// The maximum number of instructions executed while probing a contract's dispatcher.
+pub(crate) const MAX_DISPATCHER_STEPS: usize = 10_000;
+
+/// The maximum number of bytes of a dispatcher's jump table which are scanned for selectors. Each
+/// 4-byte window is a candidate which must be resolved by executing the dispatcher, so this bounds
+/// the number of executions.
+const MAX_TABLE_SCAN: usize = 1024;
+
+/// The maximum number of instructions followed after a selector comparison falls through, while
+/// looking for a jump through the dispatcher's table.
+const MAX_FALLTHROUGH_STEPS: usize = 16;
+
+/// The maximum depth searched when checking whether an operation reads the selector.
+const MAX_OPERATION_DEPTH: usize = 16;
+
+/// The kind of dispatcher a contract uses to route calls to its functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DispatcherKind {
+    /// A linear sequence of `EQ`/`JUMPI` comparisons, as emitted by solc for smaller contracts
+    /// and by most huff contracts.
+    Linear,
+    /// A binary search over the sorted selectors using `LT`/`GT` pivots, followed by `EQ`/`JUMPI`
+    /// comparisons, as emitted by solc for larger contracts.
+    BinarySearch,
+    /// The selector is hashed into a bucket (`selector % n`), whose handler is read from a table
+    /// in the code, as emitted by vyper >= 0.3.10.
+    HashBucket,
+    /// Bits of the selector index a table in the code directly, as emitted by huff's packed jump
+    /// tables.
+    JumpTable,
+}
+
+/// The [`Dispatcher`] struct describes the function dispatcher of a contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dispatcher {
+    /// The kind of dispatcher.
+    pub kind: DispatcherKind,
+
+    /// The offset of the dispatcher's table within the code, for table-based dispatchers.
+    pub table_offset: Option<usize>,
+}
+
+impl Dispatcher {
+    /// Returns the 4-byte words within the dispatcher's table, which may hold selectors that are
+    /// never pushed onto the stack (e.g. vyper's dense selector tables).
+    pub fn table_candidates(&self, bytecode: &[u8]) -> Vec<String> {
+        let Some(offset) = self.table_offset else {
+            return Vec::new();
+        };
+
+        let table = bytecode.get(offset..).unwrap_or_default();
+        table[..table.len().min(MAX_TABLE_SCAN)].windows(4).map(encode_hex).collect()
+    }
+}
+
+/// Detects the kind of dispatcher used by the contract loaded into `evm`, by executing it with a
+/// probe selector and inspecting the instructions it runs.
+pub fn detect_dispatcher(evm: &VM) -> Dispatcher {
+    let mut vm = evm.clone();
+    vm.calldata = vec![0u8; 4];
+
+    let mut kind = DispatcherKind::Linear;
+    let mut hashed = false;
+    let mut table_offset: Option<usize> = None;
+
+    for _ in 0..MAX_DISPATCHER_STEPS {
+        if vm.bytecode.len() < vm.instruction as usize {
+            break;
+        }
+
+        let state = match vm.step() {
+            Ok(state) => state,
+            Err(_) => break,
+        };
+        let instruction = &state.last_instruction;
+        let reads_selector_input = instruction.input_operations.iter().any(reads_selector);
+
+        match instruction.opcode {
+            MOD | SMOD if reads_selector_input => hashed = true,
+            LT | GT | SLT | SGT if reads_selector_input => {
+                if kind == DispatcherKind::Linear {
+                    kind = DispatcherKind::BinarySearch;
+                }
+            }
+            // the code offset being copied from depends on the selector, so this is a table lookup
+            CODECOPY if instruction.input_operations.get(1).is_some_and(reads_selector) => {
+                kind = if hashed { DispatcherKind::HashBucket } else { DispatcherKind::JumpTable };
+                let offset = instruction.inputs.get(1).and_then(|x| usize::try_from(*x).ok());
+                table_offset = match (table_offset, offset) {
+                    (Some(current), Some(offset)) => Some(current.min(offset)),
+                    (current, offset) => current.or(offset),
+                };
+            }
+            _ => {}
+        }
+
+        if vm.exitcode != 255 || !vm.returndata.is_empty() {
+            break;
+        }
+    }
+
+    Dispatcher { kind, table_offset: table_offset.filter(|offset| *offset < vm.bytecode.len()) }
+}
+
+/// Checks whether the branch `instruction` compares the selector in the calldata against
+/// `selector`, and the comparison held. Returns whether the selector was matched.
+pub(crate) fn matches_selector(instruction: &Instruction, selector: U256, taken: bool) -> bool {
+    let condition = match instruction.opcode {
+        JUMPI => instruction.input_operations.get(1),
+        _ => instruction.input_operations.first(),
+    };
+    let Some(condition) = condition else {
+        return false;
+    };
+
+    // the comparison must involve the selector, either directly or as a constant
+    if !reads_selector(condition) && !contains_constant(condition, selector, 0) {
+        return false;
+    }
+
+    // EQ jumps when the selector matches, while XOR and SUB (used by vyper) fall through
+    match (condition.opcode, condition.inputs.first()) {
+        (EQ, _) => taken,
+        (XOR | SUB, _) => !taken,
+        (ISZERO, Some(WrappedInput::Opcode(inner))) => match inner.opcode {
+            EQ => !taken,
+            XOR | SUB => taken,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Follows execution after a selector comparison fell through. Jump-table dispatchers share a
+/// single fall-through which jumps to the entry point read from the table, so if a jump to a
+/// non-constant destination follows before any other branch, its destination is returned.
+pub(crate) fn follow_table_jump(vm: &mut VM) -> Option<u128> {
+    for _ in 0..MAX_FALLTHROUGH_STEPS {
+        if vm.bytecode.len() < vm.instruction as usize {
+            return None;
+        }
+
+        let state = vm.step().ok()?;
+        let instruction = &state.last_instruction;
+        match instruction.opcode {
+            JUMP => {
+                let destination = instruction.input_operations.first()?;
+                return (!(PUSH0..=PUSH32).contains(&destination.opcode))
+                    .then(|| vm.instruction - 1);
+            }
+            JUMPI => return None,
+            _ => {}
+        }
+
+        if vm.exitcode != 255 || !vm.returndata.is_empty() {
+            return None;
+        }
+    }
+
+    None
+}
+
+/// Checks whether `operation` depends on the function selector, i.e. the first word of calldata.
+pub(crate) fn reads_selector(operation: &WrappedOpcode) -> bool {
+    fn inner(operation: &WrappedOpcode, depth: usize) -> bool {
+        if depth > MAX_OPERATION_DEPTH {
+            return false;
+        }
+        if operation.opcode == CALLDATALOAD {
+            return operation.inputs.first().is_some_and(is_zero_constant);
+        }
+
+        operation.inputs.iter().any(|input| match input {
+            WrappedInput::Opcode(operation) => inner(operation, depth + 1),
+            WrappedInput::Raw(_) => false,
+        })
+    }
+
+    inner(operation, 0)
+}
+
+/// Checks whether `operation` pushes the constant `value`, or depends on an operation which does.
+fn contains_constant(operation: &WrappedOpcode, value: U256, depth: usize) -> bool {
+    if depth > MAX_OPERATION_DEPTH {
+        return false;
+    }
+    if (PUSH0..=PUSH32).contains(&operation.opcode) {
+        return operation.inputs.first() == Some(&WrappedInput::Raw(value));
+    }
+
+    operation.inputs.iter().any(|input| match input {
+        WrappedInput::Opcode(operation) => contains_constant(operation, value, depth + 1),
+        WrappedInput::Raw(_) => false,
+    })
+}
+
+/// Checks whether `input` is the constant zero.
+fn is_zero_constant(input: &WrappedInput) -> bool {
+    match input {
+        WrappedInput::Raw(value) => value.is_zero(),
+        WrappedInput::Opcode(operation) => {
+            (PUSH0..=PUSH32).contains(&operation.opcode) &&
+                operation.inputs.iter().all(is_zero_constant)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+    use hashbrown::HashMap;
+    use heimdall_common::utils::strings::decode_hex;
+
+    use super::*;
+    use crate::ext::selectors::{find_function_selectors, resolve_entry_point};
+
+    fn new_test_vm(bytecode: &str) -> VM {
+        VM::new(
+            &decode_hex(bytecode).expect("failed to decode bytecode"),
+            &[],
+            Address::default(),
+            Address::default(),
+            Address::default(),
+            0,
+            u128::MAX,
+        )
+    }
+
+    #[test]
+    fn test_linear_dispatcher() {
+        // selector == 0xaabbccdd ? jump 0x20 : selector == 0x11223344 ? jump 0x24 : revert
+        let mut vm = new_test_vm(
+            "0x60003560e01c8063aabbccdd14610020578063112233441461002457600080fd5b6001005b600200",
+        );
+
+        assert_eq!(
+            detect_dispatcher(&vm),
+            Dispatcher { kind: DispatcherKind::Linear, table_offset: None }
+        );
+        assert_eq!(resolve_entry_point(&mut vm.clone(), "aabbccdd"), 0x20);
+        assert_eq!(resolve_entry_point(&mut vm.clone(), "11223344"), 0x24);
+        assert_eq!(resolve_entry_point(&mut vm, "deadbeef"), 0);
+    }
+
+    #[test]
+    fn test_binary_search_dispatcher() {
+        // 0x50000000 < selector ? (selector == 0xaabbccdd ? jump 0x30) : (selector == 0x11223344 ?
+        // jump 0x34)
+        let mut vm = new_test_vm(
+            "0x60003560e01c80635000000010610020578063112233441461003457600080fd5b8063aabbccdd14\
+             61003057600080fd5b6001005b600200",
+        );
+
+        assert_eq!(detect_dispatcher(&vm).kind, DispatcherKind::BinarySearch);
+        assert_eq!(resolve_entry_point(&mut vm.clone(), "aabbccdd"), 0x30);
+        assert_eq!(resolve_entry_point(&mut vm.clone(), "11223344"), 0x34);
+        assert_eq!(resolve_entry_point(&mut vm, "50000000"), 0);
+    }
+
+    #[test]
+    fn test_hash_bucket_dispatcher() {
+        // vyper-style sparse dispatcher: jumps to the bucket at table[selector % 2], which falls
+        // through to the function when `selector ^ 0x...` is zero
+        let mut vm = new_test_vm(
+            "0x60003560e01c6002810660011b61003e01600290601e39600051565b63112233448118610039576002\
+             005b63aabbccdd8118610039576001005b600080fd001b002a",
+        );
+
+        assert_eq!(
+            detect_dispatcher(&vm),
+            Dispatcher { kind: DispatcherKind::HashBucket, table_offset: Some(0x3e) }
+        );
+        assert_eq!(resolve_entry_point(&mut vm.clone(), "11223344"), 0x27);
+        assert_eq!(resolve_entry_point(&mut vm.clone(), "aabbccdd"), 0x36);
+        assert_eq!(resolve_entry_point(&mut vm, "aabbccde"), 0);
+    }
+
+    #[test]
+    fn test_jump_table_dispatcher() {
+        // the table at 0x36 holds (selector, entry point) pairs indexed by `selector >> 30`, so
+        // the selectors are never pushed onto the stack. every match falls through to 0x24, which
+        // jumps to the entry point read from the table
+        let vm = new_test_vm(
+            "0x60003560e01c600681601e1c0261003601600690601a396000518060101c82186100315761ffff16565b\
+             6001005b6002005b600080fd11223344002d5abbccdd0029",
+        );
+
+        assert_eq!(
+            detect_dispatcher(&vm),
+            Dispatcher { kind: DispatcherKind::JumpTable, table_offset: Some(0x36) }
+        );
+
+        let selectors = find_function_selectors(&vm, &[]);
+        assert_eq!(
+            selectors,
+            HashMap::from([("11223344".to_string(), 0x2d), ("5abbccdd".to_string(), 0x29)])
+        );
+    }
+}
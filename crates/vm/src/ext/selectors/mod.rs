@@ -1,3 +1,6 @@
+mod dispatcher;
+
+use alloy::primitives::U256;
 use hashbrown::{HashMap, HashSet};
 use std::{
     sync::{Arc, Mutex},
@@ -7,12 +10,17 @@ use std::{
 use eyre::Result;
 use heimdall_common::{
     ether::signatures::{ResolveSelector, ResolvedFunction},
-    utils::strings::decode_hex,
+    utils::strings::{decode_hex, encode_hex},
 };
 use tokio::task;
 use tracing::{debug, error, info, trace, warn};
 
-use crate::core::vm::VM;
+use crate::{
+    core::{opcodes::PUSH4, vm::VM},
+    ext::{disassembly::DisassembledInstruction, exec::branch::Branch},
+};
+
+pub use dispatcher::{detect_dispatcher, Dispatcher, DispatcherKind};
 
 /// Finds and resolves function selectors from disassembled bytecode
 ///
@@ -20,7 +28,7 @@ use crate::core::vm::VM;
 /// and optionally resolves them to human-readable function signatures.
 ///
 /// # Arguments
-/// * `disassembled_bytecode` - The disassembled EVM bytecode to analyze
+/// * `instructions` - The disassembled EVM bytecode to analyze
 /// * `skip_resolving` - If true, skip the process of resolving selectors to function signatures
 /// * `evm` - The VM instance to use for analysis
 ///
@@ -29,11 +37,11 @@ use crate::core::vm::VM;
 ///   - A HashMap mapping selector strings to their instruction offsets
 ///   - A HashMap mapping selector strings to their resolved function information
 pub async fn get_resolved_selectors(
-    disassembled_bytecode: &str,
+    instructions: &[DisassembledInstruction],
     skip_resolving: &bool,
     evm: &VM,
 ) -> Result<(HashMap<String, u128>, HashMap<String, Vec<ResolvedFunction>>)> {
-    let selectors = find_function_selectors(evm, disassembled_bytecode);
+    let selectors = find_function_selectors(evm, instructions);
 
     let mut resolved_selectors = HashMap::new();
     if !skip_resolving {
@@ -52,93 +60,114 @@ pub async fn get_resolved_selectors(
     Ok((selectors, resolved_selectors))
 }
 
-/// find all function selectors in the given EVM assembly.
-// TODO: update get_resolved_selectors logic to support vyper, huff
-pub fn find_function_selectors(evm: &VM, assembly: &str) -> HashMap<String, u128> {
+/// find all function selectors in the given disassembled instructions.
+///
+/// Candidate selectors are taken from every `PUSH4` instruction, along with every 4-byte word of
+/// the dispatcher's table for table-based dispatchers (see [`detect_dispatcher`]). Each candidate
+/// is kept only if executing the dispatcher with it reaches a function entry point.
+pub fn find_function_selectors(
+    evm: &VM,
+    instructions: &[DisassembledInstruction],
+) -> HashMap<String, u128> {
     let mut function_selectors = HashMap::new();
     let mut handled_selectors = HashSet::new();
 
-    // search through assembly for PUSHN (where N <= 4) instructions, optimistically assuming that
-    // they are function selectors
-    let assembly: Vec<String> = assembly.split('\n').map(|line| line.trim().to_string()).collect();
-    for line in assembly.iter() {
-        let instruction_args: Vec<String> = line.split(' ').map(|arg| arg.to_string()).collect();
+    let dispatcher = detect_dispatcher(evm);
+    debug!("detected {:?} function dispatcher", dispatcher.kind);
 
-        if instruction_args.len() >= 2 {
-            let instruction = instruction_args[1].clone();
+    // search through the instructions for PUSH4s, optimistically assuming that they are
+    // function selectors
+    let mut candidates = Vec::new();
+    for instruction in instructions.iter().filter(|instruction| instruction.opcode == PUSH4) {
+        trace!(
+            "optimistically assuming instruction {:06x} {} {} is a function selector",
+            instruction.pc,
+            instruction.name,
+            encode_hex(&instruction.immediate)
+        );
 
-            if &instruction == "PUSH4" {
-                let function_selector = instruction_args[2].clone();
+        candidates.push(encode_hex(&instruction.immediate));
+    }
 
-                // check if this function selector has already been handled
-                if handled_selectors.contains(&function_selector) {
-                    continue;
-                }
+    // table-based dispatchers may store selectors in the table rather than pushing them
+    candidates.extend(dispatcher.table_candidates(&evm.bytecode));
 
-                trace!(
-                    "optimistically assuming instruction {} {} {} is a function selector",
-                    instruction_args[0],
-                    instruction_args[1],
-                    instruction_args[2]
-                );
-
-                // add the function selector to the handled selectors
-                handled_selectors.insert(function_selector.clone());
-
-                // get the function's entry point
-                let function_entry_point =
-                    match resolve_entry_point(&mut evm.clone(), &function_selector) {
-                        0 => continue,
-                        x => x,
-                    };
-
-                trace!(
-                    "found function selector {} at entry point {}",
-                    function_selector,
-                    function_entry_point
-                );
-
-                function_selectors.insert(function_selector, function_entry_point);
-            }
+    for function_selector in candidates {
+        // check if this function selector has already been handled
+        if !handled_selectors.insert(function_selector.clone()) {
+            continue;
         }
+
+        // get the function's entry point
+        let function_entry_point = match resolve_entry_point(&mut evm.clone(), &function_selector) {
+            0 => continue,
+            x => x,
+        };
+
+        trace!(
+            "found function selector {} at entry point {}",
+            function_selector,
+            function_entry_point
+        );
+
+        function_selectors.insert(function_selector, function_entry_point);
     }
 
-    info!("discovered {} function selectors in assembly", function_selectors.len());
+    info!("discovered {} function selectors in bytecode", function_selectors.len());
     function_selectors
 }
 
 /// resolve a selector's function entry point from the EVM bytecode
-// TODO: update resolve_entry_point logic to support vyper
+///
+/// Solidity and huff dispatchers jump to the entry point when `EQ` matches the selector, while
+/// vyper dispatchers fall through to it when `XOR` of the selectors is zero.
 pub fn resolve_entry_point(vm: &mut VM, selector: &str) -> u128 {
     let mut handled_jumps = HashSet::new();
 
     // execute the EVM call to find the entry point for the given selector
     vm.calldata = decode_hex(selector).expect("Failed to decode selector.");
-    while vm.bytecode.len() >= vm.instruction as usize {
+    let selector_value = U256::from_be_slice(&vm.calldata);
+    for _ in 0..dispatcher::MAX_DISPATCHER_STEPS {
+        if vm.bytecode.len() < vm.instruction as usize {
+            break;
+        }
+
         let call = match vm.step() {
             Ok(call) => call,
             Err(_) => break, // the call failed, so we can't resolve the selector
         };
 
-        // if the opcode is an JUMPI and it matched the selector, the next jumpi is the entry point
-        if call.last_instruction.opcode == 0x57 {
-            let jump_condition = call.last_instruction.input_operations[1].solidify();
-            let jump_taken = call.last_instruction.inputs[1].try_into().unwrap_or(1);
+        // if the opcode is an JUMPI (or RJUMPI) and it matched the selector, the next jumpi is the
+        // entry point
+        if let Some(branch) = Branch::from_instruction(&call.last_instruction, &vm.bytecode) {
+            let jump_condition = branch.condition.unwrap_or_default();
 
-            if jump_condition.contains(selector) &&
+            // the VM has already moved to the jump destination if the jump was taken, or to the
+            // next instruction otherwise
+            let jumpdest = vm.instruction - 1;
+
+            if (jump_condition.contains(selector) &&
                 jump_condition.contains("msg.data[0]") &&
                 jump_condition.contains(" == ") &&
-                jump_taken == 1
+                branch.taken) ||
+                dispatcher::matches_selector(
+                    &call.last_instruction,
+                    selector_value,
+                    branch.taken,
+                )
             {
-                return call.last_instruction.inputs[0].try_into().unwrap_or(0);
-            } else if jump_taken == 1 {
+                // jump-table dispatchers jump to the entry point after falling through
+                if !branch.taken {
+                    return dispatcher::follow_table_jump(vm).unwrap_or(jumpdest);
+                }
+                return jumpdest;
+            } else if branch.taken {
                 // if handled_jumps contains the jumpi, we have already handled this jump.
                 // loops aren't supported in the dispatcher, so we can just return 0
-                if handled_jumps.contains(&call.last_instruction.inputs[0].try_into().unwrap_or(0))
-                {
+                if handled_jumps.contains(&jumpdest) {
                     return 0;
                 } else {
-                    handled_jumps.insert(call.last_instruction.inputs[0].try_into().unwrap_or(0));
+                    handled_jumps.insert(jumpdest);
                 }
             }
         }
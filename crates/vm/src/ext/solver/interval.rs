@@ -0,0 +1,368 @@
+use alloy::primitives::U256;
+use hashbrown::{HashMap, HashSet};
+
+use crate::core::opcodes::{
+    WrappedInput, WrappedOpcode, ADD, ADDRESS, AND, BYTE, CALLDATALOAD, CALLDATASIZE, CALLER,
+    COINBASE, DIV, EQ, GT, ISZERO, LT, MUL, NOT, OR, ORIGIN, SGT, SHL, SHR, SLT, SUB, XOR,
+};
+
+use super::{constant_value, is_deterministic, PathConstraint, Satisfiability, Solver};
+
+/// A lightweight, pure-Rust [`Solver`] which tracks the range of unsigned 256-bit values each term
+/// of a path's constraints may take.
+///
+/// Constraints comparing a term against a constant (`LT`, `GT`, `EQ`, truthiness, and their
+/// negations) narrow the term's range, and a path is unsatisfiable once any range is empty. Terms
+/// are compared structurally, so relations between two terms (i.e. `x < y`) aren't understood and
+/// are skipped. As such, [`Satisfiability::Sat`] is only returned when every constraint was
+/// understood and constrains an independent input.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IntervalSolver;
+
+impl Solver for IntervalSolver {
+    fn check(&self, constraints: &[PathConstraint]) -> Satisfiability {
+        let mut domains: HashMap<&WrappedOpcode, Domain> = HashMap::new();
+        let mut understood = true;
+
+        for constraint in constraints {
+            match atom(constraint) {
+                Atom::Constant(true) => {}
+                Atom::Constant(false) => return Satisfiability::Unsat,
+                Atom::Unsupported => understood = false,
+                Atom::Bound(term, relation, value) => {
+                    let domain = domains.entry(term).or_insert_with(|| Domain::new(term));
+                    if !domain.restrict(relation, value) {
+                        return Satisfiability::Unsat;
+                    }
+                }
+            }
+        }
+
+        let terms = domains.keys().copied().collect::<Vec<_>>();
+        if understood && independent(&terms) {
+            Satisfiability::Sat
+        } else {
+            Satisfiability::Unknown
+        }
+    }
+}
+
+/// A relation between a term and a constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Relation {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Relation {
+    /// Returns the relation which holds whenever this one doesn't.
+    fn negate(self) -> Self {
+        match self {
+            Self::Lt => Self::Ge,
+            Self::Le => Self::Gt,
+            Self::Gt => Self::Le,
+            Self::Ge => Self::Lt,
+            Self::Eq => Self::Ne,
+            Self::Ne => Self::Eq,
+        }
+    }
+
+    /// Returns the relation which holds with its operands swapped.
+    fn flip(self) -> Self {
+        match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+            relation => relation,
+        }
+    }
+}
+
+/// A [`PathConstraint`], as understood by the [`IntervalSolver`].
+#[derive(Debug)]
+enum Atom<'a> {
+    /// The constraint always (or never) holds
+    Constant(bool),
+    /// The constraint holds when `term <relation> value`
+    Bound(&'a WrappedOpcode, Relation, U256),
+    /// The constraint can't be reasoned about
+    Unsupported,
+}
+
+/// Converts `constraint` into an [`Atom`].
+fn atom(constraint: &PathConstraint) -> Atom<'_> {
+    let (condition, holds) = constraint.normalize();
+    if let Some(value) = evaluate(condition) {
+        return Atom::Constant(value.is_zero() != holds);
+    }
+    if !is_deterministic(condition) {
+        return Atom::Unsupported;
+    }
+
+    let relation = match condition.opcode {
+        LT => Relation::Lt,
+        GT => Relation::Gt,
+        EQ => Relation::Eq,
+        // any other condition checks whether the term itself is non-zero
+        _ => {
+            return Atom::Bound(
+                condition,
+                if holds { Relation::Ne } else { Relation::Eq },
+                U256::ZERO,
+            )
+        }
+    };
+    let relation = if holds { relation } else { relation.negate() };
+
+    match condition.inputs.as_slice() {
+        [a, b] => match (operand(a), operand(b)) {
+            (Err(term), Ok(value)) => Atom::Bound(term, relation, value),
+            (Ok(value), Err(term)) => Atom::Bound(term, relation.flip(), value),
+            _ => Atom::Unsupported,
+        },
+        _ => Atom::Unsupported,
+    }
+}
+
+/// Returns the value of `input` if it's constant, or the operation producing it otherwise.
+fn operand(input: &WrappedInput) -> Result<U256, &WrappedOpcode> {
+    match input {
+        WrappedInput::Raw(value) => Ok(*value),
+        WrappedInput::Opcode(operation) => evaluate(operation).ok_or_else(|| operation.as_ref()),
+    }
+}
+
+/// Evaluates `operation` if it only depends on constants.
+fn evaluate(operation: &WrappedOpcode) -> Option<U256> {
+    if let Some(value) = constant_value(operation) {
+        return Some(value);
+    }
+
+    let inputs =
+        operation.inputs.iter().map(|input| operand(input).ok()).collect::<Option<Vec<U256>>>()?;
+    let boolean = |value: bool| Some(U256::from(u8::from(value)));
+
+    match (operation.opcode, inputs.as_slice()) {
+        (ADD, [a, b]) => Some(a.wrapping_add(*b)),
+        (SUB, [a, b]) => Some(a.wrapping_sub(*b)),
+        (MUL, [a, b]) => Some(a.wrapping_mul(*b)),
+        (DIV, [a, b]) => Some(a.checked_div(*b).unwrap_or_default()),
+        (AND, [a, b]) => Some(*a & *b),
+        (OR, [a, b]) => Some(*a | *b),
+        (XOR, [a, b]) => Some(*a ^ *b),
+        (NOT, [a]) => Some(!*a),
+        (SHL, [shift, value]) => Some(shift_by(*shift, |shift| *value << shift)),
+        (SHR, [shift, value]) => Some(shift_by(*shift, |shift| *value >> shift)),
+        (LT, [a, b]) => boolean(a < b),
+        (GT, [a, b]) => boolean(a > b),
+        (EQ, [a, b]) => boolean(a == b),
+        (ISZERO, [a]) => boolean(a.is_zero()),
+        _ => None,
+    }
+}
+
+/// Applies a logical shift of `shift` bits, which clears the value entirely for shifts of 256 bits
+/// or more.
+fn shift_by(shift: U256, apply: impl Fn(usize) -> U256) -> U256 {
+    if shift >= U256::from(256) {
+        return U256::ZERO;
+    }
+    apply(shift.to::<usize>())
+}
+
+/// Returns the largest value `term` can evaluate to.
+fn upper_bound(term: &WrappedOpcode) -> U256 {
+    let input_bound = |input: &WrappedInput| match operand(input) {
+        Ok(value) => value,
+        Err(operation) => upper_bound(operation),
+    };
+
+    match (term.opcode, term.inputs.as_slice()) {
+        (ADDRESS | ORIGIN | CALLER | COINBASE, _) => (U256::from(1) << 160) - U256::from(1),
+        (LT | GT | SLT | SGT | EQ | ISZERO, _) => U256::from(1),
+        (BYTE, _) => U256::from(0xff),
+        (AND, [a, b]) => input_bound(a).min(input_bound(b)),
+        (SHR, [shift, _]) => match operand(shift) {
+            Ok(shift) => shift_by(shift, |shift| U256::MAX >> shift),
+            Err(_) => U256::MAX,
+        },
+        _ => U256::MAX,
+    }
+}
+
+/// The values a term may take, i.e. every value in `min..=max` which isn't `excluded`.
+#[derive(Clone, Debug)]
+struct Domain {
+    min: U256,
+    max: U256,
+    excluded: HashSet<U256>,
+}
+
+impl Domain {
+    fn new(term: &WrappedOpcode) -> Self {
+        Self { min: U256::ZERO, max: upper_bound(term), excluded: HashSet::new() }
+    }
+
+    /// Narrows the domain to the values satisfying `relation` with `value`. Returns false if no
+    /// values remain.
+    fn restrict(&mut self, relation: Relation, value: U256) -> bool {
+        match relation {
+            Relation::Lt => match value.checked_sub(U256::from(1)) {
+                Some(max) => self.max = self.max.min(max),
+                None => return false,
+            },
+            Relation::Le => self.max = self.max.min(value),
+            Relation::Gt => match value.checked_add(U256::from(1)) {
+                Some(min) => self.min = self.min.max(min),
+                None => return false,
+            },
+            Relation::Ge => self.min = self.min.max(value),
+            Relation::Eq => {
+                self.min = self.min.max(value);
+                self.max = self.max.min(value);
+            }
+            Relation::Ne => {
+                self.excluded.insert(value);
+            }
+        }
+
+        !self.is_empty()
+    }
+
+    fn is_empty(&self) -> bool {
+        if self.min > self.max {
+            return true;
+        }
+
+        // the excluded values are distinct, so they cover the range once there are as many of
+        // them as values in it
+        let excluded =
+            self.excluded.iter().filter(|value| (self.min..=self.max).contains(*value)).count();
+        self.max - self.min < U256::from(excluded)
+    }
+}
+
+/// Returns true if every term is an input which can take any value in its domain regardless of the
+/// values of the others, in which case non-empty domains mean the constraints are satisfiable.
+fn independent(terms: &[&WrappedOpcode]) -> bool {
+    let mut calldata_offsets = Vec::new();
+    let mut reads_calldata_size = false;
+
+    for term in terms {
+        match (term.opcode, term.inputs.as_slice()) {
+            (CALLDATALOAD, [offset]) => match operand(offset) {
+                Ok(offset) => calldata_offsets.push(offset),
+                Err(_) => return false,
+            },
+            (CALLDATASIZE, []) => reads_calldata_size = true,
+            (_, []) => {}
+            _ => return false,
+        }
+    }
+
+    // words loaded from calldata must neither overlap, nor be limited by the calldata's size
+    calldata_offsets.sort();
+    (calldata_offsets.is_empty() || !reads_calldata_size) &&
+        calldata_offsets.windows(2).all(|pair| pair[1] - pair[0] >= U256::from(32))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::opcodes::SLOAD;
+
+    use super::*;
+    use crate::test_utils::{op, push};
+
+    fn arg(offset: u8) -> WrappedOpcode {
+        op(CALLDATALOAD, vec![push(offset)])
+    }
+
+    fn check(constraints: Vec<(WrappedOpcode, bool)>) -> Satisfiability {
+        let constraints = constraints
+            .into_iter()
+            .map(|(condition, holds)| PathConstraint::new(condition, holds))
+            .collect::<Vec<_>>();
+        IntervalSolver.check(&constraints)
+    }
+
+    #[test]
+    fn test_contradictory_ranges_are_unsat() {
+        // x > 5, then x < 3
+        let result = check(vec![
+            (op(GT, vec![arg(4), push(5)]), true),
+            (op(LT, vec![arg(4), push(3)]), true),
+        ]);
+        assert_eq!(result, Satisfiability::Unsat);
+    }
+
+    #[test]
+    fn test_overlapping_ranges_are_sat() {
+        // x > 5, then !(x >= 10), written as 10 > x
+        let result = check(vec![
+            (op(GT, vec![arg(4), push(5)]), true),
+            (op(GT, vec![push(10), arg(4)]), true),
+        ]);
+        assert_eq!(result, Satisfiability::Sat);
+    }
+
+    #[test]
+    fn test_negated_equality_is_unsat() {
+        let eq = op(EQ, vec![arg(4), push(1)]);
+        let result = check(vec![(op(ISZERO, vec![eq.clone()]), true), (eq, true)]);
+        assert_eq!(result, Satisfiability::Unsat);
+    }
+
+    #[test]
+    fn test_excluded_values_cover_range() {
+        // x != 0, then x < 1
+        let result = check(vec![(arg(4), true), (op(LT, vec![arg(4), push(1)]), true)]);
+        assert_eq!(result, Satisfiability::Unsat);
+    }
+
+    #[test]
+    fn test_masked_term_upper_bound() {
+        let masked = op(AND, vec![arg(4), push(0xff)]);
+        let result = check(vec![(op(GT, vec![masked, push(0xff)]), true)]);
+        assert_eq!(result, Satisfiability::Unsat);
+
+        let caller = op(CALLER, Vec::new());
+        let result = check(vec![(op(EQ, vec![caller, push(1)]), false)]);
+        assert_eq!(result, Satisfiability::Sat);
+    }
+
+    #[test]
+    fn test_constant_conditions() {
+        assert_eq!(check(vec![(push(0), true)]), Satisfiability::Unsat);
+        assert_eq!(check(vec![(push(1), true)]), Satisfiability::Sat);
+        assert_eq!(check(Vec::new()), Satisfiability::Sat);
+    }
+
+    #[test]
+    fn test_non_deterministic_terms_are_unknown() {
+        // storage may change between the two checks, so these aren't contradictory
+        let slot = op(SLOAD, vec![push(0)]);
+        let result = check(vec![
+            (op(EQ, vec![slot.clone(), push(0)]), true),
+            (op(EQ, vec![slot, push(1)]), true),
+        ]);
+        assert_eq!(result, Satisfiability::Unknown);
+    }
+
+    #[test]
+    fn test_relations_between_terms_are_unknown() {
+        let result = check(vec![(op(LT, vec![arg(4), arg(36)]), true)]);
+        assert_eq!(result, Satisfiability::Unknown);
+
+        // overlapping calldata words aren't independent
+        let result = check(vec![
+            (op(GT, vec![arg(4), push(1)]), true),
+            (op(GT, vec![arg(5), push(1)]), true),
+        ]);
+        assert_eq!(result, Satisfiability::Unknown);
+    }
+}
@@ -0,0 +1,206 @@
+//! Constraint solvers, which decide whether the branch conditions collected along a symbolic
+//! execution path can all hold at once.
+
+mod interval;
+#[cfg(feature = "z3")]
+mod z3;
+
+use std::{fmt::Debug, sync::Arc};
+
+use alloy::primitives::U256;
+
+use crate::core::opcodes::{
+    WrappedInput, WrappedOpcode, ADD, ADDRESS, BASEFEE, BLOBBASEFEE, BLOBHASH, BLOCKHASH,
+    CALLDATALOAD, CALLDATASIZE, CALLER, CALLVALUE, CHAINID, CODESIZE, COINBASE, GASLIMIT, GASPRICE,
+    ISZERO, LT, NUMBER, ORIGIN, PREVRANDAO, PUSH0, PUSH32, SAR, SIGNEXTEND, TIMESTAMP,
+};
+
+#[cfg(feature = "z3")]
+pub use self::z3::Z3Solver;
+pub use interval::IntervalSolver;
+
+/// Opcodes whose result is fixed for the duration of a transaction, i.e. reading them twice along
+/// one path always yields the same value.
+const ENVIRONMENT_OPCODES: [u8; 18] = [
+    ADDRESS,
+    ORIGIN,
+    CALLER,
+    CALLVALUE,
+    CALLDATALOAD,
+    CALLDATASIZE,
+    CODESIZE,
+    GASPRICE,
+    BLOCKHASH,
+    COINBASE,
+    TIMESTAMP,
+    NUMBER,
+    PREVRANDAO,
+    GASLIMIT,
+    CHAINID,
+    BASEFEE,
+    BLOBHASH,
+    BLOBBASEFEE,
+];
+
+/// The result of checking a set of [`PathConstraint`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Satisfiability {
+    /// The constraints can all hold, so the path is reachable
+    Sat,
+    /// The constraints contradict each other, so the path is unreachable
+    Unsat,
+    /// The solver couldn't decide whether the constraints can all hold
+    #[default]
+    Unknown,
+}
+
+impl Satisfiability {
+    /// Returns false only if the constraints were proven to be unsatisfiable.
+    pub fn is_feasible(&self) -> bool {
+        *self != Self::Unsat
+    }
+}
+
+/// A branch condition which must hold (or must not hold) for execution to follow a path.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathConstraint {
+    /// The condition of the conditional jump
+    pub condition: WrappedOpcode,
+
+    /// Whether the condition is non-zero along the path
+    pub holds: bool,
+}
+
+impl PathConstraint {
+    /// Creates a new [`PathConstraint`].
+    pub fn new(condition: WrappedOpcode, holds: bool) -> Self {
+        Self { condition, holds }
+    }
+
+    /// Returns the condition with any `ISZERO` negations stripped, along with whether the
+    /// stripped condition must be non-zero.
+    pub fn normalize(&self) -> (&WrappedOpcode, bool) {
+        let mut condition = &self.condition;
+        let mut holds = self.holds;
+
+        while let (ISZERO, [WrappedInput::Opcode(inner)]) =
+            (condition.opcode, condition.inputs.as_slice())
+        {
+            condition = inner.as_ref();
+            holds = !holds;
+        }
+
+        (condition, holds)
+    }
+}
+
+/// A [`Solver`] decides whether a path's constraints can be satisfied, so that symbolic execution
+/// can prune paths which are impossible.
+///
+/// Solvers must be conservative: [`Satisfiability::Unsat`] prunes the path, so it may only be
+/// returned if the constraints are certainly contradictory.
+pub trait Solver: Debug + Send + Sync {
+    /// Checks whether every constraint can hold at once.
+    fn check(&self, constraints: &[PathConstraint]) -> Satisfiability;
+}
+
+/// Returns the solver used when pruning is requested, e.g. with `--solver`, which is [`Z3Solver`]
+/// since heimdall is built with the `z3` feature.
+#[cfg(feature = "z3")]
+pub fn default_solver() -> Arc<dyn Solver> {
+    Arc::new(Z3Solver::default())
+}
+
+/// Returns the solver used when pruning is requested, e.g. with `--solver`, which is
+/// [`IntervalSolver`] unless heimdall is built with the `z3` feature.
+#[cfg(not(feature = "z3"))]
+pub fn default_solver() -> Arc<dyn Solver> {
+    Arc::new(IntervalSolver)
+}
+
+/// Returns the value of `operation` if it's a constant, i.e. the result of a PUSH instruction.
+pub(crate) fn constant_value(operation: &WrappedOpcode) -> Option<U256> {
+    if !(PUSH0..=PUSH32).contains(&operation.opcode) {
+        return None;
+    }
+
+    match operation.inputs.first() {
+        Some(WrappedInput::Raw(value)) => Some(*value),
+        Some(WrappedInput::Opcode(_)) => None,
+        None => Some(U256::ZERO),
+    }
+}
+
+/// Returns true if `operation` only depends on constants, i.e. it evaluates to the same value
+/// regardless of the transaction's inputs.
+pub(crate) fn is_constant(operation: &WrappedOpcode) -> bool {
+    if constant_value(operation).is_some() {
+        return true;
+    }
+
+    let opcode = operation.opcode;
+    let pure = (ADD..=SIGNEXTEND).contains(&opcode) || (LT..=SAR).contains(&opcode);
+
+    pure && operation.inputs.iter().all(|input| match input {
+        WrappedInput::Raw(_) => true,
+        WrappedInput::Opcode(inner) => is_constant(inner),
+    })
+}
+
+/// Returns true if `operation` always evaluates to the same value along a path. Anything derived
+/// from memory, storage, gas or the result of a message-call may change between two evaluations
+/// of the same expression, so such expressions can't be reasoned about.
+pub(crate) fn is_deterministic(operation: &WrappedOpcode) -> bool {
+    let opcode = operation.opcode;
+    let pure = (ADD..=SIGNEXTEND).contains(&opcode) ||
+        (LT..=SAR).contains(&opcode) ||
+        (PUSH0..=PUSH32).contains(&opcode) ||
+        ENVIRONMENT_OPCODES.contains(&opcode);
+
+    pure && operation.inputs.iter().all(|input| match input {
+        WrappedInput::Raw(_) => true,
+        WrappedInput::Opcode(inner) => is_deterministic(inner),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::opcodes::{EQ, MLOAD, SLOAD};
+
+    use super::*;
+    use crate::test_utils::{op, push};
+
+    #[test]
+    fn test_normalize_strips_negations() {
+        let condition = op(LT, vec![push(1), push(2)]);
+        let negated = op(ISZERO, vec![condition.clone()]);
+
+        let constraint = PathConstraint::new(negated.clone(), true);
+        assert_eq!(constraint.normalize(), (&condition, false));
+
+        let constraint = PathConstraint::new(op(ISZERO, vec![negated]), true);
+        assert_eq!(constraint.normalize(), (&condition, true));
+    }
+
+    #[test]
+    fn test_is_deterministic() {
+        assert!(is_deterministic(&op(LT, vec![op(CALLDATALOAD, vec![push(4)]), push(5)])));
+        assert!(!is_deterministic(&op(LT, vec![op(SLOAD, vec![push(0)]), push(5)])));
+        assert!(!is_deterministic(&op(MLOAD, vec![push(0x40)])));
+    }
+
+    #[test]
+    fn test_constant_value() {
+        assert_eq!(constant_value(&push(7)), Some(U256::from(7)));
+        assert_eq!(constant_value(&WrappedOpcode::new(PUSH0, Vec::new())), Some(U256::ZERO));
+        assert_eq!(constant_value(&op(CALLER, Vec::new())), None);
+    }
+
+    #[test]
+    fn test_is_constant() {
+        assert!(is_constant(&op(ISZERO, vec![push(1)])));
+        assert!(is_constant(&op(LT, vec![op(ADD, vec![push(1), push(2)]), push(0x10)])));
+        assert!(!is_constant(&op(EQ, vec![op(CALLER, Vec::new()), push(1)])));
+        assert!(!is_constant(&op(ISZERO, vec![op(CALLDATALOAD, vec![push(4)])])));
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use alloy::primitives::U256;
+use z3::{
+    ast::{Array, Ast, Bool, BV},
+    Config, Context, Params, SatResult, Sort,
+};
+
+use crate::core::opcodes::{
+    WrappedInput, WrappedOpcode, ADD, AND, CALLDATALOAD, DIV, EQ, GT, ISZERO, LT, MOD, MUL, NOT,
+    OR, SAR, SGT, SHL, SHR, SLT, SUB, XOR,
+};
+
+use super::{constant_value, is_deterministic, PathConstraint, Satisfiability, Solver};
+
+/// A [`Solver`] backed by the Z3 SMT solver, which models every condition as a 256-bit
+/// bit-vector expression.
+///
+/// Calldata is modelled as an array of bytes, so overlapping `CALLDATALOAD`s are related. Any
+/// other operation Z3 has no equivalent for is treated as an opaque value, and constraints which
+/// aren't deterministic (i.e. read storage or memory) are skipped.
+#[derive(Clone, Copy, Debug)]
+pub struct Z3Solver {
+    /// How long Z3 may spend on a single check before giving up
+    timeout: Duration,
+}
+
+impl Z3Solver {
+    /// Creates a new [`Z3Solver`], which gives up on checks taking longer than `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl Default for Z3Solver {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500))
+    }
+}
+
+thread_local! {
+    /// Creating a Z3 context is far more expensive than a check, so each thread reuses one. Contexts
+    /// can't be shared between threads.
+    static CONTEXT: Context = Context::new(&Config::new());
+}
+
+impl Solver for Z3Solver {
+    fn check(&self, constraints: &[PathConstraint]) -> Satisfiability {
+        CONTEXT.with(|ctx| {
+            let solver = z3::Solver::new(ctx);
+            let mut params = Params::new(ctx);
+            params.set_u32("timeout", self.timeout.as_millis().try_into().unwrap_or(u32::MAX));
+            solver.set_params(&params);
+
+            let encoder = Encoder::new(ctx);
+            let mut complete = true;
+            for constraint in constraints {
+                if !is_deterministic(&constraint.condition) {
+                    complete = false;
+                    continue;
+                }
+
+                let non_zero =
+                    encoder.encode(&constraint.condition)._eq(&encoder.word(U256::ZERO)).not();
+                solver.assert(&if constraint.holds { non_zero } else { non_zero.not() });
+            }
+
+            match solver.check() {
+                SatResult::Unsat => Satisfiability::Unsat,
+                SatResult::Sat if complete => Satisfiability::Sat,
+                _ => Satisfiability::Unknown,
+            }
+        })
+    }
+}
+
+/// Encodes [`WrappedOpcode`]s as Z3 bit-vector expressions.
+struct Encoder<'ctx> {
+    ctx: &'ctx Context,
+    calldata: Array<'ctx>,
+}
+
+impl<'ctx> Encoder<'ctx> {
+    fn new(ctx: &'ctx Context) -> Self {
+        let calldata =
+            Array::new_const(ctx, "calldata", &Sort::bitvector(ctx, 256), &Sort::bitvector(ctx, 8));
+        Self { ctx, calldata }
+    }
+
+    /// Encodes a constant word.
+    fn word(&self, value: U256) -> BV<'ctx> {
+        value
+            .as_limbs()
+            .iter()
+            .rev()
+            .map(|limb| BV::from_u64(self.ctx, *limb, 64))
+            .reduce(|high, low| high.concat(&low))
+            .expect("words have four limbs")
+    }
+
+    /// Encodes a boolean as the word `1` or `0`, as the EVM's comparison opcodes do.
+    fn boolean(&self, condition: &Bool<'ctx>) -> BV<'ctx> {
+        condition.ite(&self.word(U256::from(1)), &self.word(U256::ZERO))
+    }
+
+    fn input(&self, input: &WrappedInput) -> BV<'ctx> {
+        match input {
+            WrappedInput::Raw(value) => self.word(*value),
+            WrappedInput::Opcode(operation) => self.encode(operation),
+        }
+    }
+
+    fn encode(&self, operation: &WrappedOpcode) -> BV<'ctx> {
+        if let Some(value) = constant_value(operation) {
+            return self.word(value);
+        }
+
+        let zero = self.word(U256::ZERO);
+        let inputs = operation.inputs.iter().map(|input| self.input(input)).collect::<Vec<_>>();
+        match (operation.opcode, inputs.as_slice()) {
+            (ADD, [a, b]) => a.bvadd(b),
+            (SUB, [a, b]) => a.bvsub(b),
+            (MUL, [a, b]) => a.bvmul(b),
+            // the EVM defines division and modulo by zero as zero
+            (DIV, [a, b]) => b._eq(&zero).ite(&zero, &a.bvudiv(b)),
+            (MOD, [a, b]) => b._eq(&zero).ite(&zero, &a.bvurem(b)),
+            (AND, [a, b]) => a.bvand(b),
+            (OR, [a, b]) => a.bvor(b),
+            (XOR, [a, b]) => a.bvxor(b),
+            (NOT, [a]) => a.bvnot(),
+            (SHL, [shift, value]) => value.bvshl(shift),
+            (SHR, [shift, value]) => value.bvlshr(shift),
+            (SAR, [shift, value]) => value.bvashr(shift),
+            (LT, [a, b]) => self.boolean(&a.bvult(b)),
+            (GT, [a, b]) => self.boolean(&a.bvugt(b)),
+            (SLT, [a, b]) => self.boolean(&a.bvslt(b)),
+            (SGT, [a, b]) => self.boolean(&a.bvsgt(b)),
+            (EQ, [a, b]) => self.boolean(&a._eq(b)),
+            (ISZERO, [a]) => self.boolean(&a._eq(&zero)),
+            (CALLDATALOAD, [offset]) => (0..32u8)
+                .map(|index| {
+                    self.calldata
+                        .select(&offset.bvadd(&self.word(U256::from(index))))
+                        .as_bv()
+                        .expect("calldata holds bytes")
+                })
+                .reduce(|high, low| high.concat(&low))
+                .expect("words have 32 bytes"),
+
+            // anything else is deterministic, so identical operations share one opaque value
+            _ => BV::new_const(self.ctx, operation.to_string(), 256),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::opcodes::CALLER;
+
+    use super::*;
+    use crate::test_utils::{op, push};
+
+    #[test]
+    fn test_z3_contradictory_ranges_are_unsat() {
+        let x = op(CALLDATALOAD, vec![push(4)]);
+        let constraints = vec![
+            PathConstraint::new(op(GT, vec![x.clone(), push(5)]), true),
+            PathConstraint::new(op(LT, vec![x, push(3)]), true),
+        ];
+        assert_eq!(Z3Solver::default().check(&constraints), Satisfiability::Unsat);
+    }
+
+    #[test]
+    fn test_z3_overlapping_calldata_is_related() {
+        // the low byte of the word at 4 is the high byte of the word at 35
+        let low = op(AND, vec![op(CALLDATALOAD, vec![push(4)]), push(0xff)]);
+        let high = op(SHR, vec![push(248), op(CALLDATALOAD, vec![push(35)])]);
+        let constraints = vec![
+            PathConstraint::new(op(EQ, vec![low, push(1)]), true),
+            PathConstraint::new(op(EQ, vec![high, push(2)]), true),
+        ];
+        assert_eq!(Z3Solver::default().check(&constraints), Satisfiability::Unsat);
+    }
+
+    #[test]
+    fn test_z3_satisfiable() {
+        let caller = op(CALLER, Vec::new());
+        let constraints =
+            vec![PathConstraint::new(op(ISZERO, vec![op(EQ, vec![caller, push(1)])]), true)];
+        assert_eq!(Z3Solver::default().check(&constraints), Satisfiability::Sat);
+    }
+}
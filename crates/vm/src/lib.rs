@@ -8,3 +8,7 @@ pub mod core;
 
 /// Extensions to the core VM, including execution utilities, lexers, and selector analysis
 pub mod ext;
+
+/// Fixtures for building symbolic expressions in tests
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
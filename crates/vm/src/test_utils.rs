@@ -0,0 +1,36 @@
+//! Fixtures for building symbolic expressions in tests.
+
+use std::sync::Arc;
+
+use alloy::primitives::U256;
+
+use crate::core::opcodes::{WrappedInput, WrappedOpcode, ADD, AND, CALLDATALOAD, PUSH1};
+
+/// Wraps `opcode` applied to `inputs`.
+pub fn op(opcode: u8, inputs: Vec<WrappedOpcode>) -> WrappedOpcode {
+    WrappedOpcode::new(
+        opcode,
+        inputs.into_iter().map(|input| WrappedInput::Opcode(Arc::new(input))).collect(),
+    )
+}
+
+/// A `PUSH1` of `value`.
+pub fn push(value: u8) -> WrappedOpcode {
+    WrappedOpcode::new(PUSH1, vec![WrappedInput::Raw(U256::from(value))])
+}
+
+/// The sum of `a` and `b`.
+pub fn add(a: WrappedOpcode, b: WrappedOpcode) -> WrappedOpcode {
+    op(ADD, vec![a, b])
+}
+
+/// The calldata word at `offset`.
+pub fn load(offset: WrappedOpcode) -> WrappedOpcode {
+    op(CALLDATALOAD, vec![offset])
+}
+
+/// A loop counter, masked from the calldata word at `offset`. Counters are read from the stack
+/// by solc, but need to be symbolic for the tests which use them.
+pub fn counter(offset: u8) -> WrappedOpcode {
+    op(AND, vec![load(push(offset)), push(0xff)])
+}